@@ -0,0 +1,60 @@
+//! Span throughput with every core recording at once, through one batch queue and through
+//! `batch_queue_shards`, with batches counted instead of exported (`benchmark_mode`).
+//!
+//! Run with `cargo bench --bench batch_queue`.
+
+use std::thread;
+use std::time::Instant;
+
+use raccoon_otel::{build_pipeline, OtelOptions};
+use tracing_subscriber::layer::SubscriberExt;
+
+const SPANS_PER_THREAD: u64 = 50_000;
+
+fn record_spans(count: u64) {
+    for i in 0..count {
+        let _span =
+            tracing::info_span!("handle_request", http.method = "GET", user.id = i).entered();
+    }
+}
+
+fn measure(label: &str, options: OtelOptions, threads: usize) -> anyhow::Result<()> {
+    let pipeline = build_pipeline("batch-queue", Some(options))?;
+    let dispatch = tracing::Dispatch::new(tracing_subscriber::registry().with(pipeline.layer()));
+    let guard = pipeline.into_guard();
+
+    let start = Instant::now();
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                tracing::dispatcher::with_default(&dispatch, || record_spans(SPANS_PER_THREAD))
+            });
+        }
+    });
+    let report = guard.throughput_report();
+    let elapsed = start.elapsed();
+
+    let spans = SPANS_PER_THREAD * threads as u64;
+    println!(
+        "{label}: {spans} spans on {threads} threads in {elapsed:?}: {:.0} spans/s \
+         ({} exported, {} dropped)",
+        spans as f64 / elapsed.as_secs_f64(),
+        report.spans,
+        report.dropped,
+    );
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let threads = thread::available_parallelism().map_or(4, usize::from);
+    // Large enough that neither run drops spans, so only the queue itself is compared
+    std::env::set_var("OTEL_BSP_MAX_QUEUE_SIZE", "1000000");
+    let options = || OtelOptions::builder().benchmark_mode(true);
+    measure("one queue", options().build(), threads)?;
+    measure(
+        "sharded",
+        options().batch_queue_shards(threads).build(),
+        threads,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! Allocations and time per span through the whole pipeline, with batches counted instead
+//! of exported (`benchmark_mode`), so the numbers only cover instrumentation overhead.
+//! Also measures spans beneath an unsampled span, with and without `skip_unsampled_spans`.
+//!
+//! Run with `cargo bench --bench span_overhead`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use raccoon_otel::{build_pipeline, OtelOptions};
+use tracing_subscriber::layer::SubscriberExt;
+
+const SPANS: u64 = 200_000;
+
+/// Counts every allocation made by the process, including the batch processor's thread.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn record_spans(count: u64) {
+    for i in 0..count {
+        let _span =
+            tracing::info_span!("handle_request", http.method = "GET", user.id = i).entered();
+    }
+}
+
+/// `count` spans inside one root span, which the sampler drops.
+fn record_unsampled_children(count: u64) {
+    let _root = tracing::info_span!("batch").entered();
+    record_spans(count);
+}
+
+fn measure(label: &str, options: OtelOptions, record: fn(u64)) -> anyhow::Result<()> {
+    let pipeline = build_pipeline("span-overhead", Some(options))?;
+    let subscriber = tracing_subscriber::registry().with(pipeline.layer());
+    let guard = pipeline.into_guard();
+
+    tracing::subscriber::with_default(subscriber, || {
+        record(1_000);
+        let _ = guard.throughput_report();
+
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        record(SPANS);
+        let report = guard.throughput_report();
+        let elapsed = start.elapsed();
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+        let per_span = |value: f64| value / SPANS as f64;
+        println!(
+            "{label}: {SPANS} spans in {elapsed:?}: {:.0} ns and {:.1} allocations per span \
+             ({} exported, {} dropped)",
+            per_span(elapsed.as_nanos() as f64),
+            per_span(allocations as f64),
+            report.spans.saturating_sub(1_000),
+            report.dropped,
+        );
+    });
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let options = || OtelOptions::builder().benchmark_mode(true);
+    measure("sampled", options().build(), record_spans)?;
+
+    // Read by the SDK when each of the following pipelines is built
+    std::env::set_var("OTEL_TRACES_SAMPLER", "always_off");
+    measure("unsampled", options().build(), record_unsampled_children)?;
+    measure(
+        "unsampled, skip_unsampled_spans",
+        options().skip_unsampled_spans(true).build(),
+        record_unsampled_children,
+    )?;
+    Ok(())
+}
@@ -23,6 +23,7 @@ async fn main() -> anyhow::Result<()> {
 
     fetch_user("user-42").await;
     process_order("order-123", 3).await;
+    drain_queue().await;
 
     tracing::info!("Demo application finished");
 
@@ -58,6 +59,26 @@ async fn process_item(order_id: &str, item_number: u32) {
     }
 }
 
+/// Batch consumer: each job span follows from the span that enqueued it,
+/// which shows up as a span link in the backend.
+async fn drain_queue() {
+    let enqueued: Vec<tracing::Span> = (1..=2)
+        .map(|job_id| {
+            let span = tracing::info_span!("enqueue_job", job_id);
+            span.in_scope(|| tracing::info!(job_id, "Job enqueued"));
+            span
+        })
+        .collect();
+
+    for (i, producer) in enqueued.iter().enumerate() {
+        let job_id = i + 1;
+        let consumer = tracing::info_span!("process_job", job_id);
+        consumer.follows_from(producer);
+        let _entered = consumer.enter();
+        tracing::info!(job_id, "Job processed");
+    }
+}
+
 async fn simulate_work(duration: Duration) {
     tokio::time::sleep(duration).await;
 }
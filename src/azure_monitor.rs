@@ -0,0 +1,96 @@
+//! Application Insights connection strings.
+
+const DEFAULT_INGESTION_ENDPOINT: &str = "https://dc.services.visualstudio.com";
+
+/// The parts of an Application Insights connection string used for export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConnectionString {
+    pub(crate) instrumentation_key: String,
+    pub(crate) ingestion_endpoint: String,
+}
+
+impl ConnectionString {
+    /// Parse `InstrumentationKey=...;IngestionEndpoint=https://...` (keys are
+    /// case-insensitive). Without `IngestionEndpoint` the endpoint is derived from
+    /// `EndpointSuffix` and `Location`, or the global endpoint is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the string has no `InstrumentationKey`.
+    pub(crate) fn parse(value: &str) -> anyhow::Result<Self> {
+        let mut instrumentation_key = None;
+        let mut ingestion_endpoint = None;
+        let mut endpoint_suffix = None;
+        let mut location = None;
+        for part in value.split(';') {
+            let Some((key, value)) = part.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "instrumentationkey" => instrumentation_key = Some(value),
+                "ingestionendpoint" => ingestion_endpoint = Some(value),
+                "endpointsuffix" => endpoint_suffix = Some(value),
+                "location" => location = Some(value),
+                _ => {}
+            }
+        }
+
+        let Some(instrumentation_key) = instrumentation_key.filter(|key| !key.is_empty()) else {
+            anyhow::bail!("Azure Monitor connection string has no `InstrumentationKey`");
+        };
+        let ingestion_endpoint = match (ingestion_endpoint, endpoint_suffix) {
+            (Some(endpoint), _) => endpoint.trim_end_matches('/').to_owned(),
+            (None, Some(suffix)) => {
+                let suffix = suffix.trim_matches('.');
+                match location {
+                    Some(location) => format!("https://{location}.dc.{suffix}"),
+                    None => format!("https://dc.{suffix}"),
+                }
+            }
+            (None, None) => DEFAULT_INGESTION_ENDPOINT.to_owned(),
+        };
+        Ok(Self {
+            instrumentation_key: instrumentation_key.to_owned(),
+            ingestion_endpoint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_strings_name_the_ingestion_endpoint() {
+        let parsed = ConnectionString::parse(
+            "InstrumentationKey=00000000-0000-0000-0000-000000000000;\
+             IngestionEndpoint=https://westeurope-5.in.applicationinsights.azure.com/;\
+             LiveEndpoint=https://westeurope.livediagnostics.monitor.azure.com/",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.instrumentation_key,
+            "00000000-0000-0000-0000-000000000000"
+        );
+        assert_eq!(
+            parsed.ingestion_endpoint,
+            "https://westeurope-5.in.applicationinsights.azure.com"
+        );
+
+        let sovereign =
+            ConnectionString::parse("instrumentationkey=abc;EndpointSuffix=applicationinsights.us")
+                .unwrap();
+        assert_eq!(
+            sovereign.ingestion_endpoint,
+            "https://dc.applicationinsights.us"
+        );
+        assert_eq!(
+            ConnectionString::parse("InstrumentationKey=abc")
+                .unwrap()
+                .ingestion_endpoint,
+            DEFAULT_INGESTION_ENDPOINT
+        );
+        assert!(ConnectionString::parse("IngestionEndpoint=https://x").is_err());
+    }
+}
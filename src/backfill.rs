@@ -0,0 +1,337 @@
+//! Spans, span events and logs with timestamps of their own, for telemetry recorded
+//! earlier than it is exported, such as readings a device buffered while offline.
+//!
+//! `tracing` stamps everything with the time it's dispatched. These helpers build the OTel
+//! records directly instead, with the times given, so a replayed reading keeps the time it
+//! was taken:
+//!
+//! ```
+//! use std::time::{Duration, SystemTime};
+//!
+//! use raccoon_otel::backfill;
+//! use raccoon_otel::re_exports::opentelemetry::KeyValue;
+//!
+//! // When the device buffered its readings
+//! let taken = SystemTime::now() - Duration::from_secs(3600);
+//! let at = |secs| taken + Duration::from_secs(secs);
+//!
+//! let mut span = backfill::span("sensor.sync", taken);
+//! span.set_attribute(KeyValue::new("device.id", "thermo-7"));
+//! span.add_event("reading", at(1), vec![KeyValue::new("celsius", 21.5)]);
+//! span.log(tracing::Level::WARN, at(2), "battery low", [("battery.percent", 4)]);
+//! span.end(at(3));
+//! ```
+//!
+//! Spans go to the global tracer provider and logs to the logger provider, both of which
+//! `setup_otel` registers unless `set_global(false)` is used. Spans are children of the
+//! current `tracing` span, and `RUST_LOG` filtering doesn't apply.
+
+use std::borrow::Cow;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use opentelemetry::global::BoxedTracer;
+use opentelemetry::logs::{AnyValue, LogRecord as _, Logger as _, LoggerProvider as _, Severity};
+use opentelemetry::trace::{SpanKind, TraceContextExt, Tracer};
+use opentelemetry::{Context, Key, KeyValue};
+use opentelemetry_sdk::logs::{SdkLogger, SdkLoggerProvider};
+use tracing::Level;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Logger of the last logger provider registered by setup.
+static LOGGER: RwLock<Option<SdkLogger>> = RwLock::new(None);
+
+/// Make `provider` the one [`log`] emits to.
+pub(crate) fn set_logger_provider(provider: &SdkLoggerProvider) {
+    let logger = provider.logger("raccoon-otel");
+    *LOGGER.write().unwrap_or_else(|e| e.into_inner()) = Some(logger);
+}
+
+fn global_logger() -> Option<SdkLogger> {
+    LOGGER.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Start a span at `start_time`, as a child of the current `tracing` span.
+pub fn span(name: impl Into<Cow<'static, str>>, start_time: SystemTime) -> BackfilledSpan {
+    BackfilledSpan::start(
+        Arc::new(opentelemetry::global::tracer("raccoon-otel")),
+        global_logger(),
+        name,
+        start_time,
+        &tracing::Span::current().context(),
+    )
+}
+
+/// Emit a log record timestamped `timestamp`, correlated with the current `tracing` span.
+///
+/// Does nothing if setup hasn't registered a logger provider.
+pub fn log<K, V>(
+    level: Level,
+    timestamp: SystemTime,
+    body: impl Into<AnyValue>,
+    attributes: impl IntoIterator<Item = (K, V)>,
+) where
+    K: Into<Key>,
+    V: Into<AnyValue>,
+{
+    emit(
+        global_logger().as_ref(),
+        &tracing::Span::current().context(),
+        level,
+        timestamp,
+        body.into(),
+        attributes,
+    );
+}
+
+/// A span from [`span`], whose events and end carry the times they're given.
+///
+/// A span dropped without [`end`](Self::end) ends at the latest time it was given, rather
+/// than at the time it's dropped.
+#[derive(Debug)]
+#[must_use = "the span ends when dropped, so an unused span records nothing of note"]
+pub struct BackfilledSpan {
+    tracer: Arc<BoxedTracer>,
+    logger: Option<SdkLogger>,
+    cx: Context,
+    latest: SystemTime,
+    ended: bool,
+}
+
+impl BackfilledSpan {
+    fn start(
+        tracer: Arc<BoxedTracer>,
+        logger: Option<SdkLogger>,
+        name: impl Into<Cow<'static, str>>,
+        start_time: SystemTime,
+        parent: &Context,
+    ) -> Self {
+        let span = tracer
+            .span_builder(name)
+            .with_kind(SpanKind::Internal)
+            .with_start_time(start_time)
+            .start_with_context(tracer.as_ref(), parent);
+        Self {
+            tracer,
+            logger,
+            cx: parent.with_span(span),
+            latest: start_time,
+            ended: false,
+        }
+    }
+
+    /// Start a child span at `start_time`.
+    pub fn child(&self, name: impl Into<Cow<'static, str>>, start_time: SystemTime) -> Self {
+        Self::start(
+            Arc::clone(&self.tracer),
+            self.logger.clone(),
+            name,
+            start_time,
+            &self.cx,
+        )
+    }
+
+    /// Set an attribute on the span.
+    pub fn set_attribute(&mut self, attribute: KeyValue) {
+        self.cx.span().set_attribute(attribute);
+    }
+
+    /// Add an event that happened at `timestamp`.
+    pub fn add_event(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        timestamp: SystemTime,
+        attributes: Vec<KeyValue>,
+    ) {
+        self.latest = self.latest.max(timestamp);
+        self.cx
+            .span()
+            .add_event_with_timestamp(name, timestamp, attributes);
+    }
+
+    /// Emit a log record timestamped `timestamp`, correlated with this span. See [`log`].
+    pub fn log<K, V>(
+        &mut self,
+        level: Level,
+        timestamp: SystemTime,
+        body: impl Into<AnyValue>,
+        attributes: impl IntoIterator<Item = (K, V)>,
+    ) where
+        K: Into<Key>,
+        V: Into<AnyValue>,
+    {
+        self.latest = self.latest.max(timestamp);
+        emit(
+            self.logger.as_ref(),
+            &self.cx,
+            level,
+            timestamp,
+            body.into(),
+            attributes,
+        );
+    }
+
+    /// The span's context, e.g. to parent a `tracing` span to it with `set_parent`.
+    pub fn context(&self) -> Context {
+        self.cx.clone()
+    }
+
+    /// End the span at `end_time`.
+    pub fn end(mut self, end_time: SystemTime) {
+        self.finish(end_time);
+    }
+
+    fn finish(&mut self, end_time: SystemTime) {
+        if !self.ended {
+            self.ended = true;
+            self.cx.span().end_with_timestamp(end_time);
+        }
+    }
+}
+
+impl Drop for BackfilledSpan {
+    fn drop(&mut self) {
+        self.finish(self.latest);
+    }
+}
+
+fn emit<K, V>(
+    logger: Option<&SdkLogger>,
+    cx: &Context,
+    level: Level,
+    timestamp: SystemTime,
+    body: AnyValue,
+    attributes: impl IntoIterator<Item = (K, V)>,
+) where
+    K: Into<Key>,
+    V: Into<AnyValue>,
+{
+    let Some(logger) = logger else {
+        return;
+    };
+    let mut record = logger.create_log_record();
+    record.set_timestamp(timestamp);
+    record.set_observed_timestamp(SystemTime::now());
+    record.set_severity_number(severity(level));
+    record.set_severity_text(level.as_str());
+    record.set_body(body);
+    record.add_attributes(attributes);
+    let span_context = cx.span().span_context().clone();
+    if span_context.is_valid() {
+        record.set_trace_context(
+            span_context.trace_id(),
+            span_context.span_id(),
+            Some(span_context.trace_flags()),
+        );
+    }
+    logger.emit(record);
+}
+
+/// The OTel severity of a `tracing` level, as the log bridge maps it.
+pub(crate) fn severity(level: Level) -> Severity {
+    match level {
+        Level::TRACE => Severity::Trace,
+        Level::DEBUG => Severity::Debug,
+        Level::INFO => Severity::Info,
+        Level::WARN => Severity::Warn,
+        _ => Severity::Error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::InstrumentationScope;
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord};
+    use opentelemetry_sdk::trace::{SdkTracerProvider, SpanData, SpanProcessor};
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Collect(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanProcessor for Collect {
+        fn on_start(&self, _: &mut opentelemetry_sdk::trace::Span, _: &Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            self.0.lock().unwrap().push(span);
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _: Duration) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct CollectLogs(Arc<Mutex<Vec<SdkLogRecord>>>);
+
+    impl LogProcessor for CollectLogs {
+        fn emit(&self, record: &mut SdkLogRecord, _: &InstrumentationScope) {
+            self.0.lock().unwrap().push(record.clone());
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn records_keep_the_given_times() {
+        let spans = Arc::default();
+        let tracer = SdkTracerProvider::builder()
+            .with_span_processor(Collect(Arc::clone(&spans)))
+            .build()
+            .tracer("test");
+        let logs = Arc::default();
+        let logger = SdkLoggerProvider::builder()
+            .with_log_processor(CollectLogs(Arc::clone(&logs)))
+            .build()
+            .logger("test");
+        let t = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        let sync = BackfilledSpan::start(
+            Arc::new(BoxedTracer::new(Box::new(tracer))),
+            Some(logger),
+            "sensor.sync",
+            t(100),
+            &Context::new(),
+        );
+        let mut upload = sync.child("sensor.upload", t(101));
+        upload.add_event("reading", t(102), vec![]);
+        upload.log(Level::WARN, t(103), "battery low", [("battery.percent", 4)]);
+        // Dropped without an end, so it ends at its last time
+        drop(upload);
+        sync.end(t(110));
+
+        let spans = spans.lock().unwrap();
+        let times: Vec<_> = spans
+            .iter()
+            .map(|s| (s.name.as_ref(), s.start_time, s.end_time))
+            .collect();
+        assert_eq!(
+            times,
+            [
+                ("sensor.upload", t(101), t(103)),
+                ("sensor.sync", t(100), t(110))
+            ]
+        );
+        assert_eq!(spans[0].parent_span_id, spans[1].span_context.span_id());
+        assert_eq!(spans[0].events.events[0].timestamp, t(102));
+
+        let logs = logs.lock().unwrap();
+        assert_eq!(logs[0].timestamp(), Some(t(103)));
+        assert_eq!(logs[0].severity_number(), Some(Severity::Warn));
+        assert_eq!(
+            logs[0].trace_context().map(|cx| cx.span_id),
+            Some(spans[0].span_context.span_id())
+        );
+    }
+}
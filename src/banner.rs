@@ -0,0 +1,84 @@
+//! The startup banner: one INFO event describing the effective configuration.
+
+use std::collections::HashMap;
+
+use crate::env::ResolvedConfig;
+use crate::options::Protocol;
+use crate::redact::RedactedMap;
+
+/// Emit the startup banner through the global subscriber, so it is both printed and exported.
+///
+/// Header and gRPC metadata values and secret-looking resource attributes are redacted;
+/// endpoints carry no credentials after normalization.
+pub(crate) fn emit(config: &ResolvedConfig) {
+    let resource: HashMap<String, String> = crate::resource::build_resource(
+        &config.service_name,
+        &config.resource_attributes,
+        &config.resource_key_values,
+    )
+    .iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    let span_filters: Vec<String> = config
+        .drop_span_filters
+        .iter()
+        .map(|filter| format!("{filter:?}"))
+        .collect();
+
+    tracing::info!(
+        service.name = config.service_name.as_str(),
+        config.version = env!("CARGO_PKG_VERSION"),
+        config.traces_endpoint =
+            describe_endpoint(&config.traces_protocol, &config.traces_endpoint),
+        config.traces_protocol = config.traces_protocol.to_string(),
+        config.logs_endpoint = describe_endpoint(&config.logs_protocol, &config.logs_endpoint),
+        config.logs_protocol = config.logs_protocol.to_string(),
+        config.sampler = sampler(config),
+        config.log_filter = crate::subscriber::env_filter().to_string(),
+        config.span_filters = span_filters.join(", "),
+        config.headers = RedactedMap::all(&config.headers).pairs().join(", "),
+        config.grpc_metadata = RedactedMap::all(&config.grpc_metadata).pairs().join(", "),
+        config.resource = RedactedMap::matching(&resource, &config.redact_keys)
+            .pairs()
+            .join(", "),
+        "raccoon-otel started"
+    );
+}
+
+fn describe_endpoint(protocol: &Protocol, endpoint: &str) -> String {
+    match protocol {
+        Protocol::File { path } => path.display().to_string(),
+        _ => endpoint.to_owned(),
+    }
+}
+
+/// The trace sampler in `OTEL_TRACES_SAMPLER` notation, after any remote sampling and
+/// sampling rules.
+fn sampler(config: &ResolvedConfig) -> String {
+    let configured = match config.sampling_rules.len() {
+        0 => root_sampler(config),
+        rules => format!("{rules} sampling rules, then {}", root_sampler(config)),
+    };
+    match &config.remote_sampling {
+        Some((url, _)) => format!("remote ({url}), until fetched {configured}"),
+        None => match &config.opamp {
+            Some((endpoint, _)) => format!("OpAMP ({endpoint}), until configured {configured}"),
+            None => configured,
+        },
+    }
+}
+
+fn root_sampler(config: &ResolvedConfig) -> String {
+    if let Some(min_ratio) = config.adaptive_sampling_min_ratio {
+        return format!("parentbased_adaptive (min ratio {min_ratio})");
+    }
+    // Without adaptive sampling the SDK reads the standard env vars itself
+    let name = std::env::var("OTEL_TRACES_SAMPLER")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "parentbased_always_on".to_owned());
+    match std::env::var("OTEL_TRACES_SAMPLER_ARG") {
+        Ok(arg) if !arg.is_empty() => format!("{name} ({arg})"),
+        _ => name,
+    }
+}
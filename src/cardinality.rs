@@ -0,0 +1,285 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use opentelemetry_sdk::trace::SpanData;
+
+/// Distinct span names, attribute keys, and values per key tracked in a window. Past
+/// this, new ones aren't counted and the report marks the count as a lower bound.
+const MAX_TRACKED: usize = 10_000;
+
+/// Entries listed per section of the stderr report.
+const REPORT_TOP: usize = 5;
+
+/// Span names and attribute value cardinality seen over one window, to find the
+/// instrumentation behind a backend cost blowup.
+///
+/// Obtained from [`OtelGuard::cardinality_report`](crate::OtelGuard::cardinality_report)
+/// when [`cardinality_report`](crate::OtelOptionsBuilder::cardinality_report) is set. Its
+/// [`Display`](fmt::Display) form is the line printed to stderr at the end of each window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CardinalityReport {
+    /// How long the window lasted.
+    pub window: Duration,
+    /// Spans exported in the window.
+    pub spans: u64,
+    /// Distinct span names, most frequent first.
+    pub span_names: Vec<(String, u64)>,
+    /// `true` if more span names were seen than could be tracked, so
+    /// [`span_names`](Self::span_names) is incomplete.
+    pub span_names_capped: bool,
+    /// Span attribute keys, most distinct values first.
+    pub attributes: Vec<AttributeCardinality>,
+}
+
+/// How many distinct values a span attribute key had over a [`CardinalityReport`] window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AttributeCardinality {
+    /// The attribute key.
+    pub key: String,
+    /// Spans that had the attribute.
+    pub spans: u64,
+    /// Distinct values of the attribute.
+    pub distinct_values: usize,
+    /// `true` if more values were seen than could be tracked, so
+    /// [`distinct_values`](Self::distinct_values) is a lower bound.
+    pub capped: bool,
+}
+
+impl fmt::Display for CardinalityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plus = |capped: bool| if capped { "+" } else { "" };
+        write!(
+            f,
+            "{} spans in {}s, {}{} span names",
+            self.spans,
+            self.window.as_secs(),
+            self.span_names.len(),
+            plus(self.span_names_capped)
+        )?;
+        for (i, (name, count)) in self.span_names.iter().take(REPORT_TOP).enumerate() {
+            let separator = if i == 0 { " (top: " } else { ", " };
+            write!(f, "{separator}{name:?} {count}")?;
+        }
+        if !self.span_names.is_empty() {
+            f.write_str(")")?;
+        }
+        for (i, attribute) in self.attributes.iter().take(REPORT_TOP).enumerate() {
+            let separator = if i == 0 {
+                "; most distinct attribute values: "
+            } else {
+                ", "
+            };
+            write!(
+                f,
+                "{separator}{} {}{}",
+                attribute.key,
+                attribute.distinct_values,
+                plus(attribute.capped)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Tracks the spans exported in the current window and keeps the report of the last
+/// complete one. Windows roll over lazily, when a span or a report request arrives.
+#[derive(Debug)]
+pub(crate) struct CardinalityAnalyzer {
+    window: Duration,
+    state: Mutex<AnalyzerState>,
+}
+
+#[derive(Debug)]
+struct AnalyzerState {
+    started: Instant,
+    current: Window,
+    last: Option<CardinalityReport>,
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    spans: u64,
+    span_names: HashMap<String, u64>,
+    span_names_capped: bool,
+    attributes: HashMap<String, KeyValues>,
+}
+
+#[derive(Debug, Default)]
+struct KeyValues {
+    spans: u64,
+    // Hashes rather than values, so long values don't pile up in memory
+    values: HashSet<u64>,
+    capped: bool,
+}
+
+impl CardinalityAnalyzer {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            state: Mutex::new(AnalyzerState {
+                started: Instant::now(),
+                current: Window::default(),
+                last: None,
+            }),
+        }
+    }
+
+    pub(crate) fn record(&self, span: &SpanData) {
+        let mut state = self.lock();
+        if let Some(report) = self.roll(&mut state) {
+            eprintln!("raccoon-otel: cardinality report: {report}");
+        }
+        let window = &mut state.current;
+        window.spans += 1;
+        if let Some(count) = window.span_names.get_mut(span.name.as_ref()) {
+            *count += 1;
+        } else if window.span_names.len() < MAX_TRACKED {
+            window.span_names.insert(span.name.to_string(), 1);
+        } else {
+            window.span_names_capped = true;
+        }
+        for attribute in &span.attributes {
+            let key = attribute.key.as_str();
+            if !window.attributes.contains_key(key) && window.attributes.len() >= MAX_TRACKED {
+                continue;
+            }
+            let values = window.attributes.entry(key.to_owned()).or_default();
+            values.spans += 1;
+            let mut hasher = DefaultHasher::new();
+            attribute.value.as_str().hash(&mut hasher);
+            let hash = hasher.finish();
+            if values.values.len() < MAX_TRACKED {
+                values.values.insert(hash);
+            } else if !values.values.contains(&hash) {
+                values.capped = true;
+            }
+        }
+    }
+
+    /// The report of the last complete window, if one has ended yet.
+    pub(crate) fn report(&self) -> Option<CardinalityReport> {
+        let mut state = self.lock();
+        self.roll(&mut state);
+        state.last.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, AnalyzerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Close the current window if it has run its length, returning its report.
+    fn roll(&self, state: &mut AnalyzerState) -> Option<CardinalityReport> {
+        let elapsed = state.started.elapsed();
+        if elapsed < self.window {
+            return None;
+        }
+        state.started = Instant::now();
+        let report = std::mem::take(&mut state.current).into_report(elapsed);
+        state.last = Some(report.clone());
+        Some(report)
+    }
+}
+
+impl Window {
+    fn into_report(self, window: Duration) -> CardinalityReport {
+        let mut span_names: Vec<_> = self.span_names.into_iter().collect();
+        span_names.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let mut attributes: Vec<_> = self
+            .attributes
+            .into_iter()
+            .map(|(key, values)| AttributeCardinality {
+                key,
+                spans: values.spans,
+                distinct_values: values.values.len(),
+                capped: values.capped,
+            })
+            .collect();
+        attributes.sort_by(|a, b| {
+            b.distinct_values
+                .cmp(&a.distinct_values)
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        CardinalityReport {
+            window,
+            spans: self.spans,
+            span_names,
+            span_names_capped: self.span_names_capped,
+            attributes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::time::UNIX_EPOCH;
+
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status};
+    use opentelemetry::{InstrumentationScope, KeyValue};
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+
+    use super::*;
+
+    fn span(name: &'static str, attributes: Vec<KeyValue>) -> SpanData {
+        SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Server,
+            name: Cow::Borrowed(name),
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+            attributes,
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: InstrumentationScope::builder("raccoon-otel").build(),
+        }
+    }
+
+    #[test]
+    fn reports_rank_names_by_count_and_keys_by_distinct_values() {
+        let analyzer = CardinalityAnalyzer::new(Duration::from_secs(60));
+        for user in 0..3 {
+            analyzer.record(&span(
+                "GET /users",
+                vec![
+                    KeyValue::new("user.id", user),
+                    KeyValue::new("http.method", "GET"),
+                ],
+            ));
+        }
+        analyzer.record(&span(
+            "POST /orders",
+            vec![KeyValue::new("http.method", "POST")],
+        ));
+        assert_eq!(analyzer.report(), None);
+
+        // End the window
+        analyzer.lock().started -= Duration::from_secs(60);
+        let report = analyzer.report().unwrap();
+        assert_eq!(report.spans, 4);
+        assert_eq!(
+            report.span_names,
+            [("GET /users".to_owned(), 3), ("POST /orders".to_owned(), 1)]
+        );
+        let keys: Vec<_> = report
+            .attributes
+            .iter()
+            .map(|a| (a.key.as_str(), a.spans, a.distinct_values))
+            .collect();
+        assert_eq!(keys, [("user.id", 3, 3), ("http.method", 4, 2)]);
+        assert_eq!(
+            report.to_string(),
+            "4 spans in 60s, 2 span names (top: \"GET /users\" 3, \"POST /orders\" 1); \
+             most distinct attribute values: user.id 3, http.method 2"
+        );
+    }
+}
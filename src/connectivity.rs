@@ -0,0 +1,94 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::env::ResolvedConfig;
+use crate::exporters::vsock::VsockAddr;
+use crate::options::{Protocol, VerifyMode};
+
+/// Upper bound on each connection attempt, whatever the export timeout.
+const MAX_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run the startup connectivity check selected by `config.verify_connectivity`.
+///
+/// # Errors
+///
+/// Returns an error in [`VerifyMode::FailFast`] mode if an endpoint can't be reached.
+pub(crate) fn verify(config: &ResolvedConfig) -> anyhow::Result<()> {
+    // Benchmark mode never connects
+    if config.verify_connectivity == VerifyMode::Off || config.benchmark_mode {
+        return Ok(());
+    }
+
+    // Signals written to a file have no endpoint to check
+    let is_file = |protocol: &Protocol| matches!(protocol, Protocol::File { .. });
+    let mut endpoints = Vec::new();
+    if cfg!(feature = "traces") && !is_file(&config.traces_protocol) {
+        endpoints.push(config.traces_endpoint.as_str());
+    }
+    if cfg!(feature = "logs") && !is_file(&config.logs_protocol) {
+        endpoints.push(config.logs_endpoint.as_str());
+    }
+    if cfg!(feature = "metrics") && config.statsd.is_none() && !is_file(&config.protocol) {
+        endpoints.push(config.endpoint.as_str());
+    }
+    endpoints.sort_unstable();
+    endpoints.dedup();
+
+    let timeout = config.traces_export_timeout.min(MAX_PROBE_TIMEOUT);
+    // vsock endpoints aren't TCP, and the host is only reachable from inside the microVM
+    for endpoint in endpoints
+        .into_iter()
+        .filter(|e| VsockAddr::parse(e).is_none())
+    {
+        if let Err(e) = probe(endpoint, timeout) {
+            let message = format!("OTLP endpoint `{endpoint}` is not reachable: {e}");
+            match config.verify_connectivity {
+                VerifyMode::FailFast => anyhow::bail!(message),
+                VerifyMode::Warn | VerifyMode::Off => eprintln!(
+                    "raccoon-otel: WARNING: {message}. Telemetry will not be exported \
+                     until it becomes reachable."
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Open (and close) a TCP connection to the endpoint's host and port.
+pub(crate) fn probe(endpoint: &str, timeout: Duration) -> std::io::Result<()> {
+    let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg.to_owned());
+    let url = url::Url::parse(endpoint).map_err(|_| invalid("invalid URL"))?;
+    let host = url.host_str().ok_or_else(|| invalid("missing host"))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| invalid("missing port"))?;
+    // `host_str` keeps the brackets around IPv6 literals, which `to_socket_addrs` rejects
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let mut last_error = None;
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| invalid("host resolved to no addresses")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn probe_reports_closed_ports() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let timeout = Duration::from_secs(1);
+
+        assert!(probe(&format!("http://127.0.0.1:{port}"), timeout).is_ok());
+        drop(listener);
+        assert!(probe(&format!("http://127.0.0.1:{port}"), timeout).is_err());
+    }
+}
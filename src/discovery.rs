@@ -0,0 +1,176 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::connectivity::probe;
+use crate::env::normalize_endpoint;
+use crate::exporters::vsock::VsockAddr;
+use crate::options::Protocol;
+
+/// How long each candidate may take to accept a connection. Candidates are local, so a
+/// slow one is as good as absent.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Find a responsive local OTLP endpoint for
+/// [`discover_endpoint`](crate::OtelOptionsBuilder::discover_endpoint), returning it with
+/// the protocol it speaks, or `None` (with a warning) if no candidate responds.
+///
+/// `protocol` is the explicitly chosen protocol, if any.
+pub(crate) fn discover(
+    protocol: Option<&Protocol>,
+    metadata_file: Option<&Path>,
+) -> Option<(String, Protocol)> {
+    let candidates = candidates(protocol, metadata_file);
+    for (endpoint, protocol) in &candidates {
+        // vsock endpoints can't be probed over TCP, so naming one is taken as enough
+        if VsockAddr::parse(endpoint).is_some() || probe(endpoint, PROBE_TIMEOUT).is_ok() {
+            eprintln!("raccoon-otel: discovered OTLP endpoint {endpoint} ({protocol:?})");
+            return Some((endpoint.clone(), protocol.clone()));
+        }
+    }
+    let tried: Vec<_> = candidates.iter().map(|(e, _)| e.as_str()).collect();
+    eprintln!(
+        "raccoon-otel: WARNING: no OTLP endpoint discovered (tried {}), using the default",
+        tried.join(", ")
+    );
+    None
+}
+
+/// Endpoints to try, in order, with the protocol each would be used with. Unix domain
+/// sockets aren't among them, as there's no OTLP transport to use one with.
+fn candidates(
+    protocol: Option<&Protocol>,
+    metadata_file: Option<&Path>,
+) -> Vec<(String, Protocol)> {
+    let mut candidates = Vec::new();
+    if let Some(path) = metadata_file {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                let (endpoint, file_protocol) = read_metadata(&contents);
+                let protocol = protocol
+                    .cloned()
+                    .or(file_protocol)
+                    .unwrap_or(Protocol::HttpProtobuf);
+                match endpoint.map(|e| normalize_endpoint(&e, &protocol)) {
+                    Some(Ok(endpoint)) => candidates.push((endpoint, protocol)),
+                    Some(Err(e)) => eprintln!(
+                        "raccoon-otel: ignoring the endpoint in {}: {e:#}",
+                        path.display()
+                    ),
+                    None => {}
+                }
+            }
+            // Platforms without the file are expected in a mixed fleet
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => eprintln!("raccoon-otel: failed to read {}: {e}", path.display()),
+        }
+    }
+    match protocol {
+        None => {
+            candidates.push(("http://localhost:4318".to_owned(), Protocol::HttpProtobuf));
+            if cfg!(feature = "grpc") {
+                candidates.push(("http://localhost:4317".to_owned(), Protocol::Grpc));
+            }
+        }
+        Some(Protocol::Grpc) => {
+            candidates.push(("http://localhost:4317".to_owned(), Protocol::Grpc));
+        }
+        Some(protocol @ (Protocol::HttpProtobuf | Protocol::HttpJson)) => {
+            candidates.push(("http://localhost:4318".to_owned(), protocol.clone()));
+        }
+        // Not OTLP collectors; only the metadata file can name their endpoint
+        Some(
+            Protocol::File { .. }
+            | Protocol::Jaeger
+            | Protocol::Zipkin
+            | Protocol::AzureMonitor { .. },
+        ) => {}
+    }
+    candidates
+}
+
+/// The `OTEL_EXPORTER_OTLP_ENDPOINT` and `OTEL_EXPORTER_OTLP_PROTOCOL` set by an env-style
+/// file: `KEY=value` lines, optionally `export`ed or quoted, with `#` comments.
+fn read_metadata(contents: &str) -> (Option<String>, Option<Protocol>) {
+    let mut endpoint = None;
+    let mut protocol = None;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        match key.trim() {
+            "OTEL_EXPORTER_OTLP_ENDPOINT" if !value.is_empty() => {
+                endpoint = Some(value.to_owned());
+            }
+            "OTEL_EXPORTER_OTLP_PROTOCOL" => protocol = value.parse().ok(),
+            _ => {}
+        }
+    }
+    (endpoint, protocol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_file_endpoint_comes_before_localhost() {
+        let path =
+            std::env::temp_dir().join(format!("raccoon-otel-discovery-{}.env", std::process::id()));
+        std::fs::write(
+            &path,
+            "# written by the node agent\n\
+             export OTEL_EXPORTER_OTLP_ENDPOINT=\"http://10.0.0.1:4317\"\n\
+             OTEL_EXPORTER_OTLP_PROTOCOL=grpc\n",
+        )
+        .unwrap();
+        let found = candidates(Some(&Protocol::Grpc), Some(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            found,
+            [
+                ("http://10.0.0.1:4317".to_owned(), Protocol::Grpc),
+                ("http://localhost:4317".to_owned(), Protocol::Grpc),
+            ]
+        );
+
+        // A missing file only leaves the localhost candidates
+        let found = candidates(Some(&Protocol::HttpJson), Some(&path));
+        assert_eq!(
+            found,
+            [("http://localhost:4318".to_owned(), Protocol::HttpJson)]
+        );
+    }
+
+    #[test]
+    fn candidates_are_tried_file_then_http_then_grpc() {
+        let path = std::env::temp_dir().join(format!(
+            "raccoon-otel-discovery-order-{}.env",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            "OTEL_EXPORTER_OTLP_ENDPOINT=http://agent.local:4318\n",
+        )
+        .unwrap();
+        let found = candidates(None, Some(&path));
+        std::fs::remove_file(&path).unwrap();
+
+        let mut expected = vec![
+            ("http://agent.local:4318".to_owned(), Protocol::HttpProtobuf),
+            ("http://localhost:4318".to_owned(), Protocol::HttpProtobuf),
+        ];
+        if cfg!(feature = "grpc") {
+            expected.push(("http://localhost:4317".to_owned(), Protocol::Grpc));
+        }
+        assert_eq!(found, expected);
+
+        // Without a file only localhost is left, and nothing for a protocol that isn't OTLP
+        assert_eq!(candidates(None, None), expected[1..]);
+        assert_eq!(candidates(Some(&Protocol::Zipkin), None), []);
+    }
+}
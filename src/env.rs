@@ -1,11 +1,34 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
-use crate::options::{OtelOptions, Protocol};
+use anyhow::Context;
+use opentelemetry::trace::SpanKind;
+use opentelemetry::KeyValue;
+
+use crate::azure_monitor::ConnectionString;
+use crate::discovery;
+use crate::exporters::vsock::VsockAddr;
+use crate::layers::event_counts::EventCountRule;
+use crate::options::{
+    AmbientAttributeFn, AuthorizationFn, AuxiliaryGuard, Callback, ConsoleFormat, DeadLetterOutput,
+    ExportBudget, ExportHookFn, ExtraLayer, HeaderSource, HistogramAggregation, LargeIntegerMode,
+    MetricsTemporality, OtelOptions, Protocol, SpanNameMapperFn, TakeOnce, VerifyMode,
+};
+use crate::redact::RedactedMap;
+use crate::sinks::gelf::GelfOptions;
+use crate::sinks::statsd::StatsdOptions;
+use crate::sinks::syslog::SyslogOptions;
+use crate::span_filter::SpanFilter;
+use crate::tenant::TenantOptions;
 
 const DEFAULT_GRPC_ENDPOINT: &str = "http://localhost:4317";
 const DEFAULT_HTTP_ENDPOINT: &str = "http://localhost:4318";
+const DEFAULT_JAEGER_ENDPOINT: &str = "http://localhost:14268";
+const DEFAULT_ZIPKIN_ENDPOINT: &str = "http://localhost:9411";
 const DEFAULT_EXPORT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Size at which `Protocol::File` output is rotated (the collector's file exporter default).
+const DEFAULT_FILE_MAX_BYTES: u64 = 100 * 1024 * 1024;
+const DEFAULT_FILE_MAX_BACKUPS: usize = 10;
 
 /// Fully resolved configuration after merging programmatic options, env vars, and defaults.
 ///
@@ -13,83 +36,649 @@ const DEFAULT_EXPORT_TIMEOUT: Duration = Duration::from_secs(30);
 /// 1. Programmatic — values set in [`OtelOptions`]
 /// 2. Environment variables — `OTEL_EXPORTER_OTLP_*`
 /// 3. Defaults — localhost endpoints, 30s timeout
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub(crate) struct ResolvedConfig {
     pub service_name: String,
+    /// General endpoint and protocol, used by signals without their own.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
     pub endpoint: String,
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
     pub protocol: Protocol,
-    // TODO: pass programmatic headers to exporter builders (tonic MetadataMap / reqwest headers).
-    // The OTLP SDK already reads OTEL_EXPORTER_OTLP_HEADERS natively for env-var-based headers.
-    #[allow(dead_code)]
+    pub traces_endpoint: String,
+    pub traces_protocol: Protocol,
+    pub logs_endpoint: String,
+    pub logs_protocol: Protocol,
+    pub grpc_http_fallback: bool,
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    pub grpc_keepalive: Option<(Duration, Duration)>,
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    pub grpc_connect_timeout: Option<Duration>,
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    pub grpc_adaptive_window: Option<bool>,
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    pub grpc_dns_refresh: Option<Duration>,
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    pub dedicated_export_thread: bool,
+    pub verify_connectivity: VerifyMode,
+    #[cfg_attr(not(any(feature = "grpc", feature = "http")), allow(dead_code))]
     pub headers: HashMap<String, String>,
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    pub grpc_metadata: HashMap<String, String>,
+    #[cfg_attr(not(any(feature = "grpc", feature = "http")), allow(dead_code))]
+    pub user_agent: Option<String>,
     pub resource_attributes: HashMap<String, String>,
-    pub export_timeout: Duration,
+    pub resource_key_values: Vec<KeyValue>,
+    pub traces_export_timeout: Duration,
+    pub logs_export_timeout: Duration,
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub metrics_export_timeout: Duration,
+    pub set_global: bool,
+    pub span_name_mapper: Option<Callback<SpanNameMapperFn>>,
+    pub max_attribute_value_length: Option<usize>,
+    pub attribute_renames: Vec<(String, String)>,
+    pub span_event_summary: Option<(usize, usize)>,
+    pub drop_span_filters: Vec<SpanFilter>,
+    pub event_counters: Vec<EventCountRule>,
+    pub span_kind_rules: Vec<(SpanFilter, SpanKind)>,
+    pub sampling_rules: Vec<(SpanFilter, f64)>,
+    pub large_integer_mode: LargeIntegerMode,
+    pub expand_json_fields: bool,
+    pub console_format: ConsoleFormat,
+    pub gelf: Option<GelfOptions>,
+    pub syslog: Option<SyslogOptions>,
+    pub statsd: Option<StatsdOptions>,
+    pub debug_logs_on_error_only: bool,
+    pub dedup_logs_window: Option<Duration>,
+    pub export_budget: Option<ExportBudget>,
+    pub max_export_bytes: Option<usize>,
+    pub adaptive_sampling_min_ratio: Option<f64>,
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub metrics_temporality: MetricsTemporality,
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub histogram_aggregation: HistogramAggregation,
+    #[cfg_attr(not(feature = "file"), allow(dead_code))]
+    pub file_rotation: (u64, usize),
+    pub flush_watchdog: Option<Duration>,
+    pub cardinality_window: Option<Duration>,
+    pub remote_sampling: Option<(String, Duration)>,
+    pub opamp: Option<(String, Duration)>,
+    pub receiver: Option<String>,
+    pub profiling: Option<(std::path::PathBuf, Duration)>,
+    pub dead_letter_output: Option<DeadLetterOutput>,
+    pub startup_banner: bool,
+    pub benchmark_mode: bool,
+    pub skip_unsampled_spans: bool,
+    pub async_bridge: Option<usize>,
+    pub batch_queue_shards: Option<usize>,
+    pub adaptive_batch_size: bool,
+    pub factor_common_attributes: bool,
+    pub consistent_sampling: bool,
+    pub monotonic_timestamps: bool,
+    pub extra_layers: TakeOnce<ExtraLayer>,
+    pub auxiliary_guards: TakeOnce<AuxiliaryGuard>,
+    pub inherit_trace_context: bool,
+    pub redact_keys: Vec<String>,
+    pub tenant: Option<TenantOptions>,
+    pub ambient_attributes: Vec<(String, Callback<AmbientAttributeFn>)>,
+    pub authorization: Option<Callback<AuthorizationFn>>,
+    pub on_export: Option<Callback<ExportHookFn>>,
+    #[cfg(feature = "gcp")]
+    pub gcp: bool,
+    pub record_path: Option<std::path::PathBuf>,
+    pub log_file: Option<std::path::PathBuf>,
+    #[cfg(feature = "metrics")]
+    pub metric_views: Vec<Callback<crate::options::MetricViewFn>>,
+    #[cfg(feature = "test-util")]
+    pub id_generator: Option<crate::testing::SharedIdGenerator>,
+    #[cfg(feature = "test-util")]
+    pub test_clock: Option<crate::testing::TestClock>,
+    #[cfg(feature = "test-util")]
+    pub span_capture: Option<crate::testing::SpanCapture>,
+}
+
+impl std::fmt::Debug for ResolvedConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ResolvedConfig {
+            service_name,
+            endpoint,
+            protocol,
+            traces_endpoint,
+            traces_protocol,
+            logs_endpoint,
+            logs_protocol,
+            grpc_http_fallback,
+            grpc_keepalive,
+            grpc_connect_timeout,
+            grpc_adaptive_window,
+            grpc_dns_refresh,
+            dedicated_export_thread,
+            verify_connectivity,
+            headers,
+            grpc_metadata,
+            user_agent,
+            resource_attributes,
+            resource_key_values,
+            traces_export_timeout,
+            logs_export_timeout,
+            metrics_export_timeout,
+            set_global,
+            span_name_mapper,
+            max_attribute_value_length,
+            attribute_renames,
+            span_event_summary,
+            drop_span_filters,
+            event_counters,
+            span_kind_rules,
+            sampling_rules,
+            large_integer_mode,
+            expand_json_fields,
+            console_format,
+            gelf,
+            syslog,
+            statsd,
+            debug_logs_on_error_only,
+            dedup_logs_window,
+            export_budget,
+            max_export_bytes,
+            adaptive_sampling_min_ratio,
+            metrics_temporality,
+            histogram_aggregation,
+            file_rotation,
+            flush_watchdog,
+            cardinality_window,
+            remote_sampling,
+            opamp,
+            receiver,
+            profiling,
+            dead_letter_output,
+            startup_banner,
+            benchmark_mode,
+            skip_unsampled_spans,
+            async_bridge,
+            batch_queue_shards,
+            adaptive_batch_size,
+            factor_common_attributes,
+            consistent_sampling,
+            monotonic_timestamps,
+            extra_layers,
+            auxiliary_guards,
+            inherit_trace_context,
+            redact_keys,
+            tenant,
+            ambient_attributes,
+            authorization,
+            on_export,
+            #[cfg(feature = "gcp")]
+            gcp,
+            record_path,
+            log_file,
+            #[cfg(feature = "metrics")]
+            metric_views,
+            #[cfg(feature = "test-util")]
+            id_generator,
+            #[cfg(feature = "test-util")]
+            test_clock,
+            #[cfg(feature = "test-util")]
+            span_capture,
+        } = self;
+        let mut f = f.debug_struct("ResolvedConfig");
+        f.field("service_name", service_name);
+        f.field("endpoint", endpoint);
+        f.field("protocol", protocol);
+        f.field("traces_endpoint", traces_endpoint);
+        f.field("traces_protocol", traces_protocol);
+        f.field("logs_endpoint", logs_endpoint);
+        f.field("logs_protocol", logs_protocol);
+        f.field("grpc_http_fallback", grpc_http_fallback);
+        f.field("grpc_keepalive", grpc_keepalive);
+        f.field("grpc_connect_timeout", grpc_connect_timeout);
+        f.field("grpc_adaptive_window", grpc_adaptive_window);
+        f.field("grpc_dns_refresh", grpc_dns_refresh);
+        f.field("dedicated_export_thread", dedicated_export_thread);
+        f.field("verify_connectivity", verify_connectivity);
+        f.field("headers", &RedactedMap::all(headers));
+        f.field("grpc_metadata", &RedactedMap::all(grpc_metadata));
+        f.field("user_agent", user_agent);
+        f.field(
+            "resource_attributes",
+            &RedactedMap::matching(resource_attributes, redact_keys),
+        );
+        f.field(
+            "resource_key_values",
+            &RedactedMap::matching(
+                &crate::resource::display_map(resource_key_values),
+                redact_keys,
+            ),
+        );
+        f.field("traces_export_timeout", traces_export_timeout);
+        f.field("logs_export_timeout", logs_export_timeout);
+        f.field("metrics_export_timeout", metrics_export_timeout);
+        f.field("set_global", set_global);
+        f.field("span_name_mapper", span_name_mapper);
+        f.field("max_attribute_value_length", max_attribute_value_length);
+        f.field("attribute_renames", attribute_renames);
+        f.field("span_event_summary", span_event_summary);
+        f.field("drop_span_filters", drop_span_filters);
+        f.field("event_counters", event_counters);
+        f.field("span_kind_rules", span_kind_rules);
+        f.field("sampling_rules", sampling_rules);
+        f.field("large_integer_mode", large_integer_mode);
+        f.field("expand_json_fields", expand_json_fields);
+        f.field("console_format", console_format);
+        f.field("gelf", gelf);
+        f.field("syslog", syslog);
+        f.field("statsd", statsd);
+        f.field("debug_logs_on_error_only", debug_logs_on_error_only);
+        f.field("dedup_logs_window", dedup_logs_window);
+        f.field("export_budget", export_budget);
+        f.field("max_export_bytes", max_export_bytes);
+        f.field("adaptive_sampling_min_ratio", adaptive_sampling_min_ratio);
+        f.field("metrics_temporality", metrics_temporality);
+        f.field("histogram_aggregation", histogram_aggregation);
+        f.field("file_rotation", file_rotation);
+        f.field("flush_watchdog", flush_watchdog);
+        f.field("cardinality_window", cardinality_window);
+        f.field("remote_sampling", remote_sampling);
+        f.field("opamp", opamp);
+        f.field("receiver", receiver);
+        f.field("profiling", profiling);
+        f.field("dead_letter_output", dead_letter_output);
+        f.field("startup_banner", startup_banner);
+        f.field("benchmark_mode", benchmark_mode);
+        f.field("skip_unsampled_spans", skip_unsampled_spans);
+        f.field("async_bridge", async_bridge);
+        f.field("batch_queue_shards", batch_queue_shards);
+        f.field("adaptive_batch_size", adaptive_batch_size);
+        f.field("factor_common_attributes", factor_common_attributes);
+        f.field("consistent_sampling", consistent_sampling);
+        f.field("monotonic_timestamps", monotonic_timestamps);
+        f.field("extra_layers", extra_layers);
+        f.field("auxiliary_guards", auxiliary_guards);
+        f.field("inherit_trace_context", inherit_trace_context);
+        f.field("redact_keys", redact_keys);
+        f.field("tenant", tenant);
+        f.field("ambient_attributes", ambient_attributes);
+        f.field("authorization", authorization);
+        f.field("on_export", on_export);
+        #[cfg(feature = "gcp")]
+        f.field("gcp", gcp);
+        f.field("record_path", record_path);
+        f.field("log_file", log_file);
+        #[cfg(feature = "metrics")]
+        f.field("metric_views", metric_views);
+        #[cfg(feature = "test-util")]
+        f.field("id_generator", id_generator);
+        #[cfg(feature = "test-util")]
+        f.field("test_clock", test_clock);
+        #[cfg(feature = "test-util")]
+        f.field("span_capture", span_capture);
+        f.finish()
+    }
 }
 
 /// Resolve configuration by merging programmatic options, env vars, and defaults.
-pub(crate) fn resolve_config(service_name: &str, opts: &OtelOptions) -> ResolvedConfig {
+///
+/// # Errors
+///
+/// Returns an error if the endpoint is not a usable URL (see [`normalize_endpoint`]).
+pub(crate) fn resolve_config(
+    service_name: &str,
+    opts: &OtelOptions,
+) -> anyhow::Result<ResolvedConfig> {
     let service_name =
         env_var_non_empty("OTEL_SERVICE_NAME").unwrap_or_else(|| service_name.to_owned());
 
-    let protocol = opts
+    if let Some(ref invalid) = opts.invalid_protocol {
+        anyhow::bail!("{invalid}");
+    }
+    let chosen_protocol = opts
         .protocol
-        .or_else(parse_protocol_env)
-        .unwrap_or(Protocol::HttpProtobuf);
-
-    let default_endpoint = match protocol {
-        Protocol::Grpc => DEFAULT_GRPC_ENDPOINT,
-        Protocol::HttpProtobuf | Protocol::HttpJson => DEFAULT_HTTP_ENDPOINT,
-    };
-
-    let endpoint = opts
+        .clone()
+        .or_else(|| parse_protocol_env("OTEL_EXPORTER_OTLP_PROTOCOL"));
+    let explicit_endpoint = opts
         .endpoint
         .clone()
-        .or_else(|| env_var_non_empty("OTEL_EXPORTER_OTLP_ENDPOINT"))
-        .unwrap_or_else(|| default_endpoint.to_owned());
+        .or_else(|| env_var_non_empty("OTEL_EXPORTER_OTLP_ENDPOINT"));
+    // Discovery only looks for an endpoint when none is set, and benchmark mode never
+    // connects
+    let discovered =
+        if opts.discover_endpoint && explicit_endpoint.is_none() && !opts.benchmark_mode {
+            discovery::discover(chosen_protocol.as_ref(), opts.discovery_file.as_deref())
+        } else {
+            None
+        };
+    let (discovered_endpoint, discovered_protocol) = discovered.unzip();
+    let explicit_endpoint = explicit_endpoint.or(discovered_endpoint);
+    let protocol = chosen_protocol
+        .or(discovered_protocol)
+        .unwrap_or(Protocol::HttpProtobuf);
+    // Per-signal protocols fall back to the general one
+    let traces_protocol = opts
+        .traces_protocol
+        .clone()
+        .or_else(|| parse_protocol_env("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL"))
+        .unwrap_or_else(|| protocol.clone());
+    let logs_protocol = opts
+        .logs_protocol
+        .clone()
+        .or_else(|| parse_protocol_env("OTEL_EXPORTER_OTLP_LOGS_PROTOCOL"))
+        .unwrap_or_else(|| protocol.clone());
+    // Without an explicit endpoint each signal gets the default port for its protocol
+    let endpoint_for = |signal_protocol: &Protocol| {
+        let default_endpoint = match signal_protocol {
+            Protocol::Grpc => DEFAULT_GRPC_ENDPOINT,
+            Protocol::HttpProtobuf | Protocol::HttpJson | Protocol::File { .. } => {
+                DEFAULT_HTTP_ENDPOINT
+            }
+            Protocol::Jaeger => DEFAULT_JAEGER_ENDPOINT,
+            Protocol::Zipkin => DEFAULT_ZIPKIN_ENDPOINT,
+            // The connection string names the ingestion endpoint
+            Protocol::AzureMonitor { connection_string } => {
+                let connection = ConnectionString::parse(connection_string)?;
+                return normalize_endpoint(&connection.ingestion_endpoint, signal_protocol);
+            }
+        };
+        normalize_endpoint(
+            explicit_endpoint.as_deref().unwrap_or(default_endpoint),
+            signal_protocol,
+        )
+    };
+    let endpoint = endpoint_for(&protocol)?;
+    let traces_endpoint = if traces_protocol == protocol {
+        endpoint.clone()
+    } else {
+        endpoint_for(&traces_protocol)?
+    };
+    let logs_endpoint = if logs_protocol == protocol {
+        endpoint.clone()
+    } else {
+        endpoint_for(&logs_protocol)?
+    };
 
     let mut headers = parse_headers_env();
     // Programmatic headers take precedence over env var headers
     headers.extend(opts.headers.clone());
+    headers.extend(read_header_sources(&opts.header_sources)?);
+    #[cfg_attr(not(feature = "gcp"), allow(unused_mut))]
+    let mut resource_attributes = opts.resource_attributes.clone();
+    #[cfg(feature = "gcp")]
+    if opts.gcp {
+        apply_gcp_project(&mut headers, &mut resource_attributes);
+    }
 
-    let export_timeout = opts
-        .export_timeout
-        .or_else(parse_timeout_env)
-        .unwrap_or(DEFAULT_EXPORT_TIMEOUT);
+    if let Some(ref invalid) = opts.invalid_export_timeout {
+        anyhow::bail!("Invalid export timeout `{invalid}`: expected e.g. 30s, 500ms or 1m30s");
+    }
+    let event_counters = opts
+        .event_counters
+        .iter()
+        .map(|(selector, metric)| EventCountRule::parse(selector, metric))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(anyhow::Error::msg)?;
 
-    ResolvedConfig {
+    // Programmatic wins; otherwise the per-signal variable overrides the general one
+    let general_timeout = parse_timeout_env("OTEL_EXPORTER_OTLP_TIMEOUT");
+    let export_timeout = |signal_var: &str| {
+        opts.export_timeout
+            .or_else(|| parse_timeout_env(signal_var))
+            .or(general_timeout)
+            .unwrap_or(DEFAULT_EXPORT_TIMEOUT)
+    };
+
+    Ok(ResolvedConfig {
         service_name,
         endpoint,
         protocol,
+        traces_endpoint,
+        traces_protocol,
+        logs_endpoint,
+        logs_protocol,
+        grpc_http_fallback: opts.grpc_http_fallback.unwrap_or(false),
+        grpc_keepalive: opts.grpc_keepalive,
+        grpc_connect_timeout: opts.grpc_connect_timeout,
+        grpc_adaptive_window: opts.grpc_adaptive_window,
+        grpc_dns_refresh: opts.grpc_dns_refresh,
+        dedicated_export_thread: opts.dedicated_export_thread,
+        verify_connectivity: opts.verify_connectivity.unwrap_or_default(),
         headers,
-        resource_attributes: opts.resource_attributes.clone(),
-        export_timeout,
+        grpc_metadata: opts.grpc_metadata.clone(),
+        user_agent: opts
+            .user_agent
+            .as_ref()
+            .map(|app| format!("{app} raccoon-otel/{}", env!("CARGO_PKG_VERSION"))),
+        resource_attributes,
+        resource_key_values: opts.resource_key_values.clone(),
+        traces_export_timeout: export_timeout("OTEL_EXPORTER_OTLP_TRACES_TIMEOUT"),
+        logs_export_timeout: export_timeout("OTEL_EXPORTER_OTLP_LOGS_TIMEOUT"),
+        metrics_export_timeout: export_timeout("OTEL_EXPORTER_OTLP_METRICS_TIMEOUT"),
+        set_global: opts.set_global.unwrap_or(true),
+        span_name_mapper: opts.span_name_mapper.clone(),
+        max_attribute_value_length: opts
+            .max_attribute_value_length
+            .or_else(parse_attribute_value_length_env),
+        attribute_renames: opts.attribute_renames.clone(),
+        span_event_summary: opts.span_event_summary,
+        drop_span_filters: opts.drop_span_filters.clone(),
+        event_counters,
+        span_kind_rules: opts.span_kind_rules.clone(),
+        sampling_rules: opts.sampling_rules.clone(),
+        large_integer_mode: opts.large_integer_mode.unwrap_or_default(),
+        expand_json_fields: opts.expand_json_fields.unwrap_or(false),
+        console_format: opts.console_format.unwrap_or_default(),
+        gelf: opts.gelf.clone(),
+        syslog: opts.syslog.clone(),
+        statsd: opts.statsd.clone(),
+        debug_logs_on_error_only: opts.debug_logs_on_error_only.unwrap_or(false),
+        dedup_logs_window: opts.dedup_logs_window,
+        export_budget: opts.export_budget,
+        max_export_bytes: opts.max_export_bytes,
+        adaptive_sampling_min_ratio: opts.adaptive_sampling_min_ratio,
+        metrics_temporality: opts
+            .metrics_temporality
+            .or_else(parse_temporality_env)
+            .unwrap_or_default(),
+        histogram_aggregation: opts
+            .histogram_aggregation
+            .or_else(parse_histogram_aggregation_env)
+            .unwrap_or_default(),
+        record_path: opts.record_path.clone(),
+        log_file: opts.log_file.clone(),
+        file_rotation: opts
+            .file_rotation
+            .unwrap_or((DEFAULT_FILE_MAX_BYTES, DEFAULT_FILE_MAX_BACKUPS)),
+        flush_watchdog: opts.flush_watchdog,
+        cardinality_window: opts.cardinality_window,
+        remote_sampling: opts.remote_sampling.clone(),
+        opamp: opts.opamp.clone(),
+        receiver: opts.receiver.clone(),
+        profiling: opts.profiling.clone(),
+        dead_letter_output: opts.dead_letter_output.clone(),
+        startup_banner: opts.startup_banner.unwrap_or(false),
+        benchmark_mode: opts.benchmark_mode,
+        skip_unsampled_spans: opts.skip_unsampled_spans,
+        async_bridge: opts.async_bridge,
+        batch_queue_shards: opts.batch_queue_shards,
+        adaptive_batch_size: opts.adaptive_batch_size,
+        factor_common_attributes: opts.factor_common_attributes,
+        consistent_sampling: opts.consistent_sampling,
+        monotonic_timestamps: opts.monotonic_timestamps,
+        extra_layers: opts.extra_layers.clone(),
+        auxiliary_guards: opts.auxiliary_guards.clone(),
+        inherit_trace_context: opts.inherit_trace_context.unwrap_or(true),
+        redact_keys: opts.redact_keys.clone(),
+        tenant: opts.tenant.clone(),
+        ambient_attributes: opts.ambient_attributes.clone(),
+        authorization: opts.authorization.clone(),
+        on_export: opts.on_export.clone(),
+        #[cfg(feature = "gcp")]
+        gcp: opts.gcp,
+        #[cfg(feature = "metrics")]
+        metric_views: opts.metric_views.clone(),
+        #[cfg(feature = "test-util")]
+        id_generator: opts.id_generator.clone(),
+        #[cfg(feature = "test-util")]
+        test_clock: opts.test_clock.clone(),
+        #[cfg(feature = "test-util")]
+        span_capture: opts.span_capture.clone(),
+    })
+}
+
+/// Stamp the Google Cloud project on the resource and bill Telemetry API calls to it,
+/// warning on stderr when it can't be found. Explicit values are kept.
+#[cfg(feature = "gcp")]
+fn apply_gcp_project(
+    headers: &mut HashMap<String, String>,
+    resource_attributes: &mut HashMap<String, String>,
+) {
+    let Some(project_id) = crate::gcp::project_id() else {
+        eprintln!(
+            "raccoon-otel: couldn't find the Google Cloud project ID; set GOOGLE_CLOUD_PROJECT \
+             when running outside Google Cloud"
+        );
+        return;
+    };
+    for key in ["gcp.project_id", "cloud.account.id"] {
+        resource_attributes
+            .entry(key.to_owned())
+            .or_insert_with(|| project_id.clone());
     }
+    resource_attributes
+        .entry("cloud.provider".to_owned())
+        .or_insert_with(|| "gcp".to_owned());
+    headers
+        .entry("x-goog-user-project".to_owned())
+        .or_insert(project_id);
+}
+
+/// Validate the OTLP endpoint and fix common mistakes, warning on stderr about each fix.
+///
+/// - A missing scheme defaults to `http://`
+/// - `grpc://` / `grpcs://` become `http://` / `https://`
+/// - A trailing `/v1/traces`, `/v1/logs` or `/v1/metrics` is removed, since the signal
+///   path is appended per exporter
+///
+/// A port that belongs to the other protocol (4317 for HTTP, 4318 for gRPC) only warns.
+/// `vsock://<cid>:<port>` endpoints are kept as they are, for gRPC only.
+///
+/// # Errors
+///
+/// Returns an error if the endpoint can't be parsed, has no host, or uses a scheme other
+/// than HTTP(S) or vsock.
+pub(crate) fn normalize_endpoint(raw: &str, protocol: &Protocol) -> anyhow::Result<String> {
+    if let Some(addr) = VsockAddr::parse(raw) {
+        let addr = addr.map_err(anyhow::Error::msg)?;
+        if *protocol != Protocol::Grpc {
+            anyhow::bail!("vsock endpoint `{raw}` requires the gRPC protocol, not {protocol:?}");
+        }
+        return Ok(addr.to_string());
+    }
+
+    let trimmed = raw.trim();
+    let with_scheme = match trimmed.split_once("://") {
+        None => {
+            eprintln!("raccoon-otel: OTLP endpoint `{trimmed}` has no scheme, assuming http://");
+            format!("http://{trimmed}")
+        }
+        Some(("grpc", rest)) => {
+            eprintln!(
+                "raccoon-otel: OTLP endpoint `{trimmed}` uses grpc://, using http:// instead"
+            );
+            format!("http://{rest}")
+        }
+        Some(("grpcs", rest)) => {
+            eprintln!(
+                "raccoon-otel: OTLP endpoint `{trimmed}` uses grpcs://, using https:// instead"
+            );
+            format!("https://{rest}")
+        }
+        Some(_) => trimmed.to_owned(),
+    };
+
+    let url =
+        url::Url::parse(&with_scheme).with_context(|| format!("Invalid OTLP endpoint `{raw}`"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        anyhow::bail!(
+            "Invalid OTLP endpoint `{raw}`: unsupported scheme `{}` (expected http or https)",
+            url.scheme()
+        );
+    }
+    let host = url
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .with_context(|| format!("Invalid OTLP endpoint `{raw}`: missing host"))?;
+
+    let mut path = url.path().trim_end_matches('/');
+    for signal_path in ["/v1/traces", "/v1/logs", "/v1/metrics"] {
+        if let Some(base) = path.strip_suffix(signal_path) {
+            eprintln!(
+                "raccoon-otel: OTLP endpoint `{raw}` ends with `{signal_path}`, which is \
+                 appended per signal; using the base URL instead"
+            );
+            path = base;
+            break;
+        }
+    }
+
+    let wrong_port = match protocol {
+        Protocol::Grpc => Some(4318),
+        Protocol::HttpProtobuf | Protocol::HttpJson => Some(4317),
+        Protocol::File { .. }
+        | Protocol::Jaeger
+        | Protocol::Zipkin
+        | Protocol::AzureMonitor { .. } => None,
+    };
+    if let Some(wrong_port) = wrong_port.filter(|&port| url.port() == Some(port)) {
+        eprintln!(
+            "raccoon-otel: OTLP endpoint `{raw}` uses port {wrong_port} but the protocol is \
+             {protocol:?}; the default ports are 4317 for gRPC and 4318 for HTTP"
+        );
+    }
+
+    let port = url.port().map(|p| format!(":{p}")).unwrap_or_default();
+    Ok(format!("{}://{host}{port}{path}", url.scheme()))
 }
 
 fn env_var_non_empty(key: &str) -> Option<String> {
     std::env::var(key).ok().filter(|s| !s.is_empty())
 }
 
-fn parse_protocol_env() -> Option<Protocol> {
-    env_var_non_empty("OTEL_EXPORTER_OTLP_PROTOCOL").and_then(|v| match v.as_str() {
-        "grpc" => Some(Protocol::Grpc),
-        "http/protobuf" => Some(Protocol::HttpProtobuf),
-        "http/json" => Some(Protocol::HttpJson),
-        _ => None,
-    })
+/// Read and parse an env var, warning on stderr when it is set but `parse` rejects it.
+///
+/// `expected` describes the accepted values for the warning.
+fn env_var_parsed<T>(
+    key: &str,
+    expected: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Option<T> {
+    let value = env_var_non_empty(key)?;
+    let parsed = parse(&value);
+    if parsed.is_none() {
+        eprintln!("raccoon-otel: ignoring invalid {key}={value:?} (expected {expected})");
+    }
+    parsed
+}
+
+fn parse_protocol_env(key: &str) -> Option<Protocol> {
+    env_var_parsed(key, "grpc, http/protobuf or http/json", |v| v.parse().ok())
 }
 
 fn parse_headers_env() -> HashMap<String, String> {
     env_var_non_empty("OTEL_EXPORTER_OTLP_HEADERS")
         .map(|val| {
             val.split(',')
+                .filter(|pair| !pair.trim().is_empty())
                 .filter_map(|pair| {
-                    let (key, value) = pair.split_once('=')?;
-                    let key = key.trim();
-                    let value = value.trim();
-                    if key.is_empty() {
+                    let parsed = pair
+                        .split_once('=')
+                        .map(|(key, value)| (key.trim(), value.trim()))
+                        .filter(|(key, _)| !key.is_empty());
+                    let Some((key, value)) = parsed else {
+                        eprintln!(
+                            "raccoon-otel: ignoring invalid entry {pair:?} in \
+                             OTEL_EXPORTER_OTLP_HEADERS (expected key=value)"
+                        );
                         return None;
-                    }
+                    };
                     Some((key.to_owned(), value.to_owned()))
                 })
                 .collect()
@@ -97,10 +686,107 @@ fn parse_headers_env() -> HashMap<String, String> {
         .unwrap_or_default()
 }
 
-fn parse_timeout_env() -> Option<Duration> {
-    env_var_non_empty("OTEL_EXPORTER_OTLP_TIMEOUT")
-        .and_then(|v| v.parse::<u64>().ok())
-        .map(Duration::from_millis)
+/// Read the values of headers set with `header_from_env` / `header_from_file`.
+///
+/// Secrets are read on every call, so calling this again picks up rotated values.
+///
+/// # Errors
+///
+/// Returns an error if a variable is unset or a file can't be read. The error names the
+/// source but never includes a value.
+pub(crate) fn read_header_sources(
+    sources: &[(String, HeaderSource)],
+) -> anyhow::Result<Vec<(String, String)>> {
+    sources
+        .iter()
+        .map(|(name, source)| {
+            let value = match source {
+                HeaderSource::Env(var) => env_var_non_empty(var).with_context(|| {
+                    format!("Header `{name}`: environment variable {var} is not set")
+                })?,
+                HeaderSource::File(path) => std::fs::read_to_string(path)
+                    .with_context(|| format!("Header `{name}`: failed to read {}", path.display()))?
+                    .trim()
+                    .to_owned(),
+            };
+            Ok((name.clone(), value))
+        })
+        .collect()
+}
+
+fn parse_timeout_env(key: &str) -> Option<Duration> {
+    env_var_parsed(
+        key,
+        "milliseconds or a duration such as 30s",
+        parse_duration,
+    )
+}
+
+/// Parse a duration such as `"30s"`, `"500ms"`, `"2m"` or `"1m 30s"`.
+///
+/// Units are `ms`, `s`, `m` and `h`; a bare number is milliseconds, as the OTel spec
+/// defines for timeout variables.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(millis) = value.parse::<u64>() {
+        return Some(Duration::from_millis(millis));
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount: u64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "ms" => Duration::from_millis(1),
+            "s" => Duration::from_secs(1),
+            "m" => Duration::from_secs(60),
+            "h" => Duration::from_secs(3600),
+            _ => return None,
+        };
+        total = total.checked_add(unit.checked_mul(u32::try_from(amount).ok()?)?)?;
+        rest = rest[unit_len..].trim_start();
+    }
+    (!value.is_empty()).then_some(total)
+}
+
+fn parse_attribute_value_length_env() -> Option<usize> {
+    env_var_parsed(
+        "OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT",
+        "a non-negative integer",
+        |v| v.parse::<usize>().ok(),
+    )
+}
+
+fn parse_temporality_env() -> Option<MetricsTemporality> {
+    env_var_parsed(
+        "OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE",
+        "cumulative, delta or lowmemory",
+        |v| match v.to_ascii_lowercase().as_str() {
+            "cumulative" => Some(MetricsTemporality::Cumulative),
+            "delta" => Some(MetricsTemporality::Delta),
+            "lowmemory" => Some(MetricsTemporality::LowMemory),
+            _ => None,
+        },
+    )
+}
+
+fn parse_histogram_aggregation_env() -> Option<HistogramAggregation> {
+    env_var_parsed(
+        "OTEL_EXPORTER_OTLP_METRICS_DEFAULT_HISTOGRAM_AGGREGATION",
+        "explicit_bucket_histogram or base2_exponential_bucket_histogram",
+        |v| match v {
+            "explicit_bucket_histogram" => Some(HistogramAggregation::ExplicitBucket),
+            "base2_exponential_bucket_histogram" => Some(HistogramAggregation::Base2Exponential),
+            _ => None,
+        },
+    )
 }
 
 #[cfg(test)]
@@ -116,8 +802,14 @@ mod tests {
         std::env::remove_var("OTEL_SERVICE_NAME");
         std::env::remove_var("OTEL_EXPORTER_OTLP_ENDPOINT");
         std::env::remove_var("OTEL_EXPORTER_OTLP_PROTOCOL");
+        std::env::remove_var("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL");
+        std::env::remove_var("OTEL_EXPORTER_OTLP_LOGS_PROTOCOL");
         std::env::remove_var("OTEL_EXPORTER_OTLP_HEADERS");
         std::env::remove_var("OTEL_EXPORTER_OTLP_TIMEOUT");
+        std::env::remove_var("OTEL_EXPORTER_OTLP_TRACES_TIMEOUT");
+        std::env::remove_var("OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT");
+        std::env::remove_var("OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE");
+        std::env::remove_var("OTEL_EXPORTER_OTLP_METRICS_DEFAULT_HISTOGRAM_AGGREGATION");
     }
 
     #[test]
@@ -126,14 +818,16 @@ mod tests {
         clear_otel_env();
 
         let opts = OtelOptions::default();
-        let resolved = resolve_config("test-service", &opts);
+        let resolved = resolve_config("test-service", &opts).unwrap();
 
         assert_eq!(resolved.service_name, "test-service");
         assert_eq!(resolved.endpoint, "http://localhost:4318");
         assert_eq!(resolved.protocol, Protocol::HttpProtobuf);
         assert!(resolved.headers.is_empty());
         assert!(resolved.resource_attributes.is_empty());
-        assert_eq!(resolved.export_timeout, Duration::from_secs(30));
+        assert_eq!(resolved.traces_export_timeout, Duration::from_secs(30));
+        assert!(resolved.set_global);
+        assert_eq!(resolved.max_attribute_value_length, None);
     }
 
     #[test]
@@ -148,11 +842,11 @@ mod tests {
             .export_timeout(Duration::from_secs(60))
             .build();
 
-        let resolved = resolve_config("test-service", &opts);
+        let resolved = resolve_config("test-service", &opts).unwrap();
 
         assert_eq!(resolved.endpoint, "http://programmatic:4317");
         assert_eq!(resolved.protocol, Protocol::HttpProtobuf);
-        assert_eq!(resolved.export_timeout, Duration::from_secs(60));
+        assert_eq!(resolved.traces_export_timeout, Duration::from_secs(60));
 
         clear_otel_env();
     }
@@ -171,6 +865,34 @@ mod tests {
         clear_otel_env();
     }
 
+    #[test]
+    fn secret_headers_are_read_from_env_and_files() {
+        let _lock = ENV_LOCK.lock();
+        clear_otel_env();
+        let key_file =
+            std::env::temp_dir().join(format!("raccoon-otel-key-{}", std::process::id()));
+        std::fs::write(&key_file, "file-secret\n").unwrap();
+        std::env::set_var("RACCOON_OTEL_TEST_TOKEN", "Bearer env-secret");
+
+        let opts = OtelOptions::builder()
+            .headers([("Authorization", "placeholder")])
+            .header_from_env("Authorization", "RACCOON_OTEL_TEST_TOKEN")
+            .header_from_file("api-key", &key_file)
+            .build();
+        let resolved = resolve_config("test-service", &opts).unwrap();
+
+        assert_eq!(resolved.headers["Authorization"], "Bearer env-secret");
+        assert_eq!(resolved.headers["api-key"], "file-secret");
+
+        std::env::remove_var("RACCOON_OTEL_TEST_TOKEN");
+        let err = resolve_config("test-service", &opts).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("RACCOON_OTEL_TEST_TOKEN is not set"));
+
+        let _ = std::fs::remove_file(&key_file);
+    }
+
     #[test]
     fn http_protocol_uses_port_4318_default() {
         let _lock = ENV_LOCK.lock();
@@ -180,8 +902,146 @@ mod tests {
             .protocol(Protocol::HttpProtobuf)
             .build();
 
-        let resolved = resolve_config("test-service", &opts);
+        let resolved = resolve_config("test-service", &opts).unwrap();
 
         assert_eq!(resolved.endpoint, "http://localhost:4318");
     }
+
+    #[test]
+    fn attribute_value_length_limit_from_env() {
+        let _lock = ENV_LOCK.lock();
+        clear_otel_env();
+        std::env::set_var("OTEL_ATTRIBUTE_VALUE_LENGTH_LIMIT", "1024");
+
+        let resolved = resolve_config("test-service", &OtelOptions::default()).unwrap();
+
+        assert_eq!(resolved.max_attribute_value_length, Some(1024));
+
+        clear_otel_env();
+    }
+
+    #[test]
+    fn metrics_preferences_from_env() {
+        let _lock = ENV_LOCK.lock();
+        clear_otel_env();
+        std::env::set_var("OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE", "Delta");
+        std::env::set_var(
+            "OTEL_EXPORTER_OTLP_METRICS_DEFAULT_HISTOGRAM_AGGREGATION",
+            "base2_exponential_bucket_histogram",
+        );
+
+        let resolved = resolve_config("test-service", &OtelOptions::default()).unwrap();
+
+        assert_eq!(resolved.metrics_temporality, MetricsTemporality::Delta);
+        assert_eq!(
+            resolved.histogram_aggregation,
+            HistogramAggregation::Base2Exponential
+        );
+
+        clear_otel_env();
+    }
+
+    #[test]
+    fn invalid_env_values_fall_back_to_defaults() {
+        let _lock = ENV_LOCK.lock();
+        clear_otel_env();
+        std::env::set_var("OTEL_EXPORTER_OTLP_PROTOCOL", "http-protobuf");
+        std::env::set_var("OTEL_EXPORTER_OTLP_TIMEOUT", "thirty");
+        std::env::set_var("OTEL_EXPORTER_OTLP_HEADERS", "key1=val1,broken");
+
+        let resolved = resolve_config("test-service", &OtelOptions::default()).unwrap();
+
+        assert_eq!(resolved.protocol, Protocol::HttpProtobuf);
+        assert_eq!(resolved.traces_export_timeout, DEFAULT_EXPORT_TIMEOUT);
+        assert_eq!(resolved.headers.len(), 1);
+
+        clear_otel_env();
+    }
+
+    #[test]
+    fn timeouts_accept_duration_syntax_per_signal() {
+        let _lock = ENV_LOCK.lock();
+        clear_otel_env();
+        std::env::set_var("OTEL_EXPORTER_OTLP_TIMEOUT", "2m");
+        std::env::set_var("OTEL_EXPORTER_OTLP_TRACES_TIMEOUT", "1m 30s");
+
+        let resolved = resolve_config("test-service", &OtelOptions::default()).unwrap();
+
+        assert_eq!(resolved.traces_export_timeout, Duration::from_secs(90));
+        assert_eq!(resolved.logs_export_timeout, Duration::from_secs(120));
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2500"), Some(Duration::from_millis(2500)));
+        assert_eq!(parse_duration("30 seconds"), None);
+
+        clear_otel_env();
+    }
+
+    #[test]
+    fn protocols_resolve_per_signal() {
+        let _lock = ENV_LOCK.lock();
+        clear_otel_env();
+        std::env::set_var("OTEL_EXPORTER_OTLP_LOGS_PROTOCOL", "http/protobuf");
+
+        let opts = OtelOptions::builder().protocol(Protocol::Grpc).build();
+        let resolved = resolve_config("test-service", &opts).unwrap();
+
+        assert_eq!(resolved.traces_protocol, Protocol::Grpc);
+        assert_eq!(resolved.traces_endpoint, "http://localhost:4317");
+        assert_eq!(resolved.logs_protocol, Protocol::HttpProtobuf);
+        assert_eq!(resolved.logs_endpoint, "http://localhost:4318");
+
+        clear_otel_env();
+    }
+
+    #[test]
+    fn protocol_str_parses_spec_names() {
+        let _lock = ENV_LOCK.lock();
+        clear_otel_env();
+
+        for protocol in [Protocol::Grpc, Protocol::HttpProtobuf, Protocol::HttpJson] {
+            assert_eq!(protocol.to_string().parse::<Protocol>(), Ok(protocol));
+        }
+
+        let opts = OtelOptions::builder().protocol_str("http/json").build();
+        let resolved = resolve_config("test-service", &opts).unwrap();
+        assert_eq!(resolved.protocol, Protocol::HttpJson);
+
+        let opts = OtelOptions::builder().protocol_str("http").build();
+        let err = resolve_config("test-service", &opts).unwrap_err();
+        assert!(err.to_string().contains("Invalid OTLP protocol `http`"));
+    }
+
+    #[test]
+    fn endpoint_mistakes_are_corrected() {
+        let http = &Protocol::HttpProtobuf;
+        assert_eq!(
+            normalize_endpoint("collector:4318", http).unwrap(),
+            "http://collector:4318"
+        );
+        assert_eq!(
+            normalize_endpoint("http://collector:4318/v1/traces/", http).unwrap(),
+            "http://collector:4318"
+        );
+        assert_eq!(
+            normalize_endpoint("vsock://2:4317/", &Protocol::Grpc).unwrap(),
+            "vsock://2:4317"
+        );
+        assert_eq!(
+            normalize_endpoint("grpc://collector:4317", &Protocol::Grpc).unwrap(),
+            "http://collector:4317"
+        );
+        assert_eq!(
+            normalize_endpoint("https://otlp.example.com/otel", http).unwrap(),
+            "https://otlp.example.com/otel"
+        );
+    }
+
+    #[test]
+    fn malformed_endpoints_are_rejected() {
+        let http = &Protocol::HttpProtobuf;
+        assert!(normalize_endpoint("http://", http).is_err());
+        assert!(normalize_endpoint("ftp://collector:4318", http).is_err());
+        assert!(normalize_endpoint("vsock://2:4318", http).is_err());
+        assert!(normalize_endpoint("http://collector:99999", http).is_err());
+    }
 }
@@ -0,0 +1,100 @@
+use std::time::SystemTime;
+
+use opentelemetry_sdk::logs::LogBatch;
+use opentelemetry_sdk::trace::SpanData;
+
+use crate::exporters::size::{log_size, span_size};
+
+/// Read-only summary of a batch about to be exported, passed to the
+/// [`on_export`](crate::OtelOptionsBuilder::on_export) hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExportBatch {
+    /// `"spans"` or `"logs"`.
+    pub signal: &'static str,
+    /// Spans or log records in the batch.
+    pub items: usize,
+    /// Encoded size of the batch's items in bytes, as in an uncompressed OTLP protobuf
+    /// request. The request's resource and scope aren't counted.
+    pub estimated_bytes: u64,
+    /// Earliest span start or log record timestamp in the batch.
+    pub first_timestamp: Option<SystemTime>,
+    /// Latest span end or log record timestamp in the batch.
+    pub last_timestamp: Option<SystemTime>,
+}
+
+impl ExportBatch {
+    pub(crate) fn spans(batch: &[SpanData]) -> Self {
+        Self {
+            signal: "spans",
+            items: batch.len(),
+            estimated_bytes: batch.iter().map(span_size).sum(),
+            first_timestamp: batch.iter().map(|span| span.start_time).min(),
+            last_timestamp: batch.iter().map(|span| span.end_time).max(),
+        }
+    }
+
+    pub(crate) fn logs(batch: &LogBatch<'_>) -> Self {
+        let timestamps = || {
+            batch
+                .iter()
+                .filter_map(|(record, _)| record.timestamp().or(record.observed_timestamp()))
+        };
+        Self {
+            signal: "logs",
+            items: batch.iter().count(),
+            estimated_bytes: batch.iter().map(|(record, _)| log_size(record)).sum(),
+            first_timestamp: timestamps().min(),
+            last_timestamp: timestamps().max(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use opentelemetry::trace::{SpanContext, SpanKind, Status};
+    use opentelemetry::InstrumentationScope;
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+
+    use super::*;
+
+    fn span(start: u64, end: u64) -> SpanData {
+        SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: opentelemetry::trace::SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Internal,
+            name: Cow::Borrowed("work"),
+            start_time: UNIX_EPOCH + Duration::from_secs(start),
+            end_time: UNIX_EPOCH + Duration::from_secs(end),
+            attributes: Vec::new(),
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: InstrumentationScope::builder("raccoon-otel").build(),
+        }
+    }
+
+    #[test]
+    fn span_batches_cover_the_earliest_start_to_the_latest_end() {
+        let batch = [span(20, 25), span(10, 30), span(15, 16)];
+        let summary = ExportBatch::spans(&batch);
+        assert_eq!(summary.signal, "spans");
+        assert_eq!(summary.items, 3);
+        assert_eq!(summary.estimated_bytes, 3 * span_size(&batch[0]));
+        assert_eq!(
+            summary.first_timestamp,
+            Some(UNIX_EPOCH + Duration::from_secs(10))
+        );
+        assert_eq!(
+            summary.last_timestamp,
+            Some(UNIX_EPOCH + Duration::from_secs(30))
+        );
+
+        assert_eq!(ExportBatch::spans(&[]).first_timestamp, None);
+    }
+}
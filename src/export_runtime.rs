@@ -0,0 +1,51 @@
+use tokio::runtime::{EnterGuard, Runtime};
+
+use crate::env::ResolvedConfig;
+use crate::options::Protocol;
+
+/// Tokio runtime with one worker on its own OS thread, for
+/// [`dedicated_export_thread`](crate::OtelOptionsBuilder::dedicated_export_thread).
+///
+/// gRPC channels run their connection tasks on the runtime that is current when they are
+/// created, so exporters built while it is entered never touch the application's runtime.
+#[derive(Debug)]
+pub(crate) struct ExportRuntime {
+    runtime: Option<Runtime>,
+}
+
+impl ExportRuntime {
+    /// Start the runtime if it is enabled and some signal exports over gRPC.
+    pub(crate) fn start(config: &ResolvedConfig) -> std::io::Result<Option<Self>> {
+        let uses_grpc = [
+            &config.traces_protocol,
+            &config.logs_protocol,
+            &config.protocol,
+        ]
+        .contains(&&Protocol::Grpc);
+        if !config.dedicated_export_thread || !uses_grpc {
+            return Ok(None);
+        }
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("raccoon-otel-export")
+            .enable_all()
+            .build()?;
+        Ok(Some(Self {
+            runtime: Some(runtime),
+        }))
+    }
+
+    /// Make this the current runtime until the guard is dropped.
+    pub(crate) fn enter(&self) -> Option<EnterGuard<'_>> {
+        self.runtime.as_ref().map(Runtime::enter)
+    }
+}
+
+impl Drop for ExportRuntime {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which panics inside the application's async context
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
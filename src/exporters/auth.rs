@@ -0,0 +1,340 @@
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+#[cfg(feature = "metrics")]
+use opentelemetry_sdk::metrics::data::ResourceMetrics;
+#[cfg(feature = "metrics")]
+use opentelemetry_sdk::metrics::exporter::PushMetricExporter;
+#[cfg(feature = "metrics")]
+use opentelemetry_sdk::metrics::Temporality;
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use super::tenant::ExporterFactory;
+use crate::options::{AuthorizationFn, Callback};
+
+/// GCP tokens are replaced this long before they expire, so no export races the expiry.
+#[cfg(feature = "gcp")]
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Where the `Authorization` header value comes from.
+pub(crate) enum AuthSource {
+    /// Called before each batch.
+    Callback(Callback<AuthorizationFn>),
+    /// The Google Cloud service account's access token from the metadata server.
+    #[cfg(feature = "gcp")]
+    Gcp,
+}
+
+/// The current `Authorization` value from an [`AuthSource`], reusing expiring tokens until
+/// shortly before they expire.
+struct Credentials {
+    source: AuthSource,
+    #[cfg_attr(not(feature = "gcp"), allow(dead_code))]
+    cached: Mutex<Option<(String, Instant)>>,
+}
+
+impl Credentials {
+    fn new(source: AuthSource) -> Self {
+        Self {
+            source,
+            cached: Mutex::new(None),
+        }
+    }
+
+    fn authorization(&self) -> Result<String, OTelSdkError> {
+        match self.source {
+            AuthSource::Callback(ref provider) => Ok((provider.0)()),
+            #[cfg(feature = "gcp")]
+            AuthSource::Gcp => {
+                let mut cached = self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+                if let Some((ref authorization, expires_at)) = *cached {
+                    if Instant::now() + REFRESH_MARGIN < expires_at {
+                        return Ok(authorization.clone());
+                    }
+                }
+                let token = crate::gcp::access_token().map_err(|e| {
+                    OTelSdkError::InternalFailure(format!(
+                        "Failed to authenticate to Google Cloud: {e:#}"
+                    ))
+                })?;
+                let authorization = format!("Bearer {}", token.value);
+                *cached = Some((authorization.clone(), token.expires_at));
+                Ok(authorization)
+            }
+        }
+    }
+}
+
+struct Current<X> {
+    exporter: Arc<X>,
+    authorization: String,
+}
+
+/// An exporter sending a refreshable `Authorization` header, rebuilt whenever the header
+/// value changes.
+pub(crate) struct AuthRefresh<X> {
+    credentials: Credentials,
+    factory: ExporterFactory<X>,
+    current: Mutex<Option<Current<X>>>,
+    resource: Option<Resource>,
+}
+
+impl<X> AuthRefresh<X> {
+    /// `factory` builds an exporter sending the given `Authorization` value.
+    pub(crate) fn new(source: AuthSource, factory: ExporterFactory<X>) -> Self {
+        Self {
+            credentials: Credentials::new(source),
+            factory,
+            current: Mutex::new(None),
+            resource: None,
+        }
+    }
+
+    /// The exporter for the current `Authorization` value, rebuilt (and given the resource
+    /// with `init`) if the value changed.
+    fn exporter(&self, init: impl FnOnce(&mut X, &Resource)) -> Result<Arc<X>, OTelSdkError> {
+        let authorization = self.credentials.authorization()?;
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(ref current) = *current {
+            if current.authorization == authorization {
+                return Ok(Arc::clone(&current.exporter));
+            }
+        }
+
+        let mut exporter = (self.factory)(&authorization).map_err(|e| {
+            OTelSdkError::InternalFailure(format!("Failed to create authenticated exporter: {e:#}"))
+        })?;
+        if let Some(ref resource) = self.resource {
+            init(&mut exporter, resource);
+        }
+        let exporter = Arc::new(exporter);
+        *current = Some(Current {
+            exporter: Arc::clone(&exporter),
+            authorization,
+        });
+        Ok(exporter)
+    }
+
+    fn take(&self) -> Option<Arc<X>> {
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        current.take().map(|current| current.exporter)
+    }
+}
+
+impl<X> fmt::Debug for AuthRefresh<X> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthRefresh").finish_non_exhaustive()
+    }
+}
+
+/// gRPC call credentials: sets the `authorization` metadata of every export call from
+/// [`Credentials`], so tokens rotate without rebuilding the channel.
+#[cfg(feature = "grpc")]
+#[derive(Clone)]
+pub(crate) struct CallCredentials(Arc<Credentials>);
+
+#[cfg(feature = "grpc")]
+impl CallCredentials {
+    pub(crate) fn new(source: AuthSource) -> Self {
+        Self(Arc::new(Credentials::new(source)))
+    }
+}
+
+#[cfg(feature = "grpc")]
+impl tonic::service::Interceptor for CallCredentials {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        let authorization = self
+            .0
+            .authorization()
+            .map_err(|e| tonic::Status::unauthenticated(e.to_string()))?;
+        let value = tonic::metadata::MetadataValue::try_from(authorization).map_err(|_| {
+            tonic::Status::unauthenticated("The authorization value isn't valid gRPC metadata")
+        })?;
+        request.metadata_mut().insert("authorization", value);
+        Ok(request)
+    }
+}
+
+/// Span exporter that sends through a [`AuthRefresh`] exporter when there is one, and through
+/// `inner` otherwise.
+#[derive(Debug)]
+pub(crate) struct AuthSpanExporter<E, X> {
+    inner: E,
+    auth: Option<AuthRefresh<X>>,
+}
+
+impl<E, X> AuthSpanExporter<E, X> {
+    pub(crate) fn new(inner: E, auth: Option<AuthRefresh<X>>) -> Self {
+        Self { inner, auth }
+    }
+}
+
+impl<E: SpanExporter, X: SpanExporter> SpanExporter for AuthSpanExporter<E, X> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        match self.auth {
+            Some(ref auth) => auth.exporter(|e, r| e.set_resource(r))?.export(batch).await,
+            None => self.inner.export(batch).await,
+        }
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        if let Some(mut exporter) = self.auth.as_ref().and_then(AuthRefresh::take) {
+            // No export is in flight during shutdown, so this is the only reference
+            if let Some(exporter) = Arc::get_mut(&mut exporter) {
+                let _ = exporter.shutdown_with_timeout(timeout);
+            }
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(ref mut auth) = self.auth {
+            auth.resource = Some(resource.clone());
+        }
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that sends through a [`AuthRefresh`] exporter when there is one, and through
+/// `inner` otherwise.
+#[derive(Debug)]
+pub(crate) struct AuthLogExporter<E, X> {
+    inner: E,
+    auth: Option<AuthRefresh<X>>,
+}
+
+impl<E, X> AuthLogExporter<E, X> {
+    pub(crate) fn new(inner: E, auth: Option<AuthRefresh<X>>) -> Self {
+        Self { inner, auth }
+    }
+}
+
+impl<E: LogExporter, X: LogExporter> LogExporter for AuthLogExporter<E, X> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        match self.auth {
+            Some(ref auth) => auth.exporter(|e, r| e.set_resource(r))?.export(batch).await,
+            None => self.inner.export(batch).await,
+        }
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        if let Some(exporter) = self.auth.as_ref().and_then(AuthRefresh::take) {
+            let _ = exporter.shutdown_with_timeout(timeout);
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(ref mut auth) = self.auth {
+            auth.resource = Some(resource.clone());
+        }
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Metric exporter that sends through a [`AuthRefresh`] exporter when there is one, and
+/// through `inner` otherwise.
+#[cfg(feature = "metrics")]
+#[derive(Debug)]
+pub(crate) struct AuthMetricExporter<E, X> {
+    inner: E,
+    auth: Option<AuthRefresh<X>>,
+}
+
+#[cfg(feature = "metrics")]
+impl<E, X> AuthMetricExporter<E, X> {
+    pub(crate) fn new(inner: E, auth: Option<AuthRefresh<X>>) -> Self {
+        Self { inner, auth }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl<E: PushMetricExporter, X: PushMetricExporter> PushMetricExporter for AuthMetricExporter<E, X> {
+    async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+        match self.auth {
+            // Metrics carry their resource, so new exporters need no setup
+            Some(ref auth) => auth.exporter(|_, _| {})?.export(metrics).await,
+            None => self.inner.export(metrics).await,
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        if let Some(exporter) = self.auth.as_ref().and_then(AuthRefresh::take) {
+            let _ = exporter.shutdown_with_timeout(timeout);
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn temporality(&self) -> Temporality {
+        // Every exporter is built from the same config
+        self.inner.temporality()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn exporter_is_rebuilt_when_the_authorization_changes() {
+        let token = Arc::new(Mutex::new("Bearer a".to_owned()));
+        let provider = Arc::clone(&token);
+        let builds = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&builds);
+        let auth = AuthRefresh::new(
+            AuthSource::Callback(Callback(Arc::new(move || provider.lock().unwrap().clone()))),
+            Box::new(move |authorization| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(authorization.to_owned())
+            }),
+        );
+
+        assert_eq!(*auth.exporter(|_, _| {}).unwrap(), "Bearer a");
+        assert_eq!(*auth.exporter(|_, _| {}).unwrap(), "Bearer a");
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        *token.lock().unwrap() = "Bearer b".to_owned();
+        assert_eq!(*auth.exporter(|_, _| {}).unwrap(), "Bearer b");
+        assert_eq!(builds.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "grpc")]
+    #[test]
+    fn call_credentials_set_the_authorization_of_each_call() {
+        use tonic::service::Interceptor;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let mut credentials =
+            CallCredentials::new(AuthSource::Callback(Callback(Arc::new(move || {
+                format!("Bearer {}", counter.fetch_add(1, Ordering::SeqCst))
+            }))));
+
+        for expected in ["Bearer 0", "Bearer 1"] {
+            let request = credentials.call(tonic::Request::new(())).unwrap();
+            assert_eq!(request.metadata().get("authorization").unwrap(), expected);
+        }
+
+        let mut invalid =
+            CallCredentials::new(AuthSource::Callback(Callback(Arc::new(|| "\n".to_owned()))));
+        let status = invalid.call(tonic::Request::new(())).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+}
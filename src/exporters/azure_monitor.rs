@@ -0,0 +1,389 @@
+//! Application Insights export over its ingestion ("track") API.
+//!
+//! Application Insights doesn't take OTLP, so spans and log records are mapped to its
+//! telemetry envelopes the way the Azure Monitor OpenTelemetry exporters do: server and
+//! consumer spans become requests, other spans dependencies, log records messages or
+//! exceptions, and `exception` span events exceptions.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use opentelemetry::logs::{AnyValue, Severity};
+use opentelemetry::trace::{SpanId, SpanKind, Status, TraceId};
+use opentelemetry::{Key, Value};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+use serde_json::{json, Map};
+
+use super::http_post::HttpPoster;
+use super::time::format_time;
+use crate::azure_monitor::ConnectionString;
+
+type Properties = Map<String, serde_json::Value>;
+
+/// Envelope fields shared by every item sent to one Application Insights resource.
+#[derive(Debug)]
+struct Ingestion {
+    poster: HttpPoster,
+    instrumentation_key: String,
+    // ai.cloud.role and friends, from the resource
+    tags: Properties,
+}
+
+impl Ingestion {
+    fn new(
+        connection: &ConnectionString,
+        endpoint: &str,
+        headers: HashMap<String, String>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            poster: HttpPoster::new(endpoint, "/v2.1/track", headers, timeout),
+            instrumentation_key: connection.instrumentation_key.clone(),
+            tags: Properties::new(),
+        }
+    }
+
+    /// Map the resource to the cloud role (`[service.namespace.]service.name`) and role
+    /// instance (`service.instance.id`, or `host.name`) Application Insights groups by.
+    fn set_resource(&mut self, resource: &Resource) {
+        let get = |key: &'static str| resource.get(&Key::from_static_str(key));
+        self.tags.clear();
+        if let Some(name) = get("service.name") {
+            let role = match get("service.namespace") {
+                Some(namespace) => format!("{}.{}", namespace.as_str(), name.as_str()),
+                None => name.as_str().into_owned(),
+            };
+            self.tags.insert("ai.cloud.role".into(), role.into());
+        }
+        if let Some(instance) = get("service.instance.id").or_else(|| get("host.name")) {
+            self.tags
+                .insert("ai.cloud.roleInstance".into(), instance.as_str().into());
+        }
+        self.tags.insert(
+            "ai.internal.sdkVersion".into(),
+            concat!("raccoon-otel:", env!("CARGO_PKG_VERSION")).into(),
+        );
+    }
+
+    fn envelope(
+        &self,
+        name: &str,
+        time: SystemTime,
+        operation: Option<(TraceId, SpanId)>,
+        base_data: serde_json::Value,
+    ) -> serde_json::Value {
+        let mut tags = self.tags.clone();
+        if let Some((trace_id, parent_id)) = operation {
+            tags.insert("ai.operation.id".into(), trace_id.to_string().into());
+            if parent_id != SpanId::INVALID {
+                tags.insert("ai.operation.parentId".into(), parent_id.to_string().into());
+            }
+        }
+        json!({
+            "name": format!("Microsoft.ApplicationInsights.{name}"),
+            "time": format_time(time),
+            "iKey": self.instrumentation_key,
+            "tags": tags,
+            "data": { "baseType": format!("{name}Data"), "baseData": base_data },
+        })
+    }
+
+    fn send(&self, envelopes: Vec<serde_json::Value>) -> OTelSdkResult {
+        if envelopes.is_empty() {
+            return Ok(());
+        }
+        let body = serde_json::Value::Array(envelopes).to_string();
+        self.poster.post("application/json", body.into_bytes())
+    }
+}
+
+/// Sends spans to Application Insights as requests and dependencies.
+#[derive(Debug)]
+pub(crate) struct AzureMonitorSpanExporter {
+    ingestion: Ingestion,
+}
+
+impl AzureMonitorSpanExporter {
+    pub(crate) fn new(
+        connection: &ConnectionString,
+        endpoint: &str,
+        headers: HashMap<String, String>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            ingestion: Ingestion::new(connection, endpoint, headers, timeout),
+        }
+    }
+}
+
+impl SpanExporter for AzureMonitorSpanExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let mut envelopes = Vec::with_capacity(batch.len());
+        for span in &batch {
+            span_envelopes(&self.ingestion, span, &mut envelopes);
+        }
+        self.ingestion.send(envelopes)
+    }
+
+    fn shutdown_with_timeout(&mut self, _timeout: Duration) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.ingestion.set_resource(resource);
+    }
+}
+
+/// Sends log records to Application Insights as messages and exceptions.
+#[derive(Debug)]
+pub(crate) struct AzureMonitorLogExporter {
+    ingestion: Ingestion,
+}
+
+impl AzureMonitorLogExporter {
+    pub(crate) fn new(
+        connection: &ConnectionString,
+        endpoint: &str,
+        headers: HashMap<String, String>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            ingestion: Ingestion::new(connection, endpoint, headers, timeout),
+        }
+    }
+}
+
+impl LogExporter for AzureMonitorLogExporter {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let envelopes = batch
+            .iter()
+            .map(|(record, _)| {
+                let properties: Properties = record
+                    .attributes_iter()
+                    .map(|(key, value)| (key.to_string(), any_value_string(value).into()))
+                    .collect();
+                let message = record.body().map(any_value_string).unwrap_or_default();
+                let time = record
+                    .timestamp()
+                    .or_else(|| record.observed_timestamp())
+                    .unwrap_or_else(SystemTime::now);
+                let operation = record.trace_context().map(|cx| (cx.trace_id, cx.span_id));
+                let (name, data) = message_data(message, record.severity_number(), properties);
+                self.ingestion.envelope(name, time, operation, data)
+            })
+            .collect();
+        self.ingestion.send(envelopes)
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.ingestion.set_resource(resource);
+    }
+}
+
+fn span_envelopes(ingestion: &Ingestion, span: &SpanData, out: &mut Vec<serde_json::Value>) {
+    let trace_id = span.span_context.trace_id();
+    let span_id = span.span_context.span_id();
+    let attribute = |key: &str| {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.as_str().into_owned())
+    };
+    let properties: Properties = span
+        .attributes
+        .iter()
+        .map(|kv| (kv.key.to_string(), kv.value.as_str().into_owned().into()))
+        .collect();
+    let success = !matches!(span.status, Status::Error { .. });
+    let duration = span
+        .end_time
+        .duration_since(span.start_time)
+        .unwrap_or_default();
+    let status_code =
+        attribute("http.response.status_code").or_else(|| attribute("http.status_code"));
+
+    let (name, data) = match span.span_kind {
+        SpanKind::Server | SpanKind::Consumer => {
+            let mut data = json!({
+                "ver": 2,
+                "id": span_id.to_string(),
+                "name": span.name,
+                "duration": format_duration(duration),
+                "success": success,
+                "responseCode": status_code.unwrap_or_else(|| "0".to_owned()),
+                "properties": properties,
+            });
+            if let Some(url) = attribute("url.full") {
+                data["url"] = url.into();
+            }
+            ("Request", data)
+        }
+        SpanKind::Client | SpanKind::Producer | SpanKind::Internal => {
+            let dependency_type = if attribute("http.request.method").is_some() {
+                Some("HTTP".to_owned())
+            } else {
+                attribute("db.system.name")
+                    .or_else(|| attribute("db.system"))
+                    .or_else(|| attribute("rpc.system"))
+                    .or_else(|| attribute("messaging.system"))
+            };
+            let target = attribute("server.address").map(|host| match attribute("server.port") {
+                Some(port) => format!("{host}:{port}"),
+                None => host,
+            });
+            let mut data = json!({
+                "ver": 2,
+                "id": span_id.to_string(),
+                "name": span.name,
+                "duration": format_duration(duration),
+                "success": success,
+                "type": dependency_type.unwrap_or_else(|| "InProc".to_owned()),
+                "properties": properties,
+            });
+            if let Some(code) = status_code {
+                data["resultCode"] = code.into();
+            }
+            if let Some(target) = target {
+                data["target"] = target.into();
+            }
+            if let Some(command) = attribute("url.full").or_else(|| attribute("db.query.text")) {
+                data["data"] = command.into();
+            }
+            ("RemoteDependency", data)
+        }
+    };
+    let mut envelope = ingestion.envelope(
+        name,
+        span.start_time,
+        Some((trace_id, span.parent_span_id)),
+        data,
+    );
+    if name == "Request" {
+        // Operations are listed by their request's name in the portal
+        envelope["tags"]["ai.operation.name"] = span.name.as_ref().into();
+    }
+    out.push(envelope);
+
+    // Exception events are sent as exceptions inside the span's operation; other events
+    // are already exported as log records
+    for event in span.events.iter().filter(|event| event.name == "exception") {
+        let properties: Properties = event
+            .attributes
+            .iter()
+            .map(|kv| (kv.key.to_string(), json_string(&kv.value)))
+            .collect();
+        let (name, data) = message_data(event.name.to_string(), None, properties);
+        out.push(ingestion.envelope(name, event.timestamp, Some((trace_id, span_id)), data));
+    }
+}
+
+/// `MessageData`, or `ExceptionData` when `exception.*` attributes are present.
+fn message_data(
+    message: String,
+    severity: Option<Severity>,
+    mut properties: Properties,
+) -> (&'static str, serde_json::Value) {
+    let severity_level = severity.map(severity_level);
+    let mut take = |key: &str| match properties.remove(key) {
+        Some(serde_json::Value::String(s)) => Some(s),
+        _ => None,
+    };
+    let exception_type = take("exception.type");
+    let exception_message = take("exception.message");
+    let stack = take("exception.stacktrace");
+
+    if exception_type.is_none() && exception_message.is_none() {
+        let mut data = json!({ "ver": 2, "message": message, "properties": properties });
+        if let Some(level) = severity_level {
+            data["severityLevel"] = level.into();
+        }
+        return ("Message", data);
+    }
+    let mut exception = json!({
+        "typeName": exception_type.unwrap_or_else(|| "Exception".to_owned()),
+        "message": exception_message.unwrap_or(message),
+        "hasFullStack": stack.is_some(),
+    });
+    if let Some(stack) = stack {
+        exception["stack"] = stack.into();
+    }
+    let mut data = json!({ "ver": 2, "exceptions": [exception], "properties": properties });
+    if let Some(level) = severity_level {
+        data["severityLevel"] = level.into();
+    }
+    ("Exception", data)
+}
+
+/// Application Insights `SeverityLevel`: Verbose 0, Information 1, Warning 2, Error 3,
+/// Critical 4.
+fn severity_level(severity: Severity) -> u8 {
+    match severity as i32 {
+        ..=8 => 0,
+        9..=12 => 1,
+        13..=16 => 2,
+        17..=20 => 3,
+        _ => 4,
+    }
+}
+
+fn json_string(value: &Value) -> serde_json::Value {
+    value.as_str().into_owned().into()
+}
+
+fn any_value_string(value: &AnyValue) -> String {
+    match value {
+        AnyValue::String(s) => s.to_string(),
+        AnyValue::Int(i) => i.to_string(),
+        AnyValue::Double(d) => d.to_string(),
+        AnyValue::Boolean(b) => b.to_string(),
+        other => format!("{other:?}"),
+    }
+}
+
+/// `d.hh:mm:ss.ffffff`, the duration format of the track API.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!(
+        "{}.{:02}:{:02}:{:02}.{:06}",
+        secs / 86_400,
+        secs / 3_600 % 24,
+        secs / 60 % 60,
+        secs % 60,
+        duration.subsec_micros()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    #[test]
+    fn track_api_formats() {
+        assert_eq!(
+            format_time(UNIX_EPOCH + Duration::from_micros(1_709_210_096_123_456)),
+            "2024-02-29T12:34:56.123456Z"
+        );
+        assert_eq!(
+            format_duration(Duration::from_micros(90_061_000_250)),
+            "1.01:01:01.000250"
+        );
+
+        let mut properties = Properties::new();
+        properties.insert("exception.type".into(), "io::Error".into());
+        properties.insert("exception.message".into(), "disk full".into());
+        let (name, data) = message_data("write failed".into(), Some(Severity::Error), properties);
+        assert_eq!(name, "Exception");
+        assert_eq!(data["severityLevel"], 3);
+        assert_eq!(data["exceptions"][0]["typeName"], "io::Error");
+        assert_eq!(data["exceptions"][0]["message"], "disk full");
+    }
+}
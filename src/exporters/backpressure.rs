@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::samplers::adaptive::AdaptiveRatio;
+
+/// Span exporter that reports backpressure to an [`AdaptiveRatio`].
+///
+/// An export counts as pressured if it fails (including collector throttling responses)
+/// or if the batch is full, which means spans are queueing faster than they're exported.
+/// Sits right behind the batch processor, whose `max_export_batch_size` is `full_batch`,
+/// so it sees whole batches rather than the chunks they're exported in.
+#[derive(Debug)]
+pub(crate) struct BackpressureSpanExporter<E> {
+    inner: E,
+    ratio: Option<Arc<AdaptiveRatio>>,
+    full_batch: usize,
+}
+
+impl<E> BackpressureSpanExporter<E> {
+    pub(crate) fn new(inner: E, ratio: Option<Arc<AdaptiveRatio>>, full_batch: usize) -> Self {
+        Self {
+            inner,
+            ratio,
+            full_batch,
+        }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for BackpressureSpanExporter<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let full = batch.len() >= self.full_batch;
+        let result = self.inner.export(batch).await;
+        if let Some(ref ratio) = self.ratio {
+            ratio.observe(full || result.is_err());
+        }
+        result
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::time::UNIX_EPOCH;
+
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status};
+    use opentelemetry::InstrumentationScope;
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+
+    use super::*;
+    use crate::exporters::batch_size::{AdaptiveBatchSpanExporter, MAX_BATCH_SIZE};
+
+    #[derive(Debug)]
+    struct Accept;
+
+    impl SpanExporter for Accept {
+        async fn export(&self, _: Vec<SpanData>) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    fn spans(count: usize) -> Vec<SpanData> {
+        let span = SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Internal,
+            name: Cow::Borrowed("work"),
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+            attributes: Vec::new(),
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: InstrumentationScope::builder("test").build(),
+        };
+        vec![span; count]
+    }
+
+    #[tokio::test]
+    async fn adaptive_batch_chunks_are_not_backpressure() {
+        let ratio = Arc::new(AdaptiveRatio::new(0.1));
+        let exporter = BackpressureSpanExporter::new(
+            AdaptiveBatchSpanExporter::new(Accept, true, Duration::from_secs(10)),
+            Some(Arc::clone(&ratio)),
+            MAX_BATCH_SIZE,
+        );
+
+        // Steady load: exported in chunks of the adapted size, but never a full batch
+        for _ in 0..10 {
+            exporter.export(spans(600)).await.unwrap();
+        }
+        assert_eq!(ratio.ratio(), 1.0);
+
+        for _ in 0..3 {
+            exporter.export(spans(MAX_BATCH_SIZE)).await.unwrap();
+        }
+        assert_eq!(ratio.ratio(), 0.5);
+    }
+}
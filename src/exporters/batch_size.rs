@@ -0,0 +1,215 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use opentelemetry::InstrumentationScope;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter, SdkLogRecord};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::processors::queue::queue_size;
+
+/// Smallest batch the size adapts down to.
+const MIN_BATCH_SIZE: usize = 32;
+
+/// Largest batch the size adapts up to, and the batch processors' `max_export_batch_size`
+/// with [`adaptive_batch_size`](crate::OtelOptionsBuilder::adaptive_batch_size), capped
+/// at their queue size by the SDK.
+pub(crate) const MAX_BATCH_SIZE: usize = 1024;
+
+/// The SDK's default `max_export_batch_size`, where the size starts.
+const START_BATCH_SIZE: usize = 512;
+
+/// The batch span processor's `max_export_batch_size`: [`MAX_BATCH_SIZE`] with
+/// [`adaptive_batch_size`](crate::OtelOptionsBuilder::adaptive_batch_size), or
+/// `OTEL_BSP_MAX_EXPORT_BATCH_SIZE`, capped at the queue size as the SDK does.
+pub(crate) fn span_batch_size(adaptive: bool) -> usize {
+    let size = if adaptive {
+        MAX_BATCH_SIZE
+    } else {
+        std::env::var("OTEL_BSP_MAX_EXPORT_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(START_BATCH_SIZE)
+    };
+    size.min(queue_size("OTEL_BSP_MAX_QUEUE_SIZE"))
+}
+
+/// An export taking at least this long is dominated by the round trip, so fewer, larger
+/// batches move more telemetry.
+const SLOW_EXPORT: Duration = Duration::from_millis(250);
+
+/// An export taking less than this means the collector keeps up, so smaller batches reach
+/// it sooner at no cost.
+const FAST_EXPORT: Duration = Duration::from_millis(50);
+
+/// Batch size adapted to how long exports take: grown by half after a slow export, cut by
+/// a quarter after a fast one, and halved after a failure or an export taking half the
+/// export timeout, so batches stay clear of it.
+#[derive(Debug)]
+struct BatchSize {
+    size: AtomicUsize,
+    timeout: Duration,
+}
+
+impl BatchSize {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            size: AtomicUsize::new(START_BATCH_SIZE),
+            timeout,
+        }
+    }
+
+    fn get(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    fn observe(&self, latency: Duration, ok: bool) {
+        let size = self.get();
+        let next = if !ok || latency >= self.timeout / 2 {
+            size / 2
+        } else if latency >= SLOW_EXPORT {
+            size + size / 2
+        } else if latency < FAST_EXPORT {
+            size - size / 4
+        } else {
+            size
+        };
+        self.size.store(
+            next.clamp(MIN_BATCH_SIZE, MAX_BATCH_SIZE),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Time `export` and adapt the size to it.
+    async fn timed(
+        &self,
+        export: impl std::future::Future<Output = OTelSdkResult>,
+    ) -> OTelSdkResult {
+        let start = Instant::now();
+        let result = export.await;
+        self.observe(start.elapsed(), result.is_ok());
+        result
+    }
+}
+
+/// Span exporter that exports each batch in chunks of a size adapted to the observed
+/// export latency, for [`adaptive_batch_size`](crate::OtelOptionsBuilder::adaptive_batch_size).
+/// A pass-through when off.
+///
+/// Chunks are exported in order; the first failure is returned and the remaining chunks
+/// are dropped, as the whole batch would have been.
+#[derive(Debug)]
+pub(crate) struct AdaptiveBatchSpanExporter<E> {
+    inner: E,
+    size: Option<BatchSize>,
+}
+
+impl<E> AdaptiveBatchSpanExporter<E> {
+    pub(crate) fn new(inner: E, enabled: bool, timeout: Duration) -> Self {
+        Self {
+            inner,
+            size: enabled.then(|| BatchSize::new(timeout)),
+        }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for AdaptiveBatchSpanExporter<E> {
+    async fn export(&self, mut batch: Vec<SpanData>) -> OTelSdkResult {
+        let Some(ref size) = self.size else {
+            return self.inner.export(batch).await;
+        };
+        loop {
+            let chunk = size.get();
+            if batch.len() <= chunk {
+                return size.timed(self.inner.export(batch)).await;
+            }
+            let rest = batch.split_off(chunk);
+            size.timed(self.inner.export(batch)).await?;
+            batch = rest;
+        }
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that exports each batch in chunks of a size adapted to the observed
+/// export latency, for [`adaptive_batch_size`](crate::OtelOptionsBuilder::adaptive_batch_size).
+/// A pass-through when off.
+#[derive(Debug)]
+pub(crate) struct AdaptiveBatchLogExporter<E> {
+    inner: E,
+    size: Option<BatchSize>,
+}
+
+impl<E> AdaptiveBatchLogExporter<E> {
+    pub(crate) fn new(inner: E, enabled: bool, timeout: Duration) -> Self {
+        Self {
+            inner,
+            size: enabled.then(|| BatchSize::new(timeout)),
+        }
+    }
+}
+
+impl<E: LogExporter> LogExporter for AdaptiveBatchLogExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let Some(ref size) = self.size else {
+            return self.inner.export(batch).await;
+        };
+        let records: Vec<(&SdkLogRecord, &InstrumentationScope)> = batch.iter().collect();
+        let mut rest = &records[..];
+        loop {
+            let (chunk, next) = rest.split_at(rest.len().min(size.get()));
+            size.timed(self.inner.export(LogBatch::new(chunk))).await?;
+            if next.is_empty() {
+                return Ok(());
+            }
+            rest = next;
+        }
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_grows_with_slow_exports_and_backs_off_near_the_timeout() {
+        let size = BatchSize::new(Duration::from_secs(10));
+        size.observe(Duration::from_millis(100), true);
+        assert_eq!(size.get(), START_BATCH_SIZE);
+
+        size.observe(Duration::from_secs(1), true);
+        assert_eq!(size.get(), 768);
+        size.observe(Duration::from_secs(1), true);
+        assert_eq!(size.get(), MAX_BATCH_SIZE);
+
+        size.observe(Duration::from_secs(6), true);
+        assert_eq!(size.get(), 512);
+        size.observe(Duration::from_millis(100), false);
+        assert_eq!(size.get(), 256);
+
+        for _ in 0..20 {
+            size.observe(Duration::from_millis(10), true);
+        }
+        assert_eq!(size.get(), MIN_BATCH_SIZE);
+    }
+}
@@ -0,0 +1,251 @@
+use std::future::Future;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use opentelemetry::logs::Severity;
+use opentelemetry::trace::Status;
+use opentelemetry::InstrumentationScope;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter, SdkLogRecord};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use super::size::{log_size, span_size};
+use crate::options::ExportBudget;
+use crate::stats::{Signal, StatsCounters};
+
+/// Minimum time between two "budget exceeded" warnings on stderr.
+const WARN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Token bucket shared by the span and log exporters, so both signals draw from one budget.
+///
+/// The bucket holds at most one second's worth of budget, refilled continuously.
+#[derive(Debug)]
+pub(crate) struct BudgetLimiter {
+    budget: ExportBudget,
+    state: Mutex<BucketState>,
+    stats: Arc<StatsCounters>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    refilled: Instant,
+    warned: Option<Instant>,
+}
+
+impl BudgetLimiter {
+    pub(crate) fn new(budget: ExportBudget, stats: Arc<StatsCounters>) -> Self {
+        Self {
+            budget,
+            state: Mutex::new(BucketState {
+                tokens: budget_rate(budget),
+                refilled: Instant::now(),
+                warned: None,
+            }),
+            stats,
+        }
+    }
+
+    /// Decide which items of a batch fit in the budget.
+    ///
+    /// `items` holds `(priority, estimated_bytes)` per item. In bytes mode the highest
+    /// priority items are admitted first; in batches mode the batch is all or nothing.
+    fn admit(&self, items: &[(i32, u64)]) -> Vec<bool> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let rate = budget_rate(self.budget);
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.refilled).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * rate).min(rate);
+        state.refilled = now;
+
+        match self.budget {
+            ExportBudget::BatchesPerSecond(_) => {
+                let fits = state.tokens >= 1.0;
+                if fits {
+                    state.tokens -= 1.0;
+                }
+                vec![fits; items.len()]
+            }
+            ExportBudget::BytesPerSecond(_) => {
+                let mut order: Vec<usize> = (0..items.len()).collect();
+                order.sort_by_key(|&i| std::cmp::Reverse(items[i].0));
+
+                let mut keep = vec![false; items.len()];
+                for i in order {
+                    let cost = items[i].1 as f64;
+                    if cost <= state.tokens {
+                        state.tokens -= cost;
+                        keep[i] = true;
+                    }
+                }
+                keep
+            }
+        }
+    }
+
+    fn record_drops(&self, signal: Signal, dropped: u64) {
+        if dropped == 0 {
+            return;
+        }
+        self.stats
+            .dropped_over_budget(signal)
+            .fetch_add(dropped, Ordering::Relaxed);
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        if state
+            .warned
+            .is_some_and(|at| now.duration_since(at) < WARN_INTERVAL)
+        {
+            return;
+        }
+        state.warned = Some(now);
+        drop(state);
+
+        let totals = self.stats.snapshot();
+        eprintln!(
+            "raccoon-otel: export budget exceeded, dropped {dropped} {} \
+             (total dropped: {} spans, {} logs)",
+            signal.name(),
+            totals.spans_dropped_over_budget,
+            totals.logs_dropped_over_budget
+        );
+    }
+}
+
+fn budget_rate(budget: ExportBudget) -> f64 {
+    match budget {
+        ExportBudget::BytesPerSecond(bytes) => bytes as f64,
+        ExportBudget::BatchesPerSecond(batches) => f64::from(batches),
+    }
+}
+
+/// Span exporter that drops spans exceeding the export budget before they reach `inner`.
+///
+/// Spans with an error status are kept in preference to the rest.
+#[derive(Debug)]
+pub(crate) struct BudgetSpanExporter<E> {
+    inner: E,
+    limiter: Option<Arc<BudgetLimiter>>,
+}
+
+impl<E> BudgetSpanExporter<E> {
+    pub(crate) fn new(inner: E, limiter: Option<Arc<BudgetLimiter>>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for BudgetSpanExporter<E> {
+    fn export(&self, mut batch: Vec<SpanData>) -> impl Future<Output = OTelSdkResult> + Send {
+        if let Some(ref limiter) = self.limiter {
+            let items: Vec<_> = batch
+                .iter()
+                .map(|span| (span_priority(span), span_size(span)))
+                .collect();
+            let mut keep = limiter.admit(&items).into_iter();
+            let before = batch.len();
+            batch.retain(|_| keep.next().unwrap_or(false));
+            limiter.record_drops(Signal::Spans, (before - batch.len()) as u64);
+        }
+
+        async move {
+            if batch.is_empty() {
+                return Ok(());
+            }
+            self.inner.export(batch).await
+        }
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that drops records exceeding the export budget before they reach `inner`.
+///
+/// Higher-severity records are kept in preference to lower-severity ones.
+#[derive(Debug)]
+pub(crate) struct BudgetLogExporter<E> {
+    inner: E,
+    limiter: Option<Arc<BudgetLimiter>>,
+}
+
+impl<E> BudgetLogExporter<E> {
+    pub(crate) fn new(inner: E, limiter: Option<Arc<BudgetLimiter>>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+impl<E: LogExporter> LogExporter for BudgetLogExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let mut records: Vec<(&SdkLogRecord, &InstrumentationScope)> = batch.iter().collect();
+        if let Some(ref limiter) = self.limiter {
+            let items: Vec<_> = records
+                .iter()
+                .map(|(record, _)| (log_priority(record), log_size(record)))
+                .collect();
+            let mut keep = limiter.admit(&items).into_iter();
+            let before = records.len();
+            records.retain(|_| keep.next().unwrap_or(false));
+            limiter.record_drops(Signal::Logs, (before - records.len()) as u64);
+        }
+
+        if records.is_empty() {
+            return Ok(());
+        }
+        self.inner.export(LogBatch::new(&records)).await
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+fn span_priority(span: &SpanData) -> i32 {
+    match span.status {
+        Status::Error { .. } => 1,
+        _ => 0,
+    }
+}
+
+fn log_priority(record: &SdkLogRecord) -> i32 {
+    record.severity_number().unwrap_or(Severity::Info) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(budget: ExportBudget) -> BudgetLimiter {
+        BudgetLimiter::new(budget, Arc::default())
+    }
+
+    #[test]
+    fn bytes_budget_keeps_highest_priority_first() {
+        let limiter = limiter(ExportBudget::BytesPerSecond(250));
+        // Only two of the three items fit; the lowest priority one is dropped
+        let keep = limiter.admit(&[(5, 100), (17, 100), (9, 100)]);
+        assert_eq!(keep, vec![false, true, true]);
+    }
+
+    #[test]
+    fn batches_budget_is_all_or_nothing() {
+        let limiter = limiter(ExportBudget::BatchesPerSecond(1));
+        assert_eq!(limiter.admit(&[(0, 10), (0, 10)]), vec![true, true]);
+        assert_eq!(limiter.admit(&[(0, 10), (0, 10)]), vec![false, false]);
+    }
+}
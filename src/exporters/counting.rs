@@ -0,0 +1,92 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+#[cfg(feature = "metrics")]
+use std::time::Duration;
+
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+#[cfg(feature = "metrics")]
+use opentelemetry_sdk::metrics::{
+    data::ResourceMetrics, exporter::PushMetricExporter, Temporality,
+};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+
+use crate::stats::StatsCounters;
+
+/// Span exporter that counts spans and drops them, for
+/// [`benchmark_mode`](crate::OtelOptionsBuilder::benchmark_mode).
+#[derive(Debug)]
+pub(crate) struct CountingSpanExporter {
+    stats: Arc<StatsCounters>,
+}
+
+impl CountingSpanExporter {
+    pub(crate) fn new(stats: Arc<StatsCounters>) -> Self {
+        Self { stats }
+    }
+}
+
+impl SpanExporter for CountingSpanExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        self.stats
+            .spans_exported
+            .fetch_add(batch.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Log exporter that counts log records and drops them, for
+/// [`benchmark_mode`](crate::OtelOptionsBuilder::benchmark_mode).
+#[derive(Debug)]
+pub(crate) struct CountingLogExporter {
+    stats: Arc<StatsCounters>,
+}
+
+impl CountingLogExporter {
+    pub(crate) fn new(stats: Arc<StatsCounters>) -> Self {
+        Self { stats }
+    }
+}
+
+impl LogExporter for CountingLogExporter {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        self.stats
+            .logs_exported
+            .fetch_add(batch.iter().count() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Metric exporter that drops every collection, for
+/// [`benchmark_mode`](crate::OtelOptionsBuilder::benchmark_mode).
+#[cfg(feature = "metrics")]
+#[derive(Debug)]
+pub(crate) struct DiscardMetricExporter {
+    temporality: Temporality,
+}
+
+#[cfg(feature = "metrics")]
+impl DiscardMetricExporter {
+    pub(crate) fn new(temporality: Temporality) -> Self {
+        Self { temporality }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl PushMetricExporter for DiscardMetricExporter {
+    async fn export(&self, _metrics: &ResourceMetrics) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn temporality(&self) -> Temporality {
+        self.temporality
+    }
+}
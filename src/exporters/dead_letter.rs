@@ -0,0 +1,334 @@
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use opentelemetry::logs::AnyValue;
+use opentelemetry::trace::Status;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter, SdkLogRecord};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use super::time::format_time;
+use crate::health::ExportHealth;
+use crate::options::DeadLetterOutput;
+
+/// Where telemetry the endpoint didn't take is written, one line per span or log record.
+/// Shared by both signals, so their lines interleave in export order.
+pub(crate) struct DeadLetterWriter {
+    output: Mutex<Box<dyn std::io::Write + Send>>,
+    destination: String,
+    // Whether lines are being written, so the switch is announced once per outage
+    writing: AtomicBool,
+}
+
+impl std::fmt::Debug for DeadLetterWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeadLetterWriter")
+            .field("destination", &self.destination)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DeadLetterWriter {
+    pub(crate) fn open(output: &DeadLetterOutput) -> anyhow::Result<Self> {
+        let (output, destination): (Box<dyn std::io::Write + Send>, _) = match output {
+            DeadLetterOutput::Stderr => (Box::new(std::io::stderr()), "stderr".to_owned()),
+            DeadLetterOutput::File(path) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create the directory for {}", path.display())
+                    })?;
+                }
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| {
+                        format!("Failed to open the dead letter file {}", path.display())
+                    })?;
+                (Box::new(file), path.display().to_string())
+            }
+        };
+        Ok(Self {
+            output: Mutex::new(output),
+            destination,
+            writing: AtomicBool::new(false),
+        })
+    }
+
+    fn write(&self, lines: &[String]) {
+        if !self.writing.swap(true, Ordering::Relaxed) {
+            eprintln!(
+                "raccoon-otel: the OTLP endpoint keeps failing, writing telemetry to {} \
+                 until it recovers",
+                self.destination
+            );
+        }
+        let mut output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        for line in lines {
+            let _ = writeln!(output, "{line}");
+        }
+        let _ = output.flush();
+    }
+
+    fn recovered(&self) {
+        self.writing.store(false, Ordering::Relaxed);
+    }
+
+    /// The lines to write if the coming export fails: only built while the endpoint is
+    /// unhealthy, so healthy exports cost nothing extra.
+    fn lines_for(
+        writer: Option<&Self>,
+        health: &ExportHealth,
+        lines: impl FnOnce() -> Vec<String>,
+    ) -> Option<Vec<String>> {
+        writer.filter(|_| !health.is_healthy()).map(|_| lines())
+    }
+
+    /// Write `lines` here if the export failed, or note that the endpoint took it.
+    fn settle(writer: Option<&Self>, result: &OTelSdkResult, lines: Option<Vec<String>>) {
+        let Some(writer) = writer else {
+            return;
+        };
+        match (result, lines) {
+            (Ok(()), _) => writer.recovered(),
+            (Err(_), Some(lines)) => writer.write(&lines),
+            (Err(_), None) => {}
+        }
+    }
+}
+
+/// Span exporter that writes batches to a [`DeadLetterWriter`] instead of dropping them
+/// once the endpoint is unhealthy (see [`ExportHealth`]).
+#[derive(Debug)]
+pub(crate) struct DeadLetterSpanExporter<E> {
+    inner: E,
+    health: Arc<ExportHealth>,
+    writer: Option<Arc<DeadLetterWriter>>,
+}
+
+impl<E> DeadLetterSpanExporter<E> {
+    pub(crate) fn new(
+        inner: E,
+        health: Arc<ExportHealth>,
+        writer: Option<Arc<DeadLetterWriter>>,
+    ) -> Self {
+        Self {
+            inner,
+            health,
+            writer,
+        }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for DeadLetterSpanExporter<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let writer = self.writer.as_deref();
+        let lines = DeadLetterWriter::lines_for(writer, &self.health, || {
+            batch.iter().map(span_line).collect()
+        });
+        let result = self.inner.export(batch).await;
+        DeadLetterWriter::settle(writer, &result, lines);
+        result
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that writes batches to a [`DeadLetterWriter`] instead of dropping them
+/// once the endpoint is unhealthy (see [`ExportHealth`]).
+#[derive(Debug)]
+pub(crate) struct DeadLetterLogExporter<E> {
+    inner: E,
+    health: Arc<ExportHealth>,
+    writer: Option<Arc<DeadLetterWriter>>,
+}
+
+impl<E> DeadLetterLogExporter<E> {
+    pub(crate) fn new(
+        inner: E,
+        health: Arc<ExportHealth>,
+        writer: Option<Arc<DeadLetterWriter>>,
+    ) -> Self {
+        Self {
+            inner,
+            health,
+            writer,
+        }
+    }
+}
+
+impl<E: LogExporter> LogExporter for DeadLetterLogExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let writer = self.writer.as_deref();
+        let lines = DeadLetterWriter::lines_for(writer, &self.health, || {
+            batch.iter().map(|(record, _)| log_line(record)).collect()
+        });
+        let result = self.inner.export(batch).await;
+        DeadLetterWriter::settle(writer, &result, lines);
+        result
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// `<start> span <trace id> <span id> <name> <duration>ms [parent=..] [error=..] key=value..`
+fn span_line(span: &SpanData) -> String {
+    let duration = span
+        .end_time
+        .duration_since(span.start_time)
+        .unwrap_or_default();
+    let mut line = format!(
+        "{} span {} {} {} {:.3}ms",
+        format_time(span.start_time),
+        span.span_context.trace_id(),
+        span.span_context.span_id(),
+        quoted(&span.name),
+        duration.as_secs_f64() * 1000.0
+    );
+    if span.parent_span_id != opentelemetry::trace::SpanId::INVALID {
+        push_field(&mut line, "parent", &span.parent_span_id.to_string());
+    }
+    if let Status::Error { ref description } = span.status {
+        push_field(&mut line, "error", description);
+    }
+    for attribute in &span.attributes {
+        push_field(&mut line, attribute.key.as_str(), &attribute.value.as_str());
+    }
+    line
+}
+
+/// `<time> log <severity> [<trace id> <span id>] <body> key=value..`
+fn log_line(record: &SdkLogRecord) -> String {
+    let time = record
+        .timestamp()
+        .or_else(|| record.observed_timestamp())
+        .map_or_else(|| "-".to_owned(), format_time);
+    let severity = record
+        .severity_text()
+        .map(str::to_owned)
+        .or_else(|| {
+            record
+                .severity_number()
+                .map(|s| format!("{s:?}").to_uppercase())
+        })
+        .unwrap_or_else(|| "-".to_owned());
+    let mut line = format!("{time} log {severity}");
+    if let Some(context) = record.trace_context() {
+        let _ = write!(line, " {} {}", context.trace_id, context.span_id);
+    }
+    let body = record.body().map(any_value).unwrap_or_default();
+    let _ = write!(line, " {}", quoted(&body));
+    for (key, value) in record.attributes_iter() {
+        push_field(&mut line, key.as_str(), &any_value(value));
+    }
+    line
+}
+
+fn any_value(value: &AnyValue) -> String {
+    match value {
+        AnyValue::String(s) => s.to_string(),
+        AnyValue::Int(i) => i.to_string(),
+        AnyValue::Double(d) => d.to_string(),
+        AnyValue::Boolean(b) => b.to_string(),
+        other => format!("{other:?}"),
+    }
+}
+
+fn push_field(line: &mut String, key: &str, value: &str) {
+    let _ = write!(line, " {key}={}", quoted(value));
+}
+
+/// `value` as is, or debug-quoted if it's empty or would break the line into more words.
+fn quoted(value: &str) -> String {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') {
+        format!("{value:?}")
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::time::UNIX_EPOCH;
+
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, TraceFlags, TraceId, TraceState};
+    use opentelemetry::{InstrumentationScope, KeyValue};
+    use opentelemetry_sdk::error::OTelSdkError;
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+
+    use super::*;
+
+    #[test]
+    fn spans_are_written_one_line_each() {
+        let start = UNIX_EPOCH + Duration::from_secs(1);
+        let span = SpanData {
+            span_context: SpanContext::new(
+                TraceId::from(1),
+                SpanId::from(2),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: SpanId::from(3),
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Server,
+            name: Cow::Borrowed("GET /users"),
+            start_time: start,
+            end_time: start + Duration::from_millis(5),
+            attributes: vec![
+                KeyValue::new("http.response.status_code", 500),
+                KeyValue::new("user", ""),
+            ],
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::error("db timed out"),
+            instrumentation_scope: InstrumentationScope::builder("raccoon-otel").build(),
+        };
+        let line = span_line(&span);
+        assert_eq!(
+            line,
+            "1970-01-01T00:00:01.000000Z span 00000000000000000000000000000001 \
+             0000000000000002 \"GET /users\" 5.000ms parent=0000000000000003 \
+             error=\"db timed out\" http.response.status_code=500 user=\"\""
+        );
+
+        let path = std::env::temp_dir()
+            .join(format!("raccoon-otel-dead-letter-{}", std::process::id()))
+            .join("telemetry.log");
+        let writer = DeadLetterWriter::open(&DeadLetterOutput::File(path.clone())).unwrap();
+        let health = ExportHealth::default();
+        let failed = OTelSdkError::InternalFailure("connection refused".to_owned());
+        // Healthy endpoints keep their failures to themselves
+        assert!(DeadLetterWriter::lines_for(Some(&writer), &health, Vec::new).is_none());
+        for _ in 0..3 {
+            health.record("spans", Err(&failed));
+        }
+        let lines = DeadLetterWriter::lines_for(Some(&writer), &health, || vec![line.clone()]);
+        DeadLetterWriter::settle(Some(&writer), &Err(failed), lines);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{line}\n"));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::Context;
+use tonic::transport::channel::Change;
+use tonic::transport::{Channel, Endpoint, Uri};
+
+/// Pending address changes the balancer can hold before the resolver waits.
+const CHANGE_BUFFER: usize = 64;
+
+/// A channel balancing requests across every address `uri`'s host resolves to, resolving
+/// it again every `interval` so replicas that come and go are picked up.
+///
+/// `endpoint_for` builds the tuned endpoint for one address; the original authority is
+/// kept, so `:authority` and TLS still name the host. Resolution runs on a task that ends
+/// once the channel is dropped.
+///
+/// # Errors
+///
+/// Returns an error if `uri` is not a valid URI or has no host.
+pub(crate) fn balanced_channel(
+    uri: &str,
+    interval: Duration,
+    endpoint_for: impl Fn(&str) -> anyhow::Result<Endpoint> + Send + 'static,
+) -> anyhow::Result<Channel> {
+    let origin: Uri = uri
+        .parse()
+        .with_context(|| format!("Invalid gRPC endpoint `{uri}`"))?;
+    let host = origin
+        .host()
+        .with_context(|| format!("Invalid gRPC endpoint `{uri}`: missing host"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_owned();
+    let scheme = origin.scheme_str().unwrap_or("http").to_owned();
+    let port = origin
+        .port_u16()
+        .unwrap_or(if scheme == "https" { 443 } else { 80 });
+    let endpoint_at = move |addr: SocketAddr| {
+        endpoint_for(&format!("{scheme}://{addr}"))
+            .map(|endpoint| endpoint.origin(origin.clone()))
+            .inspect_err(|e| eprintln!("raccoon-otel: skipping collector address {addr}: {e:#}"))
+            .ok()
+    };
+
+    let (channel, changes) = Channel::balance_channel::<SocketAddr>(CHANGE_BUFFER);
+    tokio::spawn(async move {
+        let mut current = HashSet::new();
+        let mut failing = false;
+        while !changes.is_closed() {
+            match tokio::net::lookup_host((host.as_str(), port)).await {
+                Ok(addrs) => {
+                    failing = false;
+                    let resolved: HashSet<_> = addrs.collect();
+                    for change in address_changes(&current, &resolved, &endpoint_at) {
+                        if changes.send(change).await.is_err() {
+                            return;
+                        }
+                    }
+                    // An empty answer keeps the last known replicas rather than none
+                    if !resolved.is_empty() {
+                        current = resolved;
+                    }
+                }
+                Err(e) if !failing => {
+                    failing = true;
+                    eprintln!(
+                        "raccoon-otel: WARNING: failed to resolve gRPC endpoint host `{host}`, \
+                         keeping {} known address(es): {e}",
+                        current.len()
+                    );
+                }
+                Err(_) => {}
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+    Ok(channel)
+}
+
+/// The balancer changes that take it from the `current` addresses to the `resolved` ones.
+/// An empty `resolved` set changes nothing.
+fn address_changes<E>(
+    current: &HashSet<SocketAddr>,
+    resolved: &HashSet<SocketAddr>,
+    endpoint_at: impl Fn(SocketAddr) -> Option<E>,
+) -> Vec<Change<SocketAddr, E>> {
+    if resolved.is_empty() {
+        return Vec::new();
+    }
+    let removed = current
+        .difference(resolved)
+        .map(|&addr| Change::Remove(addr));
+    let added = resolved
+        .difference(current)
+        .filter_map(|&addr| endpoint_at(addr).map(|endpoint| Change::Insert(addr, endpoint)));
+    removed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_follow_the_resolved_addresses() {
+        let addr = |last: u8| SocketAddr::from(([10, 0, 0, last], 4317));
+        let current = HashSet::from([addr(1), addr(2)]);
+        let resolved = HashSet::from([addr(2), addr(3)]);
+        let changes = address_changes(&current, &resolved, |addr| Some(addr.to_string()));
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|change| match change {
+                Change::Insert(addr, endpoint) => format!("+{addr} {endpoint}"),
+                Change::Remove(addr) => format!("-{addr}"),
+            })
+            .collect();
+        assert_eq!(summary, ["-10.0.0.1:4317", "+10.0.0.3:4317 10.0.0.3:4317"]);
+
+        assert!(address_changes(&current, &HashSet::new(), |_| Some(())).is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use opentelemetry::{InstrumentationScope, Key, KeyValue};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+/// Span exporter that moves attributes every span of a scope in the batch shares, with the
+/// same value, onto the scope, so the OTLP request carries them once per scope instead of
+/// once per span. For
+/// [`factor_common_attributes`](crate::OtelOptionsBuilder::factor_common_attributes); a
+/// pass-through when off.
+///
+/// Lossy: OTLP keeps scope and span attributes apart, and backends don't merge them, so
+/// the factored attributes are no longer span attributes once exported. Only used in front
+/// of the OTLP exporters, which send each distinct scope once. The resource is left alone:
+/// it's shared by every batch and identifies the service.
+#[derive(Debug)]
+pub(crate) struct FactorSpanExporter<E> {
+    inner: E,
+    enabled: bool,
+}
+
+impl<E> FactorSpanExporter<E> {
+    pub(crate) fn new(inner: E, enabled: bool) -> Self {
+        Self { inner, enabled }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for FactorSpanExporter<E> {
+    async fn export(&self, mut batch: Vec<SpanData>) -> OTelSdkResult {
+        if self.enabled {
+            factor(&mut batch);
+        }
+        self.inner.export(batch).await
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Move the attributes shared by every span of a scope onto a copy of the scope. Scopes
+/// with a single span in the batch are left as they are.
+fn factor(batch: &mut [SpanData]) {
+    let mut scopes: HashMap<InstrumentationScope, Vec<usize>> = HashMap::new();
+    for (i, span) in batch.iter().enumerate() {
+        scopes
+            .entry(span.instrumentation_scope.clone())
+            .or_default()
+            .push(i);
+    }
+    for (scope, spans) in scopes {
+        if spans.len() < 2 {
+            continue;
+        }
+        let common = common_attributes(&scope, spans.iter().map(|&i| &batch[i]));
+        if common.is_empty() {
+            continue;
+        }
+        let keys: HashSet<Key> = common.iter().map(|kv| kv.key.clone()).collect();
+        let scope = with_attributes(&scope, common);
+        for i in spans {
+            let span = &mut batch[i];
+            span.attributes.retain(|kv| !keys.contains(&kv.key));
+            span.instrumentation_scope = scope.clone();
+        }
+    }
+}
+
+/// Attributes set exactly once, with the same value, on every span, and not already on
+/// the scope.
+fn common_attributes<'a>(
+    scope: &InstrumentationScope,
+    mut spans: impl Iterator<Item = &'a SpanData>,
+) -> Vec<KeyValue> {
+    let Some(first) = spans.next() else {
+        return Vec::new();
+    };
+    let set_once = |span: &SpanData, kv: &KeyValue| {
+        let mut same_key = span.attributes.iter().filter(|other| other.key == kv.key);
+        same_key.next() == Some(kv) && same_key.next().is_none()
+    };
+    let mut common: Vec<KeyValue> = first
+        .attributes
+        .iter()
+        .filter(|kv| set_once(first, kv) && !scope.attributes().any(|s| s.key == kv.key))
+        .cloned()
+        .collect();
+    for span in spans {
+        common.retain(|kv| set_once(span, kv));
+        if common.is_empty() {
+            break;
+        }
+    }
+    common
+}
+
+fn with_attributes(
+    scope: &InstrumentationScope,
+    attributes: Vec<KeyValue>,
+) -> InstrumentationScope {
+    let mut builder = InstrumentationScope::builder(scope.name().to_owned())
+        .with_attributes(scope.attributes().cloned().chain(attributes));
+    if let Some(version) = scope.version() {
+        builder = builder.with_version(version.to_owned());
+    }
+    if let Some(schema_url) = scope.schema_url() {
+        builder = builder.with_schema_url(schema_url.to_owned());
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::time::UNIX_EPOCH;
+
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status};
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue as ProtoKeyValue};
+    use opentelemetry_proto::tonic::trace::v1::ScopeSpans;
+    use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+
+    use super::*;
+
+    fn span(scope: &InstrumentationScope, attributes: Vec<KeyValue>) -> SpanData {
+        SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Internal,
+            name: Cow::Borrowed("work"),
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+            attributes,
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: scope.clone(),
+        }
+    }
+
+    /// The scopes of the OTLP request the batch is encoded into, by name.
+    fn encoded(batch: Vec<SpanData>) -> Vec<ScopeSpans> {
+        let resource = Resource::builder_empty().build();
+        let mut scopes = group_spans_by_resource_and_scope(batch, &(&resource).into())
+            .remove(0)
+            .scope_spans;
+        scopes.sort_by(|a, b| {
+            a.scope
+                .as_ref()
+                .unwrap()
+                .name
+                .cmp(&b.scope.as_ref().unwrap().name)
+        });
+        scopes
+    }
+
+    fn keys(attributes: &[ProtoKeyValue]) -> Vec<&str> {
+        attributes.iter().map(|kv| kv.key.as_str()).collect()
+    }
+
+    fn string(value: &str) -> Option<AnyValue> {
+        Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value.into())),
+        })
+    }
+
+    #[test]
+    fn shared_attributes_are_encoded_as_scope_attributes_instead_of_span_attributes() {
+        let app = InstrumentationScope::builder("app")
+            .with_version("1.0")
+            .with_attributes([KeyValue::new("tenant", "acme")])
+            .build();
+        let db = InstrumentationScope::builder("db").build();
+        let request = |i: i64| {
+            span(
+                &app,
+                vec![
+                    KeyValue::new("request.id", "r-42"),
+                    KeyValue::new("http.method", "GET"),
+                    KeyValue::new("tenant", "acme"),
+                    KeyValue::new("i", i),
+                ],
+            )
+        };
+        let mut batch = vec![
+            request(0),
+            request(1),
+            request(2),
+            // Alone in its scope, so left as it is
+            span(&db, vec![KeyValue::new("request.id", "r-42")]),
+        ];
+        // Differs on one span, so stays on every span
+        batch[2].attributes[1] = KeyValue::new("http.method", "POST");
+
+        factor(&mut batch);
+        let scopes = encoded(batch);
+
+        // One scope per original scope: the app spans share the rebuilt one
+        assert_eq!(scopes.len(), 2);
+        let (app, db) = (&scopes[0], &scopes[1]);
+        let scope = app.scope.as_ref().unwrap();
+        assert_eq!(scope.version, "1.0");
+        assert_eq!(keys(&scope.attributes), ["tenant", "request.id"]);
+        assert_eq!(scope.attributes[1].value, string("r-42"));
+        // The factored attribute is gone from the spans: queries on span attributes miss it
+        assert_eq!(app.spans.len(), 3);
+        for span in &app.spans {
+            // Already on the scope, so not factored again but kept on the spans
+            assert_eq!(keys(&span.attributes), ["http.method", "tenant", "i"]);
+        }
+        assert!(db.scope.as_ref().unwrap().attributes.is_empty());
+        assert_eq!(keys(&db.spans[0].attributes), ["request.id"]);
+    }
+}
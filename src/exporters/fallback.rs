@@ -0,0 +1,214 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use super::vsock::VsockAddr;
+use crate::options::Protocol;
+
+/// No export has completed yet; the first failure switches to the fallback.
+const PROBING: u8 = 0;
+/// The primary exporter worked once and is used from now on.
+const PRIMARY: u8 = 1;
+/// The primary exporter failed its first export; the fallback is used from now on.
+const FALLBACK: u8 = 2;
+
+/// Endpoint for the HTTP exporter that backs up a gRPC one, or `None` when no
+/// fallback applies.
+///
+/// The default gRPC port 4317 is swapped for the HTTP port 4318; any other port is kept,
+/// since it most likely points at a proxy serving both. vsock endpoints have no HTTP
+/// counterpart.
+pub(crate) fn http_fallback_endpoint(
+    enabled: bool,
+    protocol: &Protocol,
+    endpoint: &str,
+) -> Option<String> {
+    if !enabled || *protocol != Protocol::Grpc || VsockAddr::parse(endpoint).is_some() {
+        return None;
+    }
+    let mut url = url::Url::parse(endpoint).ok()?;
+    if url.port() == Some(4317) {
+        url.set_port(Some(4318)).ok()?;
+    }
+    Some(url.as_str().trim_end_matches('/').to_owned())
+}
+
+/// Tracks whether the primary or fallback exporter is in use.
+#[derive(Debug)]
+struct FallbackState {
+    state: AtomicU8,
+    signal: &'static str,
+}
+
+impl FallbackState {
+    fn new(signal: &'static str) -> Self {
+        Self {
+            state: AtomicU8::new(PROBING),
+            signal,
+        }
+    }
+
+    fn current(&self) -> u8 {
+        self.state.load(Ordering::Relaxed)
+    }
+
+    /// Record the outcome of the first primary export. Returns `true` when the caller
+    /// should switch to the fallback.
+    fn settle(&self, primary_failed: bool) -> bool {
+        let next = if primary_failed { FALLBACK } else { PRIMARY };
+        let switched = self
+            .state
+            .compare_exchange(PROBING, next, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok();
+        if switched && primary_failed {
+            eprintln!(
+                "raccoon-otel: first gRPC {} export failed, falling back to HTTP/protobuf",
+                self.signal
+            );
+        }
+        primary_failed && (switched || self.current() == FALLBACK)
+    }
+}
+
+/// Span exporter that switches to `fallback` for good if `primary` fails its first export.
+#[derive(Debug)]
+pub(crate) struct FallbackSpanExporter<E> {
+    primary: E,
+    fallback: Option<E>,
+    state: FallbackState,
+}
+
+impl<E> FallbackSpanExporter<E> {
+    pub(crate) fn new(primary: E, fallback: Option<E>) -> Self {
+        Self {
+            primary,
+            fallback,
+            state: FallbackState::new("span"),
+        }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for FallbackSpanExporter<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let Some(ref fallback) = self.fallback else {
+            return self.primary.export(batch).await;
+        };
+        match self.state.current() {
+            PRIMARY => self.primary.export(batch).await,
+            FALLBACK => fallback.export(batch).await,
+            _ => {
+                let result = self.primary.export(batch.clone()).await;
+                if self.state.settle(result.is_err()) {
+                    return fallback.export(batch).await;
+                }
+                result
+            }
+        }
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        if let Some(ref mut fallback) = self.fallback {
+            let _ = fallback.shutdown_with_timeout(timeout);
+        }
+        self.primary.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        if let Some(ref mut fallback) = self.fallback {
+            let _ = fallback.force_flush();
+        }
+        self.primary.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(ref mut fallback) = self.fallback {
+            fallback.set_resource(resource);
+        }
+        self.primary.set_resource(resource);
+    }
+}
+
+/// Log exporter that switches to `fallback` for good if `primary` fails its first export.
+#[derive(Debug)]
+pub(crate) struct FallbackLogExporter<E> {
+    primary: E,
+    fallback: Option<E>,
+    state: FallbackState,
+}
+
+impl<E> FallbackLogExporter<E> {
+    pub(crate) fn new(primary: E, fallback: Option<E>) -> Self {
+        Self {
+            primary,
+            fallback,
+            state: FallbackState::new("log"),
+        }
+    }
+}
+
+impl<E: LogExporter> LogExporter for FallbackLogExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let Some(ref fallback) = self.fallback else {
+            return self.primary.export(batch).await;
+        };
+        match self.state.current() {
+            PRIMARY => self.primary.export(batch).await,
+            FALLBACK => fallback.export(batch).await,
+            _ => {
+                let records: Vec<_> = batch.iter().collect();
+                let result = self.primary.export(LogBatch::new(&records)).await;
+                if self.state.settle(result.is_err()) {
+                    return fallback.export(LogBatch::new(&records)).await;
+                }
+                result
+            }
+        }
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        if let Some(ref fallback) = self.fallback {
+            let _ = fallback.shutdown_with_timeout(timeout);
+        }
+        self.primary.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(ref mut fallback) = self.fallback {
+            fallback.set_resource(resource);
+        }
+        self.primary.set_resource(resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_endpoint_swaps_default_grpc_port() {
+        assert_eq!(
+            http_fallback_endpoint(true, &Protocol::Grpc, "http://collector:4317").as_deref(),
+            Some("http://collector:4318")
+        );
+        assert_eq!(
+            http_fallback_endpoint(true, &Protocol::Grpc, "https://otel.example.com").as_deref(),
+            Some("https://otel.example.com")
+        );
+        assert_eq!(
+            http_fallback_endpoint(false, &Protocol::Grpc, "http://collector:4317"),
+            None
+        );
+        assert_eq!(
+            http_fallback_endpoint(true, &Protocol::HttpProtobuf, "http://collector:4318"),
+            None
+        );
+        assert_eq!(
+            http_fallback_endpoint(true, &Protocol::Grpc, "vsock://2:4317"),
+            None
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::Duration;
+
+use anyhow::Context;
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
+use opentelemetry_proto::transform::logs::tonic::group_logs_by_resource_and_scope;
+use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use super::otlp_json::{logs_json, traces_json};
+
+/// Writers by path, so signals configured with the same file append to one handle.
+static WRITERS: OnceLock<Mutex<HashMap<PathBuf, Weak<OtlpFileWriter>>>> = OnceLock::new();
+
+/// Appends OTLP/JSON lines to a file, rotating it once it reaches `max_bytes`.
+///
+/// Rotated files are renamed `<path>.1` (newest) to `<path>.<max_backups>` (oldest);
+/// older ones are deleted.
+#[derive(Debug)]
+pub(crate) struct OtlpFileWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    max_backups: usize,
+    file: Mutex<(File, u64)>,
+}
+
+impl OtlpFileWriter {
+    /// The writer for `path`, opening the file for appending unless a live writer for it
+    /// already exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be opened.
+    pub(crate) fn shared(path: &Path, rotation: (u64, usize)) -> anyhow::Result<Arc<Self>> {
+        let mut writers = WRITERS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(writer) = writers.get(path).and_then(Weak::upgrade) {
+            return Ok(writer);
+        }
+        let (max_bytes, max_backups) = rotation;
+        let file = open(path)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let writer = Arc::new(Self {
+            path: path.to_owned(),
+            max_bytes,
+            max_backups,
+            file: Mutex::new((file, size)),
+        });
+        writers.insert(path.to_owned(), Arc::downgrade(&writer));
+        Ok(writer)
+    }
+
+    fn write_line(&self, doc: &serde_json::Value) -> OTelSdkResult {
+        let mut line = serde_json::to_vec(doc)
+            .map_err(|e| OTelSdkError::InternalFailure(format!("JSON encoding failed: {e}")))?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if file.1 > 0 && file.1 + line.len() as u64 > self.max_bytes {
+            *file = (self.rotate().map_err(|e| self.failure(e))?, 0);
+        }
+        file.0.write_all(&line).map_err(|e| self.failure(e))?;
+        file.1 += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shift the backups up by one, move the current file to `<path>.1`, and open a new one.
+    fn rotate(&self) -> std::io::Result<File> {
+        let backup = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{n}"));
+            PathBuf::from(name)
+        };
+        if self.max_backups == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(backup(self.max_backups));
+            for n in (1..self.max_backups).rev() {
+                let from = backup(n);
+                if from.exists() {
+                    std::fs::rename(from, backup(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, backup(1))?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+    }
+
+    fn failure(&self, err: std::io::Error) -> OTelSdkError {
+        OTelSdkError::InternalFailure(format!(
+            "failed to write to `{}`: {err}",
+            self.path.display()
+        ))
+    }
+
+    fn flush(&self) -> OTelSdkResult {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.0.flush().map_err(|e| self.failure(e))
+    }
+}
+
+fn open(path: &Path) -> anyhow::Result<File> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory `{}`", dir.display()))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open OTLP file `{}`", path.display()))
+}
+
+/// Span exporter that writes each batch as one OTLP/JSON line.
+#[derive(Debug)]
+pub(crate) struct FileSpanExporter {
+    writer: Arc<OtlpFileWriter>,
+    resource: ResourceAttributesWithSchema,
+}
+
+impl FileSpanExporter {
+    pub(crate) fn new(writer: Arc<OtlpFileWriter>) -> Self {
+        Self {
+            writer,
+            resource: ResourceAttributesWithSchema::default(),
+        }
+    }
+}
+
+impl SpanExporter for FileSpanExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let request = ExportTraceServiceRequest {
+            resource_spans: group_spans_by_resource_and_scope(batch, &self.resource),
+        };
+        self.writer.write_line(&traces_json(&request))
+    }
+
+    fn shutdown_with_timeout(&mut self, _timeout: Duration) -> OTelSdkResult {
+        self.writer.flush()
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.writer.flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource.into();
+    }
+}
+
+/// Log exporter that writes each batch as one OTLP/JSON line.
+#[derive(Debug)]
+pub(crate) struct FileLogExporter {
+    writer: Arc<OtlpFileWriter>,
+    resource: ResourceAttributesWithSchema,
+}
+
+impl FileLogExporter {
+    pub(crate) fn new(writer: Arc<OtlpFileWriter>) -> Self {
+        Self {
+            writer,
+            resource: ResourceAttributesWithSchema::default(),
+        }
+    }
+}
+
+impl LogExporter for FileLogExporter {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let request = ExportLogsServiceRequest {
+            resource_logs: group_logs_by_resource_and_scope(batch, &self.resource),
+        };
+        self.writer.write_line(&logs_json(&request))
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+        self.writer.flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_rotate_by_size_and_keep_max_backups() {
+        let dir = std::env::temp_dir().join(format!("raccoon-otel-file-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("otlp.jsonl");
+
+        let writer = OtlpFileWriter::shared(&path, (20, 2)).unwrap();
+        assert!(Arc::ptr_eq(
+            &writer,
+            &OtlpFileWriter::shared(&path, (20, 2)).unwrap()
+        ));
+        for n in 0..4 {
+            writer
+                .write_line(&serde_json::json!({ "n": n, "pad": "xx" }))
+                .unwrap();
+        }
+
+        let read = |p: &Path| std::fs::read_to_string(p).unwrap();
+        assert_eq!(read(&path), "{\"n\":3,\"pad\":\"xx\"}\n");
+        assert_eq!(
+            read(&dir.join("otlp.jsonl.1")),
+            "{\"n\":2,\"pad\":\"xx\"}\n"
+        );
+        assert_eq!(
+            read(&dir.join("otlp.jsonl.2")),
+            "{\"n\":1,\"pad\":\"xx\"}\n"
+        );
+        assert!(!dir.join("otlp.jsonl.3").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::health::ExportHealth;
+
+/// Span exporter that records the outcome of every export in [`ExportHealth`].
+#[derive(Debug)]
+pub(crate) struct HealthSpanExporter<E> {
+    inner: E,
+    health: Arc<ExportHealth>,
+}
+
+impl<E> HealthSpanExporter<E> {
+    pub(crate) fn new(inner: E, health: Arc<ExportHealth>) -> Self {
+        Self { inner, health }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for HealthSpanExporter<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let result = self.inner.export(batch).await;
+        self.health.record("spans", result.as_ref().map(|_| ()));
+        result
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that records the outcome of every export in [`ExportHealth`].
+#[derive(Debug)]
+pub(crate) struct HealthLogExporter<E> {
+    inner: E,
+    health: Arc<ExportHealth>,
+}
+
+impl<E> HealthLogExporter<E> {
+    pub(crate) fn new(inner: E, health: Arc<ExportHealth>) -> Self {
+        Self { inner, health }
+    }
+}
+
+impl<E: LogExporter> LogExporter for HealthLogExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let result = self.inner.export(batch).await;
+        self.health.record("logs", result.as_ref().map(|_| ()));
+        result
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+
+/// Blocking HTTP POST of encoded batches, shared by the non-OTLP span exporters.
+#[derive(Debug)]
+pub(crate) struct HttpPoster {
+    url: String,
+    headers: HashMap<String, String>,
+    timeout: Duration,
+    // Created on the batch processor's thread: the blocking client must not be built or
+    // dropped inside an async runtime
+    client: OnceLock<reqwest::blocking::Client>,
+}
+
+impl HttpPoster {
+    /// POST to `path` under `endpoint`, unless `endpoint` already ends with it.
+    pub(crate) fn new(
+        endpoint: &str,
+        path: &str,
+        headers: HashMap<String, String>,
+        timeout: Duration,
+    ) -> Self {
+        let base = endpoint.trim_end_matches('/');
+        let url = if base.ends_with(path) {
+            base.to_owned()
+        } else {
+            format!("{base}{path}")
+        };
+        Self {
+            url,
+            headers,
+            timeout,
+            client: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn post(&self, content_type: &str, body: Vec<u8>) -> OTelSdkResult {
+        let mut request = self
+            .client()?
+            .post(&self.url)
+            .header("Content-Type", content_type)
+            .body(body);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request
+            .send()
+            .map_err(|e| OTelSdkError::InternalFailure(format!("Export failed: {e}")))?;
+        if !response.status().is_success() {
+            return Err(OTelSdkError::InternalFailure(format!(
+                "{} returned {}",
+                self.url,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    fn client(&self) -> Result<&reqwest::blocking::Client, OTelSdkError> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| OTelSdkError::InternalFailure(format!("HTTP client: {e}")))?;
+        Ok(self.client.get_or_init(|| client))
+    }
+}
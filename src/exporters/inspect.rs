@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::export_batch::ExportBatch;
+use crate::options::{Callback, ExportHookFn};
+
+/// Span exporter that shows each batch to the `on_export` hook before passing it on.
+#[derive(Debug)]
+pub(crate) struct InspectSpanExporter<E> {
+    inner: E,
+    hook: Option<Callback<ExportHookFn>>,
+}
+
+impl<E> InspectSpanExporter<E> {
+    pub(crate) fn new(inner: E, hook: Option<Callback<ExportHookFn>>) -> Self {
+        Self { inner, hook }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for InspectSpanExporter<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        if let Some(ref hook) = self.hook {
+            (hook.0)(&ExportBatch::spans(&batch));
+        }
+        self.inner.export(batch).await
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that shows each batch to the `on_export` hook before passing it on.
+#[derive(Debug)]
+pub(crate) struct InspectLogExporter<E> {
+    inner: E,
+    hook: Option<Callback<ExportHookFn>>,
+}
+
+impl<E> InspectLogExporter<E> {
+    pub(crate) fn new(inner: E, hook: Option<Callback<ExportHookFn>>) -> Self {
+        Self { inner, hook }
+    }
+}
+
+impl<E: LogExporter> LogExporter for InspectLogExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        if let Some(ref hook) = self.hook {
+            (hook.0)(&ExportBatch::logs(&batch));
+        }
+        self.inner.export(batch).await
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
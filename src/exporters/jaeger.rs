@@ -0,0 +1,311 @@
+//! Jaeger Thrift-over-HTTP span export, for Jaeger collectors without an OTLP receiver.
+//!
+//! Spans are mapped the way the former `opentelemetry-jaeger` exporter did and sent as a
+//! Thrift binary-encoded `Batch` to the collector's `/api/traces` endpoint.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use opentelemetry::trace::{SpanKind, Status};
+use opentelemetry::{Key, KeyValue, Value};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use super::http_post::HttpPoster;
+
+const THRIFT_BOOL: u8 = 2;
+const THRIFT_DOUBLE: u8 = 4;
+const THRIFT_I32: u8 = 8;
+const THRIFT_I64: u8 = 10;
+const THRIFT_STRING: u8 = 11;
+const THRIFT_STRUCT: u8 = 12;
+const THRIFT_LIST: u8 = 15;
+
+// jaeger.thrift TagType
+const TAG_STRING: i32 = 0;
+const TAG_DOUBLE: i32 = 1;
+const TAG_BOOL: i32 = 2;
+const TAG_LONG: i32 = 3;
+
+// jaeger.thrift SpanRefType
+const REF_FOLLOWS_FROM: i32 = 1;
+
+/// Sends spans to a Jaeger collector as Thrift over HTTP.
+#[derive(Debug)]
+pub(crate) struct JaegerSpanExporter {
+    poster: HttpPoster,
+    service_name: String,
+    process_tags: Vec<KeyValue>,
+}
+
+impl JaegerSpanExporter {
+    /// Export to `endpoint` (the collector's base URL, usually port 14268).
+    pub(crate) fn new(endpoint: &str, headers: HashMap<String, String>, timeout: Duration) -> Self {
+        Self {
+            poster: HttpPoster::new(endpoint, "/api/traces", headers, timeout),
+            service_name: "unknown_service".to_owned(),
+            process_tags: Vec::new(),
+        }
+    }
+}
+
+impl SpanExporter for JaegerSpanExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let body = encode_batch(&self.service_name, &self.process_tags, &batch);
+        self.poster.post("application/x-thrift", body)
+    }
+
+    fn shutdown_with_timeout(&mut self, _timeout: Duration) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.process_tags.clear();
+        for (key, value) in resource.iter() {
+            if key.as_str() == "service.name" {
+                self.service_name = value.as_str().into_owned();
+            } else {
+                self.process_tags
+                    .push(KeyValue::new(key.clone(), value.clone()));
+            }
+        }
+    }
+}
+
+/// Encode a jaeger.thrift `Batch` with the Thrift binary protocol.
+fn encode_batch(service_name: &str, process_tags: &[KeyValue], spans: &[SpanData]) -> Vec<u8> {
+    let mut w = ThriftWriter::default();
+    // Batch.process
+    w.field(THRIFT_STRUCT, 1);
+    w.field(THRIFT_STRING, 1);
+    w.string(service_name);
+    w.field(THRIFT_LIST, 2);
+    w.list(THRIFT_STRUCT, process_tags.len());
+    for tag in process_tags {
+        w.tag(&tag.key, &tag.value);
+    }
+    w.stop();
+    // Batch.spans
+    w.field(THRIFT_LIST, 2);
+    w.list(THRIFT_STRUCT, spans.len());
+    for span in spans {
+        encode_span(&mut w, span);
+    }
+    w.stop();
+    w.buf
+}
+
+fn encode_span(w: &mut ThriftWriter, span: &SpanData) {
+    let cx = &span.span_context;
+    let (trace_high, trace_low) = split_trace_id(cx.trace_id().to_bytes());
+    w.field(THRIFT_I64, 1);
+    w.i64(trace_low);
+    w.field(THRIFT_I64, 2);
+    w.i64(trace_high);
+    w.field(THRIFT_I64, 3);
+    w.i64(i64::from_be_bytes(cx.span_id().to_bytes()));
+    w.field(THRIFT_I64, 4);
+    w.i64(i64::from_be_bytes(span.parent_span_id.to_bytes()));
+    w.field(THRIFT_STRING, 5);
+    w.string(&span.name);
+
+    // Links become FOLLOWS_FROM references; the parent is carried by parentSpanId
+    if !span.links.is_empty() {
+        w.field(THRIFT_LIST, 6);
+        w.list(THRIFT_STRUCT, span.links.len());
+        for link in span.links.iter() {
+            let (high, low) = split_trace_id(link.span_context.trace_id().to_bytes());
+            w.field(THRIFT_I32, 1);
+            w.i32(REF_FOLLOWS_FROM);
+            w.field(THRIFT_I64, 2);
+            w.i64(low);
+            w.field(THRIFT_I64, 3);
+            w.i64(high);
+            w.field(THRIFT_I64, 4);
+            w.i64(i64::from_be_bytes(link.span_context.span_id().to_bytes()));
+            w.stop();
+        }
+    }
+
+    w.field(THRIFT_I32, 7);
+    w.i32(i32::from(cx.is_sampled()));
+    w.field(THRIFT_I64, 8);
+    w.i64(micros_since_epoch(span.start_time));
+    w.field(THRIFT_I64, 9);
+    let duration = span
+        .end_time
+        .duration_since(span.start_time)
+        .unwrap_or_default();
+    w.i64(i64::try_from(duration.as_micros()).unwrap_or(i64::MAX));
+
+    let tags = span_tags(span);
+    w.field(THRIFT_LIST, 10);
+    w.list(THRIFT_STRUCT, tags.len());
+    for tag in &tags {
+        w.tag(&tag.key, &tag.value);
+    }
+
+    if !span.events.is_empty() {
+        w.field(THRIFT_LIST, 11);
+        w.list(THRIFT_STRUCT, span.events.len());
+        for event in span.events.iter() {
+            w.field(THRIFT_I64, 1);
+            w.i64(micros_since_epoch(event.timestamp));
+            w.field(THRIFT_LIST, 2);
+            w.list(THRIFT_STRUCT, event.attributes.len() + 1);
+            w.tag(
+                &Key::from_static_str("event"),
+                &Value::from(event.name.clone()),
+            );
+            for attribute in &event.attributes {
+                w.tag(&attribute.key, &attribute.value);
+            }
+            w.stop();
+        }
+    }
+    w.stop();
+}
+
+/// The span's attributes plus the tags Jaeger uses for kind, status and scope.
+fn span_tags(span: &SpanData) -> Vec<KeyValue> {
+    let mut tags = span.attributes.clone();
+    let kind = match span.span_kind {
+        SpanKind::Client => Some("client"),
+        SpanKind::Server => Some("server"),
+        SpanKind::Producer => Some("producer"),
+        SpanKind::Consumer => Some("consumer"),
+        SpanKind::Internal => None,
+    };
+    if let Some(kind) = kind {
+        tags.push(KeyValue::new("span.kind", kind));
+    }
+    match span.status {
+        Status::Ok => tags.push(KeyValue::new("otel.status_code", "OK")),
+        Status::Error { ref description } => {
+            tags.push(KeyValue::new("otel.status_code", "ERROR"));
+            tags.push(KeyValue::new("error", true));
+            if !description.is_empty() {
+                tags.push(KeyValue::new(
+                    "otel.status_description",
+                    description.to_string(),
+                ));
+            }
+        }
+        Status::Unset => {}
+    }
+    let scope = &span.instrumentation_scope;
+    tags.push(KeyValue::new("otel.scope.name", scope.name().to_owned()));
+    if let Some(version) = scope.version() {
+        tags.push(KeyValue::new("otel.scope.version", version.to_owned()));
+    }
+    tags
+}
+
+/// Split a 128-bit trace ID into Jaeger's (high, low) halves.
+fn split_trace_id(bytes: [u8; 16]) -> (i64, i64) {
+    let (high, low) = bytes.split_at(8);
+    (
+        i64::from_be_bytes(high.try_into().unwrap_or_default()),
+        i64::from_be_bytes(low.try_into().unwrap_or_default()),
+    )
+}
+
+fn micros_since_epoch(time: SystemTime) -> i64 {
+    let micros = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros();
+    i64::try_from(micros).unwrap_or(i64::MAX)
+}
+
+/// Minimal Thrift binary protocol writer for the jaeger.thrift structs.
+#[derive(Default)]
+struct ThriftWriter {
+    buf: Vec<u8>,
+}
+
+impl ThriftWriter {
+    fn field(&mut self, field_type: u8, id: i16) {
+        self.buf.push(field_type);
+        self.buf.extend_from_slice(&id.to_be_bytes());
+    }
+
+    fn stop(&mut self) {
+        self.buf.push(0);
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.i32(i32::try_from(value.len()).unwrap_or(i32::MAX));
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn list(&mut self, element_type: u8, len: usize) {
+        self.buf.push(element_type);
+        self.i32(i32::try_from(len).unwrap_or(i32::MAX));
+    }
+
+    /// A jaeger.thrift `Tag` struct; arrays are sent as their string form.
+    fn tag(&mut self, key: &Key, value: &Value) {
+        self.field(THRIFT_STRING, 1);
+        self.string(key.as_str());
+        match value {
+            Value::Bool(b) => {
+                self.field(THRIFT_I32, 2);
+                self.i32(TAG_BOOL);
+                self.field(THRIFT_BOOL, 5);
+                self.buf.push(u8::from(*b));
+            }
+            Value::I64(i) => {
+                self.field(THRIFT_I32, 2);
+                self.i32(TAG_LONG);
+                self.field(THRIFT_I64, 6);
+                self.i64(*i);
+            }
+            Value::F64(f) => {
+                self.field(THRIFT_I32, 2);
+                self.i32(TAG_DOUBLE);
+                self.field(THRIFT_DOUBLE, 4);
+                self.buf.extend_from_slice(&f.to_bits().to_be_bytes());
+            }
+            other => {
+                self.field(THRIFT_I32, 2);
+                self.i32(TAG_STRING);
+                self.field(THRIFT_STRING, 3);
+                self.string(&other.as_str());
+            }
+        }
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_use_the_thrift_binary_layout() {
+        let mut w = ThriftWriter::default();
+        w.tag(&Key::new("http.status_code"), &Value::I64(200));
+
+        let mut expected = vec![THRIFT_STRING, 0, 1, 0, 0, 0, 16];
+        expected.extend_from_slice(b"http.status_code");
+        expected.extend_from_slice(&[THRIFT_I32, 0, 2, 0, 0, 0, 3]);
+        expected.extend_from_slice(&[THRIFT_I64, 0, 6, 0, 0, 0, 0, 0, 0, 0, 200, 0]);
+        assert_eq!(w.buf, expected);
+
+        let id = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        assert_eq!(
+            split_trace_id(id),
+            (0x0102_0304_0506_0708, 0x090a_0b0c_0d0e_0f10)
+        );
+    }
+}
@@ -0,0 +1,33 @@
+pub(crate) mod auth;
+#[cfg(feature = "azure-monitor")]
+pub(crate) mod azure_monitor;
+pub(crate) mod backpressure;
+pub(crate) mod batch_size;
+pub(crate) mod budget;
+pub(crate) mod counting;
+pub(crate) mod dead_letter;
+#[cfg(feature = "grpc")]
+pub(crate) mod dns_balance;
+pub(crate) mod factor;
+pub(crate) mod fallback;
+#[cfg(feature = "file")]
+pub(crate) mod file;
+pub(crate) mod health;
+#[cfg(any(feature = "jaeger", feature = "zipkin", feature = "azure-monitor"))]
+pub(crate) mod http_post;
+pub(crate) mod inspect;
+#[cfg(feature = "jaeger")]
+pub(crate) mod jaeger;
+#[cfg(feature = "file")]
+pub(crate) mod otlp_json;
+pub(crate) mod queue;
+#[cfg(feature = "record")]
+pub(crate) mod record;
+pub(crate) mod size;
+pub(crate) mod split;
+pub(crate) mod tenant;
+pub(crate) mod time;
+pub(crate) mod transport;
+pub(crate) mod vsock;
+#[cfg(feature = "zipkin")]
+pub(crate) mod zipkin;
@@ -0,0 +1,343 @@
+//! OTLP/JSON encoding of export requests, as written by the collector's file exporter.
+//!
+//! Follows the protobuf JSON mapping used by OTLP/JSON: camelCase field names, trace and
+//! span IDs as lowercase hex, 64-bit integers as strings, enums as numbers, and fields
+//! with default values omitted.
+
+use base64::Engine;
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::any_value::Value as ProtoValue;
+use opentelemetry_proto::tonic::common::v1::{AnyValue, InstrumentationScope, KeyValue};
+use opentelemetry_proto::tonic::logs::v1::LogRecord;
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::Span;
+use serde_json::{Map, Value};
+
+/// Encode a trace export request as one line of OTLP/JSON (`{"resourceSpans":[...]}`).
+pub(crate) fn traces_json(request: &ExportTraceServiceRequest) -> Value {
+    let resource_spans = request
+        .resource_spans
+        .iter()
+        .map(|rs| {
+            let scope_spans = rs
+                .scope_spans
+                .iter()
+                .map(|ss| {
+                    let mut doc = Map::new();
+                    insert_opt(&mut doc, "scope", ss.scope.as_ref().map(scope_json));
+                    doc.insert(
+                        "spans".into(),
+                        ss.spans.iter().map(span_json).collect::<Vec<_>>().into(),
+                    );
+                    insert_str(&mut doc, "schemaUrl", &ss.schema_url);
+                    Value::Object(doc)
+                })
+                .collect::<Vec<_>>();
+            let mut doc = Map::new();
+            insert_opt(
+                &mut doc,
+                "resource",
+                rs.resource.as_ref().map(resource_json),
+            );
+            doc.insert("scopeSpans".into(), scope_spans.into());
+            insert_str(&mut doc, "schemaUrl", &rs.schema_url);
+            Value::Object(doc)
+        })
+        .collect::<Vec<_>>();
+    let mut doc = Map::new();
+    doc.insert("resourceSpans".into(), resource_spans.into());
+    Value::Object(doc)
+}
+
+/// Encode a log export request as one line of OTLP/JSON (`{"resourceLogs":[...]}`).
+pub(crate) fn logs_json(request: &ExportLogsServiceRequest) -> Value {
+    let resource_logs = request
+        .resource_logs
+        .iter()
+        .map(|rl| {
+            let scope_logs = rl
+                .scope_logs
+                .iter()
+                .map(|sl| {
+                    let mut doc = Map::new();
+                    insert_opt(&mut doc, "scope", sl.scope.as_ref().map(scope_json));
+                    doc.insert(
+                        "logRecords".into(),
+                        sl.log_records
+                            .iter()
+                            .map(log_record_json)
+                            .collect::<Vec<_>>()
+                            .into(),
+                    );
+                    insert_str(&mut doc, "schemaUrl", &sl.schema_url);
+                    Value::Object(doc)
+                })
+                .collect::<Vec<_>>();
+            let mut doc = Map::new();
+            insert_opt(
+                &mut doc,
+                "resource",
+                rl.resource.as_ref().map(resource_json),
+            );
+            doc.insert("scopeLogs".into(), scope_logs.into());
+            insert_str(&mut doc, "schemaUrl", &rl.schema_url);
+            Value::Object(doc)
+        })
+        .collect::<Vec<_>>();
+    let mut doc = Map::new();
+    doc.insert("resourceLogs".into(), resource_logs.into());
+    Value::Object(doc)
+}
+
+fn resource_json(resource: &Resource) -> Value {
+    let mut doc = Map::new();
+    insert_attributes(&mut doc, &resource.attributes);
+    insert_count(&mut doc, resource.dropped_attributes_count);
+    Value::Object(doc)
+}
+
+fn scope_json(scope: &InstrumentationScope) -> Value {
+    let mut doc = Map::new();
+    insert_str(&mut doc, "name", &scope.name);
+    insert_str(&mut doc, "version", &scope.version);
+    insert_attributes(&mut doc, &scope.attributes);
+    insert_count(&mut doc, scope.dropped_attributes_count);
+    Value::Object(doc)
+}
+
+fn span_json(span: &Span) -> Value {
+    let mut doc = Map::new();
+    insert_id(&mut doc, "traceId", &span.trace_id);
+    insert_id(&mut doc, "spanId", &span.span_id);
+    insert_str(&mut doc, "traceState", &span.trace_state);
+    insert_id(&mut doc, "parentSpanId", &span.parent_span_id);
+    insert_u32(&mut doc, "flags", span.flags);
+    insert_str(&mut doc, "name", &span.name);
+    insert_i32(&mut doc, "kind", span.kind);
+    insert_u64(&mut doc, "startTimeUnixNano", span.start_time_unix_nano);
+    insert_u64(&mut doc, "endTimeUnixNano", span.end_time_unix_nano);
+    insert_attributes(&mut doc, &span.attributes);
+    insert_count(&mut doc, span.dropped_attributes_count);
+    if !span.events.is_empty() {
+        let events = span.events.iter().map(|event| {
+            let mut doc = Map::new();
+            insert_u64(&mut doc, "timeUnixNano", event.time_unix_nano);
+            insert_str(&mut doc, "name", &event.name);
+            insert_attributes(&mut doc, &event.attributes);
+            insert_count(&mut doc, event.dropped_attributes_count);
+            Value::Object(doc)
+        });
+        doc.insert("events".into(), events.collect::<Vec<_>>().into());
+    }
+    insert_u32(&mut doc, "droppedEventsCount", span.dropped_events_count);
+    if !span.links.is_empty() {
+        let links = span.links.iter().map(|link| {
+            let mut doc = Map::new();
+            insert_id(&mut doc, "traceId", &link.trace_id);
+            insert_id(&mut doc, "spanId", &link.span_id);
+            insert_str(&mut doc, "traceState", &link.trace_state);
+            insert_attributes(&mut doc, &link.attributes);
+            insert_count(&mut doc, link.dropped_attributes_count);
+            insert_u32(&mut doc, "flags", link.flags);
+            Value::Object(doc)
+        });
+        doc.insert("links".into(), links.collect::<Vec<_>>().into());
+    }
+    insert_u32(&mut doc, "droppedLinksCount", span.dropped_links_count);
+    // Always present, like the collector, so an unset status is explicit
+    let mut status = Map::new();
+    if let Some(ref s) = span.status {
+        insert_str(&mut status, "message", &s.message);
+        insert_i32(&mut status, "code", s.code);
+    }
+    doc.insert("status".into(), Value::Object(status));
+    Value::Object(doc)
+}
+
+fn log_record_json(record: &LogRecord) -> Value {
+    let mut doc = Map::new();
+    insert_u64(&mut doc, "timeUnixNano", record.time_unix_nano);
+    insert_u64(
+        &mut doc,
+        "observedTimeUnixNano",
+        record.observed_time_unix_nano,
+    );
+    insert_i32(&mut doc, "severityNumber", record.severity_number);
+    insert_str(&mut doc, "severityText", &record.severity_text);
+    insert_opt(&mut doc, "body", record.body.as_ref().map(any_value_json));
+    insert_attributes(&mut doc, &record.attributes);
+    insert_count(&mut doc, record.dropped_attributes_count);
+    insert_u32(&mut doc, "flags", record.flags);
+    insert_id(&mut doc, "traceId", &record.trace_id);
+    insert_id(&mut doc, "spanId", &record.span_id);
+    insert_str(&mut doc, "eventName", &record.event_name);
+    Value::Object(doc)
+}
+
+fn any_value_json(value: &AnyValue) -> Value {
+    let mut doc = Map::new();
+    let (key, json) = match value.value {
+        Some(ProtoValue::StringValue(ref s)) => ("stringValue", Value::from(s.as_str())),
+        Some(ProtoValue::BoolValue(b)) => ("boolValue", Value::from(b)),
+        Some(ProtoValue::IntValue(i)) => ("intValue", Value::from(i.to_string())),
+        Some(ProtoValue::DoubleValue(d)) => ("doubleValue", double_json(d)),
+        Some(ProtoValue::ArrayValue(ref array)) => {
+            let values = array.values.iter().map(any_value_json).collect::<Vec<_>>();
+            let mut inner = Map::new();
+            inner.insert("values".into(), values.into());
+            ("arrayValue", Value::Object(inner))
+        }
+        Some(ProtoValue::KvlistValue(ref list)) => {
+            let mut inner = Map::new();
+            inner.insert("values".into(), key_values_json(&list.values));
+            ("kvlistValue", Value::Object(inner))
+        }
+        Some(ProtoValue::BytesValue(ref bytes)) => (
+            "bytesValue",
+            Value::from(base64::engine::general_purpose::STANDARD.encode(bytes)),
+        ),
+        None => return Value::Object(doc),
+    };
+    doc.insert(key.into(), json);
+    Value::Object(doc)
+}
+
+/// Finite doubles are numbers; NaN and infinities use the protobuf JSON string forms.
+fn double_json(d: f64) -> Value {
+    if d.is_nan() {
+        Value::from("NaN")
+    } else if d.is_infinite() {
+        Value::from(if d > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        Value::from(d)
+    }
+}
+
+fn key_values_json(attributes: &[KeyValue]) -> Value {
+    attributes
+        .iter()
+        .map(|kv| {
+            let mut doc = Map::new();
+            doc.insert("key".into(), Value::from(kv.key.as_str()));
+            doc.insert(
+                "value".into(),
+                kv.value
+                    .as_ref()
+                    .map_or_else(|| Value::Object(Map::new()), any_value_json),
+            );
+            Value::Object(doc)
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn insert_attributes(doc: &mut Map<String, Value>, attributes: &[KeyValue]) {
+    if !attributes.is_empty() {
+        doc.insert("attributes".into(), key_values_json(attributes));
+    }
+}
+
+fn insert_count(doc: &mut Map<String, Value>, dropped_attributes_count: u32) {
+    insert_u32(doc, "droppedAttributesCount", dropped_attributes_count);
+}
+
+fn insert_opt(doc: &mut Map<String, Value>, key: &str, value: Option<Value>) {
+    if let Some(value) = value {
+        doc.insert(key.into(), value);
+    }
+}
+
+fn insert_str(doc: &mut Map<String, Value>, key: &str, value: &str) {
+    if !value.is_empty() {
+        doc.insert(key.into(), Value::from(value));
+    }
+}
+
+fn insert_id(doc: &mut Map<String, Value>, key: &str, id: &[u8]) {
+    if !id.is_empty() && id.iter().any(|&b| b != 0) {
+        let hex = id.iter().map(|b| format!("{b:02x}")).collect::<String>();
+        doc.insert(key.into(), Value::from(hex));
+    }
+}
+
+fn insert_u32(doc: &mut Map<String, Value>, key: &str, value: u32) {
+    if value != 0 {
+        doc.insert(key.into(), Value::from(value));
+    }
+}
+
+fn insert_i32(doc: &mut Map<String, Value>, key: &str, value: i32) {
+    if value != 0 {
+        doc.insert(key.into(), Value::from(value));
+    }
+}
+
+/// 64-bit integers are strings in protobuf JSON, so no precision is lost.
+fn insert_u64(doc: &mut Map<String, Value>, key: &str, value: u64) {
+    if value != 0 {
+        doc.insert(key.into(), Value::from(value.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Status};
+
+    use super::*;
+
+    #[test]
+    fn spans_use_the_otlp_json_mapping() {
+        let request = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(Resource {
+                    attributes: vec![KeyValue {
+                        key: "service.name".into(),
+                        value: Some(AnyValue {
+                            value: Some(ProtoValue::StringValue("svc".into())),
+                        }),
+                    }],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    scope: None,
+                    spans: vec![Span {
+                        trace_id: vec![0; 15].into_iter().chain([1]).collect(),
+                        span_id: vec![0xab; 8],
+                        name: "GET /".into(),
+                        kind: 2,
+                        start_time_unix_nano: 1_700_000_000_000_000_000,
+                        attributes: vec![KeyValue {
+                            key: "retries".into(),
+                            value: Some(AnyValue {
+                                value: Some(ProtoValue::IntValue(3)),
+                            }),
+                        }],
+                        status: Some(Status {
+                            message: String::new(),
+                            code: 2,
+                        }),
+                        ..Default::default()
+                    }],
+                    schema_url: String::new(),
+                }],
+                schema_url: String::new(),
+            }],
+        };
+
+        let json = traces_json(&request);
+        let span = &json["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(
+            json["resourceSpans"][0]["resource"]["attributes"][0]["value"]["stringValue"],
+            "svc"
+        );
+        assert_eq!(span["traceId"], "00000000000000000000000000000001");
+        assert_eq!(span["spanId"], "abababababababab");
+        assert_eq!(span["kind"], 2);
+        assert_eq!(span["startTimeUnixNano"], "1700000000000000000");
+        assert_eq!(span["attributes"][0]["value"]["intValue"], "3");
+        assert_eq!(span["status"]["code"], 2);
+        assert!(span.get("parentSpanId").is_none());
+        assert!(span.get("endTimeUnixNano").is_none());
+    }
+}
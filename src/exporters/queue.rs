@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::processors::queue::QueueMonitor;
+
+/// Span exporter that tells a [`QueueMonitor`] when the batch processor drains its queue.
+#[derive(Debug)]
+pub(crate) struct QueueDrainSpanExporter<E> {
+    inner: E,
+    monitor: Arc<QueueMonitor>,
+}
+
+impl<E> QueueDrainSpanExporter<E> {
+    pub(crate) fn new(inner: E, monitor: Arc<QueueMonitor>) -> Self {
+        Self { inner, monitor }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for QueueDrainSpanExporter<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        self.monitor.dequeued(batch.len());
+        self.inner.export(batch).await
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that tells a [`QueueMonitor`] when the batch processor drains its queue.
+#[derive(Debug)]
+pub(crate) struct QueueDrainLogExporter<E> {
+    inner: E,
+    monitor: Arc<QueueMonitor>,
+}
+
+impl<E> QueueDrainLogExporter<E> {
+    pub(crate) fn new(inner: E, monitor: Arc<QueueMonitor>) -> Self {
+        Self { inner, monitor }
+    }
+}
+
+impl<E: LogExporter> LogExporter for QueueDrainLogExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        self.monitor.dequeued(batch.iter().count());
+        self.inner.export(batch).await
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
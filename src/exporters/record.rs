@@ -0,0 +1,101 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
+use opentelemetry_proto::transform::logs::tonic::group_logs_by_resource_and_scope;
+use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use crate::recording::{Recorder, Signal};
+
+/// Span exporter that appends every batch to a recording before exporting it.
+#[derive(Debug)]
+pub(crate) struct RecordSpanExporter<E> {
+    inner: E,
+    recorder: Option<Arc<Recorder>>,
+    resource: ResourceAttributesWithSchema,
+}
+
+impl<E> RecordSpanExporter<E> {
+    pub(crate) fn new(inner: E, recorder: Option<Arc<Recorder>>) -> Self {
+        Self {
+            inner,
+            recorder,
+            resource: ResourceAttributesWithSchema::default(),
+        }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for RecordSpanExporter<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        if let Some(ref recorder) = self.recorder {
+            let request = ExportTraceServiceRequest {
+                resource_spans: group_spans_by_resource_and_scope(batch.clone(), &self.resource),
+            };
+            recorder.record(Signal::Traces, &request);
+        }
+        self.inner.export(batch).await
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource.into();
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that appends every batch to a recording before exporting it.
+#[derive(Debug)]
+pub(crate) struct RecordLogExporter<E> {
+    inner: E,
+    recorder: Option<Arc<Recorder>>,
+    resource: ResourceAttributesWithSchema,
+}
+
+impl<E> RecordLogExporter<E> {
+    pub(crate) fn new(inner: E, recorder: Option<Arc<Recorder>>) -> Self {
+        Self {
+            inner,
+            recorder,
+            resource: ResourceAttributesWithSchema::default(),
+        }
+    }
+}
+
+impl<E: LogExporter> LogExporter for RecordLogExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        if let Some(ref recorder) = self.recorder {
+            // The conversion consumes a batch, so hand it a borrowed copy
+            let records: Vec<_> = batch.iter().collect();
+            let request = ExportLogsServiceRequest {
+                resource_logs: group_logs_by_resource_and_scope(
+                    LogBatch::new(&records),
+                    &self.resource,
+                ),
+            };
+            recorder.record(Signal::Logs, &request);
+        }
+        self.inner.export(batch).await
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.resource = resource.into();
+        self.inner.set_resource(resource);
+    }
+}
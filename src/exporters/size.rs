@@ -0,0 +1,326 @@
+//! Sizes of spans and log records in an OTLP protobuf request, counting field tags, length
+//! prefixes and `AnyValue` wrappers as well as the raw bytes. Computed without encoding, and
+//! equal to what the OTLP exporters encode.
+
+use opentelemetry::logs::AnyValue;
+use opentelemetry::trace::{SpanId, Status};
+use opentelemetry::{Array, KeyValue, Value};
+use opentelemetry_sdk::logs::SdkLogRecord;
+use opentelemetry_sdk::trace::SpanData;
+
+/// Trace ID (16 bytes), with its tag and length prefix.
+const TRACE_ID: usize = 18;
+/// Span ID (8 bytes), with its tag and length prefix.
+const SPAN_ID: usize = 10;
+/// A tagged `fixed64`, e.g. a timestamp.
+const FIXED64: usize = 9;
+/// A tagged `fixed32`, e.g. the link and log record flags.
+const FIXED32: usize = 5;
+/// The span flags, a `fixed32` whose field number 16 takes a two-byte tag.
+const SPAN_FLAGS: usize = 6;
+
+/// Bytes of `span` inside an OTLP request, including its own tag and length prefix.
+pub(crate) fn span_size(span: &SpanData) -> u64 {
+    let context = &span.span_context;
+    let parent = if span.parent_span_id == SpanId::INVALID {
+        0
+    } else {
+        SPAN_ID
+    };
+    let status = match &span.status {
+        Status::Unset => 0,
+        Status::Ok => 2,
+        Status::Error { description } => 2 + string(description),
+    };
+    let events: usize = span
+        .events
+        .iter()
+        .map(|event| {
+            field(
+                FIXED64
+                    + string(&event.name)
+                    + attributes(&event.attributes)
+                    + count(event.dropped_attributes_count),
+            )
+        })
+        .sum();
+    let links: usize = span
+        .links
+        .iter()
+        .map(|link| {
+            field(
+                TRACE_ID
+                    + SPAN_ID
+                    + FIXED32
+                    + string(&link.span_context.trace_state().header())
+                    + attributes(&link.attributes)
+                    + count(link.dropped_attributes_count),
+            )
+        })
+        .sum();
+    let size = TRACE_ID
+        + SPAN_ID
+        + parent
+        + SPAN_FLAGS
+        + string(&context.trace_state().header())
+        + string(&span.name)
+        // The kind is never `SPAN_KIND_UNSPECIFIED`, so always one byte
+        + 2
+        + 2 * FIXED64
+        + attributes(&span.attributes)
+        + count(span.dropped_attributes_count)
+        + events
+        + count(span.events.dropped_count)
+        + links
+        + count(span.links.dropped_count)
+        + field(status);
+    field(size) as u64
+}
+
+/// Bytes of `record` inside an OTLP request, including its own tag and length prefix.
+pub(crate) fn log_size(record: &SdkLogRecord) -> u64 {
+    let timestamps = [record.timestamp(), record.observed_timestamp()]
+        .iter()
+        .flatten()
+        .count();
+    let trace = record.trace_context().map_or(0, |cx| {
+        let flags = cx.trace_flags.filter(|flags| flags.to_u8() != 0);
+        TRACE_ID + SPAN_ID + flags.map_or(0, |_| FIXED32)
+    });
+    let attributes: usize = record
+        .attributes_iter()
+        .map(|(key, value)| key_value(key.as_str(), any_value_len(value)))
+        .sum();
+    let size = timestamps * FIXED64
+        + record.severity_number().map_or(0, |_| 2)
+        + record.severity_text().map_or(0, string)
+        + record.body().map_or(0, |body| field(any_value_len(body)))
+        + attributes
+        + trace
+        + record.event_name().map_or(0, string);
+    field(size) as u64
+}
+
+/// Bytes of `n` as a varint.
+fn varint(n: u64) -> usize {
+    (64 - (n | 1).leading_zeros() as usize).div_ceil(7)
+}
+
+/// A length-delimited field holding `len` bytes: tag, length prefix and contents.
+fn field(len: usize) -> usize {
+    1 + varint(len as u64) + len
+}
+
+/// A string field, which protobuf leaves out when empty.
+fn string(value: &str) -> usize {
+    if value.is_empty() {
+        0
+    } else {
+        field(value.len())
+    }
+}
+
+/// A dropped-items count, which protobuf leaves out when zero.
+fn count(n: u32) -> usize {
+    if n == 0 {
+        0
+    } else {
+        1 + varint(u64::from(n))
+    }
+}
+
+/// A repeated `KeyValue` field.
+fn attributes(attributes: &[KeyValue]) -> usize {
+    attributes
+        .iter()
+        .map(|kv| key_value(kv.key.as_str(), value_len(&kv.value)))
+        .sum()
+}
+
+/// A `KeyValue` entry whose `AnyValue` message is `value_len` bytes.
+fn key_value(key: &str, value_len: usize) -> usize {
+    field(string(key) + field(value_len))
+}
+
+/// Length of `value` as an `AnyValue` message.
+fn value_len(value: &Value) -> usize {
+    match value {
+        Value::Bool(_) => 2,
+        Value::I64(i) => 1 + varint(*i as u64),
+        Value::F64(_) => FIXED64,
+        Value::String(s) => field(s.as_str().len()),
+        Value::Array(array) => {
+            let values: usize = match array {
+                Array::Bool(values) => values.iter().map(|_| field(2)).sum(),
+                Array::I64(values) => values.iter().map(|i| field(1 + varint(*i as u64))).sum(),
+                Array::F64(values) => values.iter().map(|_| field(FIXED64)).sum(),
+                Array::String(values) => {
+                    values.iter().map(|s| field(field(s.as_str().len()))).sum()
+                }
+                _ => 0,
+            };
+            field(values)
+        }
+        _ => FIXED64,
+    }
+}
+
+/// Length of `value` as an `AnyValue` message.
+fn any_value_len(value: &AnyValue) -> usize {
+    match value {
+        AnyValue::Boolean(_) => 2,
+        AnyValue::Int(i) => 1 + varint(*i as u64),
+        AnyValue::Double(_) => FIXED64,
+        AnyValue::String(s) => field(s.as_str().len()),
+        AnyValue::Bytes(b) => field(b.len()),
+        AnyValue::ListAny(items) => {
+            field(items.iter().map(|item| field(any_value_len(item))).sum())
+        }
+        AnyValue::Map(map) => field(
+            map.iter()
+                .map(|(key, value)| key_value(key.as_str(), any_value_len(value)))
+                .sum(),
+        ),
+        _ => FIXED64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use opentelemetry::logs::{LogRecord as _, Logger as _, LoggerProvider as _, Severity};
+    use opentelemetry::trace::{
+        Event, Link, SpanContext, SpanKind, TraceFlags, TraceId, TraceState,
+    };
+    use opentelemetry::{InstrumentationScope, StringValue};
+    use opentelemetry_proto::tonic::logs::v1::LogRecord;
+    use opentelemetry_proto::tonic::trace::v1::Span;
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+    use prost::Message;
+
+    use super::*;
+
+    fn context() -> SpanContext {
+        SpanContext::new(
+            TraceId::from(0x0af7_6519_16cd_43dd_8448_eb21_1c80_319c),
+            SpanId::from(0x00f0_67aa_0ba9_02b7),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        )
+    }
+
+    /// `span`'s size as encoded by the OTLP exporter, inside its `ScopeSpans`.
+    fn encoded(span: &SpanData) -> u64 {
+        field(Span::from(span.clone()).encoded_len()) as u64
+    }
+
+    #[test]
+    fn span_estimates_match_the_encoded_otlp_size() {
+        let mut events = SpanEvents::default();
+        events.events = vec![Event::new(
+            "exception",
+            SystemTime::now(),
+            vec![KeyValue::new("exception.message", "connection reset")],
+            0,
+        )];
+        let mut links = SpanLinks::default();
+        links.links = vec![Link::new(
+            context(),
+            vec![KeyValue::new("link.kind", "retry")],
+            0,
+        )];
+        let mut span = SpanData {
+            span_context: context(),
+            parent_span_id: SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Server,
+            name: "GET /orders".into(),
+            start_time: SystemTime::now(),
+            end_time: SystemTime::now(),
+            attributes: vec![],
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: InstrumentationScope::builder("test").build(),
+        };
+        assert_eq!(span_size(&span), encoded(&span));
+
+        // Many short attributes, where the framing is a large part of the size
+        span.attributes = (0..50)
+            .map(|i| KeyValue::new(format!("attr.key{i:02}"), format!("value{i:05}")))
+            .collect();
+        span.attributes.extend([
+            KeyValue::new("http.status_code", 503_i64),
+            KeyValue::new("retry.delay", -1_i64),
+            KeyValue::new("cache.hit", false),
+            KeyValue::new("load", 0.75),
+            KeyValue::new(
+                "tags",
+                Value::Array(vec![StringValue::from("a"), "bc".into()].into()),
+            ),
+            KeyValue::new("shards", Value::Array(vec![1_i64, 300].into())),
+        ]);
+        span.parent_span_id = SpanId::from(7_u64);
+        span.span_context = SpanContext::new(
+            context().trace_id(),
+            context().span_id(),
+            TraceFlags::SAMPLED,
+            false,
+            "vendor=x".parse().unwrap(),
+        );
+        span.events = events;
+        span.links = links;
+        span.dropped_attributes_count = 3;
+        span.status = Status::error("upstream unavailable");
+        assert_eq!(span_size(&span), encoded(&span));
+    }
+
+    #[test]
+    fn log_estimates_match_the_encoded_otlp_size() {
+        let mut record = opentelemetry_sdk::logs::SdkLoggerProvider::builder()
+            .build()
+            .logger("test")
+            .create_log_record();
+        record.set_observed_timestamp(SystemTime::now());
+        let encoded = |record: &SdkLogRecord| field(LogRecord::from(record).encoded_len()) as u64;
+        assert_eq!(log_size(&record), encoded(&record));
+
+        record.set_timestamp(SystemTime::now());
+        record.set_severity_number(Severity::Warn);
+        record.set_severity_text("WARN");
+        record.set_event_name("payment.declined");
+        record.set_body("card declined by issuer".into());
+        record.set_trace_context(
+            context().trace_id(),
+            context().span_id(),
+            Some(TraceFlags::SAMPLED),
+        );
+        for i in 0..20 {
+            record.add_attribute(format!("attr.key{i:02}"), format!("value{i:05}"));
+        }
+        record.add_attribute("attempt", 3_i64);
+        record.add_attribute(
+            "items",
+            AnyValue::ListAny(Box::new(vec![AnyValue::Int(-1), AnyValue::Boolean(true)])),
+        );
+        record.add_attribute(
+            "card",
+            AnyValue::Map(Box::new(
+                [("brand".into(), AnyValue::from("visa"))]
+                    .into_iter()
+                    .collect(),
+            )),
+        );
+        assert_eq!(log_size(&record), encoded(&record));
+
+        record.set_trace_context(
+            context().trace_id(),
+            context().span_id(),
+            Some(TraceFlags::NOT_SAMPLED),
+        );
+        assert_eq!(log_size(&record), encoded(&record));
+    }
+}
@@ -0,0 +1,137 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use opentelemetry::InstrumentationScope;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter, SdkLogRecord};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+use super::size::{log_size, span_size};
+
+/// Span exporter that splits batches whose estimated size exceeds `max_bytes` into
+/// several exports, so the collector doesn't reject them as too large.
+///
+/// Chunks are exported in order; the first failure is returned and the remaining chunks
+/// are dropped, as the whole batch would have been.
+#[derive(Debug)]
+pub(crate) struct SplitSpanExporter<E> {
+    inner: E,
+    max_bytes: Option<usize>,
+}
+
+impl<E> SplitSpanExporter<E> {
+    pub(crate) fn new(inner: E, max_bytes: Option<usize>) -> Self {
+        Self { inner, max_bytes }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for SplitSpanExporter<E> {
+    async fn export(&self, mut batch: Vec<SpanData>) -> OTelSdkResult {
+        let Some(max_bytes) = self.max_bytes else {
+            return self.inner.export(batch).await;
+        };
+        let ranges = chunk_ranges(batch.iter().map(span_size), max_bytes as u64);
+        if ranges.len() <= 1 {
+            return self.inner.export(batch).await;
+        }
+
+        // Split from the back, so each `split_off` moves only its own chunk
+        let mut chunks: Vec<Vec<SpanData>> = ranges
+            .iter()
+            .rev()
+            .map(|range| batch.split_off(range.start))
+            .collect();
+        while let Some(chunk) = chunks.pop() {
+            self.inner.export(chunk).await?;
+        }
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that splits batches whose estimated size exceeds `max_bytes` into
+/// several exports, so the collector doesn't reject them as too large.
+#[derive(Debug)]
+pub(crate) struct SplitLogExporter<E> {
+    inner: E,
+    max_bytes: Option<usize>,
+}
+
+impl<E> SplitLogExporter<E> {
+    pub(crate) fn new(inner: E, max_bytes: Option<usize>) -> Self {
+        Self { inner, max_bytes }
+    }
+}
+
+impl<E: LogExporter> LogExporter for SplitLogExporter<E> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let Some(max_bytes) = self.max_bytes else {
+            return self.inner.export(batch).await;
+        };
+        let records: Vec<(&SdkLogRecord, &InstrumentationScope)> = batch.iter().collect();
+        for range in chunk_ranges(
+            records.iter().map(|(record, _)| log_size(record)),
+            max_bytes as u64,
+        ) {
+            self.inner.export(LogBatch::new(&records[range])).await?;
+        }
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Consecutive index ranges whose sizes add up to at most `max_bytes`. An item larger
+/// than `max_bytes` gets a range of its own. An empty input yields one empty range.
+fn chunk_ranges(sizes: impl Iterator<Item = u64>, max_bytes: u64) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut total = 0;
+    let mut len = 0;
+    for (i, size) in sizes.enumerate() {
+        if i > start && total + size > max_bytes {
+            ranges.push(start..i);
+            start = i;
+            total = 0;
+        }
+        total += size;
+        len = i + 1;
+    }
+    ranges.push(start..len);
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_are_split_at_the_size_limit() {
+        assert_eq!(chunk_ranges([40, 40, 40].into_iter(), 100), [0..2, 2..3]);
+        // Oversized items travel alone rather than being dropped
+        assert_eq!(
+            chunk_ranges([10, 500, 10, 10].into_iter(), 100),
+            [0..1, 1..2, 2..4]
+        );
+        assert_eq!(chunk_ranges([40, 60].into_iter(), 100), vec![0..2]);
+        assert_eq!(chunk_ranges(std::iter::empty(), 100), vec![0..0]);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use opentelemetry::logs::AnyValue;
+use opentelemetry::{Key, Value};
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+/// Builds the exporter for one tenant.
+pub(crate) type ExporterFactory<X> = Box<dyn Fn(&str) -> anyhow::Result<X> + Send + Sync>;
+
+/// Per-tenant exporters, each created on the tenant's first batch.
+pub(crate) struct TenantRouter<X> {
+    attribute: Key,
+    factory: ExporterFactory<X>,
+    exporters: Mutex<HashMap<String, Arc<X>>>,
+    resource: Option<Resource>,
+}
+
+impl<X> TenantRouter<X> {
+    pub(crate) fn new(attribute: &str, factory: ExporterFactory<X>) -> Self {
+        Self {
+            attribute: Key::new(attribute.to_owned()),
+            factory,
+            exporters: Mutex::new(HashMap::new()),
+            resource: None,
+        }
+    }
+
+    /// The exporter for `tenant`, created (and given the resource with `init`) if needed.
+    fn exporter(
+        &self,
+        tenant: &str,
+        init: impl FnOnce(&mut X, &Resource),
+    ) -> Result<Arc<X>, OTelSdkError> {
+        let mut exporters = self
+            .exporters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(exporter) = exporters.get(tenant) {
+            return Ok(Arc::clone(exporter));
+        }
+        let mut exporter = (self.factory)(tenant).map_err(|e| {
+            OTelSdkError::InternalFailure(format!(
+                "Failed to create exporter for tenant `{tenant}`: {e:#}"
+            ))
+        })?;
+        if let Some(ref resource) = self.resource {
+            init(&mut exporter, resource);
+        }
+        let exporter = Arc::new(exporter);
+        exporters.insert(tenant.to_owned(), Arc::clone(&exporter));
+        Ok(exporter)
+    }
+
+    fn drain(&self) -> Vec<Arc<X>> {
+        let mut exporters = self
+            .exporters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        exporters.drain().map(|(_, exporter)| exporter).collect()
+    }
+}
+
+impl<X> fmt::Debug for TenantRouter<X> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TenantRouter")
+            .field("attribute", &self.attribute)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Span exporter that sends each tenant's spans through that tenant's exporter, and
+/// spans without a tenant through `inner`.
+#[derive(Debug)]
+pub(crate) struct TenantRoutingSpanExporter<E, X> {
+    inner: E,
+    router: Option<TenantRouter<X>>,
+}
+
+impl<E, X> TenantRoutingSpanExporter<E, X> {
+    pub(crate) fn new(inner: E, router: Option<TenantRouter<X>>) -> Self {
+        Self { inner, router }
+    }
+}
+
+impl<E: SpanExporter, X: SpanExporter> SpanExporter for TenantRoutingSpanExporter<E, X> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let Some(ref router) = self.router else {
+            return self.inner.export(batch).await;
+        };
+
+        let mut untenanted = Vec::new();
+        let mut by_tenant: HashMap<String, Vec<SpanData>> = HashMap::new();
+        for span in batch {
+            let tenant = span.attributes.iter().find_map(|kv| match kv.value {
+                Value::String(ref s) if kv.key == router.attribute => Some(s.as_str().to_owned()),
+                _ => None,
+            });
+            match tenant {
+                Some(tenant) => by_tenant.entry(tenant).or_default().push(span),
+                None => untenanted.push(span),
+            }
+        }
+
+        let mut result = Ok(());
+        if !untenanted.is_empty() {
+            result = self.inner.export(untenanted).await;
+        }
+        for (tenant, spans) in by_tenant {
+            let exported = match router.exporter(&tenant, |e, r| e.set_resource(r)) {
+                Ok(exporter) => exporter.export(spans).await,
+                Err(e) => Err(e),
+            };
+            // Keep the first error
+            result = result.and(exported);
+        }
+        result
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        if let Some(ref router) = self.router {
+            for mut exporter in router.drain() {
+                // No export is in flight during shutdown, so this is the only reference
+                if let Some(exporter) = Arc::get_mut(&mut exporter) {
+                    let _ = exporter.shutdown_with_timeout(timeout);
+                }
+            }
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(ref mut router) = self.router {
+            router.resource = Some(resource.clone());
+        }
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log exporter that sends each tenant's records through that tenant's exporter, and
+/// records without a tenant through `inner`.
+#[derive(Debug)]
+pub(crate) struct TenantRoutingLogExporter<E, X> {
+    inner: E,
+    router: Option<TenantRouter<X>>,
+}
+
+impl<E, X> TenantRoutingLogExporter<E, X> {
+    pub(crate) fn new(inner: E, router: Option<TenantRouter<X>>) -> Self {
+        Self { inner, router }
+    }
+}
+
+impl<E: LogExporter, X: LogExporter> LogExporter for TenantRoutingLogExporter<E, X> {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        let Some(ref router) = self.router else {
+            return self.inner.export(batch).await;
+        };
+
+        let mut untenanted = Vec::new();
+        let mut by_tenant: HashMap<String, Vec<_>> = HashMap::new();
+        for (record, scope) in batch.iter() {
+            let tenant = record
+                .attributes_iter()
+                .find_map(|(key, value)| match value {
+                    AnyValue::String(s) if *key == router.attribute => Some(s.as_str().to_owned()),
+                    _ => None,
+                });
+            match tenant {
+                Some(tenant) => by_tenant.entry(tenant).or_default().push((record, scope)),
+                None => untenanted.push((record, scope)),
+            }
+        }
+
+        let mut result = Ok(());
+        if !untenanted.is_empty() {
+            result = self.inner.export(LogBatch::new(&untenanted)).await;
+        }
+        for (tenant, records) in by_tenant {
+            let exported = match router.exporter(&tenant, |e, r| e.set_resource(r)) {
+                Ok(exporter) => exporter.export(LogBatch::new(&records)).await,
+                Err(e) => Err(e),
+            };
+            // Keep the first error
+            result = result.and(exported);
+        }
+        result
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        if let Some(ref router) = self.router {
+            for exporter in router.drain() {
+                let _ = exporter.shutdown_with_timeout(timeout);
+            }
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(ref mut router) = self.router {
+            router.resource = Some(resource.clone());
+        }
+        self.inner.set_resource(resource);
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogBatch, LogExporter};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+
+#[cfg(feature = "azure-monitor")]
+use super::azure_monitor::{AzureMonitorLogExporter, AzureMonitorSpanExporter};
+use super::counting::{CountingLogExporter, CountingSpanExporter};
+use super::factor::FactorSpanExporter;
+#[cfg(feature = "file")]
+use super::file::{FileLogExporter, FileSpanExporter};
+#[cfg(feature = "jaeger")]
+use super::jaeger::JaegerSpanExporter;
+#[cfg(feature = "zipkin")]
+use super::zipkin::ZipkinSpanExporter;
+
+/// The span exporter for the configured [`Protocol`](crate::Protocol).
+// One per provider, so the gRPC variant's size doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub(crate) enum TransportSpanExporter {
+    Otlp(FactorSpanExporter<opentelemetry_otlp::SpanExporter>),
+    Counting(CountingSpanExporter),
+    #[cfg(feature = "file")]
+    File(FileSpanExporter),
+    #[cfg(feature = "jaeger")]
+    Jaeger(JaegerSpanExporter),
+    #[cfg(feature = "zipkin")]
+    Zipkin(ZipkinSpanExporter),
+    #[cfg(feature = "azure-monitor")]
+    AzureMonitor(AzureMonitorSpanExporter),
+}
+
+impl SpanExporter for TransportSpanExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        match self {
+            Self::Otlp(exporter) => exporter.export(batch).await,
+            Self::Counting(exporter) => exporter.export(batch).await,
+            #[cfg(feature = "file")]
+            Self::File(exporter) => exporter.export(batch).await,
+            #[cfg(feature = "jaeger")]
+            Self::Jaeger(exporter) => exporter.export(batch).await,
+            #[cfg(feature = "zipkin")]
+            Self::Zipkin(exporter) => exporter.export(batch).await,
+            #[cfg(feature = "azure-monitor")]
+            Self::AzureMonitor(exporter) => exporter.export(batch).await,
+        }
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        match self {
+            Self::Otlp(exporter) => exporter.shutdown_with_timeout(timeout),
+            Self::Counting(exporter) => exporter.shutdown_with_timeout(timeout),
+            #[cfg(feature = "file")]
+            Self::File(exporter) => exporter.shutdown_with_timeout(timeout),
+            #[cfg(feature = "jaeger")]
+            Self::Jaeger(exporter) => exporter.shutdown_with_timeout(timeout),
+            #[cfg(feature = "zipkin")]
+            Self::Zipkin(exporter) => exporter.shutdown_with_timeout(timeout),
+            #[cfg(feature = "azure-monitor")]
+            Self::AzureMonitor(exporter) => exporter.shutdown_with_timeout(timeout),
+        }
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        match self {
+            Self::Otlp(exporter) => exporter.force_flush(),
+            Self::Counting(exporter) => exporter.force_flush(),
+            #[cfg(feature = "file")]
+            Self::File(exporter) => exporter.force_flush(),
+            #[cfg(feature = "jaeger")]
+            Self::Jaeger(exporter) => exporter.force_flush(),
+            #[cfg(feature = "zipkin")]
+            Self::Zipkin(exporter) => exporter.force_flush(),
+            #[cfg(feature = "azure-monitor")]
+            Self::AzureMonitor(exporter) => exporter.force_flush(),
+        }
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        match self {
+            Self::Otlp(exporter) => exporter.set_resource(resource),
+            Self::Counting(exporter) => exporter.set_resource(resource),
+            #[cfg(feature = "file")]
+            Self::File(exporter) => exporter.set_resource(resource),
+            #[cfg(feature = "jaeger")]
+            Self::Jaeger(exporter) => exporter.set_resource(resource),
+            #[cfg(feature = "zipkin")]
+            Self::Zipkin(exporter) => exporter.set_resource(resource),
+            #[cfg(feature = "azure-monitor")]
+            Self::AzureMonitor(exporter) => exporter.set_resource(resource),
+        }
+    }
+}
+
+/// The log exporter for the configured [`Protocol`](crate::Protocol).
+// One per provider, so the gRPC variant's size doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub(crate) enum TransportLogExporter {
+    Otlp(opentelemetry_otlp::LogExporter),
+    Counting(CountingLogExporter),
+    #[cfg(feature = "file")]
+    File(FileLogExporter),
+    #[cfg(feature = "azure-monitor")]
+    AzureMonitor(AzureMonitorLogExporter),
+}
+
+impl LogExporter for TransportLogExporter {
+    async fn export(&self, batch: LogBatch<'_>) -> OTelSdkResult {
+        match self {
+            Self::Otlp(exporter) => exporter.export(batch).await,
+            Self::Counting(exporter) => exporter.export(batch).await,
+            #[cfg(feature = "file")]
+            Self::File(exporter) => exporter.export(batch).await,
+            #[cfg(feature = "azure-monitor")]
+            Self::AzureMonitor(exporter) => exporter.export(batch).await,
+        }
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        match self {
+            Self::Otlp(exporter) => exporter.shutdown_with_timeout(timeout),
+            Self::Counting(exporter) => exporter.shutdown_with_timeout(timeout),
+            #[cfg(feature = "file")]
+            Self::File(exporter) => exporter.shutdown_with_timeout(timeout),
+            #[cfg(feature = "azure-monitor")]
+            Self::AzureMonitor(exporter) => exporter.shutdown_with_timeout(timeout),
+        }
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        match self {
+            Self::Otlp(exporter) => exporter.set_resource(resource),
+            Self::Counting(exporter) => exporter.set_resource(resource),
+            #[cfg(feature = "file")]
+            Self::File(exporter) => exporter.set_resource(resource),
+            #[cfg(feature = "azure-monitor")]
+            Self::AzureMonitor(exporter) => exporter.set_resource(resource),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+
+/// A `vsock://<cid>:<port>` endpoint: a collector reached over virtio-vsock, as from a
+/// Firecracker or Kata microVM guest to its host (CID 2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct VsockAddr {
+    pub(crate) cid: u32,
+    pub(crate) port: u32,
+}
+
+impl VsockAddr {
+    /// The address `endpoint` names, or `None` if it isn't a `vsock://` endpoint.
+    pub(crate) fn parse(endpoint: &str) -> Option<Result<Self, String>> {
+        let rest = endpoint.trim().strip_prefix("vsock://")?;
+        let parsed = rest
+            .trim_end_matches('/')
+            .split_once(':')
+            .and_then(|(cid, port)| Some((cid.parse().ok()?, port.parse().ok()?)));
+        let invalid = || {
+            format!(
+                "Invalid vsock endpoint `{endpoint}`: expected vsock://<cid>:<port>, \
+                 e.g. vsock://2:4317"
+            )
+        };
+        Some(
+            parsed
+                .map(|(cid, port)| Self { cid, port })
+                .ok_or_else(invalid),
+        )
+    }
+}
+
+impl fmt::Display for VsockAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "vsock://{}:{}", self.cid, self.port)
+    }
+}
+
+#[cfg(all(feature = "vsock", target_os = "linux"))]
+pub(crate) use connector::VsockConnector;
+
+#[cfg(all(feature = "vsock", target_os = "linux"))]
+mod connector {
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use hyper_util::rt::TokioIo;
+    use tokio_vsock::VsockStream;
+    use tonic::transport::Uri;
+
+    use super::VsockAddr;
+
+    /// Connects tonic channels to a [`VsockAddr`], whatever URI they ask for.
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) struct VsockConnector(VsockAddr);
+
+    impl VsockConnector {
+        pub(crate) fn new(addr: VsockAddr) -> Self {
+            Self(addr)
+        }
+    }
+
+    impl tower_service::Service<Uri> for VsockConnector {
+        type Response = TokioIo<VsockStream>;
+        type Error = io::Error;
+        type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _uri: Uri) -> Self::Future {
+            let addr = tokio_vsock::VsockAddr::new(self.0.cid, self.0.port);
+            Box::pin(async move { VsockStream::connect(addr).await.map(TokioIo::new) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vsock_endpoints_name_a_cid_and_port() {
+        assert_eq!(
+            VsockAddr::parse("vsock://2:4317/"),
+            Some(Ok(VsockAddr { cid: 2, port: 4317 }))
+        );
+        assert_eq!(VsockAddr::parse("http://collector:4317"), None);
+        assert!(matches!(
+            VsockAddr::parse("vsock://host:4317"),
+            Some(Err(_))
+        ));
+        assert!(matches!(VsockAddr::parse("vsock://2"), Some(Err(_))));
+        assert_eq!(
+            VsockAddr { cid: 3, port: 4317 }.to_string(),
+            "vsock://3:4317"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Zipkin v2 JSON span export, for Zipkin deployments that don't accept OTLP.
+//!
+//! Spans are mapped following the OpenTelemetry Zipkin exporter spec and POSTed as a JSON
+//! array to the collector's `/api/v2/spans` endpoint.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use opentelemetry::trace::{SpanId, SpanKind, Status};
+use opentelemetry::Value;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+use serde_json::{json, Map};
+
+use super::http_post::HttpPoster;
+
+/// Sends spans to a Zipkin collector as v2 JSON.
+#[derive(Debug)]
+pub(crate) struct ZipkinSpanExporter {
+    poster: HttpPoster,
+    service_name: String,
+}
+
+impl ZipkinSpanExporter {
+    /// Export to `endpoint` (the collector's base URL, usually port 9411).
+    pub(crate) fn new(endpoint: &str, headers: HashMap<String, String>, timeout: Duration) -> Self {
+        Self {
+            poster: HttpPoster::new(endpoint, "/api/v2/spans", headers, timeout),
+            service_name: "unknown_service".to_owned(),
+        }
+    }
+}
+
+impl SpanExporter for ZipkinSpanExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let spans: Vec<_> = batch
+            .iter()
+            .map(|span| zipkin_span(&self.service_name, span))
+            .collect();
+        let body = serde_json::Value::Array(spans).to_string();
+        self.poster.post("application/json", body.into_bytes())
+    }
+
+    fn shutdown_with_timeout(&mut self, _timeout: Duration) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(name) = resource.get(&"service.name".into()) {
+            self.service_name = name.as_str().into_owned();
+        }
+    }
+}
+
+fn zipkin_span(service_name: &str, span: &SpanData) -> serde_json::Value {
+    let cx = &span.span_context;
+    let mut out = Map::new();
+    out.insert("traceId".into(), cx.trace_id().to_string().into());
+    out.insert("id".into(), cx.span_id().to_string().into());
+    if span.parent_span_id != SpanId::INVALID {
+        out.insert("parentId".into(), span.parent_span_id.to_string().into());
+    }
+    out.insert("name".into(), span.name.as_ref().into());
+    let kind = match span.span_kind {
+        SpanKind::Client => Some("CLIENT"),
+        SpanKind::Server => Some("SERVER"),
+        SpanKind::Producer => Some("PRODUCER"),
+        SpanKind::Consumer => Some("CONSUMER"),
+        SpanKind::Internal => None,
+    };
+    if let Some(kind) = kind {
+        out.insert("kind".into(), kind.into());
+    }
+    out.insert(
+        "timestamp".into(),
+        micros_since_epoch(span.start_time).into(),
+    );
+    let duration = span
+        .end_time
+        .duration_since(span.start_time)
+        .unwrap_or_default();
+    out.insert(
+        "duration".into(),
+        u64::try_from(duration.as_micros())
+            .unwrap_or(u64::MAX)
+            .into(),
+    );
+    out.insert(
+        "localEndpoint".into(),
+        json!({ "serviceName": service_name }),
+    );
+
+    if !span.events.is_empty() {
+        let annotations: Vec<_> = span
+            .events
+            .iter()
+            .map(|event| {
+                // Event attributes have no place in a Zipkin annotation, so they are
+                // serialized into its value: {"<name>":{"<key>":<value>,...}}
+                let value = if event.attributes.is_empty() {
+                    event.name.to_string()
+                } else {
+                    let attributes: Map<_, _> = event
+                        .attributes
+                        .iter()
+                        .map(|kv| (kv.key.to_string(), json_value(&kv.value)))
+                        .collect();
+                    json!({ event.name.as_ref(): attributes }).to_string()
+                };
+                json!({ "timestamp": micros_since_epoch(event.timestamp), "value": value })
+            })
+            .collect();
+        out.insert("annotations".into(), annotations.into());
+    }
+
+    let mut tags = Map::new();
+    for kv in &span.attributes {
+        tags.insert(kv.key.to_string(), kv.value.as_str().into_owned().into());
+    }
+    match span.status {
+        Status::Ok => {
+            tags.insert("otel.status_code".into(), "OK".into());
+        }
+        Status::Error { ref description } => {
+            tags.insert("otel.status_code".into(), "ERROR".into());
+            tags.insert("error".into(), description.to_string().into());
+        }
+        Status::Unset => {}
+    }
+    let scope = &span.instrumentation_scope;
+    tags.insert("otel.scope.name".into(), scope.name().into());
+    if let Some(version) = scope.version() {
+        tags.insert("otel.scope.version".into(), version.into());
+    }
+    out.insert("tags".into(), tags.into());
+    out.into()
+}
+
+fn json_value(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Bool(b) => (*b).into(),
+        Value::I64(i) => (*i).into(),
+        Value::F64(f) => (*f).into(),
+        other => other.as_str().into_owned().into(),
+    }
+}
+
+fn micros_since_epoch(time: SystemTime) -> u64 {
+    let micros = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros();
+    u64::try_from(micros).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use opentelemetry::trace::{SpanContext, TraceFlags, TraceId, TraceState};
+    use opentelemetry::{InstrumentationScope, KeyValue};
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+
+    use super::*;
+
+    #[test]
+    fn spans_map_to_zipkin_v2_json() {
+        let start = UNIX_EPOCH + Duration::from_secs(1);
+        let span = SpanData {
+            span_context: SpanContext::new(
+                TraceId::from(1),
+                SpanId::from(2),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: SpanId::from(3),
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Server,
+            name: Cow::Borrowed("GET /users"),
+            start_time: start,
+            end_time: start + Duration::from_millis(5),
+            attributes: vec![KeyValue::new("http.response.status_code", 500)],
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::error("boom"),
+            instrumentation_scope: InstrumentationScope::builder("raccoon-otel").build(),
+        };
+
+        assert_eq!(
+            zipkin_span("users-api", &span),
+            json!({
+                "traceId": "00000000000000000000000000000001",
+                "id": "0000000000000002",
+                "parentId": "0000000000000003",
+                "name": "GET /users",
+                "kind": "SERVER",
+                "timestamp": 1_000_000,
+                "duration": 5_000,
+                "localEndpoint": { "serviceName": "users-api" },
+                "tags": {
+                    "http.response.status_code": "500",
+                    "otel.status_code": "ERROR",
+                    "error": "boom",
+                    "otel.scope.name": "raccoon-otel",
+                },
+            })
+        );
+    }
+}
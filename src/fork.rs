@@ -0,0 +1,311 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use opentelemetry::{Context, InstrumentationScope};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord};
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+
+use crate::env::ResolvedConfig;
+use crate::guard::OtelGuard;
+use crate::samplers::adaptive::AdaptiveRatio;
+use crate::samplers::dynamic::SamplingOverride;
+
+/// Where a pipeline's providers hand spans and log records to their processor chains, so
+/// [`OtelGuard::reinit_after_fork`](crate::OtelGuard::reinit_after_fork) can point them
+/// at a pipeline rebuilt in a forked child.
+///
+/// The providers themselves can't be rebuilt, since the subscriber's layers hold them.
+/// Their samplers keep running, so the rebuilt pipeline shares their state.
+pub(crate) struct ForkPoint {
+    /// The process that built the pipeline.
+    pid: u32,
+    pub(crate) config: ResolvedConfig,
+    pub(crate) sampling_override: Arc<SamplingOverride>,
+    pub(crate) adaptive: Option<Arc<AdaptiveRatio>>,
+    spans: OnceLock<Box<dyn SpanProcessor>>,
+    logs: OnceLock<Vec<Box<dyn LogProcessor>>>,
+    rebuilt: OnceLock<Arc<ForkPoint>>,
+}
+
+impl std::fmt::Debug for ForkPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ForkPoint")
+            .field("pid", &self.pid)
+            .field("rebuilt", &self.rebuilt.get().is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ForkPoint {
+    pub(crate) fn new(
+        config: ResolvedConfig,
+        sampling_override: Arc<SamplingOverride>,
+        adaptive: Option<Arc<AdaptiveRatio>>,
+    ) -> Self {
+        Self {
+            pid: process_id(),
+            config,
+            sampling_override,
+            adaptive,
+            spans: OnceLock::new(),
+            logs: OnceLock::new(),
+            rebuilt: OnceLock::new(),
+        }
+    }
+
+    /// Whether this is a forked child of the process that built the pipeline.
+    pub(crate) fn forked(&self) -> bool {
+        self.pid != process_id()
+    }
+
+    /// Hand everything to `rebuilt` from now on. The processor chains built here are never
+    /// touched again: their threads didn't survive the fork.
+    pub(crate) fn rebuilt(&self, rebuilt: Arc<ForkPoint>) {
+        let _ = self.rebuilt.set(rebuilt);
+    }
+
+    /// Abandon the pipeline `parent` owns, once it has been rebuilt in a forked child.
+    ///
+    /// Its batch processors, export runtime and other background threads didn't survive
+    /// the fork, so shutting it down, or dropping anything that joins those threads, would
+    /// wait on threads that don't exist here. Nothing it owns is ever dropped: that
+    /// includes its reference to this point and to its log offload queue, which the
+    /// providers and layers keep handing telemetry to on its way to the rebuilt pipeline.
+    pub(crate) fn abandon(parent: OtelGuard) {
+        std::mem::forget(parent);
+    }
+
+    fn span_processor(&self) -> Option<&dyn SpanProcessor> {
+        match self.rebuilt.get() {
+            Some(rebuilt) => rebuilt.span_processor(),
+            None => self.spans.get().map(|processor| &**processor),
+        }
+    }
+
+    fn log_processors(&self) -> &[Box<dyn LogProcessor>] {
+        match self.rebuilt.get() {
+            Some(rebuilt) => rebuilt.log_processors(),
+            None => self.logs.get().map_or(&[], Vec::as_slice),
+        }
+    }
+}
+
+/// The current process ID.
+fn process_id() -> u32 {
+    #[cfg(test)]
+    if let Some(pid) = tests::FORKED_PID.get() {
+        return pid;
+    }
+    std::process::id()
+}
+
+/// The tracer provider's only span processor, running the chain registered with its
+/// [`ForkPoint`], or the rebuilt pipeline's after a fork.
+///
+/// The chain must already have its resource set.
+#[derive(Debug)]
+pub(crate) struct ForkSpanProcessor(Arc<ForkPoint>);
+
+impl ForkSpanProcessor {
+    pub(crate) fn new(processor: Box<dyn SpanProcessor>, fork: Arc<ForkPoint>) -> Self {
+        let _ = fork.spans.set(processor);
+        Self(fork)
+    }
+}
+
+impl SpanProcessor for ForkSpanProcessor {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        if let Some(processor) = self.0.span_processor() {
+            processor.on_start(span, cx);
+        }
+    }
+
+    fn on_end(&self, span: SpanData) {
+        if let Some(processor) = self.0.span_processor() {
+            processor.on_end(span);
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.0
+            .span_processor()
+            .map_or(Ok(()), SpanProcessor::force_flush)
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.0
+            .span_processor()
+            .map_or(Ok(()), |processor| processor.shutdown_with_timeout(timeout))
+    }
+}
+
+/// The logger provider's only log processor, running the processors registered with its
+/// [`ForkPoint`] in order, or the rebuilt pipeline's after a fork.
+///
+/// The processors must already have their resource set.
+#[derive(Debug)]
+pub(crate) struct ForkLogProcessor(Arc<ForkPoint>);
+
+impl ForkLogProcessor {
+    pub(crate) fn new(processors: Vec<Box<dyn LogProcessor>>, fork: Arc<ForkPoint>) -> Self {
+        let _ = fork.logs.set(processors);
+        Self(fork)
+    }
+}
+
+impl LogProcessor for ForkLogProcessor {
+    fn emit(&self, record: &mut SdkLogRecord, instrumentation: &InstrumentationScope) {
+        for processor in self.0.log_processors() {
+            processor.emit(record, instrumentation);
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.0
+            .log_processors()
+            .iter()
+            .map(|processor| processor.force_flush())
+            .fold(Ok(()), OTelSdkResult::and)
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.0
+            .log_processors()
+            .iter()
+            .map(|processor| processor.shutdown_with_timeout(timeout))
+            .fold(Ok(()), OTelSdkResult::and)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::sync::Mutex;
+
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status};
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+
+    use super::*;
+
+    thread_local! {
+        /// The process ID this thread sees, to simulate a fork.
+        pub(super) static FORKED_PID: Cell<Option<u32>> = const { Cell::new(None) };
+    }
+
+    #[derive(Debug, Default)]
+    struct Names(Arc<Mutex<Vec<String>>>);
+
+    impl SpanProcessor for Names {
+        fn on_start(&self, _: &mut Span, _: &Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            self.0.lock().unwrap().push(span.name.into_owned());
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _: Duration) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    fn span(name: &'static str) -> SpanData {
+        SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Internal,
+            name: name.into(),
+            start_time: std::time::UNIX_EPOCH,
+            end_time: std::time::UNIX_EPOCH,
+            attributes: Vec::new(),
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: InstrumentationScope::builder("raccoon-otel").build(),
+        }
+    }
+
+    fn fork_point() -> Arc<ForkPoint> {
+        let config =
+            crate::env::resolve_config("fork-test", &crate::OtelOptions::default()).unwrap();
+        Arc::new(ForkPoint::new(config, Arc::default(), None))
+    }
+
+    #[test]
+    fn spans_go_to_the_latest_rebuilt_pipeline() {
+        let (parent, child, grandchild) = (Names::default(), Names::default(), Names::default());
+        let seen = [&parent, &child, &grandchild].map(|names| Arc::clone(&names.0));
+        let points = [fork_point(), fork_point(), fork_point()];
+        let processor = ForkSpanProcessor::new(Box::new(parent), Arc::clone(&points[0]));
+        let _ = ForkSpanProcessor::new(Box::new(child), Arc::clone(&points[1]));
+        let _ = ForkSpanProcessor::new(Box::new(grandchild), Arc::clone(&points[2]));
+        assert!(!points[0].forked());
+
+        processor.on_end(span("before"));
+        points[0].rebuilt(Arc::clone(&points[1]));
+        processor.on_end(span("child"));
+        points[1].rebuilt(Arc::clone(&points[2]));
+        processor.on_end(span("grandchild"));
+
+        let names = seen.map(|names| names.lock().unwrap().clone());
+        assert_eq!(names, [["before"], ["child"], ["grandchild"]]);
+    }
+
+    /// Record spans and log records before and after a simulated fork, and return how many
+    /// of each the pipeline in use at the end exported.
+    fn spans_and_logs_after_fork(async_bridge: bool) -> (u64, u64) {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let mut options = crate::OtelOptions::builder().benchmark_mode(true);
+        if async_bridge {
+            options = options.async_bridge(1024);
+        }
+        let pipeline = crate::build_pipeline("fork-test", Some(options.build())).unwrap();
+        let subscriber = tracing_subscriber::registry().with(pipeline.layer());
+        let mut guard = pipeline.into_guard();
+        let record = |name: &str| {
+            let _span = tracing::info_span!("work", name).entered();
+            tracing::info!(name, "working");
+        };
+
+        tracing::subscriber::with_default(subscriber, || {
+            let parent = Arc::clone(guard.fork_point().unwrap());
+            guard.reinit_after_fork().unwrap();
+            assert!(Arc::ptr_eq(guard.fork_point().unwrap(), &parent));
+            record("parent");
+
+            FORKED_PID.set(Some(std::process::id() + 1));
+            assert!(parent.forked());
+            guard.reinit_after_fork().unwrap();
+            let child = Arc::clone(guard.fork_point().unwrap());
+            assert!(!Arc::ptr_eq(&child, &parent));
+            assert!(!child.forked());
+            record("child");
+
+            guard.reinit_after_fork().unwrap();
+            assert!(Arc::ptr_eq(guard.fork_point().unwrap(), &child));
+            record("child again");
+        });
+        FORKED_PID.set(None);
+        guard.shutdown();
+        let report = guard.throughput_report();
+        (report.spans, report.logs)
+    }
+
+    #[test]
+    fn reinit_after_fork_rebuilds_the_pipeline_once_in_the_child() {
+        // The parent's telemetry stays with the abandoned pipeline, and a second reinit
+        // keeps the pipeline the first one built
+        let expected = (
+            if cfg!(feature = "traces") { 2 } else { 0 },
+            if cfg!(feature = "logs") { 2 } else { 0 },
+        );
+        assert_eq!(spans_and_logs_after_fork(false), expected);
+        assert_eq!(spans_and_logs_after_fork(true), expected);
+    }
+}
@@ -0,0 +1,94 @@
+//! Google Cloud project and credential lookup.
+//!
+//! Both come from the metadata server available on GCE, GKE, Cloud Run and Cloud Functions.
+//! It is queried with a plain HTTP/1.0 request so lookups work both at setup (possibly
+//! inside an async runtime) and on the batch processor's thread.
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+
+/// Google's Telemetry API, which accepts OTLP.
+pub(crate) const TELEMETRY_ENDPOINT: &str = "https://telemetry.googleapis.com";
+
+const METADATA_HOST: &str = "169.254.169.254";
+const METADATA_TIMEOUT: Duration = Duration::from_secs(1);
+const PROJECT_ENV_VARS: [&str; 3] = [
+    "GOOGLE_CLOUD_PROJECT",
+    "GCLOUD_PROJECT",
+    "CLOUDSDK_CORE_PROJECT",
+];
+
+/// An OAuth access token for the instance's service account.
+#[derive(Debug, Clone)]
+pub(crate) struct AccessToken {
+    pub(crate) value: String,
+    pub(crate) expires_at: Instant,
+}
+
+/// The project ID from the usual environment variables, or from the metadata server.
+pub(crate) fn project_id() -> Option<String> {
+    PROJECT_ENV_VARS
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|s| !s.is_empty()))
+        .or_else(|| metadata("project/project-id").ok())
+}
+
+/// A fresh access token for the default service account.
+///
+/// # Errors
+///
+/// Returns an error if the metadata server is unreachable (i.e. not running on Google
+/// Cloud) or its response can't be parsed.
+pub(crate) fn access_token() -> anyhow::Result<AccessToken> {
+    let body = metadata("instance/service-accounts/default/token")?;
+    let json: serde_json::Value =
+        serde_json::from_str(&body).context("Invalid token response from the metadata server")?;
+    let value = json["access_token"]
+        .as_str()
+        .context("Token response from the metadata server has no `access_token`")?;
+    let expires_in = json["expires_in"].as_u64().unwrap_or(300);
+    Ok(AccessToken {
+        value: value.to_owned(),
+        expires_at: Instant::now() + Duration::from_secs(expires_in),
+    })
+}
+
+/// GET a metadata path, honoring `GCE_METADATA_HOST` like Google's client libraries.
+fn metadata(path: &str) -> anyhow::Result<String> {
+    let host = std::env::var("GCE_METADATA_HOST")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| METADATA_HOST.to_owned());
+    let address = if host.contains(':') {
+        host.clone()
+    } else {
+        format!("{host}:80")
+    };
+    let address = address
+        .to_socket_addrs()?
+        .next()
+        .with_context(|| format!("Metadata server `{host}` did not resolve"))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, METADATA_TIMEOUT)
+        .with_context(|| format!("Metadata server `{host}` is not reachable"))?;
+    stream.set_read_timeout(Some(METADATA_TIMEOUT))?;
+    stream.set_write_timeout(Some(METADATA_TIMEOUT))?;
+    write!(
+        stream,
+        "GET /computeMetadata/v1/{path} HTTP/1.0\r\nHost: {host}\r\nMetadata-Flavor: Google\r\n\r\n"
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .context("Malformed response from the metadata server")?;
+    let status = head.split_whitespace().nth(1).unwrap_or_default();
+    if status != "200" {
+        anyhow::bail!("Metadata server returned {status} for `{path}`");
+    }
+    Ok(body.trim().to_owned())
+}
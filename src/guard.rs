@@ -1,5 +1,27 @@
-use opentelemetry_sdk::logs::SdkLoggerProvider;
-use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::any::Any;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use opentelemetry::InstrumentationScope;
+use opentelemetry_sdk::logs::{SdkLogRecord, SdkLoggerProvider};
+#[cfg(feature = "metrics")]
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::trace::{SdkTracerProvider, SpanData};
+
+use crate::cardinality::{CardinalityAnalyzer, CardinalityReport};
+use crate::fork::ForkPoint;
+use crate::health::{ExportHealth, HealthStatus};
+use crate::options::AuxiliaryGuard;
+use crate::processors::ingest::IngestPoint;
+use crate::processors::offload::LogOffload;
+use crate::samplers::dynamic::SamplingOverride;
+use crate::snapshot::PipelineSnapshot;
+use crate::stats::{ExportStats, StatsCounters, ThroughputReport};
+use crate::watchdog::FlushWatchdog;
+
+/// How long a guard dropped inside an async runtime waits for the shutdown thread.
+const ASYNC_DROP_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Lifecycle guard for OpenTelemetry providers.
 ///
@@ -9,6 +31,12 @@ use opentelemetry_sdk::trace::SdkTracerProvider;
 /// Dropping the guard:
 /// 1. Flushes all pending spans, logs, and metrics
 /// 2. Shuts down all providers gracefully
+/// 3. Drops the resources handed to it with [`keep_alive`](Self::keep_alive), most
+///    recently added first
+///
+/// When the guard is dropped on a tokio runtime thread, e.g. at the end of an async
+/// `main`, blocking there could deadlock an export that needs the runtime. The shutdown
+/// then runs on its own thread instead, and the drop waits at most 10 seconds for it.
 ///
 /// # Examples
 ///
@@ -23,6 +51,29 @@ use opentelemetry_sdk::trace::SdkTracerProvider;
 pub struct OtelGuard {
     tracer_provider: Option<SdkTracerProvider>,
     logger_provider: Option<SdkLoggerProvider>,
+    #[cfg(feature = "metrics")]
+    meter_provider: Option<SdkMeterProvider>,
+    stats: Arc<StatsCounters>,
+    health: Arc<ExportHealth>,
+    sampling_override: Arc<SamplingOverride>,
+    cardinality: Option<Arc<CardinalityAnalyzer>>,
+    ingest: Arc<IngestPoint>,
+    log_offload: Option<Arc<LogOffload>>,
+    fork: Option<Arc<ForkPoint>>,
+    #[cfg(feature = "remote-sampling")]
+    remote_sampling: Option<crate::samplers::remote::RemoteSamplingPoller>,
+    #[cfg(feature = "opamp")]
+    opamp: Option<crate::opamp::OpampClient>,
+    #[cfg(feature = "receiver")]
+    receiver: Option<crate::receiver::Receiver>,
+    #[cfg(all(feature = "profiling", unix))]
+    profiler: Option<crate::profiling::Profiler>,
+    watchdog: Option<FlushWatchdog>,
+    // Behind a mutex so the guard stays `Sync` while holding `Send`-only resources
+    auxiliary: Mutex<Vec<AuxiliaryGuard>>,
+    #[cfg(feature = "grpc")]
+    export_runtime: Option<crate::export_runtime::ExportRuntime>,
+    started: Instant,
     shutdown_called: bool,
 }
 
@@ -30,14 +81,410 @@ impl OtelGuard {
     pub(crate) fn new(
         tracer_provider: Option<SdkTracerProvider>,
         logger_provider: Option<SdkLoggerProvider>,
+        stats: Arc<StatsCounters>,
+        health: Arc<ExportHealth>,
     ) -> Self {
         Self {
             tracer_provider,
             logger_provider,
+            #[cfg(feature = "metrics")]
+            meter_provider: None,
+            stats,
+            health,
+            sampling_override: Arc::default(),
+            cardinality: None,
+            ingest: Arc::default(),
+            log_offload: None,
+            fork: None,
+            #[cfg(feature = "remote-sampling")]
+            remote_sampling: None,
+            #[cfg(feature = "opamp")]
+            opamp: None,
+            #[cfg(feature = "receiver")]
+            receiver: None,
+            #[cfg(all(feature = "profiling", unix))]
+            profiler: None,
+            watchdog: None,
+            auxiliary: Mutex::default(),
+            #[cfg(feature = "grpc")]
+            export_runtime: None,
+            started: Instant::now(),
             shutdown_called: false,
         }
     }
 
+    /// A guard with no providers, for tests and builds with telemetry turned off.
+    ///
+    /// Lets code hold an `OtelGuard` unconditionally; dropping it does nothing.
+    ///
+    /// ```
+    /// let guard = raccoon_otel::OtelGuard::noop();
+    /// assert!(guard.is_healthy());
+    /// ```
+    pub fn noop() -> Self {
+        Self::new(None, None, Arc::default(), Arc::default())
+    }
+
+    pub(crate) fn tracer_provider(&self) -> Option<&SdkTracerProvider> {
+        self.tracer_provider.as_ref()
+    }
+
+    pub(crate) fn logger_provider(&self) -> Option<&SdkLoggerProvider> {
+        self.logger_provider.as_ref()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn with_meter_provider(mut self, meter_provider: Option<SdkMeterProvider>) -> Self {
+        self.meter_provider = meter_provider;
+        self
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn meter_provider(&self) -> Option<&SdkMeterProvider> {
+        self.meter_provider.as_ref()
+    }
+
+    /// Share the runtime sampling ratio with the tracer provider's sampler.
+    pub(crate) fn with_sampling_override(
+        mut self,
+        sampling_override: Arc<SamplingOverride>,
+    ) -> Self {
+        self.sampling_override = sampling_override;
+        self
+    }
+
+    /// Share the cardinality analyzer fed by the tracer provider.
+    pub(crate) fn with_cardinality(mut self, analyzer: Option<Arc<CardinalityAnalyzer>>) -> Self {
+        self.cardinality = analyzer;
+        self
+    }
+
+    /// Share the entry point into the providers' processor chains.
+    pub(crate) fn with_ingest(mut self, ingest: Arc<IngestPoint>) -> Self {
+        self.ingest = ingest;
+        self
+    }
+
+    /// Share the queue the log layer hands records to, with
+    /// [`async_bridge`](crate::OtelOptionsBuilder::async_bridge) set.
+    pub(crate) fn with_log_offload(mut self, offload: Option<Arc<LogOffload>>) -> Self {
+        self.log_offload = offload;
+        self
+    }
+
+    pub(crate) fn log_offload(&self) -> Option<&Arc<LogOffload>> {
+        self.log_offload.as_ref()
+    }
+
+    /// Share the point the providers hand telemetry to their processors through, for
+    /// [`reinit_after_fork`](Self::reinit_after_fork).
+    pub(crate) fn with_fork(mut self, fork: Arc<ForkPoint>) -> Self {
+        self.fork = Some(fork);
+        self
+    }
+
+    /// Keep the remote sampling poller running until the guard shuts down.
+    #[cfg(feature = "remote-sampling")]
+    pub(crate) fn with_remote_sampling(
+        mut self,
+        poller: Option<crate::samplers::remote::RemoteSamplingPoller>,
+    ) -> Self {
+        self.remote_sampling = poller;
+        self
+    }
+
+    /// Keep the OpAMP client connected until the guard shuts down.
+    #[cfg(feature = "opamp")]
+    pub(crate) fn with_opamp(mut self, client: Option<crate::opamp::OpampClient>) -> Self {
+        self.opamp = client;
+        self
+    }
+
+    /// Keep the OTLP receiver listening until the guard shuts down.
+    #[cfg(feature = "receiver")]
+    pub(crate) fn with_receiver(mut self, receiver: Option<crate::receiver::Receiver>) -> Self {
+        self.receiver = receiver;
+        self
+    }
+
+    /// Keep profiling until the guard shuts down.
+    #[cfg(all(feature = "profiling", unix))]
+    pub(crate) fn with_profiler(mut self, profiler: Option<crate::profiling::Profiler>) -> Self {
+        self.profiler = profiler;
+        self
+    }
+
+    #[cfg(all(feature = "profiling", unix))]
+    pub(crate) fn profiler(&self) -> Option<&crate::profiling::Profiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Keep the runtime that drives gRPC exports alive until the providers are shut down.
+    #[cfg(feature = "grpc")]
+    pub(crate) fn with_export_runtime(
+        mut self,
+        export_runtime: Option<crate::export_runtime::ExportRuntime>,
+    ) -> Self {
+        self.export_runtime = export_runtime;
+        self
+    }
+
+    /// Start the periodic flush thread, if `interval` is set. Must run after all providers
+    /// are attached.
+    pub(crate) fn with_flush_watchdog(
+        mut self,
+        interval: Option<Duration>,
+    ) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        if let Some(interval) = interval {
+            let watchdog = FlushWatchdog::start(
+                interval,
+                self.tracer_provider.clone(),
+                self.logger_provider.clone(),
+                #[cfg(feature = "metrics")]
+                self.meter_provider.clone(),
+                Arc::clone(&self.health),
+            )
+            .context("Failed to start the flush watchdog thread")?;
+            self.watchdog = Some(watchdog);
+        }
+        Ok(self)
+    }
+
+    /// Hold `resources` handed over during setup with
+    /// [`OtelOptionsBuilder::keep_alive`](crate::OtelOptionsBuilder::keep_alive).
+    pub(crate) fn with_auxiliary(self, resources: Vec<AuxiliaryGuard>) -> Self {
+        for resource in resources {
+            self.keep_alive(resource);
+        }
+        self
+    }
+
+    /// Keep `resource` (a `tracing_appender` `WorkerGuard`, a metrics poller, a signal
+    /// handler registration) alive until the guard shuts down, then drop it after the
+    /// providers have flushed, most recently added first.
+    ///
+    /// ```
+    /// let guard = raccoon_otel::OtelGuard::noop();
+    /// let (stop, stopped) = std::sync::mpsc::channel::<()>();
+    /// guard.keep_alive(stop);
+    /// drop(guard);
+    /// assert!(stopped.recv().is_err());
+    /// ```
+    pub fn keep_alive(&self, resource: impl Any + Send) {
+        self.auxiliary
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Box::new(resource));
+    }
+
+    /// Sample new traces at `ratio` (clamped to 0.0..=1.0) from now on, e.g. to raise it to
+    /// 1.0 during an incident without redeploying.
+    ///
+    /// Replaces the configured sampler, including [sampling
+    /// rules](crate::OtelOptionsBuilder::sampling_rule) and
+    /// [adaptive sampling](crate::OtelOptionsBuilder::adaptive_sampling), for root spans;
+    /// child spans keep following their parent, so traces already started aren't cut. Takes
+    /// effect immediately on every thread.
+    ///
+    /// ```no_run
+    /// let guard = raccoon_otel::setup_otel("checkout", None)?;
+    /// guard.set_sampling_ratio(1.0);
+    /// // ... investigate ...
+    /// guard.reset_sampling_ratio();
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn set_sampling_ratio(&self, ratio: f64) {
+        self.sampling_override.set(Some(ratio));
+    }
+
+    /// Go back to the configured sampler after [`set_sampling_ratio`](Self::set_sampling_ratio).
+    pub fn reset_sampling_ratio(&self) {
+        self.sampling_override.set(None);
+    }
+
+    /// Counters for telemetry dropped instead of exported, e.g. over the export budget.
+    pub fn stats(&self) -> ExportStats {
+        self.stats.snapshot()
+    }
+
+    /// Recent span and log export outcomes, for readiness and health endpoints.
+    pub fn health(&self) -> HealthStatus {
+        self.health.snapshot()
+    }
+
+    /// `true` unless the last few span or log exports all failed.
+    ///
+    /// See [`health`](Self::health) for details such as the last error.
+    pub fn is_healthy(&self) -> bool {
+        self.health.snapshot().healthy
+    }
+
+    /// Queue depths, exported batches, the last export and its error, and drop counters in
+    /// one value, for an admin endpoint such as `/debug/telemetry`.
+    pub fn snapshot(&self) -> PipelineSnapshot {
+        PipelineSnapshot::new(&self.stats, &self.health)
+    }
+
+    /// Span names and attribute value cardinality over the last complete window, with
+    /// [`cardinality_report`](crate::OtelOptionsBuilder::cardinality_report) set.
+    ///
+    /// `None` if the option isn't set or the first window hasn't ended yet.
+    pub fn cardinality_report(&self) -> Option<CardinalityReport> {
+        self.cardinality.as_ref()?.report()
+    }
+
+    /// Spans and log records counted since setup in
+    /// [`benchmark_mode`](crate::OtelOptionsBuilder::benchmark_mode), with per-second rates.
+    ///
+    /// Flushes the span and log batch processors first, so everything recorded before the
+    /// call is counted. All counts are zero outside benchmark mode.
+    pub fn throughput_report(&self) -> ThroughputReport {
+        if let Some(ref provider) = self.tracer_provider {
+            let _ = provider.force_flush();
+        }
+        if let Some(ref provider) = self.logger_provider {
+            let _ = provider.force_flush();
+        }
+        self.stats.throughput(self.started.elapsed())
+    }
+
+    /// Export spans built outside `tracing`, e.g. translated from a device's own telemetry
+    /// by a gateway, through this pipeline's span transforms, batching, retries and
+    /// resource.
+    ///
+    /// The spans are taken as they are: they aren't sampled again, and keep their own trace
+    /// and span IDs, times and instrumentation scope. They're dropped if traces are
+    /// disabled.
+    ///
+    /// ```no_run
+    /// use raccoon_otel::re_exports::opentelemetry_sdk::trace::SpanData;
+    ///
+    /// # fn translate(reading: &[u8]) -> SpanData { unimplemented!() }
+    /// # let readings: Vec<Vec<u8>> = Vec::new();
+    /// let guard = raccoon_otel::setup_otel("device-gateway", None)?;
+    /// guard.ingest_spans(readings.iter().map(|reading| translate(reading)));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn ingest_spans(&self, spans: impl IntoIterator<Item = SpanData>) {
+        for span in spans {
+            if !self.ingest.span(span) {
+                return;
+            }
+        }
+    }
+
+    /// Export log records built outside `tracing` through this pipeline's log transforms,
+    /// batching, retries and resource, as emitted under `scope`.
+    ///
+    /// Start each record from [`log_record`](Self::log_record). The records are dropped if
+    /// logs are disabled.
+    ///
+    /// ```no_run
+    /// use raccoon_otel::re_exports::opentelemetry::logs::{LogRecord as _, Severity};
+    /// use raccoon_otel::re_exports::opentelemetry::InstrumentationScope;
+    /// use raccoon_otel::OtelGuard;
+    ///
+    /// let guard = raccoon_otel::setup_otel("device-gateway", None)?;
+    /// let mut record = OtelGuard::log_record();
+    /// record.set_severity_number(Severity::Warn);
+    /// record.set_body("battery low".into());
+    /// let scope = InstrumentationScope::builder("thermo-firmware").build();
+    /// guard.ingest_logs([record], &scope);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn ingest_logs(
+        &self,
+        records: impl IntoIterator<Item = SdkLogRecord>,
+        scope: &InstrumentationScope,
+    ) {
+        for mut record in records {
+            if !self.ingest.log(&mut record, scope) {
+                return;
+            }
+        }
+    }
+
+    /// The `http://host:port` endpoint of the [OTLP receiver](crate::OtelOptionsBuilder::receiver),
+    /// to hand to child processes as `OTEL_EXPORTER_OTLP_ENDPOINT` (`receiver` feature).
+    ///
+    /// `None` if the receiver isn't configured or has stopped.
+    ///
+    /// ```no_run
+    /// let guard = raccoon_otel::setup_otel("plugin-host", None)?;
+    /// let mut plugin = std::process::Command::new("./plugins/resize");
+    /// if let Some(endpoint) = guard.receiver_endpoint() {
+    ///     plugin.env("OTEL_EXPORTER_OTLP_ENDPOINT", endpoint);
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    #[cfg(feature = "receiver")]
+    pub fn receiver_endpoint(&self) -> Option<String> {
+        self.receiver
+            .as_ref()
+            .map(crate::receiver::Receiver::endpoint)
+    }
+
+    /// Rebuild the exporters and background threads in a child process created with
+    /// `fork()` after setup, e.g. by daemonizing or a pre-fork server. Call it in the child
+    /// before recording anything; it does nothing in the process that set the pipeline up.
+    ///
+    /// A forked child only keeps the thread that called `fork()`, so the batch processors,
+    /// export runtime and other background threads it inherited are gone. This builds a new
+    /// pipeline from the same configuration and hands it everything the existing layers
+    /// record. What the parent had queued is left behind, and the stats, health and
+    /// cardinality report start over. The [OTLP receiver](crate::OtelOptionsBuilder::receiver)
+    /// isn't restarted, and metrics recorded through instruments created before the fork
+    /// (including `tracing` metric fields) aren't exported from the child. Where possible,
+    /// fork before calling [`setup_otel`](crate::setup_otel) instead.
+    ///
+    /// ```no_run
+    /// # fn daemonize() -> std::io::Result<()> { Ok(()) }
+    /// let mut guard = raccoon_otel::setup_otel("worker", None)?;
+    /// daemonize()?; // forks, and continues in the child
+    /// guard.reinit_after_fork()?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pipeline can't be rebuilt, e.g. because the endpoint is
+    /// unreachable with [`VerifyMode::FailFast`](crate::VerifyMode::FailFast). The guard is
+    /// left as it was.
+    pub fn reinit_after_fork(&mut self) -> anyhow::Result<()> {
+        match self.fork {
+            Some(ref fork) if fork.forked() => self.reinit(),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn fork_point(&self) -> Option<&Arc<ForkPoint>> {
+        self.fork.as_ref()
+    }
+
+    /// Replace this guard with a rebuilt pipeline and point the providers at it.
+    fn reinit(&mut self) -> anyhow::Result<()> {
+        let Some(fork) = self.fork.clone() else {
+            return Ok(());
+        };
+        let rebuilt = crate::pipeline::OtelPipeline::rebuild_after_fork(&fork)?.into_guard();
+        if let Some(ref next) = rebuilt.fork {
+            fork.rebuilt(Arc::clone(next));
+        }
+        if let (Some(offload), Some(next)) = (&self.log_offload, &rebuilt.log_offload) {
+            offload.rebuilt(Arc::clone(next));
+        }
+        ForkPoint::abandon(std::mem::replace(self, rebuilt));
+        Ok(())
+    }
+
+    /// An empty log record to fill in for [`ingest_logs`](Self::ingest_logs).
+    pub fn log_record() -> SdkLogRecord {
+        crate::processors::log::blank_record()
+    }
+
     /// Explicitly flush and shut down all providers.
     ///
     /// Safe to call multiple times; subsequent calls are no-ops.
@@ -50,7 +497,29 @@ impl OtelGuard {
         self.do_shutdown();
     }
 
-    fn do_shutdown(&self) {
+    fn do_shutdown(&mut self) {
+        // Before anything is flushed, so nothing more arrives from child processes
+        #[cfg(feature = "receiver")]
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.stop();
+        }
+        // Stopped first, so it can't flush a provider that is shutting down
+        if let Some(mut watchdog) = self.watchdog.take() {
+            watchdog.stop();
+        }
+        #[cfg(feature = "remote-sampling")]
+        if let Some(mut poller) = self.remote_sampling.take() {
+            poller.stop();
+        }
+        #[cfg(feature = "opamp")]
+        if let Some(mut client) = self.opamp.take() {
+            client.stop();
+        }
+        #[cfg(all(feature = "profiling", unix))]
+        if let Some(mut profiler) = self.profiler.take() {
+            profiler.stop();
+        }
+
         if let Some(ref tp) = self.tracer_provider {
             if let Err(e) = tp.force_flush() {
                 eprintln!("raccoon-otel: error flushing tracer provider: {e}");
@@ -68,14 +537,138 @@ impl OtelGuard {
                 eprintln!("raccoon-otel: error shutting down logger provider: {e}");
             }
         }
+
+        #[cfg(feature = "metrics")]
+        if let Some(ref mp) = self.meter_provider {
+            if let Err(e) = mp.force_flush() {
+                eprintln!("raccoon-otel: error flushing meter provider: {e}");
+            }
+            if let Err(e) = mp.shutdown() {
+                eprintln!("raccoon-otel: error shutting down meter provider: {e}");
+            }
+        }
+
+        // After the flush, as e.g. a file appender's worker still has events to write
+        let mut auxiliary =
+            std::mem::take(self.auxiliary.get_mut().unwrap_or_else(|e| e.into_inner()));
+        while let Some(resource) = auxiliary.pop() {
+            drop(resource);
+        }
+
+        // Last, since the providers' final exports run on it
+        #[cfg(feature = "grpc")]
+        drop(self.export_runtime.take());
     }
 }
 
 impl Drop for OtelGuard {
     fn drop(&mut self) {
-        if !self.shutdown_called {
-            self.shutdown_called = true;
+        if self.shutdown_called {
+            return;
+        }
+        self.shutdown_called = true;
+        if in_async_runtime() {
+            self.shutdown_off_runtime();
+        } else {
             self.do_shutdown();
         }
     }
 }
+
+impl OtelGuard {
+    /// Shut down on a dedicated thread, waiting at most [`ASYNC_DROP_TIMEOUT`] for it.
+    fn shutdown_off_runtime(&mut self) {
+        // Moved out, so the thread owns what it shuts down; the moved guard is marked shut
+        // down, so dropping it never gets back here
+        let mut guard = std::mem::replace(self, Self::noop());
+        let (done, finished) = mpsc::channel::<()>();
+        let spawned = std::thread::Builder::new()
+            .name("raccoon-otel-shutdown".into())
+            .spawn(move || {
+                guard.do_shutdown();
+                let _ = done.send(());
+            });
+        if let Err(e) = spawned {
+            eprintln!("raccoon-otel: failed to start the shutdown thread, telemetry lost: {e}");
+            return;
+        }
+        if let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(ASYNC_DROP_TIMEOUT) {
+            eprintln!(
+                "raccoon-otel: shutdown still running after {}s, not waiting for it; \
+                 pending telemetry may be lost",
+                ASYNC_DROP_TIMEOUT.as_secs()
+            );
+        }
+    }
+}
+
+/// Whether this thread is running a tokio runtime, or inside one entered with
+/// `Handle::enter`.
+#[cfg(any(
+    feature = "rt-tokio",
+    feature = "rt-tokio-current-thread",
+    feature = "grpc"
+))]
+fn in_async_runtime() -> bool {
+    tokio::runtime::Handle::try_current().is_ok()
+}
+
+#[cfg(not(any(
+    feature = "rt-tokio",
+    feature = "rt-tokio-current-thread",
+    feature = "grpc"
+)))]
+fn in_async_runtime() -> bool {
+    false
+}
+
+#[cfg(all(
+    test,
+    any(
+        feature = "rt-tokio",
+        feature = "rt-tokio-current-thread",
+        feature = "grpc"
+    )
+))]
+mod tests {
+    use opentelemetry::Context;
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::trace::{SpanData, SpanProcessor};
+
+    use super::*;
+
+    /// Records the name of the thread the provider is shut down on.
+    #[derive(Debug, Default)]
+    struct ShutdownThread(Arc<Mutex<Option<String>>>);
+
+    impl SpanProcessor for ShutdownThread {
+        fn on_start(&self, _: &mut opentelemetry_sdk::trace::Span, _: &Context) {}
+
+        fn on_end(&self, _: SpanData) {}
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _: Duration) -> OTelSdkResult {
+            let name = std::thread::current().name().map(str::to_owned);
+            *self.0.lock().unwrap() = name;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn dropping_on_a_runtime_thread_shuts_down_elsewhere() {
+        let thread = Arc::default();
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(ShutdownThread(Arc::clone(&thread)))
+            .build();
+        let guard = OtelGuard::new(Some(provider), None, Arc::default(), Arc::default());
+
+        drop(guard);
+        assert_eq!(
+            thread.lock().unwrap().as_deref(),
+            Some("raccoon-otel-shutdown")
+        );
+    }
+}
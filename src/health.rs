@@ -0,0 +1,134 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Consecutive failed exports after which the pipeline is reported unhealthy.
+const UNHEALTHY_AFTER_FAILURES: u64 = 3;
+
+/// Snapshot of recent export outcomes, for use in application health endpoints.
+///
+/// Obtained from [`OtelGuard::health`](crate::OtelGuard::health). Covers span and log
+/// exports, plus flushes the [flush watchdog](crate::OtelOptionsBuilder::flush_watchdog)
+/// saw time out; a pipeline that hasn't exported anything yet is healthy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HealthStatus {
+    /// `false` once several exports in a row have failed; reset by the next success.
+    pub healthy: bool,
+    /// Failed exports since the last successful one.
+    pub consecutive_failures: u64,
+    /// When an export last succeeded.
+    pub last_success: Option<SystemTime>,
+    /// When an export last failed.
+    pub last_failure: Option<SystemTime>,
+    /// The error from the most recent failed export.
+    pub last_error: Option<String>,
+}
+
+/// Live export outcomes shared between the exporters and the guard.
+#[derive(Debug, Default)]
+pub(crate) struct ExportHealth {
+    consecutive_failures: AtomicU64,
+    // Milliseconds since the Unix epoch; 0 means never
+    last_success_ms: AtomicU64,
+    last_failure_ms: AtomicU64,
+    last_error: Mutex<Option<String>>,
+    span_batches_exported: AtomicU64,
+    log_batches_exported: AtomicU64,
+}
+
+impl ExportHealth {
+    pub(crate) fn record<E: std::fmt::Display>(&self, signal: &str, result: Result<(), &E>) {
+        let now = now_ms();
+        match result {
+            Ok(()) => {
+                self.consecutive_failures.store(0, Ordering::Relaxed);
+                match signal {
+                    "spans" => &self.span_batches_exported,
+                    _ => &self.log_batches_exported,
+                }
+                .fetch_add(1, Ordering::Relaxed);
+                // Exporters connect lazily, so this is the first sign the endpoint is reachable
+                if self.last_success_ms.swap(now, Ordering::Relaxed) == 0 {
+                    eprintln!(
+                        "raccoon-otel: first {signal} export succeeded, \
+                         the OTLP endpoint is reachable"
+                    );
+                }
+            }
+            Err(e) => {
+                self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+                self.last_failure_ms.store(now, Ordering::Relaxed);
+                *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) =
+                    Some(format!("{signal} export failed: {e}"));
+            }
+        }
+    }
+
+    /// Whether fewer than [`UNHEALTHY_AFTER_FAILURES`] exports in a row have failed.
+    pub(crate) fn is_healthy(&self) -> bool {
+        self.consecutive_failures.load(Ordering::Relaxed) < UNHEALTHY_AFTER_FAILURES
+    }
+
+    pub(crate) fn snapshot(&self) -> HealthStatus {
+        let consecutive_failures = self.consecutive_failures.load(Ordering::Relaxed);
+        HealthStatus {
+            healthy: consecutive_failures < UNHEALTHY_AFTER_FAILURES,
+            consecutive_failures,
+            last_success: from_ms(self.last_success_ms.load(Ordering::Relaxed)),
+            last_failure: from_ms(self.last_failure_ms.load(Ordering::Relaxed)),
+            last_error: self
+                .last_error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        }
+    }
+
+    /// Span batches exported successfully.
+    pub(crate) fn span_batches_exported(&self) -> u64 {
+        self.span_batches_exported.load(Ordering::Relaxed)
+    }
+
+    /// Log batches exported successfully.
+    pub(crate) fn log_batches_exported(&self) -> u64 {
+        self.log_batches_exported.load(Ordering::Relaxed)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+}
+
+fn from_ms(ms: u64) -> Option<SystemTime> {
+    (ms != 0).then(|| UNIX_EPOCH + Duration::from_millis(ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_failures_turn_unhealthy_until_a_success() {
+        let health = ExportHealth::default();
+        assert!(health.snapshot().healthy);
+
+        for _ in 0..UNHEALTHY_AFTER_FAILURES {
+            health.record("spans", Err(&"connection refused"));
+        }
+        let status = health.snapshot();
+        assert!(!status.healthy);
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some("spans export failed: connection refused")
+        );
+
+        health.record::<&str>("logs", Ok(()));
+        let status = health.snapshot();
+        assert!(status.healthy);
+        assert_eq!(status.consecutive_failures, 0);
+        assert!(status.last_success.is_some());
+    }
+}
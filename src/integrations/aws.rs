@@ -0,0 +1,182 @@
+//! Client spans for AWS SDK for Rust calls. Requires the `aws-sdk` feature.
+//!
+//! Register [`TracingInterceptor`] on any SDK client config to get one `client` span per
+//! operation (e.g. `S3.GetObject`) with `rpc.service`, `rpc.method`, `aws.request_id` and
+//! the HTTP status. The active trace context is injected as `traceparent` (through the
+//! global propagator) and as an `X-Amzn-Trace-Id` header, so X-Ray and W3C backends can
+//! both stitch the call into the trace.
+//!
+//! ```ignore
+//! use raccoon_otel::integrations::aws::TracingInterceptor;
+//!
+//! let config = aws_config::load_from_env().await;
+//! let s3 = aws_sdk_s3::Client::from_conf(
+//!     aws_sdk_s3::config::Builder::from(&config)
+//!         .interceptor(TracingInterceptor::new())
+//!         .build(),
+//! );
+//! ```
+
+use std::collections::HashMap;
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::context::{
+    BeforeSerializationInterceptorContextRef, BeforeTransmitInterceptorContextMut,
+    FinalizerInterceptorContextRef,
+};
+use aws_smithy_runtime_api::client::interceptors::Intercept;
+use aws_smithy_runtime_api::client::orchestrator::Metadata;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
+use opentelemetry::trace::{SpanContext, TraceContextExt};
+use tracing::field::Empty;
+use tracing::Span;
+
+const XRAY_HEADER: &str = "x-amzn-trace-id";
+const REQUEST_ID_HEADERS: [&str; 2] = ["x-amzn-requestid", "x-amz-request-id"];
+
+/// An SDK interceptor that traces every operation a client executes.
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct TracingInterceptor;
+
+impl TracingInterceptor {
+    /// Create the interceptor.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// The span for the current execution, carried between hooks in the config bag.
+#[derive(Debug, Clone)]
+struct OperationSpan(Span);
+
+impl Storable for OperationSpan {
+    type Storer = StoreReplace<Self>;
+}
+
+impl Intercept for TracingInterceptor {
+    fn name(&self) -> &'static str {
+        "RaccoonOtelTracingInterceptor"
+    }
+
+    fn read_before_execution(
+        &self,
+        _context: &BeforeSerializationInterceptorContextRef<'_>,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let (service, operation) = cfg
+            .load::<Metadata>()
+            .map_or(("unknown", "unknown"), |m| (m.service(), m.name()));
+        let span = tracing::info_span!(
+            "aws.operation",
+            otel.name = format!("{service}.{operation}"),
+            otel.kind = "client",
+            rpc.system = "aws-api",
+            rpc.service = service,
+            rpc.method = operation,
+            aws.request_id = Empty,
+            http.response.status_code = Empty,
+            error.type = Empty,
+            otel.status_code = Empty,
+        );
+        cfg.interceptor_state().store_put(OperationSpan(span));
+        Ok(())
+    }
+
+    fn modify_before_signing(
+        &self,
+        context: &mut BeforeTransmitInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let Some(OperationSpan(span)) = cfg.load::<OperationSpan>() else {
+            return Ok(());
+        };
+        let cx = crate::propagation::context_of(span);
+        let mut carrier = HashMap::new();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&cx, &mut carrier);
+        });
+
+        let headers = context.request_mut().headers_mut();
+        for (key, value) in carrier {
+            headers.insert(key, value);
+        }
+        let span_context = cx.span().span_context().clone();
+        if span_context.is_valid() && !headers.contains_key(XRAY_HEADER) {
+            headers.insert(XRAY_HEADER, xray_header(&span_context));
+        }
+        Ok(())
+    }
+
+    fn read_after_execution(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let Some(OperationSpan(span)) = cfg.load::<OperationSpan>() else {
+            return Ok(());
+        };
+
+        if let Some(response) = context.response() {
+            span.record("http.response.status_code", response.status().as_u16());
+            let headers = response.headers();
+            if let Some(id) = REQUEST_ID_HEADERS.iter().find_map(|h| headers.get(*h)) {
+                span.record("aws.request_id", id);
+            }
+        }
+
+        if let Some(Err(error)) = context.output_or_error() {
+            let error_type = if error.is_timeout_error() {
+                "timeout".to_owned()
+            } else if error.is_connector_error() {
+                "connector".to_owned()
+            } else {
+                context
+                    .response()
+                    .and_then(|r| r.headers().get("x-amzn-errortype"))
+                    .map(|t| t.split(':').next().unwrap_or(t).to_owned())
+                    .unwrap_or_else(|| "_OTHER".to_owned())
+            };
+            span.record("error.type", error_type);
+            span.record("otel.status_code", "ERROR");
+        }
+        Ok(())
+    }
+}
+
+/// Format a span context as an X-Ray trace header
+/// (`Root=1-<8 hex>-<24 hex>;Parent=<16 hex>;Sampled=<0|1>`).
+fn xray_header(span_context: &SpanContext) -> String {
+    let trace_id = span_context.trace_id().to_string();
+    format!(
+        "Root=1-{}-{};Parent={};Sampled={}",
+        &trace_id[..8],
+        &trace_id[8..],
+        span_context.span_id(),
+        u8::from(span_context.is_sampled()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{SpanId, TraceFlags, TraceId, TraceState};
+
+    #[test]
+    fn xray_header_splits_trace_id() {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("5759e988bd862e3fe1be46a994272793").unwrap(),
+            SpanId::from_hex("53995c3f42cd8ad8").unwrap(),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        );
+        assert_eq!(
+            xray_header(&span_context),
+            "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1"
+        );
+    }
+}
@@ -0,0 +1,9 @@
+//! Tracing integrations for third-party clients that don't emit OTel-friendly spans, and for
+//! thread pools that lose the current span.
+
+#[cfg(feature = "aws-sdk")]
+pub mod aws;
+#[cfg(feature = "rayon")]
+pub mod rayon;
+#[cfg(feature = "redis")]
+pub mod redis;
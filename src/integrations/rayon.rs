@@ -0,0 +1,189 @@
+//! Carrying the current span across rayon parallel iterators (`rayon` feature).
+//!
+//! Rayon runs iterator closures on its worker threads, where the caller's span isn't
+//! current, so every span they create starts its own trace. [`instrumented`] re-enters
+//! the caller's span, OTel context and subscriber around the work of each worker:
+//!
+//! ```no_run
+//! use rayon::prelude::*;
+//! use raccoon_otel::integrations::rayon::InstrumentedParallelIterator;
+//!
+//! let _span = tracing::info_span!("resize_images").entered();
+//! let sizes: Vec<usize> = vec![1, 2, 3]
+//!     .into_par_iter()
+//!     .instrumented()
+//!     .map(|image| {
+//!         let _span = tracing::info_span!("resize", image).entered(); // child of `resize_images`
+//!         image * 2
+//!     })
+//!     .collect();
+//! ```
+//!
+//! Only the adapters after `instrumented()` run inside the span. The result is a plain
+//! [`ParallelIterator`], so call indexed adapters such as `enumerate` or `zip` before it.
+//!
+//! [`instrumented`]: InstrumentedParallelIterator::instrumented
+
+use std::sync::Arc;
+
+use ::rayon::iter::plumbing::{Consumer, Folder, UnindexedConsumer};
+use ::rayon::iter::ParallelIterator;
+
+use crate::thread::CurrentContext;
+
+/// Adds [`instrumented`](Self::instrumented) to every rayon [`ParallelIterator`].
+pub trait InstrumentedParallelIterator: ParallelIterator {
+    /// Run the rest of the iterator chain inside the current span, OTel context and
+    /// subscriber.
+    fn instrumented(self) -> Instrumented<Self> {
+        Instrumented {
+            base: self,
+            current: Arc::new(CurrentContext::capture()),
+        }
+    }
+}
+
+impl<I: ParallelIterator> InstrumentedParallelIterator for I {}
+
+/// Parallel iterator returned by [`InstrumentedParallelIterator::instrumented`].
+#[derive(Debug)]
+pub struct Instrumented<I> {
+    base: I,
+    current: Arc<CurrentContext>,
+}
+
+impl<I: ParallelIterator> ParallelIterator for Instrumented<I> {
+    type Item = I::Item;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.base.drive_unindexed(InstrumentedConsumer {
+            base: consumer,
+            current: self.current,
+        })
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        self.base.opt_len()
+    }
+}
+
+/// Hands out [`InstrumentedFolder`]s, one per piece of work a worker picks up.
+struct InstrumentedConsumer<C> {
+    base: C,
+    current: Arc<CurrentContext>,
+}
+
+impl<T, C: Consumer<T>> Consumer<T> for InstrumentedConsumer<C> {
+    type Folder = InstrumentedFolder<C::Folder>;
+    type Reducer = C::Reducer;
+    type Result = C::Result;
+
+    fn split_at(self, index: usize) -> (Self, Self, Self::Reducer) {
+        let (left, right, reducer) = self.base.split_at(index);
+        let left = Self {
+            base: left,
+            current: Arc::clone(&self.current),
+        };
+        let right = Self {
+            base: right,
+            current: self.current,
+        };
+        (left, right, reducer)
+    }
+
+    fn into_folder(self) -> Self::Folder {
+        InstrumentedFolder {
+            base: self.base.into_folder(),
+            current: self.current,
+        }
+    }
+
+    fn full(&self) -> bool {
+        self.base.full()
+    }
+}
+
+impl<T, C: UnindexedConsumer<T>> UnindexedConsumer<T> for InstrumentedConsumer<C> {
+    fn split_off_left(&self) -> Self {
+        Self {
+            base: self.base.split_off_left(),
+            current: Arc::clone(&self.current),
+        }
+    }
+
+    fn to_reducer(&self) -> Self::Reducer {
+        self.base.to_reducer()
+    }
+}
+
+/// Runs the downstream closures inside the captured context.
+struct InstrumentedFolder<F> {
+    base: F,
+    current: Arc<CurrentContext>,
+}
+
+impl<T, F: Folder<T>> Folder<T> for InstrumentedFolder<F> {
+    type Result = F::Result;
+
+    fn consume(self, item: T) -> Self {
+        let Self { base, current } = self;
+        let base = current.in_scope(|| base.consume(item));
+        Self { base, current }
+    }
+
+    fn consume_iter<I>(self, iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        // Entered once for the whole run of items, not per item
+        let Self { base, current } = self;
+        let base = current.in_scope(|| base.consume_iter(iter));
+        Self { base, current }
+    }
+
+    fn complete(self) -> Self::Result {
+        self.base.complete()
+    }
+
+    fn full(&self) -> bool {
+        self.base.full()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::rayon::prelude::*;
+    use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn parallel_work_joins_the_callers_trace() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let parent = tracing::info_span!("parent");
+            let _entered = parent.enter();
+            let parent_trace = parent.context().span().span_context().trace_id();
+
+            let traces: Vec<_> = (0..64)
+                .into_par_iter()
+                .instrumented()
+                .map(|i| {
+                    let span = tracing::info_span!("child", i);
+                    span.context().span().span_context().trace_id()
+                })
+                .collect();
+            assert_eq!(traces.len(), 64);
+            assert!(traces.iter().all(|trace| *trace == parent_trace));
+        });
+    }
+}
@@ -0,0 +1,195 @@
+//! Client spans for [`redis`] commands. Requires the `redis` feature.
+//!
+//! Wrap any connection in [`TracedConnection`] to get one `client` span per command (or
+//! pipeline) with `db.system.name = "redis"`. Only the command name is recorded; keys
+//! and values never leave the process.
+//!
+//! ```no_run
+//! # async fn run() -> redis::RedisResult<()> {
+//! use raccoon_otel::integrations::redis::TracedConnection;
+//! use redis::AsyncCommands;
+//!
+//! let client = redis::Client::open("redis://127.0.0.1/")?;
+//! let mut conn = TracedConnection::new(client.get_multiplexed_async_connection().await?);
+//! let _: () = conn.set("user:42", "alice").await?; // span "SET"
+//! # Ok(())
+//! # }
+//! ```
+
+use redis::{Cmd, ConnectionLike, Pipeline, RedisError, RedisFuture, RedisResult, Value};
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+
+/// Longest command name recorded as-is; anything longer is reported as `UNKNOWN`.
+const MAX_COMMAND_LEN: usize = 32;
+
+/// A redis connection that opens a client span around every command it sends.
+///
+/// Works with both blocking ([`redis::ConnectionLike`]) and async
+/// ([`redis::aio::ConnectionLike`]) connections.
+#[derive(Debug, Clone)]
+pub struct TracedConnection<C> {
+    inner: C,
+}
+
+impl<C> TracedConnection<C> {
+    /// Wrap `inner`.
+    pub fn new(inner: C) -> Self {
+        Self { inner }
+    }
+
+    /// Unwrap the underlying connection.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: ConnectionLike> ConnectionLike for TracedConnection<C> {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let span = command_span(&packed_command_name(cmd), self.inner.get_db(), None);
+        let _enter = span.enter();
+        record_outcome(&span, self.inner.req_packed_command(cmd))
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let span = command_span("PIPELINE", self.inner.get_db(), Some(count));
+        let _enter = span.enter();
+        record_outcome(&span, self.inner.req_packed_commands(cmd, offset, count))
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+
+    fn supports_pipelining(&self) -> bool {
+        self.inner.supports_pipelining()
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.inner.check_connection()
+    }
+
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+}
+
+impl<C: redis::aio::ConnectionLike + Send> redis::aio::ConnectionLike for TracedConnection<C> {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let name = cmd.args_iter().next().map_or_else(
+            || "UNKNOWN".to_owned(),
+            |arg| match arg {
+                redis::Arg::Simple(bytes) => sanitize_command(bytes),
+                redis::Arg::Cursor => "UNKNOWN".to_owned(),
+            },
+        );
+        let span = command_span(&name, self.inner.get_db(), None);
+        Box::pin(
+            async move {
+                let result = self.inner.req_packed_command(cmd).await;
+                record_outcome(&Span::current(), result)
+            }
+            .instrument(span),
+        )
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let span = command_span("PIPELINE", self.inner.get_db(), Some(cmd.len()));
+        Box::pin(
+            async move {
+                let result = self.inner.req_packed_commands(cmd, offset, count).await;
+                record_outcome(&Span::current(), result)
+            }
+            .instrument(span),
+        )
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.get_db()
+    }
+}
+
+fn command_span(command: &str, db: i64, batch_size: Option<usize>) -> Span {
+    tracing::info_span!(
+        "redis.command",
+        otel.name = command,
+        otel.kind = "client",
+        db.system.name = "redis",
+        db.operation.name = command,
+        db.namespace = db,
+        db.operation.batch.size = batch_size,
+        error.type = Empty,
+        otel.status_code = Empty,
+    )
+}
+
+fn record_outcome<T>(span: &Span, result: RedisResult<T>) -> RedisResult<T> {
+    if let Err(ref error) = result {
+        span.record("error.type", error_type(error));
+        span.record("otel.status_code", "ERROR");
+    }
+    result
+}
+
+fn error_type(error: &RedisError) -> String {
+    error
+        .code()
+        .map_or_else(|| format!("{:?}", error.kind()), str::to_owned)
+}
+
+/// Extract the command name from a RESP-encoded command (`*<n>\r\n$<len>\r\n<name>\r\n...`).
+fn packed_command_name(packed: &[u8]) -> String {
+    let mut lines = packed
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+    let name = match (lines.next(), lines.next(), lines.next()) {
+        (Some(array), Some(len), Some(name))
+            if array.starts_with(b"*") && len.starts_with(b"$") =>
+        {
+            name
+        }
+        _ => return "UNKNOWN".to_owned(),
+    };
+    sanitize_command(name)
+}
+
+/// Upper-case an ASCII command name, rejecting anything that doesn't look like one so
+/// user data can't leak into span names.
+fn sanitize_command(name: &[u8]) -> String {
+    if name.is_empty()
+        || name.len() > MAX_COMMAND_LEN
+        || !name
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'_' || *b == b'.')
+    {
+        return "UNKNOWN".to_owned();
+    }
+    String::from_utf8_lossy(name).to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_name_is_read_from_packed_command() {
+        let packed = redis::cmd("get").arg("user:42").get_packed_command();
+        assert_eq!(packed_command_name(&packed), "GET");
+    }
+
+    #[test]
+    fn non_command_bytes_are_not_recorded() {
+        assert_eq!(sanitize_command(b"user:42 secret"), "UNKNOWN");
+        assert_eq!(packed_command_name(b"garbage"), "UNKNOWN");
+    }
+}
@@ -0,0 +1,265 @@
+//! One-call telemetry for batch jobs and cron tasks.
+
+use std::future::Future;
+use std::time::Instant;
+
+use opentelemetry::trace::TraceContextExt;
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::options::{Callback, JobRunLoadFn, JobRunStoreFn, OtelOptions};
+
+/// The previous-run lookup and store set via
+/// [`link_job_runs`](crate::OtelOptionsBuilder::link_job_runs).
+type JobRunHistory = (Callback<JobRunLoadFn>, Callback<JobRunStoreFn>);
+
+/// Handle passed to the job closure of [`run_job`].
+#[derive(Debug, Clone)]
+pub struct JobContext {
+    name: String,
+    span: Span,
+}
+
+impl JobContext {
+    /// The job name given to [`run_job`].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The job's root span, e.g. to record fields declared on it or to parent work
+    /// spawned onto other tasks.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+/// Run a batch job with telemetry set up for its whole lifetime.
+///
+/// Sets up telemetry with `name` as the service name, runs `job` inside a root span named
+/// after the job, and records the outcome as the span status plus a summary event
+/// (`job.outcome`, `job.duration_ms`, and the error on failure). Telemetry is then flushed
+/// and shut down before returning, so short-lived processes don't lose their last batch.
+///
+/// ```no_run
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     raccoon_otel::run_job("nightly-sync", None, |cx| async move {
+///         tracing::info!(job = cx.name(), "syncing");
+///         Ok::<_, anyhow::Error>(())
+///     })
+///     .await
+/// }
+/// ```
+///
+/// With [`link_job_runs`](crate::OtelOptionsBuilder::link_job_runs), the root span is
+/// linked to the previous run's.
+///
+/// The final flush blocks the current thread, so call this from the top of `main` rather
+/// than from inside a shared runtime's worker tasks.
+///
+/// # Errors
+///
+/// Returns the job's error, or an error if telemetry setup fails (see [`setup_otel`](crate::setup_otel));
+/// in that case the job is not run.
+pub async fn run_job<F, Fut, T, E>(
+    name: &str,
+    options: Option<OtelOptions>,
+    job: F,
+) -> anyhow::Result<T>
+where
+    F: FnOnce(JobContext) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Into<anyhow::Error>,
+{
+    let history = options
+        .as_ref()
+        .and_then(|options| options.job_run_history.clone());
+    let mut guard = crate::setup_otel(name, options)?;
+    let result = run_in_span(name, history.as_ref(), job).await;
+    // The root span has ended, so it is part of the final batch
+    guard.shutdown();
+    result
+}
+
+/// Run `job` inside the job's root span and record its outcome, ending the span before
+/// returning.
+async fn run_in_span<F, Fut, T, E>(
+    name: &str,
+    history: Option<&JobRunHistory>,
+    job: F,
+) -> anyhow::Result<T>
+where
+    F: FnOnce(JobContext) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Into<anyhow::Error>,
+{
+    let span = tracing::info_span!(
+        "job",
+        otel.name = name,
+        otel.kind = "internal",
+        job.name = name,
+        otel.status_code = Empty,
+        otel.status_description = Empty,
+    );
+    if let Some((load, _)) = history {
+        let previous = (load.0)(name).and_then(|stored| crate::from_traceparent(&stored));
+        if let Some(previous) = previous {
+            span.add_link(previous.span().span_context().clone());
+        }
+    }
+    let cx = JobContext {
+        name: name.to_owned(),
+        span: span.clone(),
+    };
+    let started = Instant::now();
+    let result = job(cx).instrument(span.clone()).await.map_err(Into::into);
+    let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+    match result {
+        Ok(_) => {
+            span.record("otel.status_code", "OK");
+            tracing::info!(
+                parent: &span,
+                job.name = name,
+                job.outcome = "success",
+                job.duration_ms = duration_ms,
+                "job {name} succeeded in {duration_ms}ms"
+            );
+        }
+        Err(ref e) => {
+            let message = format!("{e:#}");
+            span.record("otel.status_code", "ERROR");
+            span.record("otel.status_description", message.as_str());
+            tracing::error!(
+                parent: &span,
+                job.name = name,
+                job.outcome = "failure",
+                job.duration_ms = duration_ms,
+                error.message = message.as_str(),
+                "job {name} failed after {duration_ms}ms: {message}"
+            );
+        }
+    }
+
+    if let Some((_, store)) = history {
+        if let Some(traceparent) = crate::propagation::traceparent(&span.context()) {
+            (store.0)(name, &traceparent);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry::trace::{Status, TracerProvider as _};
+    use opentelemetry::{KeyValue, Value};
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    /// Runs `job` as "nightly-sync", returning its result and the exported job span.
+    async fn run<T>(
+        history: Option<&JobRunHistory>,
+        job: impl Future<Output = anyhow::Result<T>>,
+    ) -> (anyhow::Result<T>, SpanData) {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _default = tracing::subscriber::set_default(subscriber);
+
+        let result = run_in_span("nightly-sync", history, |_| job).await;
+        let mut spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 1);
+        (result, spans.remove(0))
+    }
+
+    fn attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a Value> {
+        attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| &kv.value)
+    }
+
+    #[tokio::test]
+    async fn successful_jobs_record_an_ok_root_span() {
+        let (result, span) = run(None, async { Ok(42) }).await;
+        assert_eq!(result.unwrap(), 42);
+
+        assert_eq!(span.name, "nightly-sync");
+        assert_eq!(span.parent_span_id, opentelemetry::trace::SpanId::INVALID);
+        assert_eq!(span.status, Status::Ok);
+        assert_eq!(
+            attribute(&span.attributes, "job.name"),
+            Some(&Value::from("nightly-sync"))
+        );
+        assert!(span.links.is_empty());
+        let summary = &span.events[0];
+        assert_eq!(
+            attribute(&summary.attributes, "job.outcome"),
+            Some(&Value::from("success"))
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_jobs_set_an_error_status() {
+        let (result, span) = run(None, async { Err::<(), _>(anyhow::anyhow!("disk full")) }).await;
+        assert_eq!(result.unwrap_err().to_string(), "disk full");
+
+        assert_eq!(span.status, Status::error("disk full"));
+        let summary = &span.events[0];
+        assert_eq!(
+            attribute(&summary.attributes, "job.outcome"),
+            Some(&Value::from("failure"))
+        );
+        assert_eq!(
+            attribute(&summary.attributes, "error.message"),
+            Some(&Value::from("disk full"))
+        );
+    }
+
+    #[tokio::test]
+    async fn runs_link_the_previous_run_and_store_their_own() {
+        const PREVIOUS: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let stored = Arc::new(Mutex::new(Vec::new()));
+        let store = Arc::clone(&stored);
+        let history: JobRunHistory = (
+            Callback(Arc::new(|job: &str| {
+                assert_eq!(job, "nightly-sync");
+                Some(PREVIOUS.to_owned())
+            })),
+            Callback(Arc::new(move |job: &str, traceparent: &str| {
+                store
+                    .lock()
+                    .unwrap()
+                    .push((job.to_owned(), traceparent.to_owned()));
+            })),
+        );
+        let (result, span) = run(Some(&history), async {
+            Err::<(), _>(anyhow::anyhow!("disk full"))
+        })
+        .await;
+        assert!(result.is_err());
+
+        let previous = crate::from_traceparent(PREVIOUS).unwrap();
+        assert_eq!(span.links.len(), 1);
+        assert_eq!(span.links[0].span_context, *previous.span().span_context());
+
+        // Stored whatever the outcome, for the next run to link to
+        let traceparent = format!(
+            "00-{}-{}-01",
+            span.span_context.trace_id(),
+            span.span_context.span_id()
+        );
+        assert_eq!(
+            *stored.lock().unwrap(),
+            [("nightly-sync".to_owned(), traceparent)]
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use std::any::TypeId;
+use std::time::SystemTime;
+
+use opentelemetry::trace::TraceContextExt;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::{LookupSpan, SpanRef};
+use tracing_subscriber::{Layer, Registry};
+
+use crate::processors::monotonic_clock::{self, MonotonicClock};
+
+/// The sampler's decision for a span, read once when the span is first entered.
+struct Sampled(bool);
+
+/// Marks a span the wrapped layer never saw.
+struct Skipped;
+
+/// The OTel context of the nearest recorded ancestor of a skipped span, or of an unsampled
+/// span itself, so context propagated from beneath it continues its trace.
+struct UnsampledContext(opentelemetry::Context);
+
+/// The span's start and last exit, read from the monotonic clock.
+struct SpanTimes {
+    start: SystemTime,
+    end: Option<SystemTime>,
+}
+
+/// Sits in front of the OTel trace layer: parents root spans to the trace context inherited
+/// from a parent process, and keeps spans beneath an unsampled span away from it.
+///
+/// For `skip_unsampled`: `tracing-opentelemetry` records every field before the sampler
+/// runs, since the sampler may look at them. A trace's first span is therefore always
+/// recorded, but once it is known to be unsampled its descendants, their fields and their
+/// events are never visited. Skipped spans carry the context of that recorded ancestor
+/// instead (see [`skipped_context`]). The decision is read from the OTel context the wrapped layer
+/// attaches on enter, so spans created before their parent is entered are still recorded.
+///
+/// `process_parent` is attached while a root span is created, so the wrapped layer, which
+/// parents root spans to the current OTel context, continues the parent process's trace.
+///
+/// With a `clock`, span times are read from it and handed to the
+/// [`MonotonicClockSpanProcessor`](monotonic_clock::MonotonicClockSpanProcessor) while the
+/// wrapped layer ends the span.
+pub(crate) struct OtelBridgeLayer<L> {
+    inner: L,
+    skip_unsampled: bool,
+    process_parent: Option<opentelemetry::Context>,
+    clock: Option<MonotonicClock>,
+}
+
+impl<L> OtelBridgeLayer<L> {
+    pub(crate) fn new(
+        inner: L,
+        skip_unsampled: bool,
+        process_parent: Option<opentelemetry::Context>,
+        clock: Option<MonotonicClock>,
+    ) -> Self {
+        Self {
+            inner,
+            skip_unsampled,
+            process_parent,
+            clock,
+        }
+    }
+
+    /// The context of the span's parent if the sampler dropped it (or it was skipped itself).
+    fn unsampled_parent<S>(
+        &self,
+        attrs: &Attributes<'_>,
+        ctx: &Context<'_, S>,
+    ) -> Option<opentelemetry::Context>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let parent = if attrs.is_contextual() {
+            ctx.lookup_current()
+        } else {
+            attrs.parent().and_then(|id| ctx.span(id))
+        }?;
+        let extensions = parent.extensions();
+        match extensions.get::<Sampled>() {
+            Some(Sampled(false)) => extensions
+                .get::<UnsampledContext>()
+                .map(|UnsampledContext(cx)| cx.clone()),
+            _ => None,
+        }
+    }
+
+    fn skipped<S>(&self, id: &Id, ctx: &Context<'_, S>) -> bool
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        self.skip_unsampled
+            && ctx
+                .span(id)
+                .is_some_and(|span| span.extensions().get::<Skipped>().is_some())
+    }
+}
+
+impl<S, L> Layer<S> for OtelBridgeLayer<L>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    L: Layer<S>,
+{
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        self.inner.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(parent_cx) = self
+            .skip_unsampled
+            .then(|| self.unsampled_parent(attrs, &ctx))
+            .flatten()
+        {
+            if let Some(span) = ctx.span(id) {
+                let mut extensions = span.extensions_mut();
+                extensions.insert(Skipped);
+                extensions.insert(Sampled(false));
+                extensions.insert(UnsampledContext(parent_cx));
+            }
+            return;
+        }
+        if let Some(clock) = &self.clock {
+            if let Some(span) = ctx.span(id) {
+                span.extensions_mut().insert(SpanTimes {
+                    start: clock.now(),
+                    end: None,
+                });
+            }
+        }
+        if let Some(parent) = &self.process_parent {
+            if attrs.is_contextual()
+                && ctx.lookup_current().is_none()
+                && !opentelemetry::Context::map_current(|cx| cx.has_active_span())
+            {
+                let _attached = parent.clone().attach();
+                self.inner.on_new_span(attrs, id, ctx);
+                return;
+            }
+        }
+        self.inner.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if !self.skipped(id, &ctx) {
+            self.inner.on_record(id, values, ctx);
+        }
+    }
+
+    fn on_follows_from(&self, id: &Id, follows: &Id, ctx: Context<'_, S>) {
+        if !self.skipped(id, &ctx) && !self.skipped(follows, &ctx) {
+            self.inner.on_follows_from(id, follows, ctx);
+        }
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if self.skip_unsampled {
+            let parent = if event.is_contextual() {
+                ctx.lookup_current()
+            } else {
+                event.parent().and_then(|id| ctx.span(id))
+            };
+            if parent.is_some_and(|span| span.extensions().get::<Skipped>().is_some()) {
+                return;
+            }
+        }
+        self.inner.on_event(event, ctx);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if self.skipped(id, &ctx) {
+            return;
+        }
+        self.inner.on_enter(id, ctx.clone());
+        if !self.skip_unsampled {
+            return;
+        }
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if span.extensions().get::<Sampled>().is_none() {
+            // The wrapped layer has just attached this span's context
+            let cx = opentelemetry::Context::current();
+            let sampled = cx.span().span_context().is_sampled();
+            let mut extensions = span.extensions_mut();
+            extensions.insert(Sampled(sampled));
+            if !sampled {
+                extensions.insert(UnsampledContext(cx));
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if self.skipped(id, &ctx) {
+            return;
+        }
+        if let Some(clock) = &self.clock {
+            if let Some(span) = ctx.span(id) {
+                if let Some(times) = span.extensions_mut().get_mut::<SpanTimes>() {
+                    times.end = Some(clock.now());
+                }
+            }
+        }
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if self.skipped(&id, &ctx) {
+            return;
+        }
+        let times = self.clock.and_then(|clock| {
+            let span = ctx.span(&id)?;
+            let extensions = span.extensions();
+            let times = extensions.get::<SpanTimes>()?;
+            Some((times.start, times.end.unwrap_or_else(|| clock.now())))
+        });
+        match times {
+            Some((start, end)) => monotonic_clock::closing(start, end, || {
+                self.inner.on_close(id, ctx);
+            }),
+            None => self.inner.on_close(id, ctx),
+        }
+    }
+
+    fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            return Some(std::ptr::from_ref(self).cast());
+        }
+        // Keeps `OpenTelemetrySpanExt` working, which downcasts to the OTel layer
+        self.inner.downcast_raw(id)
+    }
+}
+
+/// The OTel context of `span` if `skip_unsampled` kept it from the OTel layer: that of its
+/// nearest recorded ancestor. `OpenTelemetrySpanExt::context` has nothing for such a span.
+pub(crate) fn skipped_context(span: &tracing::Span) -> Option<opentelemetry::Context> {
+    span.with_subscriber(|(id, dispatch)| {
+        let span: SpanRef<'_, Registry> = dispatch.downcast_ref::<Registry>()?.span(id)?;
+        let extensions = span.extensions();
+        extensions.get::<Skipped>()?;
+        extensions
+            .get::<UnsampledContext>()
+            .map(|UnsampledContext(cx)| cx.clone())
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    static FORMATTED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl fmt::Debug for Counted {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            FORMATTED.fetch_add(1, Ordering::Relaxed);
+            f.write_str("counted")
+        }
+    }
+
+    #[test]
+    fn fields_beneath_an_unsampled_span_are_never_formatted() {
+        let provider = SdkTracerProvider::builder()
+            .with_sampler(Sampler::AlwaysOff)
+            .build();
+        let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("test"));
+        let subscriber =
+            tracing_subscriber::registry().with(OtelBridgeLayer::new(layer, true, None, None));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _root = tracing::info_span!("root", field = ?Counted).entered();
+            let _child = tracing::info_span!("child", field = ?Counted).entered();
+            let _grandchild = tracing::info_span!("grandchild", field = ?Counted).entered();
+            tracing::info!(field = ?Counted, "event");
+        });
+        // Only the root's field, which the sampler could have looked at
+        assert_eq!(FORMATTED.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+const ECS_VERSION: &str = "8.11.0";
+
+/// Formats console events as single-line Elastic Common Schema JSON documents.
+///
+/// Emits `@timestamp`, `log.level`, `log.logger`, `message`, `service.name`,
+/// `ecs.version`, and — for an event in a span, sampled or not — that span's `trace.id` /
+/// `span.id`, so logs of unsampled traces still correlate with each other. An explicit
+/// `parent:` wins over the current span. Event fields are added as top-level keys.
+pub(crate) struct EcsFormat {
+    service_name: String,
+}
+
+impl EcsFormat {
+    pub(crate) fn new(service_name: impl Into<String>) -> Self {
+        Self {
+            service_name: service_name.into(),
+        }
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for EcsFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+
+        let mut doc = Map::new();
+        doc.insert("@timestamp".into(), timestamp.into());
+        doc.insert(
+            "log.level".into(),
+            event.metadata().level().as_str().to_lowercase().into(),
+        );
+        doc.insert("log.logger".into(), event.metadata().target().into());
+        doc.insert("service.name".into(), self.service_name.clone().into());
+        doc.insert("ecs.version".into(), ECS_VERSION.into());
+
+        if let Some(span) = ctx.parent_span() {
+            if let Some(otel) = span.extensions().get::<OtelData>() {
+                if let (Some(trace_id), Some(span_id)) = (otel.trace_id(), otel.span_id()) {
+                    doc.insert("trace.id".into(), trace_id.to_string().into());
+                    doc.insert("span.id".into(), span_id.to_string().into());
+                }
+            }
+        }
+
+        event.record(&mut EcsVisitor(&mut doc));
+
+        let line = serde_json::to_string(&doc).map_err(|_| fmt::Error)?;
+        writeln!(writer, "{line}")
+    }
+}
+
+struct EcsVisitor<'a>(&'a mut Map<String, Value>);
+
+impl EcsVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: Value) {
+        // Reserved ECS keys set by the formatter win over same-named event fields
+        self.0.entry(field.name()).or_insert(value);
+    }
+}
+
+impl Visit for EcsVisitor<'_> {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{value:?}").into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Lines(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Lines {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Lines {
+        fn docs(&self) -> Vec<Map<String, Value>> {
+            let bytes = self.0.lock().unwrap();
+            String::from_utf8_lossy(&bytes)
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    /// Runs `f` with ECS console output and an OTel layer using `sampler`.
+    fn capture(sampler: Sampler, f: impl FnOnce()) -> Vec<Map<String, Value>> {
+        let provider = SdkTracerProvider::builder().with_sampler(sampler).build();
+        let lines = Lines::default();
+        let writer = lines.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")))
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .event_format(EcsFormat::new("checkout"))
+                    .with_writer(move || writer.clone()),
+            );
+        tracing::subscriber::with_default(subscriber, f);
+        lines.docs()
+    }
+
+    fn ids(span: &tracing::Span) -> (Value, Value) {
+        let cx = span.context();
+        let span_context = cx.span().span_context().clone();
+        (
+            span_context.trace_id().to_string().into(),
+            span_context.span_id().to_string().into(),
+        )
+    }
+
+    #[test]
+    fn ids_come_from_the_events_own_span() {
+        let mut expected = Vec::new();
+        let docs = capture(Sampler::AlwaysOn, || {
+            let current = tracing::info_span!("current");
+            let other = tracing::info_span!("other");
+            expected.push(ids(&current));
+            expected.push(ids(&other));
+            let _entered = current.enter();
+            tracing::info!("contextual");
+            tracing::info!(parent: &other, "explicit");
+            tracing::info!(parent: None, "root");
+        });
+
+        assert_eq!(docs.len(), 3);
+        for (doc, (trace_id, span_id)) in docs.iter().zip(&expected) {
+            assert_eq!(doc["trace.id"], *trace_id, "{doc:?}");
+            assert_eq!(doc["span.id"], *span_id, "{doc:?}");
+        }
+        assert_eq!(docs[2]["message"], "root");
+        assert!(!docs[2].contains_key("trace.id"));
+        assert!(!docs[2].contains_key("span.id"));
+    }
+
+    #[test]
+    fn unsampled_spans_still_carry_their_ids() {
+        let mut expected = None;
+        let docs = capture(Sampler::AlwaysOff, || {
+            let span = tracing::info_span!("unsampled");
+            assert!(!span.context().span().span_context().is_sampled());
+            expected = Some(ids(&span));
+            let _entered = span.enter();
+            tracing::info!("inside");
+        });
+
+        let (trace_id, span_id) = expected.unwrap();
+        assert_eq!(docs[0]["trace.id"], trace_id);
+        assert_eq!(docs[0]["span.id"], span_id);
+        assert_eq!(docs[0]["service.name"], "checkout");
+    }
+}
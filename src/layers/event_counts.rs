@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use tracing::{Level, Metadata};
+
+/// A `count_events` rule: the events it matches and the counter they increment.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EventCountRule {
+    target: Option<String>,
+    level: Option<Level>,
+    metric: String,
+}
+
+impl EventCountRule {
+    /// Parse a selector of comma-separated `target:<module path>` and `level:<level>`
+    /// conditions, all of which an event must meet.
+    pub(crate) fn parse(selector: &str, metric: &str) -> Result<Self, String> {
+        if metric.trim().is_empty() {
+            return Err(format!(
+                "Event counter for `{selector}` has an empty metric name"
+            ));
+        }
+        let mut rule = Self {
+            target: None,
+            level: None,
+            metric: metric.trim().to_owned(),
+        };
+        for condition in selector.split(',').map(str::trim) {
+            match condition.split_once(':') {
+                Some(("target", target)) if !target.trim().is_empty() => {
+                    rule.target = Some(target.trim().to_owned());
+                }
+                Some(("level", level)) => {
+                    let level = Level::from_str(level.trim()).map_err(|_| {
+                        format!(
+                            "Event counter selector `{selector}` has an unknown level `{level}`"
+                        )
+                    })?;
+                    rule.level = Some(level);
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid event counter selector `{selector}`: expected \
+                         `target:<module path>` and/or `level:<level>`, separated by commas"
+                    ))
+                }
+            }
+        }
+        Ok(rule)
+    }
+
+    /// Whether the event is in the target module (or beneath it) and at least as severe
+    /// as the level.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub(crate) fn matches(&self, metadata: &Metadata<'_>) -> bool {
+        let target_matches = self.target.as_deref().is_none_or(|target| {
+            metadata
+                .target()
+                .strip_prefix(target)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        });
+        // More verbose levels compare greater
+        target_matches && self.level.is_none_or(|level| *metadata.level() <= level)
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) use layer::EventCountLayer;
+
+#[cfg(feature = "metrics")]
+mod layer {
+    use opentelemetry::metrics::{Counter, MeterProvider as _};
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use tracing::{Event, Subscriber};
+    use tracing_subscriber::layer::Context;
+    use tracing_subscriber::Layer;
+
+    use super::EventCountRule;
+
+    /// Increments a counter for every event matching a rule, with the event's `level` as
+    /// an attribute. An event matching several rules increments each of their counters.
+    pub(crate) struct EventCountLayer {
+        rules: Vec<(EventCountRule, Counter<u64>)>,
+    }
+
+    impl EventCountLayer {
+        pub(crate) fn new(provider: &SdkMeterProvider, rules: &[EventCountRule]) -> Self {
+            let meter = provider.meter("raccoon-otel");
+            let rules = rules
+                .iter()
+                .map(|rule| {
+                    let counter = meter.u64_counter(rule.metric.clone()).build();
+                    (rule.clone(), counter)
+                })
+                .collect();
+            Self { rules }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for EventCountLayer {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let metadata = event.metadata();
+            for (rule, counter) in &self.rules {
+                if rule.matches(metadata) {
+                    counter.add(1, &[KeyValue::new("level", metadata.level().as_str())]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::callsite::Identifier;
+    use tracing::field::FieldSet;
+    use tracing::metadata::Kind;
+    use tracing::Callsite;
+
+    use super::*;
+
+    struct NoCallsite;
+
+    impl Callsite for NoCallsite {
+        fn set_interest(&self, _: tracing::subscriber::Interest) {}
+
+        fn metadata(&self) -> &Metadata<'_> {
+            unimplemented!()
+        }
+    }
+
+    static CALLSITE: NoCallsite = NoCallsite;
+
+    fn event(target: &'static str, level: Level) -> Metadata<'static> {
+        let fields = FieldSet::new(&[], Identifier(&CALLSITE));
+        Metadata::new(
+            "event",
+            target,
+            level,
+            None,
+            None,
+            None,
+            fields,
+            Kind::EVENT,
+        )
+    }
+
+    #[test]
+    fn rules_match_the_target_module_and_level() {
+        let rule = EventCountRule::parse("target:payment, level:warn", "payment.problems").unwrap();
+        assert!(rule.matches(&event("payment", Level::WARN)));
+        assert!(rule.matches(&event("payment::refund", Level::ERROR)));
+        assert!(!rule.matches(&event("payment::refund", Level::INFO)));
+        assert!(!rule.matches(&event("payments", Level::ERROR)));
+
+        let any_level = EventCountRule::parse("target:payment", "payment.events.total").unwrap();
+        assert!(any_level.matches(&event("payment", Level::TRACE)));
+
+        for (selector, metric) in [
+            ("payment", "m"),
+            ("target:", "m"),
+            ("level:loud", "m"),
+            ("target:payment", " "),
+        ] {
+            assert!(
+                EventCountRule::parse(selector, metric).is_err(),
+                "{selector}"
+            );
+        }
+    }
+}
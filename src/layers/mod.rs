@@ -0,0 +1,6 @@
+pub(crate) mod bridge;
+#[cfg(feature = "json")]
+pub(crate) mod ecs;
+pub(crate) mod event_counts;
+pub(crate) mod offload;
+pub(crate) mod typed_fields;
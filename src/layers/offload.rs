@@ -0,0 +1,148 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use opentelemetry::logs::{AnyValue, LogRecord as _, Logger as _};
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::Key;
+use opentelemetry_sdk::logs::{SdkLogRecord, SdkLogger};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+use crate::processors::log::LogTransform;
+use crate::processors::offload::LogOffload;
+
+/// Converts events to OTel log records like the `opentelemetry-appender-tracing` bridge,
+/// but hands them to the logger's processors on a background thread, for
+/// [`async_bridge`](crate::OtelOptionsBuilder::async_bridge).
+///
+/// Field values are copied and the trace context is read on the instrumented thread, as
+/// are the transforms in `caller_transforms`, which read that thread's context (tenant and
+/// ambient attributes). The rest of the chain then finds them already stamped. A record
+/// that doesn't fit in the queue is emitted on the instrumented thread.
+pub(crate) struct OffloadLogLayer {
+    logger: SdkLogger,
+    caller_transforms: Vec<Box<dyn LogTransform>>,
+    offload: Arc<LogOffload>,
+}
+
+impl OffloadLogLayer {
+    pub(crate) fn new(
+        logger: SdkLogger,
+        caller_transforms: Vec<Box<dyn LogTransform>>,
+        offload: Arc<LogOffload>,
+    ) -> Self {
+        Self {
+            logger,
+            caller_transforms,
+            offload,
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for OffloadLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // The logger checks this too, but on the background thread it never applies
+        if opentelemetry::Context::is_current_telemetry_suppressed() {
+            return;
+        }
+        let metadata = event.metadata();
+        let mut record = self.logger.create_log_record();
+        record.set_target(metadata.target());
+        record.set_event_name(metadata.name());
+        record.set_severity_number(crate::backfill::severity(*metadata.level()));
+        record.set_severity_text(metadata.level().as_str());
+        event.record(&mut RecordVisitor(&mut record));
+        record.set_observed_timestamp(SystemTime::now());
+        opentelemetry::Context::map_current(|cx| {
+            if cx.has_active_span() {
+                let span_context = cx.span().span_context().clone();
+                record.set_trace_context(
+                    span_context.trace_id(),
+                    span_context.span_id(),
+                    Some(span_context.trace_flags()),
+                );
+            }
+        });
+        for transform in &self.caller_transforms {
+            if !transform.apply(&mut record) {
+                return;
+            }
+        }
+        self.offload.emit(record, &self.logger);
+    }
+}
+
+/// Records event fields on a log record the way the appender bridge does: `message` as
+/// the body, anything else as an attribute.
+struct RecordVisitor<'a>(&'a mut SdkLogRecord);
+
+impl RecordVisitor<'_> {
+    fn add(&mut self, field: &Field, value: AnyValue) {
+        if field.name() == "message" {
+            self.0.set_body(value);
+        } else {
+            self.0
+                .add_attribute(Key::from_static_str(field.name()), value);
+        }
+    }
+
+    /// Integers that don't fit in an `i64` are recorded as strings, like the bridge does.
+    fn add_integer<T: TryInto<i64> + fmt::Debug + Copy>(&mut self, field: &Field, value: T) {
+        match value.try_into() {
+            Ok(value) => self.add(field, AnyValue::Int(value)),
+            Err(_) => self.add(field, AnyValue::from(format!("{value:?}"))),
+        }
+    }
+}
+
+impl Visit for RecordVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.add(field, AnyValue::from(format!("{value:?}")));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.add(field, AnyValue::from(value.to_owned()));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0
+            .add_attribute(Key::from_static_str(field.name()), value);
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0
+            .add_attribute(Key::from_static_str(field.name()), value);
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0
+            .add_attribute(Key::from_static_str(field.name()), value);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.add_integer(field, value);
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.add_integer(field, value);
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.add_integer(field, value);
+    }
+
+    fn record_bytes(&mut self, field: &Field, value: &[u8]) {
+        self.0
+            .add_attribute(Key::from_static_str(field.name()), value);
+    }
+
+    fn record_error(&mut self, _field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.0.add_attribute(
+            Key::from_static_str("exception.message"),
+            AnyValue::from(value.to_string()),
+        );
+    }
+}
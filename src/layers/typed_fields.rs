@@ -0,0 +1,354 @@
+use std::any::TypeId;
+use std::sync::OnceLock;
+
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::{KeyValue, Value};
+use tracing::dispatcher::WeakDispatch;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::{ExtensionsMut, LookupSpan};
+use tracing_subscriber::Layer;
+
+use crate::options::LargeIntegerMode;
+
+/// Typed values recorded before the OTel span was started, set on it once it is.
+struct Pending(Vec<KeyValue>);
+
+/// Re-records unsigned and 128-bit integer span fields as typed OTel attributes.
+///
+/// `tracing-opentelemetry` stringifies every integer type it has no dedicated visitor
+/// method for (`u64`, `i128`, `u128`). This layer wraps the `OpenTelemetryLayer` and sets
+/// the typed value on the OTel span after the wrapped layer recorded the string, so the
+/// duplicate-key transform keeps it at export.
+///
+/// The OTel span isn't started for it: that would fix the span's parent and sampling
+/// decision before `set_parent` is called. Values recorded before the span starts are held
+/// until it is first entered, or until it closes.
+pub(crate) struct TypedFieldsLayer<L> {
+    inner: L,
+    mode: LargeIntegerMode,
+    dispatch: OnceLock<WeakDispatch>,
+}
+
+impl<L> TypedFieldsLayer<L> {
+    pub(crate) fn new(inner: L, mode: LargeIntegerMode) -> Self {
+        Self {
+            inner,
+            mode,
+            dispatch: OnceLock::new(),
+        }
+    }
+
+    /// Set `attributes` on the span's OTel span if it has started, or hold them until it does.
+    fn record<S>(&self, id: &Id, attributes: Vec<KeyValue>, ctx: &Context<'_, S>)
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if started(&mut extensions) {
+            self.set_attributes(&mut extensions, attributes);
+        } else if let Some(Pending(pending)) = extensions.get_mut::<Pending>() {
+            pending.extend(attributes);
+        } else {
+            extensions.insert(Pending(attributes));
+        }
+    }
+
+    /// Set the held values once the OTel span has started, or, with `start`, start it.
+    fn set_pending<S>(&self, id: &Id, ctx: &Context<'_, S>, start: bool)
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if !start && !started(&mut extensions) {
+            return;
+        }
+        if let Some(Pending(attributes)) = extensions.remove::<Pending>() {
+            self.set_attributes(&mut extensions, attributes);
+        }
+    }
+
+    fn set_attributes(&self, extensions: &mut ExtensionsMut<'_>, attributes: Vec<KeyValue>) {
+        let Some(dispatch) = self.dispatch.get().and_then(WeakDispatch::upgrade) else {
+            return;
+        };
+        if let Some(cx) = tracing_opentelemetry::get_otel_context(extensions, &dispatch) {
+            let otel_span = cx.span();
+            for kv in attributes {
+                otel_span.set_attribute(kv);
+            }
+        }
+    }
+}
+
+/// Whether the wrapped layer has started the span's OTel span.
+fn started(extensions: &mut ExtensionsMut<'_>) -> bool {
+    extensions
+        .get_mut::<OtelData>()
+        .is_some_and(|data| data.trace_id().is_some())
+}
+
+impl<S, L> Layer<S> for TypedFieldsLayer<L>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    L: Layer<S>,
+{
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        let _ = self.dispatch.set(subscriber.downgrade());
+        self.inner.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(
+        &self,
+        metadata: &'static Metadata<'static>,
+    ) -> tracing::subscriber::Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_new_span(attrs, id, ctx.clone());
+        let mut visitor = IntegerVisitor::new(self.mode);
+        attrs.record(&mut visitor);
+        if !visitor.attributes.is_empty() {
+            self.record(id, visitor.attributes, &ctx);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        self.inner.on_record(id, values, ctx.clone());
+        let mut visitor = IntegerVisitor::new(self.mode);
+        values.record(&mut visitor);
+        if !visitor.attributes.is_empty() {
+            self.record(id, visitor.attributes, &ctx);
+        }
+    }
+
+    fn on_follows_from(&self, id: &Id, follows: &Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(id, follows, ctx);
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        self.inner.on_event(event, ctx);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx.clone());
+        // The wrapped layer starts the OTel span on first enter
+        self.set_pending(id, &ctx, false);
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        // The span's parent can't change anymore, and the wrapped layer would start it to end it
+        self.set_pending(&id, &ctx, true);
+        self.inner.on_close(id, ctx);
+    }
+
+    fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            return Some(std::ptr::from_ref(self).cast());
+        }
+        // Keeps `OpenTelemetrySpanExt` working, which downcasts to the OTel layer
+        self.inner.downcast_raw(id)
+    }
+}
+
+struct IntegerVisitor {
+    mode: LargeIntegerMode,
+    attributes: Vec<KeyValue>,
+}
+
+impl IntegerVisitor {
+    fn new(mode: LargeIntegerMode) -> Self {
+        Self {
+            mode,
+            attributes: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, field: &Field, value: Value) {
+        self.attributes.push(KeyValue::new(field.name(), value));
+    }
+}
+
+impl Visit for IntegerVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        let value = convert_integer(value.into(), self.mode);
+        self.push(field, value);
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        let value = convert_integer(value, self.mode);
+        self.push(field, value);
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        let value = match i128::try_from(value) {
+            Ok(v) => convert_integer(v, self.mode),
+            Err(_) => convert_out_of_range(value.to_string(), value as f64, i64::MAX, self.mode),
+        };
+        self.push(field, value);
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+/// Convert an integer to an OTel value, applying `mode` when it doesn't fit in an `i64`.
+fn convert_integer(value: i128, mode: LargeIntegerMode) -> Value {
+    match i64::try_from(value) {
+        Ok(v) => Value::I64(v),
+        Err(_) => {
+            let saturated = if value < 0 { i64::MIN } else { i64::MAX };
+            convert_out_of_range(value.to_string(), value as f64, saturated, mode)
+        }
+    }
+}
+
+fn convert_out_of_range(
+    as_string: String,
+    as_float: f64,
+    saturated: i64,
+    mode: LargeIntegerMode,
+) -> Value {
+    match mode {
+        LargeIntegerMode::String => Value::String(as_string.into()),
+        LargeIntegerMode::Float => Value::F64(as_float),
+        LargeIntegerMode::Saturate => Value::I64(saturated),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use opentelemetry::trace::{
+        SpanContext, SpanId, TraceFlags, TraceId, TraceState, TracerProvider as _,
+    };
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::trace::{SdkTracerProvider, Span, SpanData, SpanProcessor};
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Collected(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanProcessor for Collected {
+        fn on_start(&self, _: &mut Span, _: &opentelemetry::Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            self.0.lock().unwrap().push(span);
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _: Duration) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn typed_fields_dont_start_the_span_before_its_parent_is_set() {
+        let collected = Collected::default();
+        let spans = Arc::clone(&collected.0);
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(collected)
+            .build();
+        let layer = TypedFieldsLayer::new(
+            tracing_opentelemetry::layer().with_tracer(provider.tracer("test")),
+            LargeIntegerMode::String,
+        );
+        let remote = SpanContext::new(
+            TraceId::from(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736),
+            SpanId::from(0x00f0_67aa_0ba9_02b7),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            let span = tracing::info_span!("work", len = 3_u64, total = tracing::field::Empty);
+            span.set_parent(opentelemetry::Context::new().with_remote_span_context(remote.clone()))
+                .unwrap();
+            let _entered = span.enter();
+            span.record("total", 7_u64);
+        });
+
+        let spans = spans.lock().unwrap();
+        let span = &spans[0];
+        assert_eq!(span.span_context.trace_id(), remote.trace_id());
+        assert_eq!(span.parent_span_id, remote.span_id());
+        // The typed value comes after the string one, and wins at export
+        let last = |key: &str| {
+            span.attributes
+                .iter()
+                .rev()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.clone())
+        };
+        assert_eq!(last("len"), Some(Value::I64(3)));
+        assert_eq!(last("total"), Some(Value::I64(7)));
+    }
+
+    #[test]
+    fn in_range_integers_become_i64() {
+        assert_eq!(
+            convert_integer(42, LargeIntegerMode::String),
+            Value::I64(42)
+        );
+    }
+
+    #[test]
+    fn out_of_range_integers_follow_mode() {
+        let big = u64::MAX as i128;
+
+        assert_eq!(
+            convert_integer(big, LargeIntegerMode::String),
+            Value::String(u64::MAX.to_string().into())
+        );
+        assert_eq!(
+            convert_integer(big, LargeIntegerMode::Float),
+            Value::F64(u64::MAX as f64)
+        );
+        assert_eq!(
+            convert_integer(big, LargeIntegerMode::Saturate),
+            Value::I64(i64::MAX)
+        );
+    }
+}
@@ -36,17 +36,81 @@
 //! # }
 //! ```
 
+mod azure_monitor;
+mod banner;
+mod cardinality;
+mod connectivity;
+mod discovery;
 mod env;
+mod export_batch;
+#[cfg(feature = "grpc")]
+mod export_runtime;
+mod exporters;
+mod fork;
+#[cfg(feature = "gcp")]
+mod gcp;
 mod guard;
+mod health;
+mod job;
+mod layers;
+#[cfg(feature = "opamp")]
+mod opamp;
 mod options;
+mod pipeline;
+mod processors;
+#[cfg(all(feature = "profiling", unix))]
+mod profiling;
 mod providers;
+#[cfg(feature = "receiver")]
+mod receiver;
+#[cfg(feature = "record")]
+mod recording;
+mod redact;
 mod resource;
+mod samplers;
+mod sinks;
+mod snapshot;
+mod span_filter;
+mod stats;
 mod subscriber;
+mod tenant;
+mod thread;
+mod validation;
+mod watchdog;
 
+pub mod backfill;
+#[cfg(any(feature = "redis", feature = "aws-sdk", feature = "rayon"))]
+pub mod integrations;
+pub mod propagation;
 pub mod re_exports;
+pub mod semconv;
+pub mod spans;
+#[cfg(feature = "test-util")]
+pub mod testing;
+pub mod timing;
 
+pub use cardinality::{AttributeCardinality, CardinalityReport};
+pub use export_batch::ExportBatch;
 pub use guard::OtelGuard;
-pub use options::{OtelOptions, OtelOptionsBuilder, Protocol};
+pub use health::HealthStatus;
+pub use job::{run_job, JobContext};
+pub use options::{
+    ConsoleFormat, DeadLetterOutput, ExportBudget, HistogramAggregation, LargeIntegerMode,
+    MetricsTemporality, OtelOptions, OtelOptionsBuilder, ParseProtocolError, Protocol, VerifyMode,
+};
+pub use pipeline::{build_pipeline, OtelPipeline};
+pub use propagation::{current_traceparent, from_traceparent};
+#[cfg(feature = "record")]
+pub use recording::replay;
+pub use sinks::gelf::{GelfOptions, GelfTransport};
+pub use sinks::statsd::{StatsdOptions, StatsdTransport};
+pub use sinks::syslog::{SyslogFacility, SyslogOptions, SyslogTransport};
+pub use snapshot::PipelineSnapshot;
+pub use span_filter::SpanFilter;
+pub use stats::{ExportStats, ThroughputReport};
+pub use tenant::TenantOptions;
+pub use thread::{spawn_instrumented, WithCurrentContext};
+pub use validation::ValidationError;
 
 use anyhow::Context;
 
@@ -67,40 +131,60 @@ use anyhow::Context;
 /// # Errors
 ///
 /// Returns an error if:
+/// - The endpoint is not a valid HTTP(S) URL
+/// - The endpoint is unreachable and [`VerifyMode::FailFast`] is set
 /// - A required transport feature is not enabled (e.g. `grpc` or `http`)
 /// - Provider or exporter initialization fails
 /// - The global tracing subscriber has already been set
 pub fn setup_otel(service_name: &str, options: Option<OtelOptions>) -> anyhow::Result<OtelGuard> {
     let opts = options.unwrap_or_default();
-    let resolved = env::resolve_config(service_name, &opts);
+    let resolved = env::resolve_config(service_name, &opts)?;
 
     // Set up W3C trace context propagation for distributed tracing
     opentelemetry::global::set_text_map_propagator(
         opentelemetry_sdk::propagation::TraceContextPropagator::new(),
     );
 
-    let resource = resource::build_resource(&resolved.service_name, &resolved.resource_attributes);
+    let pipeline = pipeline::OtelPipeline::from_config(&resolved)?;
+    let guard = pipeline.into_guard();
 
-    let tracer_provider = if cfg!(feature = "traces") {
-        Some(
-            providers::tracer::build_tracer_provider(resource.clone(), &resolved)
-                .context("Failed to initialize tracer provider")?,
-        )
-    } else {
-        None
-    };
+    subscriber::compose_subscriber(&guard, &resolved)
+        .context("Failed to compose and set global subscriber")?;
 
-    let logger_provider = if cfg!(feature = "logs") {
-        Some(
-            providers::logger::build_logger_provider(resource, &resolved)
-                .context("Failed to initialize logger provider")?,
-        )
-    } else {
-        None
-    };
+    if resolved.startup_banner {
+        banner::emit(&resolved);
+    }
 
-    subscriber::compose_subscriber(tracer_provider.as_ref(), logger_provider.as_ref())
-        .context("Failed to compose and set global subscriber")?;
+    Ok(guard)
+}
+
+/// Set up console logging only, with OpenTelemetry export turned off.
+///
+/// Installs the same `fmt` output and `RUST_LOG` filtering as [`setup_otel`], but no
+/// exporters, and returns [`OtelGuard::noop`]. Use it in tests or when telemetry is
+/// disabled, so the calling code looks the same either way:
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// let telemetry_enabled = std::env::var("TELEMETRY").is_ok();
+/// let _guard = if telemetry_enabled {
+///     raccoon_otel::setup_otel("my-service", None)?
+/// } else {
+///     raccoon_otel::setup_noop("my-service")?
+/// };
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the global tracing subscriber has already been set.
+pub fn setup_noop(service_name: &str) -> anyhow::Result<OtelGuard> {
+    subscriber::compose_noop_subscriber().context("Failed to compose and set global subscriber")?;
+    tracing::debug!(
+        service.name = service_name,
+        "OpenTelemetry export is disabled"
+    );
 
-    Ok(OtelGuard::new(tracer_provider, logger_provider))
+    Ok(OtelGuard::noop())
 }
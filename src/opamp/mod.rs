@@ -0,0 +1,346 @@
+//! OpAMP client (`opamp` feature): reports the agent's description and export health to
+//! an OpAMP server over HTTP, and applies the sampling strategy the server sends as remote
+//! configuration.
+
+mod proto;
+
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context};
+use opentelemetry_sdk::trace::{IdGenerator, RandomIdGenerator};
+use opentelemetry_sdk::Resource;
+use prost::Message;
+
+use crate::health::ExportHealth;
+use crate::samplers::dynamic::SamplingOverride;
+use crate::samplers::remote::RemoteRates;
+
+/// How long one exchange with the server may take, so a hung server can't stall shutdown.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Name of the remote configuration file holding the sampling strategy.
+const SAMPLING_CONFIG_FILE: &str = "sampling";
+
+const CAPABILITIES: u64 = proto::REPORTS_STATUS
+    | proto::ACCEPTS_REMOTE_CONFIG
+    | proto::REPORTS_HEALTH
+    | proto::REPORTS_REMOTE_CONFIG;
+
+/// Background thread that exchanges status with the OpAMP server every `interval`.
+#[derive(Debug)]
+pub(crate) struct OpampClient {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OpampClient {
+    pub(crate) fn start(
+        endpoint: &str,
+        interval: Duration,
+        resource: &Resource,
+        health: Arc<ExportHealth>,
+        target: Arc<SamplingOverride>,
+    ) -> anyhow::Result<Self> {
+        let endpoint = url::Url::parse(endpoint).context("Invalid OpAMP endpoint")?;
+        let mut agent = Agent::new(resource, health, target);
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name("raccoon-otel-opamp".into())
+            .spawn(move || {
+                // Built on this thread, as the blocking client can't be dropped inside an
+                // async runtime
+                let client = match reqwest::blocking::Client::builder()
+                    .timeout(REQUEST_TIMEOUT)
+                    .build()
+                {
+                    Ok(client) => client,
+                    Err(e) => {
+                        eprintln!("raccoon-otel: OpAMP client disabled: {e}");
+                        return;
+                    }
+                };
+                let mut failing = false;
+                loop {
+                    let report = agent.report();
+                    let status_changed = match exchange(&client, endpoint.as_str(), &report) {
+                        Ok(response) => {
+                            failing = false;
+                            agent.handle(response)
+                        }
+                        Err(e) => {
+                            // Once per outage rather than every interval
+                            if !failing {
+                                eprintln!(
+                                    "raccoon-otel: failed to reach the OpAMP server at \
+                                     {endpoint}: {e:#}"
+                                );
+                            }
+                            failing = true;
+                            false
+                        }
+                    };
+                    // Report a newly applied config right away rather than an interval later
+                    let wait = if status_changed {
+                        Duration::ZERO
+                    } else {
+                        interval
+                    };
+                    // Both a stop message and a dropped sender end the loop
+                    if !matches!(stopped.recv_timeout(wait), Err(RecvTimeoutError::Timeout)) {
+                        break;
+                    }
+                }
+                if !failing {
+                    let _ = exchange(&client, endpoint.as_str(), &agent.disconnect());
+                }
+            })
+            .context("Failed to start the OpAMP client thread")?;
+        Ok(Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Stop the thread, telling the server the agent is disconnecting.
+    pub(crate) fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for OpampClient {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// The agent's side of the protocol, separate from the transport.
+struct Agent {
+    instance_uid: Vec<u8>,
+    sequence_num: u64,
+    description: proto::AgentDescription,
+    started: SystemTime,
+    health: Arc<ExportHealth>,
+    target: Arc<SamplingOverride>,
+    config_status: Option<proto::RemoteConfigStatus>,
+}
+
+impl Agent {
+    fn new(resource: &Resource, health: Arc<ExportHealth>, target: Arc<SamplingOverride>) -> Self {
+        let mut description = proto::AgentDescription::default();
+        for (key, value) in resource.iter() {
+            let attribute = proto::KeyValue {
+                key: key.to_string(),
+                value: Some(proto::AnyValue {
+                    string_value: value.to_string(),
+                }),
+            };
+            // `service.*` identifies the agent; host and runtime details only describe it
+            if key.as_str().starts_with("service.") {
+                description.identifying_attributes.push(attribute);
+            } else {
+                description.non_identifying_attributes.push(attribute);
+            }
+        }
+        Self {
+            instance_uid: RandomIdGenerator::default()
+                .new_trace_id()
+                .to_bytes()
+                .to_vec(),
+            sequence_num: 0,
+            description,
+            started: SystemTime::now(),
+            health,
+            target,
+            config_status: None,
+        }
+    }
+
+    /// The full agent state; sent every time, so the server never has to ask for it.
+    fn report(&mut self) -> proto::AgentToServer {
+        let health = self.health.snapshot();
+        self.sequence_num += 1;
+        proto::AgentToServer {
+            instance_uid: self.instance_uid.clone(),
+            sequence_num: self.sequence_num,
+            agent_description: Some(self.description.clone()),
+            capabilities: CAPABILITIES,
+            health: Some(proto::ComponentHealth {
+                healthy: health.healthy,
+                start_time_unix_nano: unix_nanos(self.started),
+                last_error: health.last_error.unwrap_or_default(),
+                status: if health.healthy {
+                    "exporting"
+                } else {
+                    "export failing"
+                }
+                .to_owned(),
+                status_time_unix_nano: unix_nanos(SystemTime::now()),
+            }),
+            remote_config_status: self.config_status.clone(),
+            agent_disconnect: None,
+        }
+    }
+
+    fn disconnect(&mut self) -> proto::AgentToServer {
+        proto::AgentToServer {
+            agent_disconnect: Some(proto::AgentDisconnect {}),
+            ..self.report()
+        }
+    }
+
+    /// Act on the server's response, returning whether the remote config status changed.
+    fn handle(&mut self, response: proto::ServerToAgent) -> bool {
+        if let Some(error) = response.error_response {
+            eprintln!(
+                "raccoon-otel: the OpAMP server rejected the status report: {}",
+                error.error_message
+            );
+        }
+        let Some(remote_config) = response.remote_config else {
+            return false;
+        };
+        let hash = remote_config.config_hash;
+        if self
+            .config_status
+            .as_ref()
+            .is_some_and(|status| status.last_remote_config_hash == hash)
+        {
+            return false;
+        }
+        let files = remote_config.config.unwrap_or_default().config_map;
+        let status = match self.apply(&files) {
+            Ok(()) => proto::RemoteConfigStatus {
+                last_remote_config_hash: hash,
+                status: proto::REMOTE_CONFIG_APPLIED,
+                error_message: String::new(),
+            },
+            Err(e) => {
+                eprintln!("raccoon-otel: failed to apply the OpAMP remote config: {e:#}");
+                proto::RemoteConfigStatus {
+                    last_remote_config_hash: hash,
+                    status: proto::REMOTE_CONFIG_FAILED,
+                    error_message: format!("{e:#}"),
+                }
+            }
+        };
+        self.config_status = Some(status);
+        true
+    }
+
+    /// Apply the sampling strategy from the remote config, refusing anything else.
+    fn apply(
+        &self,
+        files: &std::collections::HashMap<String, proto::AgentConfigFile>,
+    ) -> anyhow::Result<()> {
+        let mut files = files.iter();
+        let file = match (files.next(), files.next()) {
+            // An empty config leaves the current strategy in place
+            (None, _) => return Ok(()),
+            // Servers often send a single config under an empty name
+            (Some((name, file)), None) if name.is_empty() || name == SAMPLING_CONFIG_FILE => file,
+            _ => bail!(
+                "only a single `{SAMPLING_CONFIG_FILE}` file can be applied at runtime; \
+                 exporters, endpoints and filters are fixed when the pipeline starts"
+            ),
+        };
+        let body = std::str::from_utf8(&file.body).context("the sampling config isn't UTF-8")?;
+        let rates = RemoteRates::parse(body)?;
+        if self.target.set_remote(rates) {
+            eprintln!("raccoon-otel: applied the sampling strategy from the OpAMP server");
+        }
+        Ok(())
+    }
+}
+
+fn exchange(
+    client: &reqwest::blocking::Client,
+    endpoint: &str,
+    message: &proto::AgentToServer,
+) -> anyhow::Result<proto::ServerToAgent> {
+    let body = client
+        .post(endpoint)
+        .header("Content-Type", "application/x-protobuf")
+        .body(message.encode_to_vec())
+        .send()
+        .and_then(reqwest::blocking::Response::error_for_status)
+        .and_then(reqwest::blocking::Response::bytes)?;
+    proto::ServerToAgent::decode(body).context("the OpAMP response isn't a ServerToAgent message")
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn remote_config(hash: &[u8], files: &[(&str, &str)]) -> proto::ServerToAgent {
+        let config_map = files
+            .iter()
+            .map(|(name, body)| {
+                let file = proto::AgentConfigFile {
+                    body: body.as_bytes().to_vec(),
+                    content_type: "application/json".to_owned(),
+                };
+                (name.to_string(), file)
+            })
+            .collect::<HashMap<_, _>>();
+        proto::ServerToAgent {
+            error_response: None,
+            remote_config: Some(proto::AgentRemoteConfig {
+                config: Some(proto::AgentConfigMap { config_map }),
+                config_hash: hash.to_vec(),
+            }),
+        }
+    }
+
+    #[test]
+    fn sampling_config_is_applied_and_reported() {
+        let resource = Resource::builder_empty()
+            .with_service_name("checkout")
+            .with_attribute(opentelemetry::KeyValue::new("host.name", "web-1"))
+            .build();
+        let target = Arc::new(SamplingOverride::default());
+        let mut agent = Agent::new(&resource, Arc::default(), Arc::clone(&target));
+
+        let report = proto::AgentToServer::decode(&*agent.report().encode_to_vec()).unwrap();
+        assert_eq!(report.instance_uid.len(), 16);
+        assert_eq!(report.sequence_num, 1);
+        let description = report.agent_description.unwrap();
+        assert_eq!(description.identifying_attributes[0].key, "service.name");
+        assert_eq!(description.non_identifying_attributes[0].key, "host.name");
+        assert!(report.health.unwrap().healthy);
+
+        let sampling = r#"{"probabilisticSampling": {"samplingRate": 0.25}}"#;
+        assert!(agent.handle(remote_config(b"v1", &[("sampling", sampling)])));
+        assert_eq!(target.root_ratio("anything"), Some(0.25));
+        let status = agent.report().remote_config_status.unwrap();
+        assert_eq!(status.status, proto::REMOTE_CONFIG_APPLIED);
+        assert_eq!(status.last_remote_config_hash, b"v1");
+        // The same config again isn't re-applied
+        assert!(!agent.handle(remote_config(b"v1", &[("sampling", sampling)])));
+
+        // Anything but sampling is refused, leaving the applied strategy in place
+        let files = [("sampling", "{}"), ("exporters", "{}")];
+        for config in [&files[..], &[("sampling", "not json")]] {
+            assert!(agent.handle(remote_config(config[0].1.as_bytes(), config)));
+            let status = agent.report().remote_config_status.unwrap();
+            assert_eq!(status.status, proto::REMOTE_CONFIG_FAILED);
+            assert!(!status.error_message.is_empty());
+        }
+        assert_eq!(target.root_ratio("anything"), Some(0.25));
+    }
+}
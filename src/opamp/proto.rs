@@ -0,0 +1,120 @@
+//! The subset of the OpAMP protocol (`opamp.proto` and `anyvalue.proto`) the client uses.
+//!
+//! Field tags follow the specification; fields the client never sends or reads are left
+//! out, and prost skips them when decoding.
+
+use std::collections::HashMap;
+
+/// `AgentCapabilities` flags.
+pub(crate) const REPORTS_STATUS: u64 = 0x1;
+pub(crate) const ACCEPTS_REMOTE_CONFIG: u64 = 0x2;
+pub(crate) const REPORTS_HEALTH: u64 = 0x800;
+pub(crate) const REPORTS_REMOTE_CONFIG: u64 = 0x1000;
+
+/// `RemoteConfigStatuses` values.
+pub(crate) const REMOTE_CONFIG_APPLIED: i32 = 1;
+pub(crate) const REMOTE_CONFIG_FAILED: i32 = 3;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct AgentToServer {
+    #[prost(bytes = "vec", tag = "1")]
+    pub(crate) instance_uid: Vec<u8>,
+    #[prost(uint64, tag = "2")]
+    pub(crate) sequence_num: u64,
+    #[prost(message, optional, tag = "3")]
+    pub(crate) agent_description: Option<AgentDescription>,
+    #[prost(uint64, tag = "4")]
+    pub(crate) capabilities: u64,
+    #[prost(message, optional, tag = "5")]
+    pub(crate) health: Option<ComponentHealth>,
+    #[prost(message, optional, tag = "7")]
+    pub(crate) remote_config_status: Option<RemoteConfigStatus>,
+    #[prost(message, optional, tag = "9")]
+    pub(crate) agent_disconnect: Option<AgentDisconnect>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct AgentDescription {
+    #[prost(message, repeated, tag = "1")]
+    pub(crate) identifying_attributes: Vec<KeyValue>,
+    #[prost(message, repeated, tag = "2")]
+    pub(crate) non_identifying_attributes: Vec<KeyValue>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct KeyValue {
+    #[prost(string, tag = "1")]
+    pub(crate) key: String,
+    #[prost(message, optional, tag = "2")]
+    pub(crate) value: Option<AnyValue>,
+}
+
+/// Only the `string_value` case of the `AnyValue` oneof, which has the same encoding.
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct AnyValue {
+    #[prost(string, tag = "1")]
+    pub(crate) string_value: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ComponentHealth {
+    #[prost(bool, tag = "1")]
+    pub(crate) healthy: bool,
+    #[prost(fixed64, tag = "2")]
+    pub(crate) start_time_unix_nano: u64,
+    #[prost(string, tag = "3")]
+    pub(crate) last_error: String,
+    #[prost(string, tag = "4")]
+    pub(crate) status: String,
+    #[prost(fixed64, tag = "5")]
+    pub(crate) status_time_unix_nano: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct RemoteConfigStatus {
+    #[prost(bytes = "vec", tag = "1")]
+    pub(crate) last_remote_config_hash: Vec<u8>,
+    #[prost(int32, tag = "2")]
+    pub(crate) status: i32,
+    #[prost(string, tag = "3")]
+    pub(crate) error_message: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct AgentDisconnect {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ServerToAgent {
+    #[prost(message, optional, tag = "2")]
+    pub(crate) error_response: Option<ServerErrorResponse>,
+    #[prost(message, optional, tag = "3")]
+    pub(crate) remote_config: Option<AgentRemoteConfig>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ServerErrorResponse {
+    #[prost(string, tag = "2")]
+    pub(crate) error_message: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct AgentRemoteConfig {
+    #[prost(message, optional, tag = "1")]
+    pub(crate) config: Option<AgentConfigMap>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub(crate) config_hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct AgentConfigMap {
+    #[prost(map = "string, message", tag = "1")]
+    pub(crate) config_map: HashMap<String, AgentConfigFile>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct AgentConfigFile {
+    #[prost(bytes = "vec", tag = "1")]
+    pub(crate) body: Vec<u8>,
+    #[prost(string, tag = "2")]
+    pub(crate) content_type: String,
+}
@@ -1,8 +1,29 @@
+use std::any::Any;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use opentelemetry::trace::SpanKind;
+use opentelemetry::KeyValue;
+#[cfg(feature = "metrics")]
+use opentelemetry_sdk::metrics::{Instrument, Stream};
+use tracing_subscriber::{Layer, Registry};
+
+use crate::export_batch::ExportBatch;
+use crate::redact::RedactedMap;
+use crate::sinks::gelf::GelfOptions;
+use crate::sinks::statsd::StatsdOptions;
+use crate::sinks::syslog::SyslogOptions;
+use crate::span_filter::SpanFilter;
+use crate::tenant::TenantOptions;
+use crate::validation::ValidationError;
+
 /// OTLP transport protocol.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Protocol {
     /// gRPC transport (port 4317).
     Grpc,
@@ -10,19 +31,524 @@ pub enum Protocol {
     HttpProtobuf,
     /// HTTP with JSON encoding (port 4318).
     HttpJson,
+    /// OTLP/JSON lines appended to a local file, one line per batch, in the format of the
+    /// collector's file exporter. The file is rotated by size (see
+    /// [`OtelOptionsBuilder::file_rotation`]). Traces and logs only; requires the `file`
+    /// feature.
+    File {
+        /// The file to write. Missing parent directories are created.
+        path: PathBuf,
+    },
+    /// Jaeger Thrift over HTTP, sent to the collector's `/api/traces` endpoint (port
+    /// 14268), for Jaeger deployments without an OTLP receiver. Traces only; requires the
+    /// `jaeger` feature.
+    Jaeger,
+    /// Zipkin v2 JSON, sent to the collector's `/api/v2/spans` endpoint (port 9411), for
+    /// Zipkin deployments that don't accept OTLP. Traces only; requires the `zipkin`
+    /// feature.
+    Zipkin,
+    /// Application Insights' ingestion API, with the endpoint and instrumentation key taken
+    /// from `connection_string`. Traces and logs only; requires the `azure-monitor`
+    /// feature. See `OtelOptions::azure_monitor()`.
+    AzureMonitor {
+        /// The Application Insights connection string.
+        connection_string: String,
+    },
+}
+
+impl fmt::Display for Protocol {
+    /// The `OTEL_EXPORTER_OTLP_PROTOCOL` name, or `file`, `jaeger`, `zipkin` or
+    /// `azure-monitor` for the non-OTLP protocols.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Grpc => "grpc",
+            Self::HttpProtobuf => "http/protobuf",
+            Self::HttpJson => "http/json",
+            Self::File { .. } => "file",
+            Self::Jaeger => "jaeger",
+            Self::Zipkin => "zipkin",
+            Self::AzureMonitor { .. } => "azure-monitor",
+        })
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = ParseProtocolError;
+
+    /// Parse an `OTEL_EXPORTER_OTLP_PROTOCOL` value: `grpc`, `http/protobuf` or `http/json`.
+    ///
+    /// The non-OTLP protocols ([`Protocol::File`], [`Protocol::Jaeger`],
+    /// [`Protocol::Zipkin`] and [`Protocol::AzureMonitor`]) can't be parsed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grpc" => Ok(Self::Grpc),
+            "http/protobuf" => Ok(Self::HttpProtobuf),
+            "http/json" => Ok(Self::HttpJson),
+            _ => Err(ParseProtocolError {
+                value: s.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Error returned when a string is not a known [`Protocol`] name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseProtocolError {
+    value: String,
+}
+
+impl fmt::Display for ParseProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid OTLP protocol `{}`: expected grpc, http/protobuf or http/json",
+            self.value
+        )
+    }
+}
+
+impl std::error::Error for ParseProtocolError {}
+
+/// Where a secret header value is read from at setup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HeaderSource {
+    Env(String),
+    File(PathBuf),
+}
+
+/// Format of the console (stdout) output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConsoleFormat {
+    /// Human-readable `tracing_subscriber::fmt` output (default).
+    #[default]
+    Text,
+    /// One Elastic Common Schema JSON document per line. Requires the `json` feature.
+    Ecs,
+}
+
+/// How integer span fields that don't fit in an OTel `i64` attribute are exported.
+///
+/// Integers within range (including `u64` up to `i64::MAX`) are always exported as
+/// integer attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LargeIntegerMode {
+    /// Export the decimal string (default, matches how log records are bridged).
+    #[default]
+    String,
+    /// Export as a lossy `f64`, keeping the attribute numeric.
+    Float,
+    /// Clamp to `i64::MAX` (or `i64::MIN`).
+    Saturate,
+}
+
+/// Aggregation temporality requested from the metrics exporter.
+///
+/// Mirrors `OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetricsTemporality {
+    /// Totals since process start (default; what Prometheus-style backends expect).
+    #[default]
+    Cumulative,
+    /// Changes since the last export (required by e.g. Datadog).
+    Delta,
+    /// Delta for synchronous counters and histograms, cumulative otherwise.
+    LowMemory,
+}
+
+/// Default aggregation for histogram instruments.
+///
+/// Mirrors `OTEL_EXPORTER_OTLP_METRICS_DEFAULT_HISTOGRAM_AGGREGATION`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistogramAggregation {
+    /// Fixed bucket boundaries (default).
+    #[default]
+    ExplicitBucket,
+    /// Base-2 exponential buckets that adapt to the recorded range.
+    Base2Exponential,
+}
+
+/// Upper bound on export throughput, shared by all signals of a pipeline.
+///
+/// Telemetry over the budget is dropped at export time and counted in
+/// [`ExportStats`](crate::ExportStats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportBudget {
+    /// Estimated payload bytes per second. When a batch doesn't fit, higher-severity
+    /// log records and error spans are kept first.
+    BytesPerSecond(u64),
+    /// Export batches per second. Batches over the budget are dropped whole.
+    BatchesPerSecond(u32),
+}
+
+/// What setup does with the result of the startup connectivity check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerifyMode {
+    /// Don't check (default); exporters connect lazily on their first export.
+    #[default]
+    Off,
+    /// Print a warning on stderr when the endpoint can't be reached, then continue.
+    Warn,
+    /// Fail setup when the endpoint can't be reached.
+    FailFast,
+}
+
+/// Where telemetry goes once the OTLP endpoint is considered dead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeadLetterOutput {
+    /// Write to the process's stderr.
+    Stderr,
+    /// Append to a file, creating it (and its directory) if needed.
+    File(PathBuf),
+}
+
+/// Signature of the callback set via [`OtelOptionsBuilder::span_name_mapper`].
+pub(crate) type SpanNameMapperFn = dyn for<'a> Fn(&'a str) -> Cow<'a, str> + Send + Sync;
+
+/// Signature of the `Authorization` header provider set via
+/// [`OtelOptionsBuilder::authorization`].
+pub(crate) type AuthorizationFn = dyn Fn() -> String + Send + Sync;
+
+/// Signature of the hook set via [`OtelOptionsBuilder::on_export`].
+pub(crate) type ExportHookFn = dyn Fn(&ExportBatch) + Send + Sync;
+
+/// Signatures of the previous-run lookup and store set via
+/// [`OtelOptionsBuilder::link_job_runs`].
+pub(crate) type JobRunLoadFn = dyn Fn(&str) -> Option<String> + Send + Sync;
+pub(crate) type JobRunStoreFn = dyn Fn(&str, &str) + Send + Sync;
+
+/// Signature of a value provider set via [`OtelOptionsBuilder::ambient_attribute`].
+pub(crate) type AmbientAttributeFn = dyn Fn() -> Option<String> + Send + Sync;
+
+/// Signature of a metric view registered via [`OtelOptionsBuilder::with_view`].
+#[cfg(feature = "metrics")]
+pub(crate) type MetricViewFn = dyn Fn(&Instrument) -> Option<Stream> + Send + Sync;
+
+/// A user-supplied callback, shared between options, resolved config, and processors.
+pub(crate) struct Callback<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Callback(..)")
+    }
+}
+
+/// A layer added via [`OtelOptionsBuilder::with_extra_layer`].
+pub(crate) type ExtraLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// A resource handed to the guard via [`OtelOptionsBuilder::keep_alive`].
+pub(crate) type AuxiliaryGuard = Box<dyn Any + Send>;
+
+/// Values that setup moves out of the options, shared between clones of the options.
+/// Each can only be moved once, so the first setup takes them all.
+pub(crate) struct TakeOnce<T>(Arc<Mutex<Vec<T>>>);
+
+impl<T> TakeOnce<T> {
+    pub(crate) fn push(&self, value: T) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).push(value);
+    }
+
+    pub(crate) fn take(&self) -> Vec<T> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl<T> Clone for TakeOnce<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> Default for TakeOnce<T> {
+    fn default() -> Self {
+        Self(Arc::default())
+    }
+}
+
+impl<T> fmt::Debug for TakeOnce<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.0.lock().map_or(0, |values| values.len());
+        write!(f, "TakeOnce({count})")
+    }
 }
 
 /// Configuration options for OpenTelemetry setup.
 ///
 /// Use [`OtelOptions::builder()`] to construct an instance.
 /// All fields are optional; unset values fall back to environment variables, then defaults.
-#[derive(Debug, Clone, Default)]
+#[derive(Clone, Default)]
 pub struct OtelOptions {
     pub(crate) endpoint: Option<String>,
     pub(crate) protocol: Option<Protocol>,
+    pub(crate) traces_protocol: Option<Protocol>,
+    pub(crate) logs_protocol: Option<Protocol>,
+    pub(crate) grpc_http_fallback: Option<bool>,
+    pub(crate) grpc_keepalive: Option<(Duration, Duration)>,
+    pub(crate) grpc_connect_timeout: Option<Duration>,
+    pub(crate) grpc_adaptive_window: Option<bool>,
+    pub(crate) grpc_dns_refresh: Option<Duration>,
+    pub(crate) dedicated_export_thread: bool,
+    pub(crate) discover_endpoint: bool,
+    pub(crate) discovery_file: Option<PathBuf>,
+    pub(crate) verify_connectivity: Option<VerifyMode>,
     pub(crate) headers: HashMap<String, String>,
+    pub(crate) header_sources: Vec<(String, HeaderSource)>,
+    pub(crate) grpc_metadata: HashMap<String, String>,
+    pub(crate) user_agent: Option<String>,
     pub(crate) resource_attributes: HashMap<String, String>,
+    pub(crate) resource_key_values: Vec<KeyValue>,
     pub(crate) export_timeout: Option<Duration>,
+    pub(crate) invalid_export_timeout: Option<String>,
+    pub(crate) invalid_protocol: Option<ParseProtocolError>,
+    pub(crate) set_global: Option<bool>,
+    pub(crate) span_name_mapper: Option<Callback<SpanNameMapperFn>>,
+    pub(crate) max_attribute_value_length: Option<usize>,
+    pub(crate) attribute_renames: Vec<(String, String)>,
+    pub(crate) span_event_summary: Option<(usize, usize)>,
+    pub(crate) drop_span_filters: Vec<SpanFilter>,
+    pub(crate) event_counters: Vec<(String, String)>,
+    pub(crate) span_kind_rules: Vec<(SpanFilter, SpanKind)>,
+    pub(crate) sampling_rules: Vec<(SpanFilter, f64)>,
+    pub(crate) large_integer_mode: Option<LargeIntegerMode>,
+    pub(crate) expand_json_fields: Option<bool>,
+    pub(crate) console_format: Option<ConsoleFormat>,
+    pub(crate) gelf: Option<GelfOptions>,
+    pub(crate) syslog: Option<SyslogOptions>,
+    pub(crate) statsd: Option<StatsdOptions>,
+    pub(crate) debug_logs_on_error_only: Option<bool>,
+    pub(crate) dedup_logs_window: Option<Duration>,
+    pub(crate) export_budget: Option<ExportBudget>,
+    pub(crate) max_export_bytes: Option<usize>,
+    pub(crate) adaptive_sampling_min_ratio: Option<f64>,
+    pub(crate) metrics_temporality: Option<MetricsTemporality>,
+    pub(crate) histogram_aggregation: Option<HistogramAggregation>,
+    pub(crate) record_path: Option<PathBuf>,
+    pub(crate) log_file: Option<PathBuf>,
+    pub(crate) file_rotation: Option<(u64, usize)>,
+    pub(crate) flush_watchdog: Option<Duration>,
+    pub(crate) cardinality_window: Option<Duration>,
+    pub(crate) remote_sampling: Option<(String, Duration)>,
+    pub(crate) opamp: Option<(String, Duration)>,
+    pub(crate) receiver: Option<String>,
+    pub(crate) profiling: Option<(PathBuf, Duration)>,
+    pub(crate) dead_letter_output: Option<DeadLetterOutput>,
+    pub(crate) startup_banner: Option<bool>,
+    pub(crate) benchmark_mode: bool,
+    pub(crate) skip_unsampled_spans: bool,
+    pub(crate) async_bridge: Option<usize>,
+    pub(crate) batch_queue_shards: Option<usize>,
+    pub(crate) adaptive_batch_size: bool,
+    pub(crate) factor_common_attributes: bool,
+    pub(crate) consistent_sampling: bool,
+    pub(crate) monotonic_timestamps: bool,
+    pub(crate) extra_layers: TakeOnce<ExtraLayer>,
+    pub(crate) auxiliary_guards: TakeOnce<AuxiliaryGuard>,
+    pub(crate) inherit_trace_context: Option<bool>,
+    pub(crate) redact_keys: Vec<String>,
+    pub(crate) tenant: Option<TenantOptions>,
+    pub(crate) ambient_attributes: Vec<(String, Callback<AmbientAttributeFn>)>,
+    pub(crate) authorization: Option<Callback<AuthorizationFn>>,
+    pub(crate) on_export: Option<Callback<ExportHookFn>>,
+    pub(crate) job_run_history: Option<(Callback<JobRunLoadFn>, Callback<JobRunStoreFn>)>,
+    #[cfg(feature = "gcp")]
+    pub(crate) gcp: bool,
+    #[cfg(feature = "metrics")]
+    pub(crate) metric_views: Vec<Callback<MetricViewFn>>,
+    #[cfg(feature = "test-util")]
+    pub(crate) id_generator: Option<crate::testing::SharedIdGenerator>,
+    #[cfg(feature = "test-util")]
+    pub(crate) test_clock: Option<crate::testing::TestClock>,
+    #[cfg(feature = "test-util")]
+    pub(crate) span_capture: Option<crate::testing::SpanCapture>,
+}
+
+// Header values and secret-looking resource attributes are masked, so options can be
+// logged with `{:?}` safely
+impl fmt::Debug for OtelOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let OtelOptions {
+            endpoint,
+            protocol,
+            traces_protocol,
+            logs_protocol,
+            grpc_http_fallback,
+            grpc_keepalive,
+            grpc_connect_timeout,
+            grpc_adaptive_window,
+            grpc_dns_refresh,
+            dedicated_export_thread,
+            discover_endpoint,
+            discovery_file,
+            verify_connectivity,
+            headers,
+            header_sources,
+            grpc_metadata,
+            user_agent,
+            resource_attributes,
+            resource_key_values,
+            export_timeout,
+            invalid_export_timeout,
+            invalid_protocol,
+            set_global,
+            span_name_mapper,
+            max_attribute_value_length,
+            attribute_renames,
+            span_event_summary,
+            drop_span_filters,
+            event_counters,
+            span_kind_rules,
+            sampling_rules,
+            large_integer_mode,
+            expand_json_fields,
+            console_format,
+            gelf,
+            syslog,
+            statsd,
+            debug_logs_on_error_only,
+            dedup_logs_window,
+            export_budget,
+            max_export_bytes,
+            adaptive_sampling_min_ratio,
+            metrics_temporality,
+            histogram_aggregation,
+            record_path,
+            log_file,
+            file_rotation,
+            flush_watchdog,
+            cardinality_window,
+            remote_sampling,
+            opamp,
+            receiver,
+            profiling,
+            dead_letter_output,
+            startup_banner,
+            benchmark_mode,
+            skip_unsampled_spans,
+            async_bridge,
+            batch_queue_shards,
+            adaptive_batch_size,
+            factor_common_attributes,
+            consistent_sampling,
+            monotonic_timestamps,
+            extra_layers,
+            auxiliary_guards,
+            inherit_trace_context,
+            redact_keys,
+            tenant,
+            ambient_attributes,
+            authorization,
+            on_export,
+            job_run_history,
+            #[cfg(feature = "gcp")]
+            gcp,
+            #[cfg(feature = "metrics")]
+            metric_views,
+            #[cfg(feature = "test-util")]
+            id_generator,
+            #[cfg(feature = "test-util")]
+            test_clock,
+            #[cfg(feature = "test-util")]
+            span_capture,
+        } = self;
+        let mut f = f.debug_struct("OtelOptions");
+        f.field("endpoint", endpoint);
+        f.field("protocol", protocol);
+        f.field("traces_protocol", traces_protocol);
+        f.field("logs_protocol", logs_protocol);
+        f.field("grpc_http_fallback", grpc_http_fallback);
+        f.field("grpc_keepalive", grpc_keepalive);
+        f.field("grpc_connect_timeout", grpc_connect_timeout);
+        f.field("grpc_adaptive_window", grpc_adaptive_window);
+        f.field("grpc_dns_refresh", grpc_dns_refresh);
+        f.field("dedicated_export_thread", dedicated_export_thread);
+        f.field("discover_endpoint", discover_endpoint);
+        f.field("discovery_file", discovery_file);
+        f.field("verify_connectivity", verify_connectivity);
+        f.field("headers", &RedactedMap::all(headers));
+        f.field("header_sources", header_sources);
+        f.field("grpc_metadata", &RedactedMap::all(grpc_metadata));
+        f.field("user_agent", user_agent);
+        f.field(
+            "resource_attributes",
+            &RedactedMap::matching(resource_attributes, redact_keys),
+        );
+        f.field(
+            "resource_key_values",
+            &RedactedMap::matching(
+                &crate::resource::display_map(resource_key_values),
+                redact_keys,
+            ),
+        );
+        f.field("export_timeout", export_timeout);
+        f.field("invalid_export_timeout", invalid_export_timeout);
+        f.field("invalid_protocol", invalid_protocol);
+        f.field("set_global", set_global);
+        f.field("span_name_mapper", span_name_mapper);
+        f.field("max_attribute_value_length", max_attribute_value_length);
+        f.field("attribute_renames", attribute_renames);
+        f.field("span_event_summary", span_event_summary);
+        f.field("drop_span_filters", drop_span_filters);
+        f.field("event_counters", event_counters);
+        f.field("span_kind_rules", span_kind_rules);
+        f.field("sampling_rules", sampling_rules);
+        f.field("large_integer_mode", large_integer_mode);
+        f.field("expand_json_fields", expand_json_fields);
+        f.field("console_format", console_format);
+        f.field("gelf", gelf);
+        f.field("syslog", syslog);
+        f.field("statsd", statsd);
+        f.field("debug_logs_on_error_only", debug_logs_on_error_only);
+        f.field("dedup_logs_window", dedup_logs_window);
+        f.field("export_budget", export_budget);
+        f.field("max_export_bytes", max_export_bytes);
+        f.field("adaptive_sampling_min_ratio", adaptive_sampling_min_ratio);
+        f.field("metrics_temporality", metrics_temporality);
+        f.field("histogram_aggregation", histogram_aggregation);
+        f.field("record_path", record_path);
+        f.field("log_file", log_file);
+        f.field("file_rotation", file_rotation);
+        f.field("flush_watchdog", flush_watchdog);
+        f.field("cardinality_window", cardinality_window);
+        f.field("remote_sampling", remote_sampling);
+        f.field("opamp", opamp);
+        f.field("receiver", receiver);
+        f.field("profiling", profiling);
+        f.field("dead_letter_output", dead_letter_output);
+        f.field("startup_banner", startup_banner);
+        f.field("benchmark_mode", benchmark_mode);
+        f.field("skip_unsampled_spans", skip_unsampled_spans);
+        f.field("async_bridge", async_bridge);
+        f.field("batch_queue_shards", batch_queue_shards);
+        f.field("adaptive_batch_size", adaptive_batch_size);
+        f.field("factor_common_attributes", factor_common_attributes);
+        f.field("consistent_sampling", consistent_sampling);
+        f.field("monotonic_timestamps", monotonic_timestamps);
+        f.field("extra_layers", extra_layers);
+        f.field("auxiliary_guards", auxiliary_guards);
+        f.field("inherit_trace_context", inherit_trace_context);
+        f.field("redact_keys", redact_keys);
+        f.field("tenant", tenant);
+        f.field("ambient_attributes", ambient_attributes);
+        f.field("authorization", authorization);
+        f.field("on_export", on_export);
+        f.field("job_run_history", job_run_history);
+        #[cfg(feature = "gcp")]
+        f.field("gcp", gcp);
+        #[cfg(feature = "metrics")]
+        f.field("metric_views", metric_views);
+        #[cfg(feature = "test-util")]
+        f.field("id_generator", id_generator);
+        #[cfg(feature = "test-util")]
+        f.field("test_clock", test_clock);
+        #[cfg(feature = "test-util")]
+        f.field("span_capture", span_capture);
+        f.finish()
+    }
 }
 
 impl OtelOptions {
@@ -30,20 +556,332 @@ impl OtelOptions {
     pub fn builder() -> OtelOptionsBuilder {
         OtelOptionsBuilder::default()
     }
+
+    /// Create a builder that exports traces and logs to Application Insights, using the
+    /// ingestion endpoint and instrumentation key of `connection_string` (as found on the
+    /// resource's overview page or in `APPLICATIONINSIGHTS_CONNECTION_STRING`).
+    ///
+    /// `service.name` (prefixed by `service.namespace`) becomes the cloud role and
+    /// `service.instance.id` the role instance. Application Insights doesn't take OTLP
+    /// metrics, so with the `metrics` feature set metrics to another protocol.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use raccoon_otel::OtelOptions;
+    ///
+    /// let connection_string = std::env::var("APPLICATIONINSIGHTS_CONNECTION_STRING")?;
+    /// let options = OtelOptions::azure_monitor(connection_string).build();
+    /// # Ok::<(), std::env::VarError>(())
+    /// ```
+    #[cfg(feature = "azure-monitor")]
+    pub fn azure_monitor(connection_string: impl Into<String>) -> OtelOptionsBuilder {
+        Self::builder().protocol(Protocol::AzureMonitor {
+            connection_string: connection_string.into(),
+        })
+    }
+
+    /// Create a builder that exports to Google Cloud Trace, Logging and Monitoring through
+    /// the Telemetry API's OTLP endpoint.
+    ///
+    /// At setup, the project is read from `GOOGLE_CLOUD_PROJECT` (or the metadata server)
+    /// and added to the resource as `gcp.project_id`. Exports authenticate with the
+    /// service account's access token from the metadata server, refreshed before it
+    /// expires; outside Google Cloud, set an `Authorization` header instead. Log severities
+    /// are named the way Cloud Logging expects.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use raccoon_otel::{setup_otel, OtelOptions};
+    ///
+    /// let _guard = setup_otel("checkout-api", Some(OtelOptions::gcp().build()))?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    #[cfg(feature = "gcp")]
+    pub fn gcp() -> OtelOptionsBuilder {
+        let mut builder = Self::builder()
+            .endpoint(crate::gcp::TELEMETRY_ENDPOINT)
+            .protocol(Protocol::HttpProtobuf);
+        builder.gcp = true;
+        builder
+    }
 }
 
 /// Builder for [`OtelOptions`].
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct OtelOptionsBuilder {
     endpoint: Option<String>,
     protocol: Option<Protocol>,
+    traces_protocol: Option<Protocol>,
+    logs_protocol: Option<Protocol>,
+    grpc_http_fallback: Option<bool>,
+    grpc_keepalive: Option<(Duration, Duration)>,
+    grpc_connect_timeout: Option<Duration>,
+    grpc_adaptive_window: Option<bool>,
+    grpc_dns_refresh: Option<Duration>,
+    dedicated_export_thread: bool,
+    discover_endpoint: bool,
+    discovery_file: Option<PathBuf>,
+    verify_connectivity: Option<VerifyMode>,
     headers: HashMap<String, String>,
+    header_sources: Vec<(String, HeaderSource)>,
+    grpc_metadata: HashMap<String, String>,
+    user_agent: Option<String>,
     resource_attributes: HashMap<String, String>,
+    resource_key_values: Vec<KeyValue>,
     export_timeout: Option<Duration>,
+    invalid_export_timeout: Option<String>,
+    invalid_protocol: Option<ParseProtocolError>,
+    set_global: Option<bool>,
+    span_name_mapper: Option<Callback<SpanNameMapperFn>>,
+    max_attribute_value_length: Option<usize>,
+    attribute_renames: Vec<(String, String)>,
+    span_event_summary: Option<(usize, usize)>,
+    drop_span_filters: Vec<SpanFilter>,
+    event_counters: Vec<(String, String)>,
+    span_kind_rules: Vec<(SpanFilter, SpanKind)>,
+    sampling_rules: Vec<(SpanFilter, f64)>,
+    large_integer_mode: Option<LargeIntegerMode>,
+    expand_json_fields: Option<bool>,
+    console_format: Option<ConsoleFormat>,
+    gelf: Option<GelfOptions>,
+    syslog: Option<SyslogOptions>,
+    statsd: Option<StatsdOptions>,
+    debug_logs_on_error_only: Option<bool>,
+    dedup_logs_window: Option<Duration>,
+    export_budget: Option<ExportBudget>,
+    max_export_bytes: Option<usize>,
+    adaptive_sampling_min_ratio: Option<f64>,
+    metrics_temporality: Option<MetricsTemporality>,
+    histogram_aggregation: Option<HistogramAggregation>,
+    record_path: Option<PathBuf>,
+    log_file: Option<PathBuf>,
+    file_rotation: Option<(u64, usize)>,
+    flush_watchdog: Option<Duration>,
+    cardinality_window: Option<Duration>,
+    remote_sampling: Option<(String, Duration)>,
+    opamp: Option<(String, Duration)>,
+    receiver: Option<String>,
+    profiling: Option<(PathBuf, Duration)>,
+    dead_letter_output: Option<DeadLetterOutput>,
+    startup_banner: Option<bool>,
+    benchmark_mode: bool,
+    skip_unsampled_spans: bool,
+    async_bridge: Option<usize>,
+    batch_queue_shards: Option<usize>,
+    adaptive_batch_size: bool,
+    factor_common_attributes: bool,
+    consistent_sampling: bool,
+    monotonic_timestamps: bool,
+    extra_layers: TakeOnce<ExtraLayer>,
+    auxiliary_guards: TakeOnce<AuxiliaryGuard>,
+    inherit_trace_context: Option<bool>,
+    redact_keys: Vec<String>,
+    tenant: Option<TenantOptions>,
+    ambient_attributes: Vec<(String, Callback<AmbientAttributeFn>)>,
+    authorization: Option<Callback<AuthorizationFn>>,
+    on_export: Option<Callback<ExportHookFn>>,
+    job_run_history: Option<(Callback<JobRunLoadFn>, Callback<JobRunStoreFn>)>,
+    #[cfg(feature = "gcp")]
+    gcp: bool,
+    #[cfg(feature = "metrics")]
+    metric_views: Vec<Callback<MetricViewFn>>,
+    #[cfg(feature = "test-util")]
+    id_generator: Option<crate::testing::SharedIdGenerator>,
+    #[cfg(feature = "test-util")]
+    test_clock: Option<crate::testing::TestClock>,
+    #[cfg(feature = "test-util")]
+    span_capture: Option<crate::testing::SpanCapture>,
+}
+
+impl fmt::Debug for OtelOptionsBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let OtelOptionsBuilder {
+            endpoint,
+            protocol,
+            traces_protocol,
+            logs_protocol,
+            grpc_http_fallback,
+            grpc_keepalive,
+            grpc_connect_timeout,
+            grpc_adaptive_window,
+            grpc_dns_refresh,
+            dedicated_export_thread,
+            discover_endpoint,
+            discovery_file,
+            verify_connectivity,
+            headers,
+            header_sources,
+            grpc_metadata,
+            user_agent,
+            resource_attributes,
+            resource_key_values,
+            export_timeout,
+            invalid_export_timeout,
+            invalid_protocol,
+            set_global,
+            span_name_mapper,
+            max_attribute_value_length,
+            attribute_renames,
+            span_event_summary,
+            drop_span_filters,
+            event_counters,
+            span_kind_rules,
+            sampling_rules,
+            large_integer_mode,
+            expand_json_fields,
+            console_format,
+            gelf,
+            syslog,
+            statsd,
+            debug_logs_on_error_only,
+            dedup_logs_window,
+            export_budget,
+            max_export_bytes,
+            adaptive_sampling_min_ratio,
+            metrics_temporality,
+            histogram_aggregation,
+            record_path,
+            log_file,
+            file_rotation,
+            flush_watchdog,
+            cardinality_window,
+            remote_sampling,
+            opamp,
+            receiver,
+            profiling,
+            dead_letter_output,
+            startup_banner,
+            benchmark_mode,
+            skip_unsampled_spans,
+            async_bridge,
+            batch_queue_shards,
+            adaptive_batch_size,
+            factor_common_attributes,
+            consistent_sampling,
+            monotonic_timestamps,
+            extra_layers,
+            auxiliary_guards,
+            inherit_trace_context,
+            redact_keys,
+            tenant,
+            ambient_attributes,
+            authorization,
+            on_export,
+            job_run_history,
+            #[cfg(feature = "gcp")]
+            gcp,
+            #[cfg(feature = "metrics")]
+            metric_views,
+            #[cfg(feature = "test-util")]
+            id_generator,
+            #[cfg(feature = "test-util")]
+            test_clock,
+            #[cfg(feature = "test-util")]
+            span_capture,
+        } = self;
+        let mut f = f.debug_struct("OtelOptionsBuilder");
+        f.field("endpoint", endpoint);
+        f.field("protocol", protocol);
+        f.field("traces_protocol", traces_protocol);
+        f.field("logs_protocol", logs_protocol);
+        f.field("grpc_http_fallback", grpc_http_fallback);
+        f.field("grpc_keepalive", grpc_keepalive);
+        f.field("grpc_connect_timeout", grpc_connect_timeout);
+        f.field("grpc_adaptive_window", grpc_adaptive_window);
+        f.field("grpc_dns_refresh", grpc_dns_refresh);
+        f.field("dedicated_export_thread", dedicated_export_thread);
+        f.field("discover_endpoint", discover_endpoint);
+        f.field("discovery_file", discovery_file);
+        f.field("verify_connectivity", verify_connectivity);
+        f.field("headers", &RedactedMap::all(headers));
+        f.field("header_sources", header_sources);
+        f.field("grpc_metadata", &RedactedMap::all(grpc_metadata));
+        f.field("user_agent", user_agent);
+        f.field(
+            "resource_attributes",
+            &RedactedMap::matching(resource_attributes, redact_keys),
+        );
+        f.field(
+            "resource_key_values",
+            &RedactedMap::matching(
+                &crate::resource::display_map(resource_key_values),
+                redact_keys,
+            ),
+        );
+        f.field("export_timeout", export_timeout);
+        f.field("invalid_export_timeout", invalid_export_timeout);
+        f.field("invalid_protocol", invalid_protocol);
+        f.field("set_global", set_global);
+        f.field("span_name_mapper", span_name_mapper);
+        f.field("max_attribute_value_length", max_attribute_value_length);
+        f.field("attribute_renames", attribute_renames);
+        f.field("span_event_summary", span_event_summary);
+        f.field("drop_span_filters", drop_span_filters);
+        f.field("event_counters", event_counters);
+        f.field("span_kind_rules", span_kind_rules);
+        f.field("sampling_rules", sampling_rules);
+        f.field("large_integer_mode", large_integer_mode);
+        f.field("expand_json_fields", expand_json_fields);
+        f.field("console_format", console_format);
+        f.field("gelf", gelf);
+        f.field("syslog", syslog);
+        f.field("statsd", statsd);
+        f.field("debug_logs_on_error_only", debug_logs_on_error_only);
+        f.field("dedup_logs_window", dedup_logs_window);
+        f.field("export_budget", export_budget);
+        f.field("max_export_bytes", max_export_bytes);
+        f.field("adaptive_sampling_min_ratio", adaptive_sampling_min_ratio);
+        f.field("metrics_temporality", metrics_temporality);
+        f.field("histogram_aggregation", histogram_aggregation);
+        f.field("record_path", record_path);
+        f.field("log_file", log_file);
+        f.field("file_rotation", file_rotation);
+        f.field("flush_watchdog", flush_watchdog);
+        f.field("cardinality_window", cardinality_window);
+        f.field("remote_sampling", remote_sampling);
+        f.field("opamp", opamp);
+        f.field("receiver", receiver);
+        f.field("profiling", profiling);
+        f.field("dead_letter_output", dead_letter_output);
+        f.field("startup_banner", startup_banner);
+        f.field("benchmark_mode", benchmark_mode);
+        f.field("skip_unsampled_spans", skip_unsampled_spans);
+        f.field("async_bridge", async_bridge);
+        f.field("batch_queue_shards", batch_queue_shards);
+        f.field("adaptive_batch_size", adaptive_batch_size);
+        f.field("factor_common_attributes", factor_common_attributes);
+        f.field("consistent_sampling", consistent_sampling);
+        f.field("monotonic_timestamps", monotonic_timestamps);
+        f.field("extra_layers", extra_layers);
+        f.field("auxiliary_guards", auxiliary_guards);
+        f.field("inherit_trace_context", inherit_trace_context);
+        f.field("redact_keys", redact_keys);
+        f.field("tenant", tenant);
+        f.field("ambient_attributes", ambient_attributes);
+        f.field("authorization", authorization);
+        f.field("on_export", on_export);
+        f.field("job_run_history", job_run_history);
+        #[cfg(feature = "gcp")]
+        f.field("gcp", gcp);
+        #[cfg(feature = "metrics")]
+        f.field("metric_views", metric_views);
+        #[cfg(feature = "test-util")]
+        f.field("id_generator", id_generator);
+        #[cfg(feature = "test-util")]
+        f.field("test_clock", test_clock);
+        #[cfg(feature = "test-util")]
+        f.field("span_capture", span_capture);
+        f.finish()
+    }
 }
 
 impl OtelOptionsBuilder {
     /// Set the OTLP endpoint (e.g. `"http://collector:4317"`).
+    ///
+    /// With the `vsock` feature, `"vsock://<cid>:<port>"` exports gRPC over virtio-vsock,
+    /// e.g. `"vsock://2:4317"` for a collector on a microVM's host.
     pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
         self.endpoint = Some(endpoint.into());
         self
@@ -52,6 +890,326 @@ impl OtelOptionsBuilder {
     /// Set the OTLP transport protocol.
     pub fn protocol(mut self, protocol: Protocol) -> Self {
         self.protocol = Some(protocol);
+        self.invalid_protocol = None;
+        self
+    }
+
+    /// Set the transport protocol from its `OTEL_EXPORTER_OTLP_PROTOCOL` name, e.g. from a
+    /// config file or CLI flag: `"grpc"`, `"http/protobuf"` or `"http/json"`.
+    ///
+    /// An unknown name makes setup fail with a [`ParseProtocolError`].
+    pub fn protocol_str(mut self, protocol: &str) -> Self {
+        match protocol.parse() {
+            Ok(protocol) => return self.protocol(protocol),
+            Err(e) => self.invalid_protocol = Some(e),
+        }
+        self
+    }
+
+    /// Set the transport protocol for traces only, overriding [`protocol`](Self::protocol).
+    pub fn traces_protocol(mut self, protocol: Protocol) -> Self {
+        self.traces_protocol = Some(protocol);
+        self
+    }
+
+    /// Set the transport protocol for logs only, overriding [`protocol`](Self::protocol).
+    pub fn logs_protocol(mut self, protocol: Protocol) -> Self {
+        self.logs_protocol = Some(protocol);
+        self
+    }
+
+    /// Fall back to HTTP/protobuf if the first gRPC export fails (default: `false`).
+    ///
+    /// Meant for migrations where some collectors don't accept gRPC yet. The HTTP exporter
+    /// targets the same endpoint, with port 4317 swapped for 4318. Needs both the `grpc`
+    /// and `http` features.
+    pub fn grpc_http_fallback(mut self, enabled: bool) -> Self {
+        self.grpc_http_fallback = Some(enabled);
+        self
+    }
+
+    /// Send HTTP/2 keepalive pings on the gRPC connection every `interval`, closing it if
+    /// no reply arrives within `timeout`.
+    ///
+    /// Pings are sent while idle too, so load balancers don't silently drop the connection
+    /// between exports.
+    pub fn grpc_keepalive(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.grpc_keepalive = Some((interval, timeout));
+        self
+    }
+
+    /// Set how long establishing the gRPC connection may take, separately from
+    /// [`export_timeout`](Self::export_timeout).
+    pub fn grpc_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.grpc_connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Enable HTTP/2 adaptive flow control windows on the gRPC connection.
+    pub fn grpc_adaptive_window(mut self, enabled: bool) -> Self {
+        self.grpc_adaptive_window = Some(enabled);
+        self
+    }
+
+    /// Connect to every address the gRPC endpoint's host resolves to and balance exports
+    /// across them, resolving it again every `interval`.
+    ///
+    /// A single long-lived connection pins all export traffic to one collector replica
+    /// behind a headless service or DNS round robin; this spreads it and picks up replicas
+    /// as they come and go. Doesn't apply to `vsock://` endpoints.
+    pub fn grpc_dns_refresh(mut self, interval: Duration) -> Self {
+        self.grpc_dns_refresh = Some(interval);
+        self
+    }
+
+    /// Drive gRPC exports from a single-threaded Tokio runtime on a dedicated OS thread
+    /// instead of the application's runtime, so export I/O never competes with request
+    /// handling. The runtime is stopped when the guard shuts down. HTTP exports already run
+    /// off the application's runtime. Off by default.
+    pub fn dedicated_export_thread(mut self, enabled: bool) -> Self {
+        self.dedicated_export_thread = enabled;
+        self
+    }
+
+    /// Check at setup that the OTLP endpoint accepts connections (default: [`VerifyMode::Off`]).
+    ///
+    /// The check opens a TCP connection to each endpoint in use, bounded by the export
+    /// timeout (at most 5s), so a wrong host or port is caught at deploy time.
+    pub fn verify_connectivity(mut self, mode: VerifyMode) -> Self {
+        self.verify_connectivity = Some(mode);
+        self
+    }
+
+    /// Find the local agent at setup when no endpoint is set, for fleets where the
+    /// agent's location varies by platform. Off by default.
+    ///
+    /// The [`discovery_file`](Self::discovery_file) endpoint, then `localhost:4318`
+    /// (HTTP) and `localhost:4317` (gRPC, with the `grpc` feature) are tried in order,
+    /// and the first that accepts a connection is used along with its protocol. An
+    /// explicitly set protocol limits the localhost candidates to its port. When none
+    /// responds, the default endpoint is used with a warning. Agents listening only on a
+    /// Unix domain socket aren't found: OTLP export over one isn't supported.
+    ///
+    /// An `OTEL_EXPORTER_OTLP_ENDPOINT` env var skips discovery.
+    pub fn discover_endpoint(mut self, enabled: bool) -> Self {
+        self.discover_endpoint = enabled;
+        self
+    }
+
+    /// Read an env-style metadata file during [`discover_endpoint`](Self::discover_endpoint),
+    /// taking its `OTEL_EXPORTER_OTLP_ENDPOINT` (and `OTEL_EXPORTER_OTLP_PROTOCOL`, if set)
+    /// as the first candidate, e.g. a file the platform writes next to its agent.
+    pub fn discovery_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.discovery_file = Some(path.into());
+        self
+    }
+
+    /// Force-flush all providers every `interval` from a background thread.
+    ///
+    /// For environments where the process can be OOM-killed or frozen without the guard
+    /// being dropped: at most one interval of telemetry is lost. The thread is stopped
+    /// when the guard shuts down. A flush that times out, i.e. a stalled exporter, is
+    /// warned about on stderr and counted as a failed export in
+    /// [`OtelGuard::health`](crate::OtelGuard::health). Off by default.
+    pub fn flush_watchdog(mut self, interval: Duration) -> Self {
+        self.flush_watchdog = Some(interval);
+        self
+    }
+
+    /// Track span names and how many distinct values each span attribute has, and report
+    /// them every `window`, to find the instrumentation driving backend costs up (e.g. a
+    /// user id in a span name, or a full URL as an attribute).
+    ///
+    /// At the end of each window a one-line summary of the top offenders is printed to
+    /// stderr, and the full report is available from
+    /// [`OtelGuard::cardinality_report`](crate::OtelGuard::cardinality_report). Windows end
+    /// when the next span is exported or the report is requested. Up to 10 000 names, keys
+    /// and values per key are tracked; values are kept as hashes.
+    pub fn cardinality_report(mut self, window: Duration) -> Self {
+        self.cardinality_window = Some(window);
+        self
+    }
+
+    /// Emit one INFO event after setup with the effective endpoints, protocols, sampler,
+    /// filters and resource, so the backend shows which configuration a process actually
+    /// ran with. Header and gRPC metadata values are redacted. Off by default.
+    pub fn startup_banner(mut self, enabled: bool) -> Self {
+        self.startup_banner = Some(enabled);
+        self
+    }
+
+    /// Record spans and logs as usual but hand every batch to a counting sink instead of
+    /// an exporter, for measuring instrumentation overhead in benchmarks without network
+    /// noise. Metrics are collected and discarded. Read the counts with
+    /// [`OtelGuard::throughput_report`](crate::OtelGuard::throughput_report). Off by
+    /// default.
+    pub fn benchmark_mode(mut self, enabled: bool) -> Self {
+        self.benchmark_mode = enabled;
+        self
+    }
+
+    /// Don't record spans (or their fields and events) beneath a span the sampler dropped,
+    /// so unsampled work costs little more than a disabled span. The first span of an
+    /// unsampled trace is still recorded, as the sampler decides on its fields.
+    ///
+    /// Skipped spans have no OTel context of their own: the [`propagation`](crate::propagation)
+    /// helpers and [`current_traceparent`](crate::current_traceparent) carry the nearest
+    /// recorded ancestor's context from inside one, which is unsampled as well, while
+    /// `OpenTelemetrySpanExt::context` returns an empty context. Off by default.
+    pub fn skip_unsampled_spans(mut self, enabled: bool) -> Self {
+        self.skip_unsampled_spans = enabled;
+        self
+    }
+
+    /// Move log and span processing off the instrumented threads onto two background
+    /// threads, each fed by a queue of up to `capacity` items. Off by default.
+    ///
+    /// Events still have their fields copied into a log record where they happen, and spans
+    /// are still created and recorded inline, since their IDs are needed right away for
+    /// propagation. The log transforms, deduplication and batching then run on the
+    /// `raccoon-otel-logs` thread, and the span transforms of ended spans on the
+    /// `raccoon-otel-spans` thread. An item that doesn't fit in its queue is processed
+    /// inline as usual, so nothing is dropped. Flushing and shutdown wait for the queues.
+    pub fn async_bridge(mut self, capacity: usize) -> Self {
+        self.async_bridge = Some(capacity);
+        self
+    }
+
+    /// Put `shards` per-thread buffers in front of the span and log batch queues, for
+    /// machines with many cores where threads contend on the single queue. Off by default.
+    ///
+    /// Each thread fills its own shard (threads are spread round-robin), and a shard is
+    /// moved into the batch queue in bulk once it holds 256 items, by a merge thread every
+    /// 100ms, and on flush. One shard per core, from
+    /// [`std::thread::available_parallelism`], is a good start. Telemetry reaches the
+    /// batch queue up to 100ms later, and log records are copied once more.
+    pub fn batch_queue_shards(mut self, shards: usize) -> Self {
+        self.batch_queue_shards = Some(shards);
+        self
+    }
+
+    /// Adapt the size of exported span and log batches to how long exports take. Off by
+    /// default.
+    ///
+    /// Batches start at the SDK's default of 512 and range from 32 to 1024. They grow by
+    /// half after an export taking 250ms or more, where the round trip dominates, and shrink
+    /// by a quarter after one taking under 50ms. A failed export, or one taking half the
+    /// export timeout, halves them. The batch processors hand over up to 1024 items at once
+    /// (`OTEL_BSP_MAX_EXPORT_BATCH_SIZE` and `OTEL_BLRP_MAX_EXPORT_BATCH_SIZE` are
+    /// overridden), which are exported in batches of the current size. The export delay is
+    /// unchanged.
+    pub fn adaptive_batch_size(mut self, enabled: bool) -> Self {
+        self.adaptive_batch_size = enabled;
+        self
+    }
+
+    /// Send span attributes that every span of an instrumentation scope in a batch shares,
+    /// with the same value, once as scope attributes instead of on each span. Off by
+    /// default.
+    ///
+    /// A lossy wire optimization for OTLP (gRPC and HTTP) only. OTLP keeps scope and span
+    /// attributes apart and backends don't merge them, so queries and filters on span
+    /// attributes stop matching the factored ones. See the readme for a collector rule that
+    /// copies them back before they reach the backend.
+    pub fn factor_common_attributes(mut self, enabled: bool) -> Self {
+        self.factor_common_attributes = enabled;
+        self
+    }
+
+    /// Take span start, end and event timestamps from a monotonic clock anchored to the
+    /// wall clock at startup, instead of reading the wall clock for each one. An NTP
+    /// step or slew mid-span can then no longer produce spans that end before they start
+    /// or events outside their span; timestamps drift from the wall clock by however far
+    /// it has been adjusted since startup. Off by default.
+    pub fn monotonic_timestamps(mut self, enabled: bool) -> Self {
+        self.monotonic_timestamps = enabled;
+        self
+    }
+
+    /// Add a layer of your own (a custom sink, Sentry, a flame graph) to the subscriber
+    /// that [`setup_otel`](crate::setup_otel) installs, so it stays in charge of layer
+    /// ordering and the global install. Can be called repeatedly; layers see spans and
+    /// events in the order they were added, before the crate's own layers, and are subject
+    /// to the same `RUST_LOG` filter.
+    ///
+    /// The layers are shared between clones of the options and only the first setup uses
+    /// them. [`build_pipeline`](crate::build_pipeline) ignores them, as its caller
+    /// composes the subscriber. Layers can be found again with
+    /// [`Dispatch::downcast_ref`](tracing::Dispatch::downcast_ref).
+    pub fn with_extra_layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<Registry> + Send + Sync + 'static,
+    {
+        self.extra_layers.push(layer.boxed());
+        self
+    }
+
+    /// Hand `resource` (a `tracing_appender` `WorkerGuard`, a metrics poller, a signal
+    /// handler registration) to the [`OtelGuard`](crate::OtelGuard) that setup returns, so
+    /// it lives exactly as long as the telemetry pipeline. Can be called repeatedly.
+    ///
+    /// Resources are dropped after the providers have flushed and shut down, most recently
+    /// added first. They're shared between clones of the options and only the first setup
+    /// takes them. See also [`OtelGuard::keep_alive`](crate::OtelGuard::keep_alive).
+    pub fn keep_alive(self, resource: impl Any + Send) -> Self {
+        self.auxiliary_guards.push(Box::new(resource));
+        self
+    }
+
+    /// Continue the trace of a parent process that passed its span context in the
+    /// `TRACEPARENT` / `TRACESTATE` environment variables, e.g. with
+    /// [`propagation::inject_command`](crate::propagation::inject_command). Root spans
+    /// become children of the parent's span, so a tool and the processes it shells out to
+    /// show up as one trace. On by default; without the variables nothing changes.
+    pub fn inherit_trace_context(mut self, enabled: bool) -> Self {
+        self.inherit_trace_context = Some(enabled);
+        self
+    }
+
+    /// Add key patterns whose resource attribute values are masked when the options are
+    /// printed with `{:?}` or in the [startup banner](Self::startup_banner).
+    ///
+    /// A key matches when it contains a pattern, ignoring case. Keys containing
+    /// `authorization`, `token`, `secret`, `password`, `api-key`/`api_key`/`apikey`,
+    /// `credential` or `cookie` are always masked, as are all header and gRPC metadata values.
+    pub fn redact_keys(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.redact_keys
+            .extend(patterns.into_iter().map(|p| p.into().to_lowercase()));
+        self
+    }
+
+    /// Stamp the current tenant on every span and log record, and optionally export each
+    /// tenant's telemetry with its own header (see [`TenantOptions`]).
+    pub fn tenant(mut self, tenant: TenantOptions) -> Self {
+        self.tenant = Some(tenant);
+        self
+    }
+
+    /// Stamp `key` on every span and log record created while `value` returns `Some`,
+    /// e.g. a request or session ID kept in a task-local, so each `#[instrument]` doesn't
+    /// have to repeat it. Call once per attribute.
+    ///
+    /// `value` is called on the thread creating the span or record, when the span starts
+    /// and when the record is emitted. A log record's own field of the same name wins; on
+    /// spans, a field recorded after the span starts does.
+    ///
+    /// ```no_run
+    /// use raccoon_otel::OtelOptions;
+    ///
+    /// tokio::task_local! {
+    ///     static REQUEST_ID: String;
+    /// }
+    ///
+    /// let options = OtelOptions::builder()
+    ///     .ambient_attribute("request.id", || REQUEST_ID.try_with(Clone::clone).ok())
+    ///     .build();
+    /// ```
+    pub fn ambient_attribute<F>(mut self, key: impl Into<String>, value: F) -> Self
+    where
+        F: Fn() -> Option<String> + Send + Sync + 'static,
+    {
+        self.ambient_attributes
+            .push((key.into(), Callback(Arc::new(value))));
         self
     }
 
@@ -67,6 +1225,114 @@ impl OtelOptionsBuilder {
         self
     }
 
+    /// Add a header whose value is read from the environment variable `var` at setup,
+    /// so tokens stay out of code and process arguments.
+    ///
+    /// Overrides a header of the same name set with [`headers`](Self::headers). Setup fails
+    /// if the variable is not set.
+    pub fn header_from_env(mut self, name: impl Into<String>, var: impl Into<String>) -> Self {
+        self.header_sources
+            .push((name.into(), HeaderSource::Env(var.into())));
+        self
+    }
+
+    /// Add a header whose value is read from the file at `path` at setup (e.g. a mounted
+    /// Kubernetes secret). Surrounding whitespace, including the trailing newline, is trimmed.
+    ///
+    /// Overrides a header of the same name set with [`headers`](Self::headers). Setup fails
+    /// if the file can't be read.
+    pub fn header_from_file(mut self, name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.header_sources
+            .push((name.into(), HeaderSource::File(path.into())));
+        self
+    }
+
+    /// Call `provider` before each export batch for the `Authorization` header value (e.g.
+    /// `"Bearer <token>"`), for backends using short-lived OAuth/OIDC tokens. The exporter is
+    /// rebuilt whenever the value changes, and the value takes precedence over an
+    /// `Authorization` header set with [`headers`](Self::headers).
+    ///
+    /// Over gRPC, `provider` is called for every export call instead and its value is sent
+    /// as the call's `authorization` metadata, so the channel is kept as tokens rotate.
+    ///
+    /// The provider runs on the export thread, so it should return a cached token quickly;
+    /// refresh it elsewhere (e.g. in a background task) rather than blocking on a request.
+    pub fn authorization<F>(mut self, provider: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        self.authorization = Some(Callback(Arc::new(provider)));
+        self
+    }
+
+    /// Call `hook` with a summary of each span or log batch right before it's exported:
+    /// its size in items and estimated bytes, and the time range it covers. For audit
+    /// logging and estimating backend costs.
+    ///
+    /// Batches are summarized after the [export budget](Self::export_budget) has dropped
+    /// what it won't send. The hook runs on the export thread, so it should be quick;
+    /// anything logged from it through `tracing` is exported with the next batch.
+    pub fn on_export<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ExportBatch) + Send + Sync + 'static,
+    {
+        self.on_export = Some(Callback(Arc::new(hook)));
+        self
+    }
+
+    /// Link each run of a recurring [`run_job`](crate::run_job) to the previous one, so
+    /// the runs form a chain in the backend instead of isolated traces.
+    ///
+    /// `load` is called with the job name before the run and returns the `traceparent`
+    /// that `store` saved for it last time, if any; the job's root span gets a link to that
+    /// span. `store` is called with the job name and the new run's `traceparent` when the
+    /// run ends, whatever its outcome. Storage is up to you: a file, a database row, a
+    /// key in a cache.
+    ///
+    /// ```no_run
+    /// use raccoon_otel::OtelOptions;
+    ///
+    /// let options = OtelOptions::builder()
+    ///     .link_job_runs(
+    ///         |job| std::fs::read_to_string(format!("/var/lib/jobs/{job}.trace")).ok(),
+    ///         |job, traceparent| {
+    ///             let _ = std::fs::write(format!("/var/lib/jobs/{job}.trace"), traceparent);
+    ///         },
+    ///     )
+    ///     .build();
+    /// ```
+    pub fn link_job_runs<L, S>(mut self, load: L, store: S) -> Self
+    where
+        L: Fn(&str) -> Option<String> + Send + Sync + 'static,
+        S: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        self.job_run_history = Some((Callback(Arc::new(load)), Callback(Arc::new(store))));
+        self
+    }
+
+    /// Set static gRPC metadata sent with every export, in addition to [`headers`](Self::headers).
+    ///
+    /// Only used by the gRPC transport. Keys and values must be ASCII.
+    pub fn grpc_metadata(
+        mut self,
+        metadata: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        self.grpc_metadata = metadata
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        self
+    }
+
+    /// Identify the client to the collector, e.g. `"checkout/2.3.1"`.
+    ///
+    /// Sent as the `User-Agent` header (HTTP) or `user-agent` metadata (gRPC), followed by
+    /// `raccoon-otel/<version>`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
     /// Set additional resource attributes (e.g. `("deployment.environment", "production")`).
     pub fn resource_attributes(
         mut self,
@@ -79,20 +1345,532 @@ impl OtelOptionsBuilder {
         self
     }
 
+    /// Add resource attributes as ready-made [`KeyValue`]s, for typed values (numbers,
+    /// booleans, arrays) and `&'static str` keys and values that need no copying.
+    ///
+    /// Applied after [`resource_attributes`](Self::resource_attributes), so they win on a
+    /// key present in both.
+    pub fn resource_key_values(mut self, attrs: impl IntoIterator<Item = KeyValue>) -> Self {
+        self.resource_key_values = attrs.into_iter().collect();
+        self
+    }
+
     /// Set the export timeout for OTLP requests.
     pub fn export_timeout(mut self, timeout: Duration) -> Self {
         self.export_timeout = Some(timeout);
+        self.invalid_export_timeout = None;
+        self
+    }
+
+    /// Set the export timeout from a duration string such as `"30s"`, `"500ms"` or `"1m30s"`.
+    ///
+    /// A bare number is read as milliseconds. An unparseable value makes setup fail.
+    pub fn export_timeout_str(mut self, timeout: &str) -> Self {
+        match crate::env::parse_duration(timeout) {
+            Some(timeout) => return self.export_timeout(timeout),
+            None => self.invalid_export_timeout = Some(timeout.to_owned()),
+        }
+        self
+    }
+
+    /// Set whether the tracer provider is registered as the global OTel tracer provider.
+    ///
+    /// Defaults to `true`. Pass `false` when other components in the same process own
+    /// `opentelemetry::global`; the provider is then used only by this crate's layers.
+    pub fn set_global(mut self, set_global: bool) -> Self {
+        self.set_global = Some(set_global);
+        self
+    }
+
+    /// Rename spans before export, e.g. to collapse high-cardinality names such as
+    /// `GET /users/42` into a template like `GET /users/{id}`.
+    ///
+    /// The mapper receives the span name and returns either the name unchanged
+    /// (`Cow::Borrowed`) or a replacement.
+    pub fn span_name_mapper<F>(mut self, mapper: F) -> Self
+    where
+        F: for<'a> Fn(&'a str) -> Cow<'a, str> + Send + Sync + 'static,
+    {
+        self.span_name_mapper = Some(Callback(Arc::new(mapper)));
+        self
+    }
+
+    /// Truncate string attribute values on spans and logs longer than `max_len` bytes.
+    ///
+    /// Truncated values end with `...[truncated]`, and the original byte length is
+    /// recorded in a `<key>.original_length` attribute.
+    pub fn max_attribute_value_length(mut self, max_len: usize) -> Self {
+        self.max_attribute_value_length = Some(max_len);
+        self
+    }
+
+    /// Rename span, span event and log attributes before export, from the first name of
+    /// each pair to the second, e.g. `[("http.method", "http.request.method")]`, so code
+    /// instrumented against older semantic conventions exports the current names.
+    /// [`semconv::LEGACY_RENAMES`](crate::semconv::LEGACY_RENAMES) is a ready-made table.
+    ///
+    /// Calls add to the table, and a later pair for the same name wins. An attribute that
+    /// would land on a name the span or record already carries is dropped, so the current
+    /// name's value is kept. Renames apply after span drop and kind rules, which see the
+    /// names as recorded. Resource attributes aren't renamed.
+    pub fn rename_attributes<K, V>(mut self, renames: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.attribute_renames.extend(
+            renames
+                .into_iter()
+                .map(|(from, to)| (from.into(), to.into())),
+        );
+        self
+    }
+
+    /// On spans with more than `max_events` events, keep the first and last `keep` and
+    /// replace the rest with one `events.summarized` event carrying their count in
+    /// `event.summarized_count`. Off by default.
+    ///
+    /// Lifts the SDK's limit of 128 events per span, which otherwise drops every event
+    /// after the first 128, so a span holds all its events until it ends.
+    pub fn summarize_span_events(mut self, max_events: usize, keep: usize) -> Self {
+        self.span_event_summary = Some((max_events, keep));
+        self
+    }
+
+    /// Never export spans matching any of the given filters (e.g. health checks).
+    ///
+    /// Filters are evaluated against the original span name, before any
+    /// [`span_name_mapper`](Self::span_name_mapper) is applied.
+    pub fn drop_spans_matching(mut self, filters: impl IntoIterator<Item = SpanFilter>) -> Self {
+        self.drop_span_filters = filters.into_iter().collect();
+        self
+    }
+
+    /// Increment a metric counter for every event matching a selector, e.g.
+    /// `[("target:payment", "payment.events.total")]`, so an event also counts as a metric
+    /// without a second call site. Requires the `metrics` feature.
+    ///
+    /// A selector is one or both of `target:<module path>`, matching events in the module
+    /// and its submodules, and `level:<level>`, matching events at that level or more
+    /// severe, separated by a comma (`"target:payment,level:warn"`). Counters carry the
+    /// event's `level` as an attribute. Only events that pass the log level filter are
+    /// counted.
+    pub fn count_events<S, M>(mut self, rules: impl IntoIterator<Item = (S, M)>) -> Self
+    where
+        S: Into<String>,
+        M: Into<String>,
+    {
+        self.event_counters = rules
+            .into_iter()
+            .map(|(selector, metric)| (selector.into(), metric.into()))
+            .collect();
+        self
+    }
+
+    /// Give spans matching `filter` the OTel span kind `kind` (server, client, producer,
+    /// consumer), for spans that can't set the `otel.kind` field themselves, e.g. ones
+    /// created by third-party crates.
+    ///
+    /// Rules only apply to spans that are still `internal`; an explicit `otel.kind`
+    /// always wins. Rules are checked in the order added and the first match applies.
+    /// Filters see the original span name, before any
+    /// [`span_name_mapper`](Self::span_name_mapper).
+    pub fn span_kind(mut self, filter: SpanFilter, kind: SpanKind) -> Self {
+        self.span_kind_rules.push((filter, kind));
+        self
+    }
+
+    /// Sample root spans matching `filter` at `ratio` (0.0 drops them, 1.0 keeps them all),
+    /// for different rates per endpoint:
+    ///
+    /// ```no_run
+    /// use raccoon_otel::{OtelOptions, SpanFilter};
+    ///
+    /// let options = OtelOptions::builder()
+    ///     .sampling_rule(SpanFilter::name("GET /healthz"), 0.0)
+    ///     .sampling_rule(SpanFilter::name("POST /checkout"), 1.0)
+    ///     .sampling_rule(SpanFilter::any(), 0.1)
+    ///     .build();
+    /// ```
+    ///
+    /// Rules are checked in the order added and the first match applies. Root spans no
+    /// rule matches use the sampler from `OTEL_TRACES_SAMPLER` (or
+    /// [`adaptive_sampling`](Self::adaptive_sampling)); child spans follow their parent.
+    /// Filters see the span's name and the fields set when it's created.
+    pub fn sampling_rule(mut self, filter: SpanFilter, ratio: f64) -> Self {
+        self.sampling_rules.push((filter, ratio));
+        self
+    }
+
+    /// Fetch the sampling strategy from `url` every `interval` and apply it as it changes,
+    /// for central control over a fleet's sampling (`remote-sampling` feature).
+    ///
+    /// The strategy uses the Jaeger remote sampling format, so `url` can be a Jaeger
+    /// collector's sampling endpoint (e.g. `http://jaeger:14268/api/sampling`) or any static
+    /// JSON document in that format. The service name is added as the `service` query
+    /// parameter. Per-operation rates apply to root spans with that name, the default rate
+    /// to the other root spans; child spans follow their parent. Rate limiting strategies
+    /// aren't supported.
+    ///
+    /// Once fetched, the strategy replaces [sampling rules](Self::sampling_rule) and
+    /// [adaptive sampling](Self::adaptive_sampling), and stays in effect while the endpoint
+    /// can't be reached. It isn't used before the first fetch succeeds, nor while
+    /// [`OtelGuard::set_sampling_ratio`](crate::OtelGuard::set_sampling_ratio) is in effect.
+    pub fn remote_sampling(mut self, url: impl Into<String>, interval: Duration) -> Self {
+        self.remote_sampling = Some((url.into(), interval));
+        self
+    }
+
+    /// Connect to the OpAMP server at `endpoint`, exchanging status every `interval`
+    /// (`opamp` feature).
+    ///
+    /// The agent reports its resource attributes and export [health](crate::OtelGuard::health)
+    /// over OpAMP's plain HTTP transport (e.g. `http://opamp-server:4320/v1/opamp`), and
+    /// applies remote configuration carrying a sampling strategy in the same format as
+    /// [`remote_sampling`](Self::remote_sampling), as a single file named `sampling`.
+    /// Exporters, endpoints and filters are fixed once the pipeline is built, so remote
+    /// configuration changing them is reported back to the server as failed.
+    pub fn opamp(mut self, endpoint: impl Into<String>, interval: Duration) -> Self {
+        self.opamp = Some((endpoint.into(), interval));
+        self
+    }
+
+    /// Accept OTLP/HTTP exports from child processes and plugins on `addr` (e.g.
+    /// `127.0.0.1:4318`, or port 0 for any free port), forwarding them through this
+    /// pipeline (`receiver` feature).
+    ///
+    /// Spans and logs keep their IDs, times and scopes, and go through the same
+    /// transforms, batching and exporter as the process's own. They're exported with this
+    /// process's resource, each carrying the sender's resource attributes it doesn't
+    /// define. Only protobuf bodies without compression are accepted, on `/v1/traces` and
+    /// `/v1/logs`; pass `OtelGuard::receiver_endpoint()` to the senders as
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    pub fn receiver(mut self, addr: impl Into<String>) -> Self {
+        self.receiver = Some(addr.into());
+        self
+    }
+
+    /// Profile the process's CPU usage and write a pprof file to `dir` every `period`
+    /// (experimental `profiling` feature, Unix only).
+    ///
+    /// Stacks are sampled 99 times a second. Each sample is labelled with its `thread`, and
+    /// with the `trace_id` and `span_id` of the span that was running on that thread, so
+    /// slow spans can be matched with flamegraphs (e.g. `go tool pprof -tagfocus
+    /// trace_id=<id> <file>`). Files are named `<service>-<unix ms>-<pid>.pb` and the
+    /// newest 24 are kept; the profile in progress is written on shutdown.
+    pub fn profiling(mut self, dir: impl Into<PathBuf>, period: Duration) -> Self {
+        self.profiling = Some((dir.into(), period));
+        self
+    }
+
+    /// Write spans and log records to `output` instead of dropping them while the OTLP
+    /// endpoint is unhealthy.
+    ///
+    /// Once three exports in a row have failed (the pipeline turns unhealthy in
+    /// [`OtelGuard::health`](crate::OtelGuard::health)), every batch the endpoint rejects
+    /// is written one line per span or record, with its time, trace and span ids, name or
+    /// body, and attributes. A notice goes to stderr when this starts; the next successful
+    /// export stops it.
+    pub fn dead_letter_output(mut self, output: DeadLetterOutput) -> Self {
+        self.dead_letter_output = Some(output);
+        self
+    }
+
+    /// Set how integer span fields above `i64::MAX` (or below `i64::MIN`) are exported.
+    pub fn large_integer_mode(mut self, mode: LargeIntegerMode) -> Self {
+        self.large_integer_mode = Some(mode);
+        self
+    }
+
+    /// Expand log record fields containing serialized JSON objects or arrays into nested
+    /// attributes, so log backends can index the inner fields. Requires the `json` feature.
+    pub fn expand_json_fields(mut self, expand: bool) -> Self {
+        self.expand_json_fields = Some(expand);
+        self
+    }
+
+    /// Set the console (stdout) output format.
+    pub fn console_format(mut self, format: ConsoleFormat) -> Self {
+        self.console_format = Some(format);
+        self
+    }
+
+    /// Also ship events to a Graylog GELF input. Requires the `gelf` feature.
+    pub fn gelf(mut self, gelf: GelfOptions) -> Self {
+        self.gelf = Some(gelf);
+        self
+    }
+
+    /// Also ship events to a syslog receiver as RFC 5424 messages. Requires the `syslog` feature.
+    pub fn syslog(mut self, syslog: SyslogOptions) -> Self {
+        self.syslog = Some(syslog);
+        self
+    }
+
+    /// Send metrics to a StatsD / DogStatsD agent instead of the OTLP endpoint.
+    /// Requires the `statsd` feature.
+    pub fn statsd(mut self, options: StatsdOptions) -> Self {
+        self.statsd = Some(options);
+        self
+    }
+
+    /// Buffer DEBUG/TRACE log records per trace and export them only if the trace
+    /// ends in error; otherwise they are dropped when the trace's root span ends.
+    ///
+    /// INFO and above are always exported immediately. Records logged outside a span
+    /// are not buffered, and those in unsampled traces are dropped. Requires both the
+    /// `traces` and `logs` features.
+    pub fn debug_logs_on_error_only(mut self, enabled: bool) -> Self {
+        self.debug_logs_on_error_only = Some(enabled);
+        self
+    }
+
+    /// Collapse identical log records (same level, target and message) emitted within
+    /// `window` into one exported record with an `occurrence_count` attribute.
+    ///
+    /// The first occurrence is exported right away; repeats are summarised when the
+    /// window closes. Off by default.
+    pub fn dedup_logs(mut self, window: Duration) -> Self {
+        self.dedup_logs_window = Some(window);
+        self
+    }
+
+    /// Cap export throughput to protect shared egress links. Off by default.
+    ///
+    /// Drop counts are available from [`OtelGuard::stats`](crate::OtelGuard::stats)
+    /// and are reported on stderr at most once a minute.
+    pub fn export_budget(mut self, budget: ExportBudget) -> Self {
+        self.export_budget = Some(budget);
         self
     }
 
+    /// Split span and log batches so no export request exceeds roughly `max_bytes`, e.g.
+    /// just under a collector's 4 MiB gRPC message limit. Off by default.
+    ///
+    /// Items are sized as they encode, but the request's resource and scope come on top,
+    /// so leave some headroom. A single item larger than the limit is still sent on its own.
+    pub fn max_export_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_export_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Lower the trace sampling ratio when exports are backing up or failing, and raise
+    /// it back towards 1.0 once they're healthy. The ratio never drops below `min_ratio`.
+    ///
+    /// Applies to root spans only; child spans follow their parent's decision.
+    pub fn adaptive_sampling(mut self, min_ratio: f64) -> Self {
+        self.adaptive_sampling_min_ratio = Some(min_ratio);
+        self
+    }
+
+    /// Make ratio-based sampling decisions consistent across services, using
+    /// OpenTelemetry's consistent probability sampling. Off by default.
+    ///
+    /// Sampled root spans record their rejection threshold as `th` in the `ot` tracestate
+    /// entry, and decisions use the trace's randomness (the `rv` tracestate value, or the
+    /// trace ID), so services that sample the same trace independently keep nested subsets
+    /// of it instead of partial traces. Applies to
+    /// [`sampling_rule`](Self::sampling_rule), [`adaptive_sampling`](Self::adaptive_sampling),
+    /// [`OtelGuard::set_sampling_ratio`](crate::OtelGuard::set_sampling_ratio), remote
+    /// sampling, and an `OTEL_TRACES_SAMPLER` of `traceidratio` or `parentbased_traceidratio`.
+    pub fn consistent_sampling(mut self, enabled: bool) -> Self {
+        self.consistent_sampling = enabled;
+        self
+    }
+
+    /// Set the metrics aggregation temporality (default: cumulative). Requires the
+    /// `metrics` feature.
+    pub fn metrics_temporality(mut self, temporality: MetricsTemporality) -> Self {
+        self.metrics_temporality = Some(temporality);
+        self
+    }
+
+    /// Set the default histogram aggregation (default: explicit buckets). Requires the
+    /// `metrics` feature.
+    pub fn histogram_aggregation(mut self, aggregation: HistogramAggregation) -> Self {
+        self.histogram_aggregation = Some(aggregation);
+        self
+    }
+
+    /// Append every exported span and log batch to the file at `path`, to be re-sent later
+    /// with `raccoon_otel::replay()`. Batches are still exported as usual. Requires the
+    /// `record` feature.
+    pub fn record_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record_path = Some(path.into());
+        self
+    }
+
+    /// Also write every log record to a local OTLP/JSON file at `path`, next to the
+    /// configured exporter (requires the `file` feature).
+    ///
+    /// The file has the same format and [rotation](Self::file_rotation) as
+    /// [`Protocol::File`], and gets the records before log deduplication and
+    /// [`debug_logs_on_error_only`](Self::debug_logs_on_error_only) buffering, so it
+    /// keeps everything the application logged, whether or not the collector is reachable.
+    pub fn log_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.log_file = Some(path.into());
+        self
+    }
+
+    /// Rotate [`Protocol::File`] output once it reaches `max_bytes`, keeping up to
+    /// `max_backups` rotated files as `<path>.1` (newest) to `<path>.<max_backups>`
+    /// (default: 100 MiB, 10 backups).
+    pub fn file_rotation(mut self, max_bytes: u64, max_backups: usize) -> Self {
+        self.file_rotation = Some((max_bytes, max_backups));
+        self
+    }
+
+    /// Register a metric view on the meter provider, e.g. to rename an instrument, set
+    /// histogram bucket boundaries, or restrict attribute keys to control cardinality.
+    ///
+    /// Views are passed to the SDK as-is; see
+    /// [`MeterProviderBuilder::with_view`](opentelemetry_sdk::metrics::MeterProviderBuilder::with_view).
+    /// Can be called multiple times.
+    #[cfg(feature = "metrics")]
+    pub fn with_view<F>(mut self, view: F) -> Self
+    where
+        F: Fn(
+                &opentelemetry_sdk::metrics::Instrument,
+            ) -> Option<opentelemetry_sdk::metrics::Stream>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.metric_views.push(Callback(Arc::new(view)));
+        self
+    }
+
+    /// Generate trace and span IDs with `generator` instead of randomly, e.g. a
+    /// [`SequentialIdGenerator`](crate::testing::SequentialIdGenerator) for stable IDs in
+    /// tests. Requires the `test-util` feature.
+    #[cfg(feature = "test-util")]
+    pub fn id_generator<G>(mut self, generator: G) -> Self
+    where
+        G: opentelemetry_sdk::trace::IdGenerator + 'static,
+    {
+        self.id_generator = Some(crate::testing::SharedIdGenerator(Arc::new(generator)));
+        self
+    }
+
+    /// Stamp exported spans and span events with readings of `clock` instead of the system
+    /// time. Requires the `test-util` feature.
+    #[cfg(feature = "test-util")]
+    pub fn test_clock(mut self, clock: crate::testing::TestClock) -> Self {
+        self.test_clock = Some(clock);
+        self
+    }
+
+    /// Keep a copy of every finished span in `capture`, for assertions with
+    /// [`assert_span_exists!`](crate::assert_span_exists) and
+    /// [`assert_span_parent!`](crate::assert_span_parent). Spans are still exported as
+    /// usual. Requires the `test-util` feature.
+    #[cfg(feature = "test-util")]
+    pub fn capture_spans(mut self, capture: crate::testing::SpanCapture) -> Self {
+        self.span_capture = Some(capture);
+        self
+    }
+
+    /// Build the [`OtelOptions`], checking them for mistakes first.
+    ///
+    /// Catches invalid endpoint URLs, header and resource attribute names, unparseable
+    /// string values, and options that conflict or have no effect with the chosen protocol,
+    /// which [`build`](Self::build) would only report at setup, as an exporter error, or not at
+    /// all. Environment variables are not consulted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`ValidationError`] found.
+    pub fn try_build(self) -> Result<OtelOptions, ValidationError> {
+        let options = self.build();
+        crate::validation::validate(&options)?;
+        Ok(options)
+    }
+
     /// Build the [`OtelOptions`].
     pub fn build(self) -> OtelOptions {
         OtelOptions {
             endpoint: self.endpoint,
             protocol: self.protocol,
+            traces_protocol: self.traces_protocol,
+            logs_protocol: self.logs_protocol,
+            grpc_http_fallback: self.grpc_http_fallback,
+            grpc_keepalive: self.grpc_keepalive,
+            grpc_connect_timeout: self.grpc_connect_timeout,
+            grpc_adaptive_window: self.grpc_adaptive_window,
+            grpc_dns_refresh: self.grpc_dns_refresh,
+            dedicated_export_thread: self.dedicated_export_thread,
+            discover_endpoint: self.discover_endpoint,
+            discovery_file: self.discovery_file,
+            verify_connectivity: self.verify_connectivity,
             headers: self.headers,
+            header_sources: self.header_sources,
+            grpc_metadata: self.grpc_metadata,
+            user_agent: self.user_agent,
             resource_attributes: self.resource_attributes,
+            resource_key_values: self.resource_key_values,
             export_timeout: self.export_timeout,
+            invalid_export_timeout: self.invalid_export_timeout,
+            invalid_protocol: self.invalid_protocol,
+            set_global: self.set_global,
+            span_name_mapper: self.span_name_mapper,
+            max_attribute_value_length: self.max_attribute_value_length,
+            attribute_renames: self.attribute_renames,
+            span_event_summary: self.span_event_summary,
+            drop_span_filters: self.drop_span_filters,
+            event_counters: self.event_counters,
+            span_kind_rules: self.span_kind_rules,
+            sampling_rules: self.sampling_rules,
+            large_integer_mode: self.large_integer_mode,
+            expand_json_fields: self.expand_json_fields,
+            console_format: self.console_format,
+            gelf: self.gelf,
+            syslog: self.syslog,
+            statsd: self.statsd,
+            debug_logs_on_error_only: self.debug_logs_on_error_only,
+            dedup_logs_window: self.dedup_logs_window,
+            export_budget: self.export_budget,
+            max_export_bytes: self.max_export_bytes,
+            adaptive_sampling_min_ratio: self.adaptive_sampling_min_ratio,
+            metrics_temporality: self.metrics_temporality,
+            histogram_aggregation: self.histogram_aggregation,
+            record_path: self.record_path,
+            log_file: self.log_file,
+            file_rotation: self.file_rotation,
+            flush_watchdog: self.flush_watchdog,
+            cardinality_window: self.cardinality_window,
+            remote_sampling: self.remote_sampling,
+            opamp: self.opamp,
+            receiver: self.receiver,
+            profiling: self.profiling,
+            dead_letter_output: self.dead_letter_output,
+            startup_banner: self.startup_banner,
+            benchmark_mode: self.benchmark_mode,
+            skip_unsampled_spans: self.skip_unsampled_spans,
+            async_bridge: self.async_bridge,
+            batch_queue_shards: self.batch_queue_shards,
+            adaptive_batch_size: self.adaptive_batch_size,
+            factor_common_attributes: self.factor_common_attributes,
+            consistent_sampling: self.consistent_sampling,
+            monotonic_timestamps: self.monotonic_timestamps,
+            extra_layers: self.extra_layers,
+            auxiliary_guards: self.auxiliary_guards,
+            inherit_trace_context: self.inherit_trace_context,
+            redact_keys: self.redact_keys,
+            tenant: self.tenant,
+            ambient_attributes: self.ambient_attributes,
+            authorization: self.authorization,
+            on_export: self.on_export,
+            job_run_history: self.job_run_history,
+            #[cfg(feature = "gcp")]
+            gcp: self.gcp,
+            #[cfg(feature = "metrics")]
+            metric_views: self.metric_views,
+            #[cfg(feature = "test-util")]
+            id_generator: self.id_generator,
+            #[cfg(feature = "test-util")]
+            test_clock: self.test_clock,
+            #[cfg(feature = "test-util")]
+            span_capture: self.span_capture,
         }
     }
 }
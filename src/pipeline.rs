@@ -0,0 +1,372 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::cardinality::CardinalityAnalyzer;
+use crate::env::ResolvedConfig;
+use crate::exporters::budget::BudgetLimiter;
+use crate::exporters::dead_letter::DeadLetterWriter;
+use crate::fork::ForkPoint;
+use crate::guard::OtelGuard;
+use crate::health::ExportHealth;
+use crate::options::OtelOptions;
+use crate::processors::ingest::IngestPoint;
+use crate::processors::offload::LogOffload;
+use crate::processors::trace_buffer::TraceLogBuffer;
+use crate::samplers::adaptive::AdaptiveRatio;
+use crate::samplers::dynamic::SamplingOverride;
+use crate::stats::StatsCounters;
+use crate::{connectivity, env, providers, resource, subscriber};
+
+/// An isolated OpenTelemetry export pipeline.
+///
+/// Unlike [`setup_otel`](crate::setup_otel), a pipeline never touches process-global
+/// state: it does not install a global subscriber, register a global tracer provider,
+/// or set the global propagator. Several pipelines (different service names,
+/// endpoints, resources) can therefore coexist in the same process — for example a
+/// host application and its embedded plugins.
+///
+/// Attach the pipeline to a subscriber with [`OtelPipeline::layer`], then keep the
+/// guard from [`OtelPipeline::into_guard`] alive for as long as the pipeline should export.
+///
+/// # Examples
+///
+/// ```no_run
+/// use raccoon_otel::re_exports::tracing_subscriber::filter::filter_fn;
+/// use raccoon_otel::re_exports::tracing_subscriber::{self, prelude::*};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let host = raccoon_otel::build_pipeline("host-app", None)?;
+/// let plugin = raccoon_otel::build_pipeline("my-plugin", None)?;
+///
+/// tracing_subscriber::registry()
+///     .with(host.layer().with_filter(filter_fn(|m| !m.target().starts_with("my_plugin"))))
+///     .with(plugin.layer().with_filter(filter_fn(|m| m.target().starts_with("my_plugin"))))
+///     .init();
+///
+/// let _host_guard = host.into_guard();
+/// let _plugin_guard = plugin.into_guard();
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "dropping the OtelPipeline immediately shuts down its OTel providers"]
+pub struct OtelPipeline {
+    guard: OtelGuard,
+    config: ResolvedConfig,
+}
+
+impl OtelPipeline {
+    /// Build the providers for an already-resolved configuration.
+    pub(crate) fn from_config(resolved: &ResolvedConfig) -> anyhow::Result<Self> {
+        Self::build(resolved, None)
+    }
+
+    /// Rebuild the pipeline `parent` belongs to in a forked child, sharing the sampler
+    /// state its providers still use. The OTLP receiver stays with the parent process.
+    pub(crate) fn rebuild_after_fork(parent: &ForkPoint) -> anyhow::Result<Self> {
+        let mut resolved = parent.config.clone();
+        resolved.receiver = None;
+        Self::build(&resolved, Some(parent))
+    }
+
+    fn build(resolved: &ResolvedConfig, parent: Option<&ForkPoint>) -> anyhow::Result<Self> {
+        #[cfg(not(feature = "statsd"))]
+        if resolved.statsd.is_some() {
+            anyhow::bail!(
+                "StatsD output requested but the `statsd` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"statsd\"] }}"
+            );
+        }
+
+        #[cfg(not(feature = "metrics"))]
+        if !resolved.event_counters.is_empty() {
+            anyhow::bail!(
+                "Event counters requested but the `metrics` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"metrics\"] }}"
+            );
+        }
+
+        #[cfg(not(feature = "record"))]
+        if resolved.record_path.is_some() {
+            anyhow::bail!(
+                "Recording requested but the `record` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"record\"] }}"
+            );
+        }
+
+        #[cfg(not(feature = "file"))]
+        if resolved.log_file.is_some() {
+            anyhow::bail!(
+                "Log file output requested but the `file` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"file\"] }}"
+            );
+        }
+
+        #[cfg(not(feature = "remote-sampling"))]
+        if resolved.remote_sampling.is_some() {
+            anyhow::bail!(
+                "Remote sampling requested but the `remote-sampling` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"remote-sampling\"] }}"
+            );
+        }
+
+        #[cfg(not(feature = "receiver"))]
+        if resolved.receiver.is_some() {
+            anyhow::bail!(
+                "OTLP receiver requested but the `receiver` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"receiver\"] }}"
+            );
+        }
+
+        #[cfg(not(feature = "opamp"))]
+        if resolved.opamp.is_some() {
+            anyhow::bail!(
+                "OpAMP requested but the `opamp` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"opamp\"] }}"
+            );
+        }
+
+        #[cfg(not(feature = "profiling"))]
+        if resolved.profiling.is_some() {
+            anyhow::bail!(
+                "Profiling requested but the `profiling` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"profiling\"] }}"
+            );
+        }
+        #[cfg(all(feature = "profiling", not(unix)))]
+        if resolved.profiling.is_some() {
+            anyhow::bail!("Profiling is only supported on Unix");
+        }
+
+        connectivity::verify(resolved)?;
+
+        let resource = resource::build_resource(
+            &resolved.service_name,
+            &resolved.resource_attributes,
+            &resolved.resource_key_values,
+        );
+
+        // Shared by both signals: spans decide whether buffered debug logs are exported
+        let log_buffer = (resolved.debug_logs_on_error_only
+            && cfg!(feature = "traces")
+            && cfg!(feature = "logs"))
+        .then(|| Arc::new(TraceLogBuffer::default()));
+
+        // Entered while the exporters are built, so gRPC channels run on it
+        #[cfg(feature = "grpc")]
+        let export_runtime = crate::export_runtime::ExportRuntime::start(resolved)
+            .context("Failed to start the export runtime")?;
+        #[cfg(feature = "grpc")]
+        let entered = export_runtime
+            .as_ref()
+            .and_then(crate::export_runtime::ExportRuntime::enter);
+
+        let stats = Arc::new(StatsCounters::default());
+        let health = Arc::new(ExportHealth::default());
+        let fork = Arc::new(match parent {
+            Some(parent) => ForkPoint::new(
+                resolved.clone(),
+                Arc::clone(&parent.sampling_override),
+                parent.adaptive.clone(),
+            ),
+            None => ForkPoint::new(
+                resolved.clone(),
+                Arc::new(SamplingOverride::default()),
+                resolved
+                    .adaptive_sampling_min_ratio
+                    .map(|min| Arc::new(AdaptiveRatio::new(min))),
+            ),
+        });
+        let sampling_override = Arc::clone(&fork.sampling_override);
+        // One budget for both signals, since they share the egress link
+        let budget = resolved
+            .export_budget
+            .map(|budget| Arc::new(BudgetLimiter::new(budget, Arc::clone(&stats))));
+        // One file for both signals, so the replay order matches the export order
+        #[cfg(feature = "record")]
+        let recorder = resolved
+            .record_path
+            .as_deref()
+            .map(crate::recording::Recorder::open)
+            .transpose()?
+            .map(Arc::new);
+        // Also shared, so a dead endpoint is announced once for both signals
+        let dead_letter = resolved
+            .dead_letter_output
+            .as_ref()
+            .map(DeadLetterWriter::open)
+            .transpose()?
+            .map(Arc::new);
+
+        let cardinality = resolved
+            .cardinality_window
+            .map(|window| Arc::new(CardinalityAnalyzer::new(window)));
+
+        let ingest = Arc::new(IngestPoint::default());
+        let log_offload = resolved
+            .async_bridge
+            .map(|capacity| Arc::new(LogOffload::new(capacity)));
+
+        let tracer_provider = if cfg!(feature = "traces") {
+            Some(
+                providers::tracer::build_tracer_provider(
+                    resource.clone(),
+                    resolved,
+                    log_buffer.clone(),
+                    budget.clone(),
+                    Arc::clone(&health),
+                    Arc::clone(&stats),
+                    dead_letter.clone(),
+                    cardinality.clone(),
+                    &ingest,
+                    &fork,
+                    #[cfg(feature = "record")]
+                    recorder.clone(),
+                )
+                .context("Failed to initialize tracer provider")?,
+            )
+        } else {
+            None
+        };
+
+        let logger_provider = if cfg!(feature = "logs") {
+            Some(
+                providers::logger::build_logger_provider(
+                    resource.clone(),
+                    resolved,
+                    log_buffer,
+                    budget,
+                    Arc::clone(&health),
+                    Arc::clone(&stats),
+                    dead_letter,
+                    &ingest,
+                    log_offload.clone(),
+                    &fork,
+                    #[cfg(feature = "record")]
+                    recorder,
+                )
+                .context("Failed to initialize logger provider")?,
+            )
+        } else {
+            None
+        };
+
+        #[cfg(feature = "remote-sampling")]
+        let remote_sampling = match (&resolved.remote_sampling, &tracer_provider) {
+            (Some((url, interval)), Some(_)) => {
+                Some(crate::samplers::remote::RemoteSamplingPoller::start(
+                    url,
+                    &resolved.service_name,
+                    *interval,
+                    Arc::clone(&sampling_override),
+                )?)
+            }
+            _ => None,
+        };
+        #[cfg(feature = "opamp")]
+        let opamp = resolved
+            .opamp
+            .as_ref()
+            .map(|(endpoint, interval)| {
+                crate::opamp::OpampClient::start(
+                    endpoint,
+                    *interval,
+                    &resource,
+                    Arc::clone(&health),
+                    Arc::clone(&sampling_override),
+                )
+            })
+            .transpose()?;
+        // Started once both providers have set their ingest sinks
+        #[cfg(feature = "receiver")]
+        let receiver = resolved
+            .receiver
+            .as_deref()
+            .map(|addr| {
+                crate::receiver::Receiver::start(addr, Arc::clone(&ingest), resource.clone())
+            })
+            .transpose()?;
+        let guard = OtelGuard::new(tracer_provider, logger_provider, stats, health)
+            .with_sampling_override(sampling_override)
+            .with_cardinality(cardinality)
+            .with_ingest(ingest)
+            .with_log_offload(log_offload)
+            .with_fork(fork);
+        #[cfg(feature = "remote-sampling")]
+        let guard = guard.with_remote_sampling(remote_sampling);
+        #[cfg(feature = "opamp")]
+        let guard = guard.with_opamp(opamp);
+        #[cfg(feature = "receiver")]
+        let guard = guard.with_receiver(receiver);
+        #[cfg(all(feature = "profiling", unix))]
+        let guard = guard.with_profiler(
+            resolved
+                .profiling
+                .as_ref()
+                .map(|(dir, period)| {
+                    crate::profiling::Profiler::start(dir, *period, &resolved.service_name)
+                })
+                .transpose()?,
+        );
+
+        #[cfg(feature = "metrics")]
+        let guard = guard.with_meter_provider(Some(
+            providers::meter::build_meter_provider(resource, resolved)
+                .context("Failed to initialize meter provider")?,
+        ));
+        #[cfg(feature = "grpc")]
+        let guard = {
+            drop(entered);
+            guard.with_export_runtime(export_runtime)
+        };
+        let guard = guard.with_flush_watchdog(resolved.flush_watchdog)?;
+        let guard = guard.with_auxiliary(resolved.auxiliary_guards.take());
+
+        Ok(Self {
+            guard,
+            config: resolved.clone(),
+        })
+    }
+
+    /// Build a tracing [`Layer`] that exports spans and events through this pipeline.
+    ///
+    /// The layer only contains the OTel bridges — no filter or console output — so it
+    /// can be combined with per-layer filters to route telemetry between pipelines.
+    pub fn layer<S>(&self) -> Box<dyn Layer<S> + Send + Sync + 'static>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+    {
+        subscriber::otel_layer(&self.guard, &self.config).boxed()
+    }
+
+    /// Consume the pipeline and return the guard that owns its provider lifecycles.
+    pub fn into_guard(self) -> OtelGuard {
+        self.guard
+    }
+}
+
+/// Build an isolated OpenTelemetry pipeline without touching process-global state.
+///
+/// Configuration is resolved exactly like [`setup_otel`](crate::setup_otel), except that
+/// the tracer provider is never registered globally. See [`OtelPipeline`] for usage.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The endpoint is not a valid HTTP(S) URL
+/// - The endpoint is unreachable and [`VerifyMode::FailFast`](crate::VerifyMode::FailFast) is set
+/// - A required transport feature is not enabled (e.g. `grpc` or `http`)
+/// - Provider or exporter initialization fails
+pub fn build_pipeline(
+    service_name: &str,
+    options: Option<OtelOptions>,
+) -> anyhow::Result<OtelPipeline> {
+    let opts = options.unwrap_or_default();
+    let mut resolved = env::resolve_config(service_name, &opts)?;
+    resolved.set_global = false;
+
+    OtelPipeline::from_config(&resolved)
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use opentelemetry::logs::{AnyValue, LogRecord as _};
+use opentelemetry::trace::Span as _;
+use opentelemetry::{Context, Key, KeyValue};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::SdkLogRecord;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+use crate::env::ResolvedConfig;
+use crate::options::{AmbientAttributeFn, Callback};
+use crate::processors::log::LogTransform;
+
+/// Ambient attribute keys with the callbacks that read their current values.
+pub(crate) type AmbientAttributes = Vec<(Key, Callback<AmbientAttributeFn>)>;
+
+/// The configured ambient attributes, with their keys ready to stamp.
+pub(crate) fn ambient_attributes(config: &ResolvedConfig) -> AmbientAttributes {
+    config
+        .ambient_attributes
+        .iter()
+        .map(|(key, value)| (Key::new(key.clone()), value.clone()))
+        .collect()
+}
+
+/// Span processor that stamps ambient attributes (request or session IDs read from
+/// task-locals) on spans as they start, on the thread creating them.
+///
+/// The span's own attributes can't be read here without copying them, so a field of the
+/// same name given at creation is overridden once duplicate keys are collapsed.
+#[derive(Debug)]
+pub(crate) struct AmbientSpanProcessor<P> {
+    attributes: AmbientAttributes,
+    inner: P,
+}
+
+impl<P: SpanProcessor> AmbientSpanProcessor<P> {
+    pub(crate) fn new(attributes: AmbientAttributes, inner: P) -> Self {
+        Self { attributes, inner }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for AmbientSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        for (key, value) in &self.attributes {
+            if let Some(value) = (value.0)() {
+                span.set_attribute(KeyValue::new(key.clone(), value));
+            }
+        }
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Stamps ambient attributes on log records that don't already carry them.
+#[derive(Debug)]
+pub(crate) struct AmbientLogTransform {
+    attributes: AmbientAttributes,
+}
+
+impl AmbientLogTransform {
+    pub(crate) fn new(attributes: AmbientAttributes) -> Self {
+        Self { attributes }
+    }
+}
+
+impl LogTransform for AmbientLogTransform {
+    fn apply(&self, record: &mut SdkLogRecord) -> bool {
+        for (key, value) in &self.attributes {
+            if record.attributes_iter().any(|(k, _)| k == key) {
+                continue;
+            }
+            if let Some(value) = (value.0)() {
+                record.add_attribute(key.clone(), AnyValue::from(value));
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::sync::Arc;
+
+    use opentelemetry::logs::{Logger as _, LoggerProvider as _};
+    use opentelemetry_sdk::logs::SdkLoggerProvider;
+
+    use super::*;
+
+    thread_local! {
+        static REQUEST_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+    }
+
+    fn attribute(record: &SdkLogRecord, key: &str) -> Option<AnyValue> {
+        record
+            .attributes_iter()
+            .find(|(k, _)| k.as_str() == key)
+            .map(|(_, value)| value.clone())
+    }
+
+    #[test]
+    fn log_records_get_the_current_value_unless_they_have_their_own() {
+        let transform = AmbientLogTransform::new(vec![(
+            Key::new("request.id"),
+            Callback(Arc::new(|| REQUEST_ID.with(|id| id.borrow().clone()))),
+        )]);
+        let logger = SdkLoggerProvider::builder().build().logger("test");
+
+        let mut outside = logger.create_log_record();
+        transform.apply(&mut outside);
+        assert_eq!(attribute(&outside, "request.id"), None);
+
+        REQUEST_ID.with(|id| *id.borrow_mut() = Some("req-1".to_owned()));
+        let mut inside = logger.create_log_record();
+        transform.apply(&mut inside);
+        assert_eq!(
+            attribute(&inside, "request.id"),
+            Some(AnyValue::from("req-1".to_owned()))
+        );
+
+        let mut explicit = logger.create_log_record();
+        explicit.add_attribute("request.id", "req-override");
+        transform.apply(&mut explicit);
+        assert_eq!(explicit.attributes_iter().count(), 1);
+    }
+}
@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use opentelemetry_sdk::trace::SpanData;
+
+use crate::cardinality::CardinalityAnalyzer;
+use crate::processors::span::SpanTransform;
+
+/// Feeds every span that is about to be exported to the [`CardinalityAnalyzer`].
+#[derive(Debug)]
+pub(crate) struct CardinalityTransform {
+    analyzer: Arc<CardinalityAnalyzer>,
+}
+
+impl CardinalityTransform {
+    pub(crate) fn new(analyzer: Arc<CardinalityAnalyzer>) -> Self {
+        Self { analyzer }
+    }
+}
+
+impl SpanTransform for CardinalityTransform {
+    fn apply(&self, span: &mut SpanData) -> bool {
+        self.analyzer.record(span);
+        true
+    }
+}
@@ -0,0 +1,56 @@
+use opentelemetry::logs::{LogRecord as _, Severity};
+use opentelemetry_sdk::logs::SdkLogRecord;
+
+use crate::processors::log::LogTransform;
+
+/// Sets each record's severity text to the matching Cloud Logging `LogSeverity` name, so
+/// Cloud Logging's severity filters and colors apply to exported logs.
+#[derive(Debug)]
+pub(crate) struct CloudLoggingSeverityTransform;
+
+impl LogTransform for CloudLoggingSeverityTransform {
+    fn apply(&self, record: &mut SdkLogRecord) -> bool {
+        if let Some(severity) = record.severity_number() {
+            record.set_severity_text(cloud_logging_severity(severity));
+        }
+        true
+    }
+}
+
+/// Cloud Logging has no trace level and splits fatal into critical, alert and emergency.
+fn cloud_logging_severity(severity: Severity) -> &'static str {
+    match severity as i32 {
+        ..=8 => "DEBUG",
+        9..=10 => "INFO",
+        11..=12 => "NOTICE",
+        13..=16 => "WARNING",
+        17..=20 => "ERROR",
+        21..=22 => "CRITICAL",
+        23 => "ALERT",
+        _ => "EMERGENCY",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracing_levels_map_to_cloud_logging_names() {
+        let mapped: Vec<_> = [
+            Severity::Trace,
+            Severity::Debug,
+            Severity::Info,
+            Severity::Warn,
+            Severity::Error,
+            Severity::Fatal,
+        ]
+        .into_iter()
+        .map(cloud_logging_severity)
+        .collect();
+        assert_eq!(
+            mapped,
+            ["DEBUG", "DEBUG", "INFO", "WARNING", "ERROR", "CRITICAL"]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+
+use opentelemetry_sdk::trace::SpanData;
+
+use crate::processors::span::SpanTransform;
+
+/// Up to this many attributes, duplicates are found by comparing every pair and removed in
+/// place, which is faster than hashing and doesn't allocate.
+const IN_PLACE_MAX: usize = u64::BITS as usize;
+
+/// Removes duplicate span attribute keys, keeping the last value recorded for each key.
+///
+/// The OTel SDK appends on every `set_attribute`, so a field recorded again after the
+/// span started (or re-typed by the typed fields layer) would otherwise be exported twice.
+#[derive(Debug)]
+pub(crate) struct DedupAttributesTransform;
+
+impl SpanTransform for DedupAttributesTransform {
+    fn apply(&self, span: &mut SpanData) -> bool {
+        let attributes = &mut span.attributes;
+        if attributes.len() <= IN_PLACE_MAX {
+            // Bit `i` is set when a later attribute has the same key as attribute `i`
+            let mut overridden = 0u64;
+            for (i, kv) in attributes.iter().enumerate() {
+                if attributes[i + 1..].iter().any(|later| later.key == kv.key) {
+                    overridden |= 1 << i;
+                }
+            }
+            if overridden != 0 {
+                let mut index = 0;
+                attributes.retain(|_| {
+                    let keep = overridden & (1 << index) == 0;
+                    index += 1;
+                    keep
+                });
+            }
+            return true;
+        }
+
+        let mut seen = HashSet::with_capacity(attributes.len());
+        if attributes.iter().all(|kv| seen.insert(kv.key.clone())) {
+            return true;
+        }
+
+        seen.clear();
+        let mut deduped: Vec<_> = attributes
+            .drain(..)
+            .rev()
+            .filter(|kv| seen.insert(kv.key.clone()))
+            .collect();
+        deduped.reverse();
+        *attributes = deduped;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::time::UNIX_EPOCH;
+
+    use opentelemetry::trace::{SpanContext, SpanKind, Status};
+    use opentelemetry::{InstrumentationScope, KeyValue};
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+
+    use super::*;
+
+    fn deduped(attributes: Vec<KeyValue>) -> Vec<KeyValue> {
+        let mut span = SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: opentelemetry::trace::SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Internal,
+            name: Cow::Borrowed("work"),
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+            attributes,
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: InstrumentationScope::builder("test").build(),
+        };
+        assert!(DedupAttributesTransform.apply(&mut span));
+        span.attributes
+    }
+
+    #[test]
+    fn later_values_replace_earlier_ones_in_place() {
+        let attributes = deduped(vec![
+            KeyValue::new("user.id", "42"),
+            KeyValue::new("http.method", "GET"),
+            KeyValue::new("user.id", 42),
+        ]);
+        assert_eq!(
+            attributes,
+            [
+                KeyValue::new("http.method", "GET"),
+                KeyValue::new("user.id", 42)
+            ]
+        );
+
+        // Beyond the in-place limit the hashing path gives the same result
+        let mut many: Vec<_> = (0..IN_PLACE_MAX as i64)
+            .map(|i| KeyValue::new(format!("field.{i}"), i))
+            .collect();
+        many.push(KeyValue::new("field.0", "last"));
+        let attributes = deduped(many);
+        assert_eq!(attributes.len(), IN_PLACE_MAX);
+        assert_eq!(attributes.last(), Some(&KeyValue::new("field.0", "last")));
+    }
+}
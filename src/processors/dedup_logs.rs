@@ -0,0 +1,328 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use opentelemetry::logs::LogRecord as _;
+use opentelemetry::InstrumentationScope;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord};
+use opentelemetry_sdk::Resource;
+
+/// Upper bound on distinct messages tracked at once; beyond it records pass through.
+const MAX_TRACKED: usize = 1024;
+
+/// Records are duplicates if they share severity, target and body. Attributes are
+/// ignored so retry loops that log an attempt number still collapse.
+type DedupKey = (Option<i32>, Option<String>, String);
+
+#[derive(Debug, Default)]
+struct Window {
+    suppressed: i64,
+    latest: Option<(SdkLogRecord, InstrumentationScope)>,
+}
+
+/// The open windows, with their keys in the order they close. Every window lasts as
+/// long, so that's the order they opened in.
+#[derive(Debug, Default)]
+struct Windows {
+    open: HashMap<DedupKey, Window>,
+    deadlines: VecDeque<(Instant, DedupKey)>,
+}
+
+impl Windows {
+    /// Remove the windows closing at `now` or earlier, or all of them without `now`, and
+    /// return the repeats they held back with their count.
+    fn close(&mut self, now: Option<Instant>) -> Vec<(SdkLogRecord, InstrumentationScope, i64)> {
+        let mut closed = Vec::new();
+        while let Some((deadline, _)) = self.deadlines.front() {
+            if now.is_some_and(|now| *deadline > now) {
+                break;
+            }
+            let Some((_, key)) = self.deadlines.pop_front() else {
+                break;
+            };
+            if let Some(Window {
+                suppressed,
+                latest: Some((record, scope)),
+            }) = self.open.remove(&key)
+            {
+                closed.push((record, scope, suppressed));
+            }
+        }
+        closed
+    }
+}
+
+/// Thread closing windows as their deadline passes, until stopped.
+#[derive(Debug)]
+struct Closer {
+    stop: Mutex<Option<Sender<()>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Closer {
+    fn start(
+        window: Duration,
+        close: impl Fn(Option<Instant>) -> Option<Instant> + Send + 'static,
+    ) -> std::io::Result<Self> {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name("raccoon-otel-dedup".into())
+            .spawn(move || {
+                // A window opened while nothing is open closes at least `window` from now
+                let mut next = None;
+                loop {
+                    let wait = next.map_or(window, |next: Instant| {
+                        next.saturating_duration_since(Instant::now())
+                    });
+                    match stopped.recv_timeout(wait) {
+                        Err(RecvTimeoutError::Timeout) => next = close(Some(Instant::now())),
+                        _ => return,
+                    }
+                }
+            })?;
+        Ok(Self {
+            stop: Mutex::new(Some(stop)),
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    /// Stop the thread, waiting for a close in progress.
+    fn stop(&self) {
+        drop(self.stop.lock().unwrap_or_else(|e| e.into_inner()).take());
+        if let Some(thread) = self.thread.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Log processor that collapses identical records emitted within `window`.
+///
+/// The first occurrence is exported immediately. Repeats inside the window are held
+/// back; when the window closes, the latest repeat is exported once with an
+/// `occurrence_count` attribute holding how many repeats it stands for. Windows are
+/// closed by a background thread as they expire, and on flush and shutdown.
+#[derive(Debug)]
+pub(crate) struct DedupLogProcessor<P> {
+    inner: Arc<P>,
+    window: Option<Duration>,
+    windows: Arc<Mutex<Windows>>,
+    // Started with the first record, once the inner processor has its resource
+    closer: OnceLock<Option<Closer>>,
+}
+
+impl<P: LogProcessor + 'static> DedupLogProcessor<P> {
+    /// Wrap `inner`. With `window` set to `None` every record passes straight through.
+    pub(crate) fn new(inner: P, window: Option<Duration>) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            window,
+            windows: Arc::default(),
+            closer: OnceLock::new(),
+        }
+    }
+
+    /// Export the repeats held back by the windows closing at `now` or earlier (all
+    /// windows without `now`), returning when the next one closes.
+    fn close_windows(inner: &P, windows: &Mutex<Windows>, now: Option<Instant>) -> Option<Instant> {
+        let (closed, next) = {
+            let mut windows = windows.lock().unwrap_or_else(|e| e.into_inner());
+            let closed = windows.close(now);
+            (
+                closed,
+                windows.deadlines.front().map(|(deadline, _)| *deadline),
+            )
+        };
+        for (mut record, scope, suppressed) in closed {
+            record.add_attribute("occurrence_count", suppressed);
+            inner.emit(&mut record, &scope);
+        }
+        next
+    }
+
+    fn stop_closer(&self) {
+        if let Some(closer) = self.closer.get().and_then(Option::as_ref) {
+            closer.stop();
+        }
+    }
+}
+
+fn dedup_key(record: &SdkLogRecord) -> DedupKey {
+    (
+        record.severity_number().map(|s| s as i32),
+        record.target().map(|t| t.to_string()),
+        record.body().map(|b| format!("{b:?}")).unwrap_or_default(),
+    )
+}
+
+impl<P: LogProcessor + 'static> LogProcessor for DedupLogProcessor<P> {
+    fn emit(&self, record: &mut SdkLogRecord, instrumentation: &InstrumentationScope) {
+        let Some(window) = self.window else {
+            self.inner.emit(record, instrumentation);
+            return;
+        };
+        self.closer.get_or_init(|| {
+            let (inner, windows) = (Arc::clone(&self.inner), Arc::clone(&self.windows));
+            Closer::start(window, move |now| {
+                Self::close_windows(&inner, &windows, now)
+            })
+            .inspect_err(|e| {
+                eprintln!("raccoon-otel: failed to start the log dedup thread: {e}");
+            })
+            .ok()
+        });
+
+        // So a repeat after its window closed opens a new one, even before the thread runs
+        let now = Instant::now();
+        Self::close_windows(&self.inner, &self.windows, Some(now));
+
+        let key = dedup_key(record);
+        {
+            let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(open) = windows.open.get_mut(&key) {
+                open.suppressed += 1;
+                open.latest = Some((record.clone(), instrumentation.clone()));
+                return;
+            }
+            if windows.open.len() < MAX_TRACKED {
+                windows.deadlines.push_back((now + window, key.clone()));
+                windows.open.insert(key, Window::default());
+            }
+        }
+
+        self.inner.emit(record, instrumentation);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        Self::close_windows(&self.inner, &self.windows, None);
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.stop_closer();
+        Self::close_windows(&self.inner, &self.windows, None);
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.set_resource(resource);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::logs::{AnyValue, Logger as _, LoggerProvider as _, Severity};
+    use opentelemetry::Key;
+    use opentelemetry_sdk::logs::SdkLoggerProvider;
+
+    /// Records the `occurrence_count` of every exported record (`None` if absent).
+    #[derive(Debug, Default)]
+    struct Collect(Mutex<Vec<Option<i64>>>);
+
+    impl LogProcessor for Collect {
+        fn emit(&self, record: &mut SdkLogRecord, _: &InstrumentationScope) {
+            let count = record.attributes_iter().find_map(|(k, v)| match v {
+                AnyValue::Int(n) if *k == Key::from_static_str("occurrence_count") => Some(*n),
+                _ => None,
+            });
+            self.0.lock().unwrap().push(count);
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _: Duration) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    fn record(body: &'static str) -> SdkLogRecord {
+        let mut record = SdkLoggerProvider::builder()
+            .build()
+            .logger("test")
+            .create_log_record();
+        record.set_severity_number(Severity::Error);
+        record.set_body(body.into());
+        record
+    }
+
+    #[test]
+    fn repeats_collapse_into_one_counted_record() {
+        let processor = DedupLogProcessor::new(Collect::default(), Some(Duration::from_secs(60)));
+        let scope = InstrumentationScope::builder("test").build();
+
+        for _ in 0..5 {
+            processor.emit(&mut record("connection refused"), &scope);
+        }
+        processor.emit(&mut record("timed out"), &scope);
+        processor.force_flush().unwrap();
+
+        assert_eq!(
+            *processor.inner.0.lock().unwrap(),
+            vec![None, None, Some(4)]
+        );
+    }
+
+    #[test]
+    fn no_window_passes_everything_through() {
+        let processor = DedupLogProcessor::new(Collect::default(), None);
+        let scope = InstrumentationScope::builder("test").build();
+
+        for _ in 0..3 {
+            processor.emit(&mut record("connection refused"), &scope);
+        }
+
+        assert_eq!(processor.inner.0.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn windows_close_without_further_records() {
+        let processor = DedupLogProcessor::new(Collect::default(), Some(Duration::from_millis(50)));
+        let scope = InstrumentationScope::builder("test").build();
+
+        for _ in 0..3 {
+            processor.emit(&mut record("connection refused"), &scope);
+        }
+        std::thread::sleep(Duration::from_millis(500));
+
+        assert_eq!(*processor.inner.0.lock().unwrap(), vec![None, Some(2)]);
+        processor
+            .shutdown_with_timeout(Duration::from_secs(1))
+            .unwrap();
+    }
+
+    #[test]
+    fn only_expired_windows_close() {
+        let mut windows = Windows::default();
+        let start = Instant::now();
+        for (i, body) in ["a", "b", "c"].into_iter().enumerate() {
+            let key = (None, None, body.to_owned());
+            windows
+                .deadlines
+                .push_back((start + Duration::from_secs(i as u64), key.clone()));
+            windows.open.insert(
+                key,
+                Window {
+                    suppressed: 1,
+                    latest: Some((record(body), InstrumentationScope::builder("test").build())),
+                },
+            );
+        }
+
+        assert_eq!(
+            windows
+                .close(Some(start + Duration::from_millis(1500)))
+                .len(),
+            2
+        );
+        assert_eq!(windows.deadlines.len(), 1);
+        assert!(windows.open.contains_key(&(None, None, "c".to_owned())));
+        assert_eq!(windows.close(None).len(), 1);
+    }
+}
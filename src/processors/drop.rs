@@ -0,0 +1,22 @@
+use opentelemetry_sdk::trace::SpanData;
+
+use crate::processors::span::SpanTransform;
+use crate::span_filter::SpanFilter;
+
+/// Drops spans matching any of the configured [`SpanFilter`]s.
+#[derive(Debug)]
+pub(crate) struct DropSpansTransform {
+    filters: Vec<SpanFilter>,
+}
+
+impl DropSpansTransform {
+    pub(crate) fn new(filters: Vec<SpanFilter>) -> Self {
+        Self { filters }
+    }
+}
+
+impl SpanTransform for DropSpansTransform {
+    fn apply(&self, span: &mut SpanData) -> bool {
+        !self.filters.iter().any(|filter| filter.matches(span))
+    }
+}
@@ -0,0 +1,108 @@
+use opentelemetry::trace::Event;
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::trace::SpanData;
+
+use crate::processors::span::SpanTransform;
+
+const SUMMARY_EVENT: &str = "events.summarized";
+const SUMMARY_COUNT_KEY: &str = "event.summarized_count";
+
+/// On spans with more than `max_events` events, keeps the first and last `keep` and
+/// replaces the ones in between with a single summary event carrying their count.
+///
+/// The summary event is timestamped with the first event it replaces.
+#[derive(Debug)]
+pub(crate) struct EventSummaryTransform {
+    max_events: usize,
+    keep: usize,
+}
+
+impl EventSummaryTransform {
+    pub(crate) fn new(max_events: usize, keep: usize) -> Self {
+        Self { max_events, keep }
+    }
+}
+
+impl SpanTransform for EventSummaryTransform {
+    fn apply(&self, span: &mut SpanData) -> bool {
+        let events = &mut span.events.events;
+        if events.len() <= self.max_events {
+            return true;
+        }
+
+        let end = events.len() - self.keep;
+        let count = end - self.keep;
+        let timestamp = events[self.keep].timestamp;
+        events.drain(self.keep..end);
+        events.insert(
+            self.keep,
+            Event::new(
+                SUMMARY_EVENT,
+                timestamp,
+                vec![KeyValue::new(SUMMARY_COUNT_KEY, count as i64)],
+                0,
+            ),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use opentelemetry::trace::{SpanContext, SpanKind, Status};
+    use opentelemetry::InstrumentationScope;
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+
+    use super::*;
+
+    fn span_with_events(count: usize) -> SpanData {
+        let mut events = SpanEvents::default();
+        events.events = (0..count)
+            .map(|i| Event::new(format!("iteration {i}"), SystemTime::now(), vec![], 0))
+            .collect();
+        SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: opentelemetry::trace::SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Internal,
+            name: "loop".into(),
+            start_time: SystemTime::now(),
+            end_time: SystemTime::now(),
+            attributes: vec![],
+            dropped_attributes_count: 0,
+            events,
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: InstrumentationScope::builder("test").build(),
+        }
+    }
+
+    #[test]
+    fn middle_events_are_replaced_by_a_summary() {
+        let transform = EventSummaryTransform::new(10, 2);
+
+        let mut span = span_with_events(10_000);
+        assert!(transform.apply(&mut span));
+        let names: Vec<&str> = span.events.iter().map(|e| e.name.as_ref()).collect();
+        assert_eq!(
+            names,
+            [
+                "iteration 0",
+                "iteration 1",
+                SUMMARY_EVENT,
+                "iteration 9998",
+                "iteration 9999"
+            ]
+        );
+        assert_eq!(
+            span.events[2].attributes,
+            [KeyValue::new(SUMMARY_COUNT_KEY, 9_996)]
+        );
+
+        let mut short = span_with_events(10);
+        transform.apply(&mut short);
+        assert_eq!(short.events.len(), 10);
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use opentelemetry::{Context, InstrumentationScope};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord};
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+type SpanSink = Box<dyn Fn(SpanData) + Send + Sync>;
+type RecordSink = Box<dyn Fn(&mut SdkLogRecord, &InstrumentationScope) + Send + Sync>;
+
+/// Where spans and log records built outside `tracing` join the processor chains, for
+/// [`OtelGuard::ingest_spans`](crate::OtelGuard::ingest_spans) and
+/// [`OtelGuard::ingest_logs`](crate::OtelGuard::ingest_logs).
+///
+/// Each sink is set by the provider builder of its signal; one that's never set (the
+/// signal is disabled) drops what it's given.
+#[derive(Default)]
+pub(crate) struct IngestPoint {
+    spans: OnceLock<SpanSink>,
+    logs: OnceLock<RecordSink>,
+}
+
+impl fmt::Debug for IngestPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IngestPoint")
+            .field("spans", &self.spans.get().is_some())
+            .field("logs", &self.logs.get().is_some())
+            .finish()
+    }
+}
+
+impl IngestPoint {
+    /// Hand `span` to the span processors, returning `false` if traces are disabled.
+    pub(crate) fn span(&self, span: SpanData) -> bool {
+        self.spans.get().map(|sink| sink(span)).is_some()
+    }
+
+    /// Hand `record` to the log processors, returning `false` if logs are disabled.
+    pub(crate) fn log(&self, record: &mut SdkLogRecord, scope: &InstrumentationScope) -> bool {
+        self.logs.get().map(|sink| sink(record, scope)).is_some()
+    }
+}
+
+/// Span processor whose inner chain also takes ingested spans from an [`IngestPoint`].
+#[derive(Debug)]
+pub(crate) struct IngestSpanProcessor<P> {
+    inner: Arc<P>,
+}
+
+impl<P: SpanProcessor + 'static> IngestSpanProcessor<P> {
+    /// Wrap `inner`. The inner processor must already have its resource set, since it is
+    /// shared with the ingest point.
+    pub(crate) fn new(inner: P, ingest: &IngestPoint) -> Self {
+        let inner = Arc::new(inner);
+        let sink = Arc::clone(&inner);
+        let _ = ingest.spans.set(Box::new(move |span| sink.on_end(span)));
+        Self { inner }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for IngestSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.set_resource(resource);
+        }
+    }
+}
+
+/// Log processor whose inner chain also takes ingested records from an [`IngestPoint`].
+#[derive(Debug)]
+pub(crate) struct IngestLogProcessor<P> {
+    inner: Arc<P>,
+}
+
+impl<P: LogProcessor + 'static> IngestLogProcessor<P> {
+    /// Wrap `inner`. The inner processor must already have its resource set, since it is
+    /// shared with the ingest point.
+    pub(crate) fn new(inner: P, ingest: &IngestPoint) -> Self {
+        let inner = Arc::new(inner);
+        let sink = Arc::clone(&inner);
+        let _ = ingest
+            .logs
+            .set(Box::new(move |record, scope| sink.emit(record, scope)));
+        Self { inner }
+    }
+}
+
+impl<P: LogProcessor> LogProcessor for IngestLogProcessor<P> {
+    fn emit(&self, record: &mut SdkLogRecord, instrumentation: &InstrumentationScope) {
+        self.inner.emit(record, instrumentation);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.set_resource(resource);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::sync::Mutex;
+    use std::time::UNIX_EPOCH;
+
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, Status};
+    use opentelemetry_sdk::trace::{SdkTracerProvider, SpanEvents, SpanLinks};
+
+    use super::*;
+
+    /// Span names, with the resource the processor had when each arrived.
+    type Collected = Arc<Mutex<Vec<(String, Option<Resource>)>>>;
+
+    #[derive(Debug, Default)]
+    struct Collect(Collected, Option<Resource>);
+
+    impl SpanProcessor for Collect {
+        fn on_start(&self, _: &mut Span, _: &Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            let resource = self.1.clone();
+            self.0
+                .lock()
+                .unwrap()
+                .push((span.name.into_owned(), resource));
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _: Duration) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn set_resource(&mut self, resource: &Resource) {
+            self.1 = Some(resource.clone());
+        }
+    }
+
+    #[test]
+    fn ingested_spans_reach_the_inner_processor() {
+        let ingest = IngestPoint::default();
+        let span = || SpanData {
+            span_context: SpanContext::empty_context(),
+            parent_span_id: SpanId::INVALID,
+            parent_span_is_remote: false,
+            span_kind: SpanKind::Internal,
+            name: Cow::Borrowed("device.reading"),
+            start_time: UNIX_EPOCH,
+            end_time: UNIX_EPOCH,
+            attributes: Vec::new(),
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: InstrumentationScope::builder("thermo").build(),
+        };
+        assert!(!ingest.span(span()));
+
+        // The provider's own resource doesn't replace the one set up front
+        let spans = Collected::default();
+        let resource = Resource::builder_empty()
+            .with_service_name("gateway")
+            .build();
+        let mut collect = Collect(Arc::clone(&spans), None);
+        collect.set_resource(&resource);
+        let _provider = SdkTracerProvider::builder()
+            .with_span_processor(IngestSpanProcessor::new(collect, &ingest))
+            .build();
+        assert!(ingest.span(span()));
+        assert_eq!(
+            *spans.lock().unwrap(),
+            [("device.reading".to_owned(), Some(resource))]
+        );
+    }
+}
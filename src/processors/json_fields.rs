@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use opentelemetry::logs::AnyValue;
+use opentelemetry::Key;
+use opentelemetry_sdk::logs::SdkLogRecord;
+
+use crate::processors::log::{rebuild_record, LogTransform};
+
+/// Expands log record attributes holding serialized JSON objects or arrays into
+/// nested OTel map/list values, so log backends can index the inner fields.
+///
+/// Values that merely look like JSON but fail to parse are left untouched.
+#[derive(Debug)]
+pub(crate) struct JsonFieldsTransform;
+
+impl LogTransform for JsonFieldsTransform {
+    fn apply(&self, record: &mut SdkLogRecord) -> bool {
+        let mut expanded_any = false;
+        let attributes: Vec<(Key, AnyValue)> = record
+            .attributes_iter()
+            .map(|(key, value)| match parse_json(value) {
+                Some(expanded) => {
+                    expanded_any = true;
+                    (key.clone(), expanded)
+                }
+                None => (key.clone(), value.clone()),
+            })
+            .collect();
+
+        if expanded_any {
+            *record = rebuild_record(record, attributes);
+        }
+        true
+    }
+}
+
+fn parse_json(value: &AnyValue) -> Option<AnyValue> {
+    let AnyValue::String(s) = value else {
+        return None;
+    };
+    let trimmed = s.as_str().trim_start();
+    if !trimmed.starts_with('{') && !trimmed.starts_with('[') {
+        return None;
+    }
+    serde_json::from_str::<serde_json::Value>(trimmed)
+        .ok()
+        .map(to_any_value)
+}
+
+fn to_any_value(value: serde_json::Value) -> AnyValue {
+    match value {
+        serde_json::Value::Null => AnyValue::String("null".into()),
+        serde_json::Value::Bool(b) => AnyValue::Boolean(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => AnyValue::Int(i),
+            None => AnyValue::Double(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(s) => AnyValue::String(s.into()),
+        serde_json::Value::Array(items) => {
+            AnyValue::ListAny(Box::new(items.into_iter().map(to_any_value).collect()))
+        }
+        serde_json::Value::Object(fields) => {
+            let map: HashMap<Key, AnyValue> = fields
+                .into_iter()
+                .map(|(k, v)| (Key::new(k), to_any_value(v)))
+                .collect();
+            AnyValue::Map(Box::new(map))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_objects_become_nested_maps() {
+        let parsed = parse_json(&AnyValue::from(r#"{"user": {"id": 42}, "tags": ["a"]}"#));
+
+        let Some(AnyValue::Map(map)) = parsed else {
+            panic!("expected a map, got {parsed:?}");
+        };
+        let Some(AnyValue::Map(user)) = map.get(&Key::new("user")) else {
+            panic!("expected nested user map");
+        };
+        assert_eq!(user.get(&Key::new("id")), Some(&AnyValue::Int(42)));
+        assert_eq!(
+            map.get(&Key::new("tags")),
+            Some(&AnyValue::ListAny(Box::new(vec![AnyValue::from("a")])))
+        );
+    }
+
+    #[test]
+    fn plain_and_malformed_strings_are_ignored() {
+        assert_eq!(parse_json(&AnyValue::from("hello")), None);
+        assert_eq!(parse_json(&AnyValue::from("{not json")), None);
+        assert_eq!(parse_json(&AnyValue::Int(1)), None);
+    }
+}
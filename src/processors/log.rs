@@ -0,0 +1,98 @@
+use std::fmt::Debug;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use opentelemetry::logs::{AnyValue, LogRecord as _, Logger as _, LoggerProvider as _};
+use opentelemetry::{InstrumentationScope, Key};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord, SdkLogger, SdkLoggerProvider};
+use opentelemetry_sdk::Resource;
+
+/// An in-place modification applied to log records before they reach the exporter.
+pub(crate) trait LogTransform: Send + Sync + Debug {
+    /// Modify the record. Returning `false` drops it so it is never exported.
+    fn apply(&self, record: &mut SdkLogRecord) -> bool;
+}
+
+/// Log processor that runs a chain of [`LogTransform`]s, in order, before handing
+/// each record to the wrapped processor (normally the batch processor).
+#[derive(Debug)]
+pub(crate) struct TransformingLogProcessor<P> {
+    transforms: Vec<Box<dyn LogTransform>>,
+    inner: P,
+}
+
+impl<P: LogProcessor> TransformingLogProcessor<P> {
+    pub(crate) fn new(transforms: Vec<Box<dyn LogTransform>>, inner: P) -> Self {
+        Self { transforms, inner }
+    }
+}
+
+impl<P: LogProcessor> LogProcessor for TransformingLogProcessor<P> {
+    fn emit(&self, record: &mut SdkLogRecord, instrumentation: &InstrumentationScope) {
+        for transform in &self.transforms {
+            if !transform.apply(record) {
+                return;
+            }
+        }
+        self.inner.emit(record, instrumentation);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// An empty [`SdkLogRecord`].
+pub(crate) fn blank_record() -> SdkLogRecord {
+    // Records can only be created through a logger; this one has no processors.
+    static RECORD_FACTORY: OnceLock<SdkLogger> = OnceLock::new();
+    RECORD_FACTORY
+        .get_or_init(|| SdkLoggerProvider::builder().build().logger("raccoon-otel"))
+        .create_log_record()
+}
+
+/// Copy `record` into a fresh [`SdkLogRecord`] with `attributes` replacing the original ones.
+///
+/// The SDK only allows appending attributes to an existing record, so transforms that
+/// need to rewrite or remove attributes rebuild the record instead.
+pub(crate) fn rebuild_record(
+    record: &SdkLogRecord,
+    attributes: impl IntoIterator<Item = (Key, AnyValue)>,
+) -> SdkLogRecord {
+    let mut rebuilt = blank_record();
+    if let Some(name) = record.event_name() {
+        rebuilt.set_event_name(name);
+    }
+    if let Some(target) = record.target() {
+        rebuilt.set_target(target.clone());
+    }
+    if let Some(timestamp) = record.timestamp() {
+        rebuilt.set_timestamp(timestamp);
+    }
+    if let Some(observed) = record.observed_timestamp() {
+        rebuilt.set_observed_timestamp(observed);
+    }
+    if let Some(text) = record.severity_text() {
+        rebuilt.set_severity_text(text);
+    }
+    if let Some(severity) = record.severity_number() {
+        rebuilt.set_severity_number(severity);
+    }
+    if let Some(body) = record.body() {
+        rebuilt.set_body(body.clone());
+    }
+    if let Some(cx) = record.trace_context() {
+        rebuilt.set_trace_context(cx.trace_id, cx.span_id, cx.trace_flags);
+    }
+    rebuilt.add_attributes(attributes);
+    rebuilt
+}
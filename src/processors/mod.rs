@@ -0,0 +1,28 @@
+pub(crate) mod ambient;
+pub(crate) mod cardinality;
+#[cfg(feature = "gcp")]
+pub(crate) mod cloud_logging;
+pub(crate) mod dedup_attributes;
+pub(crate) mod dedup_logs;
+pub(crate) mod drop;
+pub(crate) mod event_summary;
+pub(crate) mod ingest;
+#[cfg(feature = "json")]
+pub(crate) mod json_fields;
+pub(crate) mod log;
+pub(crate) mod monotonic_clock;
+pub(crate) mod offload;
+pub(crate) mod queue;
+pub(crate) mod rename;
+pub(crate) mod shard;
+pub(crate) mod span;
+#[cfg(feature = "test-util")]
+pub(crate) mod span_capture;
+pub(crate) mod span_kind;
+pub(crate) mod span_name;
+pub(crate) mod tenant;
+#[cfg(feature = "test-util")]
+pub(crate) mod test_clock;
+pub(crate) mod thread_attributes;
+pub(crate) mod trace_buffer;
+pub(crate) mod truncate;
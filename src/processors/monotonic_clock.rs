@@ -0,0 +1,155 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant, SystemTime};
+
+use opentelemetry::Context;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+/// A clock that reads the wall clock once and then advances with [`Instant`], so its
+/// readings never go backwards when the system clock is stepped or slewed.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MonotonicClock {
+    wall: SystemTime,
+    anchor: Instant,
+}
+
+impl MonotonicClock {
+    pub(crate) fn start() -> Self {
+        Self {
+            wall: SystemTime::now(),
+            anchor: Instant::now(),
+        }
+    }
+
+    pub(crate) fn now(&self) -> SystemTime {
+        self.wall + self.anchor.elapsed()
+    }
+}
+
+thread_local! {
+    /// Start and end of the span the bridge layer is closing on this thread.
+    static CLOSING: Cell<Option<(SystemTime, SystemTime)>> = const { Cell::new(None) };
+}
+
+/// Run `close`, during which the span processor sees `start` and `end` as the times of
+/// the span that ends. The OTel layer ends the span synchronously while it's closed.
+pub(crate) fn closing<R>(start: SystemTime, end: SystemTime, close: impl FnOnce() -> R) -> R {
+    let previous = CLOSING.replace(Some((start, end)));
+    let result = close();
+    CLOSING.set(previous);
+    result
+}
+
+/// Span processor that replaces the wall clock timestamps of spans closed through the
+/// bridge layer with the [`MonotonicClock`] readings it took.
+///
+/// Event timestamps are moved by the same amount as the end time and kept within the
+/// span. Spans ended any other way keep their timestamps.
+#[derive(Debug)]
+pub(crate) struct MonotonicClockSpanProcessor<P> {
+    inner: P,
+}
+
+impl<P: SpanProcessor> MonotonicClockSpanProcessor<P> {
+    pub(crate) fn new(inner: P) -> Self {
+        Self { inner }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for MonotonicClockSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, mut span: SpanData) {
+        if let Some((start, end)) = CLOSING.get() {
+            let wall_end = span.end_time;
+            for event in span.events.events.iter_mut() {
+                event.timestamp = shift(event.timestamp, wall_end, end).clamp(start, end);
+            }
+            span.start_time = start;
+            span.end_time = end;
+        }
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// `time` moved by the difference between `from` and `to`.
+fn shift(time: SystemTime, from: SystemTime, to: SystemTime) -> SystemTime {
+    let shifted = match to.duration_since(from) {
+        Ok(ahead) => time.checked_add(ahead),
+        Err(behind) => time.checked_sub(behind.duration()),
+    };
+    shifted.unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry::trace::{Span as _, Tracer, TracerProvider as _};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Collect(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanProcessor for Collect {
+        fn on_start(&self, _: &mut Span, _: &Context) {}
+
+        fn on_end(&self, span: SpanData) {
+            self.0.lock().unwrap().push(span);
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _: Duration) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn closing_spans_take_the_monotonic_times() {
+        let spans = Arc::default();
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(MonotonicClockSpanProcessor::new(Collect(Arc::clone(
+                &spans,
+            ))))
+            .build();
+        let tracer = provider.tracer("test");
+        let t = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        // The wall clock was stepped back by 100s while the span ran
+        let mut span = tracer.build(tracer.span_builder("stepped").with_start_time(t(200)));
+        span.add_event_with_timestamp("before", t(210), vec![]);
+        span.add_event_with_timestamp("after", t(115), vec![]);
+        closing(t(1000), t(1030), || span.end_with_timestamp(t(120)));
+        // Outside the bridge layer's close, timestamps are left alone
+        tracer
+            .build(tracer.span_builder("direct").with_start_time(t(5)))
+            .end_with_timestamp(t(6));
+
+        let spans = spans.lock().unwrap();
+        let stepped = &spans[0];
+        assert_eq!((stepped.start_time, stepped.end_time), (t(1000), t(1030)));
+        let events: Vec<_> = stepped.events.iter().map(|e| e.timestamp).collect();
+        assert_eq!(events, [t(1030), t(1025)]);
+        assert_eq!((spans[1].start_time, spans[1].end_time), (t(5), t(6)));
+    }
+}
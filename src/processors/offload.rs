@@ -0,0 +1,313 @@
+use std::fmt;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use opentelemetry::logs::Logger as _;
+use opentelemetry::{Context, InstrumentationScope};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord, SdkLogger};
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+/// How long a flush waits for a queue to be worked through.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+enum Message<T> {
+    Item(T),
+    Drained(SyncSender<()>),
+    Stop,
+}
+
+/// A bounded queue with a thread of its own handing each item to a handler, so the work
+/// is done off the thread that queued it.
+pub(crate) struct Offload<T> {
+    sender: SyncSender<Message<T>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl<T> fmt::Debug for Offload<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Offload").finish_non_exhaustive()
+    }
+}
+
+impl<T: Send + 'static> Offload<T> {
+    /// Start a thread named `name` handling up to `capacity` queued items with `handle`.
+    pub(crate) fn start(
+        name: &str,
+        capacity: usize,
+        mut handle: impl FnMut(T) + Send + 'static,
+    ) -> std::io::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let thread = std::thread::Builder::new()
+            .name(name.into())
+            .spawn(move || {
+                for message in receiver {
+                    match message {
+                        Message::Item(item) => handle(item),
+                        Message::Drained(done) => {
+                            let _ = done.send(());
+                        }
+                        Message::Stop => break,
+                    }
+                }
+            })?;
+        Ok(Self {
+            sender,
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    /// Queue `item`, or hand it back if the queue is full or stopped, to be handled on the
+    /// calling thread instead.
+    pub(crate) fn send(&self, item: T) -> Result<(), T> {
+        self.sender
+            .try_send(Message::Item(item))
+            .map_err(|e| match e {
+                TrySendError::Full(Message::Item(item))
+                | TrySendError::Disconnected(Message::Item(item)) => item,
+                _ => unreachable!("only items are sent without waiting"),
+            })
+    }
+
+    /// Wait until everything queued so far is handled, for at most [`DRAIN_TIMEOUT`].
+    pub(crate) fn drain(&self) {
+        let (done, drained) = mpsc::sync_channel(1);
+        if self.sender.send(Message::Drained(done)).is_ok() {
+            let _ = drained.recv_timeout(DRAIN_TIMEOUT);
+        }
+    }
+
+    /// Handle everything queued so far, then stop the thread. Items sent afterwards are
+    /// handed back.
+    pub(crate) fn stop(&self) {
+        let thread = self.thread.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(thread) = thread {
+            let _ = self.sender.send(Message::Stop);
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Span processor handing ended spans to its inner processor (the span transforms and the
+/// batch processor) on a background thread, for
+/// [`async_bridge`](crate::OtelOptionsBuilder::async_bridge). A pass-through without a
+/// queue capacity.
+#[derive(Debug)]
+pub(crate) struct OffloadSpanProcessor<P> {
+    inner: Arc<P>,
+    capacity: Option<usize>,
+    // Started with the first span, once the inner processor has its resource
+    offload: OnceLock<Option<Offload<SpanData>>>,
+}
+
+impl<P: SpanProcessor + 'static> OffloadSpanProcessor<P> {
+    pub(crate) fn new(capacity: Option<usize>, inner: P) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            capacity,
+            offload: OnceLock::new(),
+        }
+    }
+
+    fn offload(&self) -> Option<&Offload<SpanData>> {
+        let capacity = self.capacity?;
+        self.offload
+            .get_or_init(|| {
+                let inner = Arc::clone(&self.inner);
+                Offload::start("raccoon-otel-spans", capacity, move |span| {
+                    inner.on_end(span);
+                })
+                .inspect_err(|e| {
+                    eprintln!(
+                        "raccoon-otel: failed to start the span thread, \
+                         processing spans inline: {e}"
+                    );
+                })
+                .ok()
+            })
+            .as_ref()
+    }
+
+    fn started(&self) -> Option<&Offload<SpanData>> {
+        self.offload.get().and_then(Option::as_ref)
+    }
+}
+
+impl<P: SpanProcessor + 'static> SpanProcessor for OffloadSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        let span = match self.offload() {
+            Some(offload) => match offload.send(span) {
+                Ok(()) => return,
+                Err(span) => span,
+            },
+            None => span,
+        };
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        if let Some(offload) = self.started() {
+            offload.drain();
+        }
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        if let Some(offload) = self.started() {
+            offload.stop();
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.set_resource(resource);
+        }
+    }
+}
+
+/// The queue between the [`OffloadLogLayer`](crate::layers::offload::OffloadLogLayer) and
+/// the logger its records are emitted to, on a background thread.
+#[derive(Debug)]
+pub(crate) struct LogOffload {
+    capacity: usize,
+    offload: OnceLock<Option<Offload<SdkLogRecord>>>,
+    rebuilt: OnceLock<Arc<LogOffload>>,
+}
+
+impl LogOffload {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            offload: OnceLock::new(),
+            rebuilt: OnceLock::new(),
+        }
+    }
+
+    /// Queue records to `rebuilt`, the rebuilt pipeline's queue, from now on. See
+    /// [`OtelGuard::reinit_after_fork`](crate::OtelGuard::reinit_after_fork).
+    pub(crate) fn rebuilt(&self, rebuilt: Arc<LogOffload>) {
+        let _ = self.rebuilt.set(rebuilt);
+    }
+
+    /// Start emitting queued records to `logger`.
+    pub(crate) fn start(&self, logger: SdkLogger) {
+        self.offload.get_or_init(|| {
+            Offload::start("raccoon-otel-logs", self.capacity, move |record| {
+                logger.emit(record);
+            })
+            .inspect_err(|e| {
+                eprintln!(
+                    "raccoon-otel: failed to start the log thread, processing logs inline: {e}"
+                );
+            })
+            .ok()
+        });
+    }
+
+    /// Queue `record`, or emit it to `logger` on the calling thread if the queue is full.
+    pub(crate) fn emit(&self, record: SdkLogRecord, logger: &SdkLogger) {
+        if let Some(rebuilt) = self.rebuilt.get() {
+            return rebuilt.emit(record, logger);
+        }
+        let record = match self.offload.get().and_then(Option::as_ref) {
+            Some(offload) => match offload.send(record) {
+                Ok(()) => return,
+                Err(record) => record,
+            },
+            None => record,
+        };
+        logger.emit(record);
+    }
+
+    fn drain(&self) {
+        if let Some(offload) = self.offload.get().and_then(Option::as_ref) {
+            offload.drain();
+        }
+    }
+
+    fn stop(&self) {
+        if let Some(offload) = self.offload.get().and_then(Option::as_ref) {
+            offload.stop();
+        }
+    }
+}
+
+/// Log processor that works through the [`LogOffload`] queue before flushing or shutting
+/// down, so records still queued are exported. A pass-through without a queue.
+#[derive(Debug)]
+pub(crate) struct OffloadLogProcessor<P> {
+    inner: P,
+    offload: Option<Arc<LogOffload>>,
+}
+
+impl<P: LogProcessor> OffloadLogProcessor<P> {
+    pub(crate) fn new(offload: Option<Arc<LogOffload>>, inner: P) -> Self {
+        Self { inner, offload }
+    }
+}
+
+impl<P: LogProcessor> LogProcessor for OffloadLogProcessor<P> {
+    fn emit(&self, record: &mut SdkLogRecord, instrumentation: &InstrumentationScope) {
+        self.inner.emit(record, instrumentation);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        if let Some(ref offload) = self.offload {
+            offload.drain();
+        }
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        if let Some(ref offload) = self.offload {
+            offload.stop();
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queued_items_are_handled_in_order_and_full_queues_hand_items_back() {
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let (release, blocked) = mpsc::channel::<()>();
+        let offload = {
+            let handled = Arc::clone(&handled);
+            Offload::start("offload-test", 1, move |item: u32| {
+                // The first item holds the thread until released, so the queue fills up
+                if item == 1 {
+                    let _ = blocked.recv();
+                }
+                handled.lock().unwrap().push(item);
+            })
+            .unwrap()
+        };
+        assert_eq!(offload.send(1), Ok(()));
+        // Wait for the thread to take the first item off the queue
+        while offload.send(2).is_err() {
+            std::thread::yield_now();
+        }
+        assert_eq!(offload.send(3), Err(3));
+
+        release.send(()).unwrap();
+        offload.drain();
+        assert_eq!(*handled.lock().unwrap(), [1, 2]);
+        offload.stop();
+        assert_eq!(offload.send(4), Err(4));
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use opentelemetry::{Context, InstrumentationScope};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord};
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+use crate::stats::{Signal, StatsCounters};
+
+/// Default `max_queue_size` of the SDK's batch span and log processors.
+const DEFAULT_MAX_QUEUE_SIZE: usize = 2048;
+
+/// Minimum time between two "queue full" warnings on stderr, per signal.
+const WARN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Mirror of a batch processor's queue depth, used to notice when it overflows.
+///
+/// The SDK drops items silently once its queue is full and only reports the total at
+/// shutdown. Counting items in (processor side) and out (exporter side) tells us when
+/// that happens, so drops are counted and warned about locally as they occur.
+#[derive(Debug)]
+pub(crate) struct QueueMonitor {
+    signal: Signal,
+    capacity: usize,
+    stats: Arc<StatsCounters>,
+    unreported: AtomicU64,
+    warned: Mutex<Option<Instant>>,
+}
+
+impl QueueMonitor {
+    /// Monitor the span queue, sized by `OTEL_BSP_MAX_QUEUE_SIZE` like the SDK's.
+    pub(crate) fn spans(stats: Arc<StatsCounters>) -> Self {
+        Self::new(Signal::Spans, queue_size("OTEL_BSP_MAX_QUEUE_SIZE"), stats)
+    }
+
+    /// Monitor the log queue, sized by `OTEL_BLRP_MAX_QUEUE_SIZE` like the SDK's.
+    pub(crate) fn logs(stats: Arc<StatsCounters>) -> Self {
+        Self::new(Signal::Logs, queue_size("OTEL_BLRP_MAX_QUEUE_SIZE"), stats)
+    }
+
+    fn new(signal: Signal, capacity: usize, stats: Arc<StatsCounters>) -> Self {
+        Self {
+            signal,
+            capacity,
+            stats,
+            unreported: AtomicU64::new(0),
+            warned: Mutex::new(None),
+        }
+    }
+
+    /// Account for one item handed to the batch processor.
+    fn enqueue(&self) {
+        let admitted = self
+            .pending()
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                (n < self.capacity).then_some(n + 1)
+            })
+            .is_ok();
+        if !admitted {
+            self.record_drop();
+        }
+    }
+
+    /// Account for `count` items taken off the queue for export.
+    pub(crate) fn dequeued(&self, count: usize) {
+        let _ = self
+            .pending()
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                Some(n.saturating_sub(count))
+            });
+    }
+
+    /// Items currently queued, kept in the shared counters so the guard can report it.
+    fn pending(&self) -> &AtomicUsize {
+        self.stats.queued(self.signal)
+    }
+
+    fn record_drop(&self) {
+        let counter = self.stats.dropped_queue_full(self.signal);
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.unreported.fetch_add(1, Ordering::Relaxed);
+
+        let mut warned = self.warned.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        if warned.is_some_and(|at| now.duration_since(at) < WARN_INTERVAL) {
+            return;
+        }
+        *warned = Some(now);
+        drop(warned);
+
+        let dropped = self.unreported.swap(0, Ordering::Relaxed);
+        eprintln!(
+            "raccoon-otel: {} export queue is full (capacity {}), dropped {dropped} {} \
+             (total dropped: {})",
+            self.signal.name(),
+            self.capacity,
+            self.signal.name(),
+            counter.load(Ordering::Relaxed)
+        );
+    }
+}
+
+pub(crate) fn queue_size(var: &str) -> usize {
+    std::env::var(var)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_QUEUE_SIZE)
+}
+
+/// Span processor that reports every span it forwards to a [`QueueMonitor`].
+#[derive(Debug)]
+pub(crate) struct QueueMonitoringSpanProcessor<P> {
+    inner: P,
+    monitor: Arc<QueueMonitor>,
+}
+
+impl<P: SpanProcessor> QueueMonitoringSpanProcessor<P> {
+    pub(crate) fn new(inner: P, monitor: Arc<QueueMonitor>) -> Self {
+        Self { inner, monitor }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for QueueMonitoringSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.monitor.enqueue();
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Log processor that reports every record it forwards to a [`QueueMonitor`].
+#[derive(Debug)]
+pub(crate) struct QueueMonitoringLogProcessor<P> {
+    inner: P,
+    monitor: Arc<QueueMonitor>,
+}
+
+impl<P: LogProcessor> QueueMonitoringLogProcessor<P> {
+    pub(crate) fn new(inner: P, monitor: Arc<QueueMonitor>) -> Self {
+        Self { inner, monitor }
+    }
+}
+
+impl<P: LogProcessor> LogProcessor for QueueMonitoringLogProcessor<P> {
+    fn emit(&self, record: &mut SdkLogRecord, instrumentation: &InstrumentationScope) {
+        self.monitor.enqueue();
+        self.inner.emit(record, instrumentation);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_beyond_capacity_are_counted_as_dropped() {
+        let stats = Arc::new(StatsCounters::default());
+        let monitor = QueueMonitor::new(Signal::Logs, 2, Arc::clone(&stats));
+
+        for _ in 0..3 {
+            monitor.enqueue();
+        }
+        assert_eq!(stats.snapshot().logs_dropped_queue_full, 1);
+
+        monitor.dequeued(2);
+        monitor.enqueue();
+        assert_eq!(stats.snapshot().logs_dropped_queue_full, 1);
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+use opentelemetry::logs::AnyValue;
+use opentelemetry::Key;
+use opentelemetry_sdk::logs::SdkLogRecord;
+use opentelemetry_sdk::trace::SpanData;
+
+use crate::processors::log::{rebuild_record, LogTransform};
+use crate::processors::span::SpanTransform;
+
+/// Renames attributes on spans, span events and log records from a table of old to new
+/// names. An attribute renamed onto a name that is already taken is dropped.
+#[derive(Debug)]
+pub(crate) struct RenameAttributesTransform {
+    renames: HashMap<Key, Key>,
+}
+
+impl RenameAttributesTransform {
+    /// Build from `(from, to)` pairs, where a later pair for the same `from` wins.
+    pub(crate) fn new(renames: &[(String, String)]) -> Self {
+        Self {
+            renames: renames
+                .iter()
+                .map(|(from, to)| (Key::new(from.clone()), Key::new(to.clone())))
+                .collect(),
+        }
+    }
+
+    fn applies(&self, mut keys: impl Iterator<Item = Key>) -> bool {
+        keys.any(|key| self.renames.contains_key(&key))
+    }
+
+    /// Rename the keys of `items` in place, dropping items whose new key is already taken
+    /// by an item that isn't renamed or by an earlier renamed one.
+    fn rename<T>(&self, items: &mut Vec<T>, key: impl Fn(&mut T) -> &mut Key) {
+        let mut taken: HashSet<Key> = items
+            .iter_mut()
+            .map(&key)
+            .filter(|k| !self.renames.contains_key(*k))
+            .map(|k| k.clone())
+            .collect();
+        items.retain_mut(|item| {
+            let key = key(item);
+            match self.renames.get(key) {
+                Some(to) if !taken.insert(to.clone()) => false,
+                Some(to) => {
+                    *key = to.clone();
+                    true
+                }
+                None => true,
+            }
+        });
+    }
+}
+
+impl SpanTransform for RenameAttributesTransform {
+    fn apply(&self, span: &mut SpanData) -> bool {
+        if self.applies(span.attributes.iter().map(|kv| kv.key.clone())) {
+            self.rename(&mut span.attributes, |kv| &mut kv.key);
+        }
+        for event in span.events.events.iter_mut() {
+            if self.applies(event.attributes.iter().map(|kv| kv.key.clone())) {
+                self.rename(&mut event.attributes, |kv| &mut kv.key);
+            }
+        }
+        true
+    }
+}
+
+impl LogTransform for RenameAttributesTransform {
+    fn apply(&self, record: &mut SdkLogRecord) -> bool {
+        if !self.applies(record.attributes_iter().map(|(key, _)| key.clone())) {
+            return true;
+        }
+        let mut attributes: Vec<(Key, AnyValue)> = record
+            .attributes_iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        self.rename(&mut attributes, |(key, _)| key);
+        *record = rebuild_record(record, attributes);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::KeyValue;
+
+    use super::*;
+
+    #[test]
+    fn legacy_names_are_renamed_unless_the_current_name_is_set() {
+        let transform = RenameAttributesTransform::new(&[
+            ("http.method".to_owned(), "http.request.method".to_owned()),
+            (
+                "http.status_code".to_owned(),
+                "http.response.status_code".to_owned(),
+            ),
+        ]);
+        let mut attributes = vec![
+            KeyValue::new("http.method", "GET"),
+            KeyValue::new("http.status_code", 200),
+            KeyValue::new("http.response.status_code", 204),
+            KeyValue::new("http.route", "/users/{id}"),
+        ];
+        transform.rename(&mut attributes, |kv| &mut kv.key);
+        assert_eq!(
+            attributes,
+            [
+                KeyValue::new("http.request.method", "GET"),
+                KeyValue::new("http.response.status_code", 204),
+                KeyValue::new("http.route", "/users/{id}"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use opentelemetry::{Context, InstrumentationScope};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord};
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+/// How often the merge thread moves what the shards hold into the batch queue.
+const MERGE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A shard holding this many items is merged by the thread that filled it, so a busy
+/// thread doesn't wait for the merge thread.
+const SHARD_MERGE_LEN: usize = 256;
+
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The shard this thread fills, assigned round-robin so threads spread evenly.
+    static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Per-thread buffers in front of a batch queue. Each thread fills its own shard, so
+/// threads don't contend on the queue, and shards are merged into it in bulk.
+#[derive(Debug)]
+struct Shards<T> {
+    shards: Box<[Mutex<Vec<T>>]>,
+}
+
+impl<T> Shards<T> {
+    fn new(count: usize) -> Self {
+        Self {
+            shards: (0..count.max(1)).map(|_| Mutex::default()).collect(),
+        }
+    }
+
+    /// Add `item` to the calling thread's shard, returning the shard's items if it's full.
+    fn push(&self, item: T) -> Option<Vec<T>> {
+        let index = SHARD.with(|shard| *shard) % self.shards.len();
+        let mut shard = self.shards[index].lock().unwrap_or_else(|e| e.into_inner());
+        shard.push(item);
+        (shard.len() >= SHARD_MERGE_LEN).then(|| std::mem::take(&mut *shard))
+    }
+
+    /// Take every shard's items.
+    fn take(&self) -> impl Iterator<Item = T> + '_ {
+        self.shards
+            .iter()
+            .flat_map(|shard| std::mem::take(&mut *shard.lock().unwrap_or_else(|e| e.into_inner())))
+    }
+}
+
+/// Thread merging the shards every [`MERGE_INTERVAL`] until stopped.
+#[derive(Debug)]
+struct Merger {
+    stop: Mutex<Option<Sender<()>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Merger {
+    fn start(name: &str, merge: impl Fn() + Send + 'static) -> std::io::Result<Self> {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name(name.into())
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(MERGE_INTERVAL) {
+                    merge();
+                }
+            })?;
+        Ok(Self {
+            stop: Mutex::new(Some(stop)),
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    /// Stop the thread, waiting for a merge in progress.
+    fn stop(&self) {
+        drop(self.stop.lock().unwrap_or_else(|e| e.into_inner()).take());
+        if let Some(thread) = self.thread.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Start a merge thread, reporting a failure once. The shards are then only merged when
+/// full and on flush.
+fn start_merger(signal: &str, merge: impl Fn() + Send + 'static) -> Option<Merger> {
+    Merger::start(&format!("raccoon-otel-{signal}-merge"), merge)
+        .inspect_err(|e| {
+            eprintln!("raccoon-otel: failed to start the {signal} shard merge thread: {e}");
+        })
+        .ok()
+}
+
+/// Span processor buffering ended spans per thread in front of the batch processor, for
+/// [`batch_queue_shards`](crate::OtelOptionsBuilder::batch_queue_shards). A
+/// pass-through without shards.
+#[derive(Debug)]
+pub(crate) struct ShardedSpanProcessor<P> {
+    inner: Arc<P>,
+    shards: Option<Arc<Shards<SpanData>>>,
+    // Started with the first span, once the inner processor has its resource
+    merger: OnceLock<Option<Merger>>,
+}
+
+impl<P: SpanProcessor + 'static> ShardedSpanProcessor<P> {
+    pub(crate) fn new(shards: Option<usize>, inner: P) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            shards: shards.map(|count| Arc::new(Shards::new(count))),
+            merger: OnceLock::new(),
+        }
+    }
+
+    fn merge(inner: &P, shards: &Shards<SpanData>) {
+        for span in shards.take() {
+            inner.on_end(span);
+        }
+    }
+}
+
+impl<P: SpanProcessor + 'static> SpanProcessor for ShardedSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        let Some(ref shards) = self.shards else {
+            self.inner.on_end(span);
+            return;
+        };
+        self.merger.get_or_init(|| {
+            let (inner, shards) = (Arc::clone(&self.inner), Arc::clone(shards));
+            start_merger("spans", move || Self::merge(&inner, &shards))
+        });
+        for span in shards.push(span).into_iter().flatten() {
+            self.inner.on_end(span);
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        if let Some(ref shards) = self.shards {
+            Self::merge(&self.inner, shards);
+        }
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        if let Some(merger) = self.merger.get().and_then(Option::as_ref) {
+            merger.stop();
+        }
+        if let Some(ref shards) = self.shards {
+            Self::merge(&self.inner, shards);
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.set_resource(resource);
+        }
+    }
+}
+
+/// Log processor buffering records per thread in front of the batch processor, for
+/// [`batch_queue_shards`](crate::OtelOptionsBuilder::batch_queue_shards). A
+/// pass-through without shards.
+///
+/// Records are copied into the shards, as later processors (the file sink) still see the
+/// original.
+#[derive(Debug)]
+pub(crate) struct ShardedLogProcessor<P> {
+    inner: Arc<P>,
+    shards: Option<Arc<Shards<(SdkLogRecord, InstrumentationScope)>>>,
+    merger: OnceLock<Option<Merger>>,
+}
+
+impl<P: LogProcessor + 'static> ShardedLogProcessor<P> {
+    /// Wrap `inner`, which must already have its resource set.
+    pub(crate) fn new(shards: Option<usize>, inner: P) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            shards: shards.map(|count| Arc::new(Shards::new(count))),
+            merger: OnceLock::new(),
+        }
+    }
+
+    fn merge(inner: &P, shards: &Shards<(SdkLogRecord, InstrumentationScope)>) {
+        for (mut record, scope) in shards.take() {
+            inner.emit(&mut record, &scope);
+        }
+    }
+}
+
+impl<P: LogProcessor + 'static> LogProcessor for ShardedLogProcessor<P> {
+    fn emit(&self, record: &mut SdkLogRecord, instrumentation: &InstrumentationScope) {
+        let Some(ref shards) = self.shards else {
+            self.inner.emit(record, instrumentation);
+            return;
+        };
+        self.merger.get_or_init(|| {
+            let (inner, shards) = (Arc::clone(&self.inner), Arc::clone(shards));
+            start_merger("logs", move || Self::merge(&inner, &shards))
+        });
+        let full = shards.push((record.clone(), instrumentation.clone()));
+        for (mut record, scope) in full.into_iter().flatten() {
+            self.inner.emit(&mut record, &scope);
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        if let Some(ref shards) = self.shards {
+            Self::merge(&self.inner, shards);
+        }
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        if let Some(merger) = self.merger.get().and_then(Option::as_ref) {
+            merger.stop();
+        }
+        if let Some(ref shards) = self.shards {
+            Self::merge(&self.inner, shards);
+        }
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.set_resource(resource);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_shards_are_handed_back_and_the_rest_taken_on_merge() {
+        let shards = Shards::new(4);
+        let full: Vec<_> = (0..SHARD_MERGE_LEN + 3)
+            .filter_map(|i| shards.push(i))
+            .collect();
+        // One thread fills one shard
+        assert_eq!(full, [(0..SHARD_MERGE_LEN).collect::<Vec<_>>()]);
+
+        let other = std::thread::scope(|s| s.spawn(|| shards.push(100)).join().unwrap());
+        assert_eq!(other, None);
+        let mut rest: Vec<_> = shards.take().collect();
+        rest.sort_unstable();
+        assert_eq!(
+            rest,
+            [
+                100,
+                SHARD_MERGE_LEN,
+                SHARD_MERGE_LEN + 1,
+                SHARD_MERGE_LEN + 2
+            ]
+        );
+        assert_eq!(shards.take().count(), 0);
+    }
+}
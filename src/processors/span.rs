@@ -0,0 +1,54 @@
+use std::fmt::Debug;
+use std::time::Duration;
+
+use opentelemetry::Context;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+/// An in-place modification applied to finished spans before they reach the exporter.
+pub(crate) trait SpanTransform: Send + Sync + Debug {
+    /// Modify the span. Returning `false` drops it so it is never exported.
+    fn apply(&self, span: &mut SpanData) -> bool;
+}
+
+/// Span processor that runs a chain of [`SpanTransform`]s, in order, before handing
+/// each finished span to the wrapped processor (normally the batch processor).
+#[derive(Debug)]
+pub(crate) struct TransformingSpanProcessor<P> {
+    transforms: Vec<Box<dyn SpanTransform>>,
+    inner: P,
+}
+
+impl<P: SpanProcessor> TransformingSpanProcessor<P> {
+    pub(crate) fn new(transforms: Vec<Box<dyn SpanTransform>>, inner: P) -> Self {
+        Self { transforms, inner }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for TransformingSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, mut span: SpanData) {
+        for transform in &self.transforms {
+            if !transform.apply(&mut span) {
+                return;
+            }
+        }
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
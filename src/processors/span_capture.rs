@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use opentelemetry::Context;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+use crate::testing::SpanCapture;
+
+/// Span processor that copies every span into a [`SpanCapture`] before handing it on.
+#[derive(Debug)]
+pub(crate) struct CaptureSpanProcessor<P> {
+    inner: P,
+    capture: Option<SpanCapture>,
+}
+
+impl<P: SpanProcessor> CaptureSpanProcessor<P> {
+    pub(crate) fn new(inner: P, capture: Option<SpanCapture>) -> Self {
+        Self { inner, capture }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for CaptureSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        if let Some(ref capture) = self.capture {
+            capture.push(span.clone());
+        }
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
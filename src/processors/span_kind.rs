@@ -0,0 +1,31 @@
+use opentelemetry::trace::SpanKind;
+use opentelemetry_sdk::trace::SpanData;
+
+use crate::processors::span::SpanTransform;
+use crate::span_filter::SpanFilter;
+
+/// Assigns a [`SpanKind`] to `internal` spans matching a rule; the first matching rule wins.
+///
+/// Spans whose kind was set explicitly through the `otel.kind` field are left alone.
+#[derive(Debug)]
+pub(crate) struct SpanKindTransform {
+    rules: Vec<(SpanFilter, SpanKind)>,
+}
+
+impl SpanKindTransform {
+    pub(crate) fn new(rules: Vec<(SpanFilter, SpanKind)>) -> Self {
+        Self { rules }
+    }
+}
+
+impl SpanTransform for SpanKindTransform {
+    fn apply(&self, span: &mut SpanData) -> bool {
+        if span.span_kind != SpanKind::Internal {
+            return true;
+        }
+        if let Some((_, kind)) = self.rules.iter().find(|(filter, _)| filter.matches(span)) {
+            span.span_kind = kind.clone();
+        }
+        true
+    }
+}
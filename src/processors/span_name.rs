@@ -0,0 +1,29 @@
+use std::borrow::Cow;
+
+use opentelemetry_sdk::trace::SpanData;
+
+use crate::options::{Callback, SpanNameMapperFn};
+use crate::processors::span::SpanTransform;
+
+/// Renames spans through the user's `span_name_mapper` (e.g. `/users/42` → `/users/{id}`).
+#[derive(Debug)]
+pub(crate) struct SpanNameTransform {
+    mapper: Callback<SpanNameMapperFn>,
+}
+
+impl SpanNameTransform {
+    pub(crate) fn new(mapper: Callback<SpanNameMapperFn>) -> Self {
+        Self { mapper }
+    }
+}
+
+impl SpanTransform for SpanNameTransform {
+    fn apply(&self, span: &mut SpanData) -> bool {
+        let renamed = match (self.mapper.0)(&span.name) {
+            Cow::Borrowed(name) if name == span.name => return true,
+            other => other.into_owned(),
+        };
+        span.name = Cow::Owned(renamed);
+        true
+    }
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use opentelemetry::logs::{AnyValue, LogRecord as _};
+use opentelemetry::trace::Span as _;
+use opentelemetry::{Context, Key, KeyValue};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::SdkLogRecord;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+use crate::processors::log::LogTransform;
+use crate::tenant::TenantOptions;
+
+/// Span processor that stamps the current tenant on spans as they start, while the
+/// request's context (baggage, task-locals) is still current. A no-op without a tenant.
+#[derive(Debug)]
+pub(crate) struct TenantSpanProcessor<P> {
+    tenant: Option<TenantOptions>,
+    inner: P,
+}
+
+impl<P: SpanProcessor> TenantSpanProcessor<P> {
+    pub(crate) fn new(tenant: Option<TenantOptions>, inner: P) -> Self {
+        Self { tenant, inner }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for TenantSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        if let Some(ref options) = self.tenant {
+            if let Some(tenant) = options.current(cx) {
+                span.set_attribute(KeyValue::new(options.attribute.clone(), tenant));
+            }
+        }
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Stamps the current tenant on log records that don't already carry one.
+#[derive(Debug)]
+pub(crate) struct TenantLogTransform {
+    tenant: TenantOptions,
+    attribute: Key,
+}
+
+impl TenantLogTransform {
+    pub(crate) fn new(tenant: TenantOptions) -> Self {
+        let attribute = Key::new(tenant.attribute.clone());
+        Self { tenant, attribute }
+    }
+}
+
+impl LogTransform for TenantLogTransform {
+    fn apply(&self, record: &mut SdkLogRecord) -> bool {
+        let stamped = record
+            .attributes_iter()
+            .any(|(key, _)| *key == self.attribute);
+        if !stamped {
+            if let Some(tenant) = self.tenant.current(&Context::current()) {
+                record.add_attribute(self.attribute.clone(), AnyValue::from(tenant));
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use opentelemetry::trace::{Span as _, SpanId};
+use opentelemetry::Context;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+use crate::testing::TestClock;
+
+/// Span processor that replaces span and event timestamps with readings of a
+/// [`TestClock`], so exported payloads are identical between runs.
+///
+/// Start times are read when the span starts; the end time and every event timestamp are
+/// read when it ends.
+#[derive(Debug)]
+pub(crate) struct TestClockSpanProcessor<P> {
+    clock: TestClock,
+    started: Mutex<HashMap<SpanId, SystemTime>>,
+    inner: P,
+}
+
+impl<P: SpanProcessor> TestClockSpanProcessor<P> {
+    pub(crate) fn new(clock: TestClock, inner: P) -> Self {
+        Self {
+            clock,
+            started: Mutex::new(HashMap::new()),
+            inner,
+        }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for TestClockSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        let span_id = span.span_context().span_id();
+        if let Ok(mut started) = self.started.lock() {
+            started.insert(span_id, self.clock.now());
+        }
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, mut span: SpanData) {
+        let now = self.clock.now();
+        let start = self
+            .started
+            .lock()
+            .ok()
+            .and_then(|mut started| started.remove(&span.span_context.span_id()));
+        span.start_time = start.unwrap_or(now);
+        span.end_time = now;
+        for event in span.events.events.iter_mut() {
+            event.timestamp = now;
+        }
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use opentelemetry::trace::Span as _;
+use opentelemetry::{Context, KeyValue, StringValue, Value};
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Span, SpanData, SpanProcessor};
+use opentelemetry_sdk::Resource;
+
+thread_local! {
+    /// `thread.id` and `thread.name` for the current thread, built once. The name is an
+    /// `Arc<str>`, so each span gets a reference-counted copy instead of a new `String`.
+    static THREAD_ATTRIBUTES: Vec<KeyValue> = thread_attributes();
+}
+
+/// Span processor that sets `thread.id` and `thread.name` on every span when it starts.
+///
+/// Replaces `tracing-opentelemetry`'s own thread attributes, which allocate the thread name
+/// for each span. `on_start` runs on the thread that creates the span.
+#[derive(Debug)]
+pub(crate) struct ThreadAttributesSpanProcessor<P> {
+    inner: P,
+}
+
+impl<P: SpanProcessor> ThreadAttributesSpanProcessor<P> {
+    pub(crate) fn new(inner: P) -> Self {
+        Self { inner }
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for ThreadAttributesSpanProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        THREAD_ATTRIBUTES.with(|attributes| {
+            for kv in attributes {
+                span.set_attribute(kv.clone());
+            }
+        });
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, span: SpanData) {
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+fn thread_attributes() -> Vec<KeyValue> {
+    let thread = std::thread::current();
+    let mut attributes = vec![KeyValue::new("thread.id", thread_id(&thread))];
+    if let Some(name) = thread.name() {
+        let name: Arc<str> = name.into();
+        attributes.push(KeyValue::new(
+            "thread.name",
+            Value::String(StringValue::from(name)),
+        ));
+    }
+    attributes
+}
+
+/// The number in `ThreadId(N)`, as `tracing-opentelemetry` reports it.
+fn thread_id(thread: &std::thread::Thread) -> i64 {
+    format!("{:?}", thread.id())
+        .trim_start_matches("ThreadId(")
+        .trim_end_matches(')')
+        .parse()
+        .unwrap_or_default()
+}
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use opentelemetry::logs::Severity;
+use opentelemetry::trace::{SpanId, Status, TraceId};
+use opentelemetry::InstrumentationScope;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord};
+use opentelemetry_sdk::trace::SpanData;
+use opentelemetry_sdk::Resource;
+
+use crate::processors::span::SpanTransform;
+
+const MAX_TRACES: usize = 4096;
+const MAX_RECORDS_PER_TRACE: usize = 512;
+
+type RecordSink = Box<dyn Fn(&mut SdkLogRecord, &InstrumentationScope) + Send + Sync>;
+
+/// Per-trace buffer of DEBUG/TRACE log records, released only if the trace errors.
+///
+/// The log side buffers records through [`TraceBufferingLogProcessor`]; the span side
+/// reports errors and local root completion through [`TraceErrorTransform`]. When a
+/// trace's local root span ends, its records are exported if any span in the trace had
+/// an error status or an ERROR record was logged, and discarded otherwise.
+///
+/// Memory is bounded: at most `MAX_TRACES` traces are tracked (oldest evicted first)
+/// and at most `MAX_RECORDS_PER_TRACE` records are kept per trace. Unsampled traces are
+/// never tracked: their root span never reaches the span side, so their records could
+/// never be released, and low-severity records in them are dropped right away.
+#[derive(Default)]
+pub(crate) struct TraceLogBuffer {
+    state: Mutex<BufferState>,
+    sink: OnceLock<RecordSink>,
+}
+
+#[derive(Default)]
+struct BufferState {
+    traces: HashMap<TraceId, TraceEntry>,
+    order: VecDeque<TraceId>,
+}
+
+#[derive(Default)]
+struct TraceEntry {
+    records: Vec<(SdkLogRecord, InstrumentationScope)>,
+    errored: bool,
+}
+
+impl fmt::Debug for TraceLogBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceLogBuffer").finish_non_exhaustive()
+    }
+}
+
+impl TraceLogBuffer {
+    /// Buffer `record` if it is a low-severity record in an undecided trace, or drop it
+    /// if the trace isn't sampled.
+    ///
+    /// Returns `false` if the record should be exported immediately instead.
+    fn buffer(&self, record: &SdkLogRecord, scope: &InstrumentationScope) -> bool {
+        let Some(cx) = record.trace_context() else {
+            return false;
+        };
+        let (trace_id, sampled) = (cx.trace_id, cx.trace_flags.is_none_or(|f| f.is_sampled()));
+        let severity = record.severity_number().unwrap_or(Severity::Info);
+        if !sampled {
+            return severity < Severity::Info;
+        }
+
+        let mut state = self.lock();
+        if severity >= Severity::Error {
+            state.entry(trace_id).errored = true;
+            return false;
+        }
+        if severity >= Severity::Info {
+            return false;
+        }
+
+        let evicted = state.evict_if_full(trace_id);
+        let entry = state.entry(trace_id);
+        let buffered = !entry.errored;
+        if buffered && entry.records.len() < MAX_RECORDS_PER_TRACE {
+            entry.records.push((record.clone(), scope.clone()));
+        }
+        drop(state);
+
+        self.release(evicted);
+        buffered
+    }
+
+    fn mark_error(&self, trace_id: TraceId) {
+        self.lock().entry(trace_id).errored = true;
+    }
+
+    /// The trace's local root ended: export its records if it errored, drop them otherwise.
+    fn finish(&self, trace_id: TraceId) {
+        let entry = self.lock().traces.remove(&trace_id);
+        self.release(entry);
+    }
+
+    fn release(&self, entry: Option<TraceEntry>) {
+        let (Some(entry), Some(sink)) = (entry, self.sink.get()) else {
+            return;
+        };
+        if entry.errored {
+            for (mut record, scope) in entry.records {
+                sink(&mut record, &scope);
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BufferState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl BufferState {
+    fn entry(&mut self, trace_id: TraceId) -> &mut TraceEntry {
+        if !self.traces.contains_key(&trace_id) {
+            self.order.push_back(trace_id);
+        }
+        self.traces.entry(trace_id).or_default()
+    }
+
+    /// Make room for `incoming` by evicting the oldest tracked trace.
+    fn evict_if_full(&mut self, incoming: TraceId) -> Option<TraceEntry> {
+        if self.traces.len() < MAX_TRACES || self.traces.contains_key(&incoming) {
+            return None;
+        }
+        while let Some(oldest) = self.order.pop_front() {
+            if let Some(entry) = self.traces.remove(&oldest) {
+                return Some(entry);
+            }
+        }
+        None
+    }
+}
+
+/// Log processor that diverts low-severity records into a [`TraceLogBuffer`] before
+/// the wrapped processor (normally the batch processor).
+#[derive(Debug)]
+pub(crate) struct TraceBufferingLogProcessor<P> {
+    inner: Arc<P>,
+    buffer: Option<Arc<TraceLogBuffer>>,
+}
+
+impl<P: LogProcessor + 'static> TraceBufferingLogProcessor<P> {
+    /// Wrap `inner`. The inner processor must already have its resource set, since it is
+    /// shared with the buffer's release path.
+    pub(crate) fn new(inner: P, buffer: Option<Arc<TraceLogBuffer>>) -> Self {
+        let inner = Arc::new(inner);
+        if let Some(ref buffer) = buffer {
+            let sink = Arc::clone(&inner);
+            let _ = buffer
+                .sink
+                .set(Box::new(move |record, scope| sink.emit(record, scope)));
+        }
+        Self { inner, buffer }
+    }
+}
+
+impl<P: LogProcessor> LogProcessor for TraceBufferingLogProcessor<P> {
+    fn emit(&self, record: &mut SdkLogRecord, instrumentation: &InstrumentationScope) {
+        if let Some(ref buffer) = self.buffer {
+            if buffer.buffer(record, instrumentation) {
+                return;
+            }
+        }
+        self.inner.emit(record, instrumentation);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.set_resource(resource);
+        }
+    }
+}
+
+/// Reports span errors and local root completion to a [`TraceLogBuffer`].
+#[derive(Debug)]
+pub(crate) struct TraceErrorTransform {
+    buffer: Arc<TraceLogBuffer>,
+}
+
+impl TraceErrorTransform {
+    pub(crate) fn new(buffer: Arc<TraceLogBuffer>) -> Self {
+        Self { buffer }
+    }
+}
+
+impl SpanTransform for TraceErrorTransform {
+    fn apply(&self, span: &mut SpanData) -> bool {
+        let trace_id = span.span_context.trace_id();
+        if matches!(span.status, Status::Error { .. }) {
+            self.buffer.mark_error(trace_id);
+        }
+        if span.parent_span_id == SpanId::INVALID || span.parent_span_is_remote {
+            self.buffer.finish(trace_id);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::logs::LogRecord as _;
+    use opentelemetry::trace::TraceFlags;
+
+    fn record(trace_id: TraceId, severity: Severity) -> SdkLogRecord {
+        sampled_record(trace_id, severity, true)
+    }
+
+    fn sampled_record(trace_id: TraceId, severity: Severity, sampled: bool) -> SdkLogRecord {
+        let mut record = fresh_record();
+        record.set_severity_number(severity);
+        let flags = if sampled {
+            TraceFlags::SAMPLED
+        } else {
+            TraceFlags::NOT_SAMPLED
+        };
+        record.set_trace_context(trace_id, SpanId::from(1), Some(flags));
+        record
+    }
+
+    fn fresh_record() -> SdkLogRecord {
+        use opentelemetry::logs::{Logger as _, LoggerProvider as _};
+        opentelemetry_sdk::logs::SdkLoggerProvider::builder()
+            .build()
+            .logger("test")
+            .create_log_record()
+    }
+
+    fn buffer_with_counter() -> (TraceLogBuffer, Arc<Mutex<usize>>) {
+        let buffer = TraceLogBuffer::default();
+        let released = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&released);
+        let _ = buffer
+            .sink
+            .set(Box::new(move |_, _| *counter.lock().unwrap() += 1));
+        (buffer, released)
+    }
+
+    #[test]
+    fn debug_logs_are_dropped_when_trace_succeeds() {
+        let (buffer, released) = buffer_with_counter();
+        let scope = InstrumentationScope::builder("test").build();
+        let trace_id = TraceId::from(7);
+
+        assert!(buffer.buffer(&record(trace_id, Severity::Debug), &scope));
+        assert!(!buffer.buffer(&record(trace_id, Severity::Info), &scope));
+        buffer.finish(trace_id);
+
+        assert_eq!(*released.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn debug_logs_are_released_when_trace_errors() {
+        let (buffer, released) = buffer_with_counter();
+        let scope = InstrumentationScope::builder("test").build();
+        let trace_id = TraceId::from(7);
+
+        assert!(buffer.buffer(&record(trace_id, Severity::Debug), &scope));
+        assert!(buffer.buffer(&record(trace_id, Severity::Trace), &scope));
+        assert!(!buffer.buffer(&record(trace_id, Severity::Error), &scope));
+        // Once the trace has errored, later debug logs go straight through
+        assert!(!buffer.buffer(&record(trace_id, Severity::Debug), &scope));
+        buffer.finish(trace_id);
+
+        assert_eq!(*released.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn unsampled_traces_are_not_tracked() {
+        let (buffer, released) = buffer_with_counter();
+        let scope = InstrumentationScope::builder("test").build();
+        let sampled = TraceId::from(7);
+
+        assert!(buffer.buffer(&record(sampled, Severity::Debug), &scope));
+        for i in 0..MAX_TRACES as u128 {
+            let unsampled = TraceId::from(1000 + i);
+            // Dropped, not buffered or exported
+            assert!(buffer.buffer(&sampled_record(unsampled, Severity::Debug, false), &scope));
+            assert!(!buffer.buffer(&sampled_record(unsampled, Severity::Error, false), &scope));
+            assert!(!buffer.buffer(&sampled_record(unsampled, Severity::Info, false), &scope));
+        }
+        assert_eq!(buffer.lock().traces.len(), 1);
+
+        // The sampled trace wasn't pushed out, so its records are still released
+        buffer.mark_error(sampled);
+        buffer.finish(sampled);
+        assert_eq!(*released.lock().unwrap(), 1);
+    }
+}
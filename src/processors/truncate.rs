@@ -0,0 +1,122 @@
+use opentelemetry::logs::AnyValue;
+use opentelemetry::{Key, KeyValue, Value};
+use opentelemetry_sdk::logs::SdkLogRecord;
+use opentelemetry_sdk::trace::SpanData;
+
+use crate::processors::log::{rebuild_record, LogTransform};
+use crate::processors::span::SpanTransform;
+
+const TRUNCATION_MARKER: &str = "...[truncated]";
+const ORIGINAL_LENGTH_SUFFIX: &str = ".original_length";
+
+/// Truncates string attribute values longer than `max_len` bytes on spans, span events,
+/// and log records, recording the original byte length in `<key>.original_length`.
+#[derive(Debug)]
+pub(crate) struct TruncateTransform {
+    max_len: usize,
+}
+
+impl TruncateTransform {
+    pub(crate) fn new(max_len: usize) -> Self {
+        Self { max_len }
+    }
+
+    fn truncate_key_values(&self, attributes: &mut Vec<KeyValue>) {
+        let mut lengths = Vec::new();
+        for kv in attributes.iter_mut() {
+            if let Value::String(ref s) = kv.value {
+                if let Some(truncated) = truncate_str(s.as_str(), self.max_len) {
+                    let len = s.as_str().len() as i64;
+                    lengths.push(KeyValue::new(original_length_key(kv.key.as_str()), len));
+                    kv.value = Value::String(truncated.into());
+                }
+            }
+        }
+        attributes.extend(lengths);
+    }
+}
+
+impl SpanTransform for TruncateTransform {
+    fn apply(&self, span: &mut SpanData) -> bool {
+        self.truncate_key_values(&mut span.attributes);
+        for event in span.events.events.iter_mut() {
+            self.truncate_key_values(&mut event.attributes);
+        }
+        true
+    }
+}
+
+impl LogTransform for TruncateTransform {
+    fn apply(&self, record: &mut SdkLogRecord) -> bool {
+        let needs_truncation = record.attributes_iter().any(
+            |(_, value)| matches!(value, AnyValue::String(s) if s.as_str().len() > self.max_len),
+        );
+        if !needs_truncation {
+            return true;
+        }
+
+        let mut attributes = Vec::new();
+        let mut lengths = Vec::new();
+        for (key, value) in record.attributes_iter() {
+            let truncated = match value {
+                AnyValue::String(s) => {
+                    truncate_str(s.as_str(), self.max_len).map(|t| (t, s.as_str().len()))
+                }
+                _ => None,
+            };
+            match truncated {
+                Some((truncated, len)) => {
+                    attributes.push((key.clone(), AnyValue::String(truncated.into())));
+                    lengths.push((original_length_key(key.as_str()), AnyValue::Int(len as i64)));
+                }
+                None => attributes.push((key.clone(), value.clone())),
+            }
+        }
+        attributes.extend(lengths);
+
+        *record = rebuild_record(record, attributes);
+        true
+    }
+}
+
+fn original_length_key(key: &str) -> Key {
+    Key::new(format!("{key}{ORIGINAL_LENGTH_SUFFIX}"))
+}
+
+/// Truncate `value` to at most `max_len` bytes (on a char boundary) plus the marker.
+///
+/// Returns `None` if the value already fits.
+fn truncate_str(value: &str, max_len: usize) -> Option<String> {
+    if value.len() <= max_len {
+        return None;
+    }
+    let mut end = max_len;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(format!("{}{TRUNCATION_MARKER}", &value[..end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_values_are_left_alone() {
+        assert_eq!(truncate_str("SELECT 1", 8), None);
+    }
+
+    #[test]
+    fn long_values_are_cut_and_marked() {
+        assert_eq!(
+            truncate_str("SELECT * FROM users", 8),
+            Some("SELECT *...[truncated]".to_owned())
+        );
+    }
+
+    #[test]
+    fn truncation_respects_char_boundaries() {
+        // "é" is two bytes; cutting at 2 would split it
+        assert_eq!(truncate_str("aé-b", 2), Some("a...[truncated]".to_owned()));
+    }
+}
@@ -0,0 +1,164 @@
+//! Continuous CPU profiling (`profiling` feature, Unix only): samples the process's stacks
+//! and writes one pprof file per period, with each sample labelled by the trace and span
+//! that were running.
+
+mod pprof;
+pub(crate) mod timeline;
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use prost::Message;
+
+use crate::profiling::timeline::SpanTimeline;
+
+/// Samples per second, off by one from 100 so sampling doesn't line up with periodic work.
+const FREQUENCY: i32 = 99;
+
+/// Profile files kept in the directory; older ones are deleted.
+const KEEP_PROFILES: usize = 24;
+
+/// Libraries whose frames the profiler skips, as unwinding through them can crash.
+#[cfg_attr(
+    not(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "loongarch64"
+    )),
+    allow(dead_code)
+)]
+const BLOCKLIST: [&str; 4] = ["libc", "libgcc", "pthread", "vdso"];
+
+/// Background thread that profiles the process and writes a pprof file every `period`.
+#[derive(Debug)]
+pub(crate) struct Profiler {
+    timeline: Arc<SpanTimeline>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Profiler {
+    pub(crate) fn start(dir: &Path, period: Duration, service_name: &str) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create the profile directory {}", dir.display()))?;
+        let dir = dir.to_owned();
+        let prefix = format!("{}-", file_stem(service_name));
+        let timeline = Arc::new(SpanTimeline::default());
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = {
+            let timeline = Arc::clone(&timeline);
+            std::thread::Builder::new()
+                .name("raccoon-otel-profiler".into())
+                .spawn(move || loop {
+                    let builder = ::pprof::ProfilerGuardBuilder::default().frequency(FREQUENCY);
+                    #[cfg(any(
+                        target_arch = "x86_64",
+                        target_arch = "aarch64",
+                        target_arch = "riscv64",
+                        target_arch = "loongarch64"
+                    ))]
+                    let builder = builder.blocklist(&BLOCKLIST);
+                    let profiler = match builder.build() {
+                        Ok(profiler) => profiler,
+                        Err(e) => {
+                            eprintln!("raccoon-otel: profiling disabled: {e}");
+                            return;
+                        }
+                    };
+                    let started = SystemTime::now();
+                    // Both a stop message and a dropped sender end profiling, after
+                    // writing the profile so far
+                    let stopping =
+                        !matches!(stopped.recv_timeout(period), Err(RecvTimeoutError::Timeout));
+                    let report = profiler.report().build();
+                    drop(profiler);
+                    match report {
+                        Ok(report) => {
+                            let profile = pprof::encode(&report, &timeline);
+                            if let Err(e) = write(&dir, &prefix, &profile) {
+                                eprintln!("raccoon-otel: failed to write a profile: {e:#}");
+                            }
+                        }
+                        Err(e) => eprintln!("raccoon-otel: failed to build a profile: {e}"),
+                    }
+                    timeline.prune(started);
+                    if stopping {
+                        return;
+                    }
+                })
+                .context("Failed to start the profiler thread")?
+        };
+        Ok(Self {
+            timeline,
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Span entries and exits, recorded by the [`ProfilingLayer`](timeline::ProfilingLayer).
+    pub(crate) fn timeline(&self) -> Arc<SpanTimeline> {
+        Arc::clone(&self.timeline)
+    }
+
+    /// Stop profiling, waiting for the last profile to be written.
+    pub(crate) fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Profiler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// The service name with characters that are awkward in file names replaced.
+fn file_stem(service_name: &str) -> String {
+    service_name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Write `profile` as `<prefix><unix ms>-<pid>.pb`, then delete all but the newest
+/// [`KEEP_PROFILES`] files with the prefix. The zero-padded timestamp sorts by name.
+fn write(dir: &Path, prefix: &str, profile: &pprof::Profile) -> anyhow::Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    let path = dir.join(format!("{prefix}{now:013}-{}.pb", std::process::id()));
+    std::fs::write(&path, profile.encode_to_vec())
+        .with_context(|| format!("can't write {}", path.display()))?;
+
+    let mut profiles: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(prefix))
+                .is_some_and(|rest| {
+                    rest.starts_with(|c: char| c.is_ascii_digit()) && rest.ends_with(".pb")
+                })
+        })
+        .collect();
+    profiles.sort();
+    let excess = profiles.len().saturating_sub(KEEP_PROFILES);
+    for old in &profiles[..excess] {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(())
+}
@@ -0,0 +1,276 @@
+//! Encoding profiler reports in the pprof format (`profile.proto`), with samples labelled by
+//! the span that was running.
+
+use std::collections::HashMap;
+use std::time::UNIX_EPOCH;
+
+use ::pprof::Report;
+
+use crate::profiling::timeline::SpanTimeline;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Profile {
+    #[prost(message, repeated, tag = "1")]
+    pub(crate) sample_type: Vec<ValueType>,
+    #[prost(message, repeated, tag = "2")]
+    pub(crate) sample: Vec<Sample>,
+    #[prost(message, repeated, tag = "4")]
+    pub(crate) location: Vec<Location>,
+    #[prost(message, repeated, tag = "5")]
+    pub(crate) function: Vec<Function>,
+    #[prost(string, repeated, tag = "6")]
+    pub(crate) string_table: Vec<String>,
+    #[prost(int64, tag = "9")]
+    pub(crate) time_nanos: i64,
+    #[prost(int64, tag = "10")]
+    pub(crate) duration_nanos: i64,
+    #[prost(message, optional, tag = "11")]
+    pub(crate) period_type: Option<ValueType>,
+    #[prost(int64, tag = "12")]
+    pub(crate) period: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ValueType {
+    #[prost(int64, tag = "1")]
+    pub(crate) r#type: i64,
+    #[prost(int64, tag = "2")]
+    pub(crate) unit: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Sample {
+    #[prost(uint64, repeated, tag = "1")]
+    pub(crate) location_id: Vec<u64>,
+    #[prost(int64, repeated, tag = "2")]
+    pub(crate) value: Vec<i64>,
+    #[prost(message, repeated, tag = "3")]
+    pub(crate) label: Vec<Label>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Label {
+    #[prost(int64, tag = "1")]
+    pub(crate) key: i64,
+    #[prost(int64, tag = "2")]
+    pub(crate) str: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Location {
+    #[prost(uint64, tag = "1")]
+    pub(crate) id: u64,
+    #[prost(message, repeated, tag = "4")]
+    pub(crate) line: Vec<Line>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Line {
+    #[prost(uint64, tag = "1")]
+    pub(crate) function_id: u64,
+    #[prost(int64, tag = "2")]
+    pub(crate) line: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Function {
+    #[prost(uint64, tag = "1")]
+    pub(crate) id: u64,
+    #[prost(int64, tag = "2")]
+    pub(crate) name: i64,
+    #[prost(int64, tag = "3")]
+    pub(crate) system_name: i64,
+    #[prost(int64, tag = "4")]
+    pub(crate) filename: i64,
+}
+
+/// Builds a [`Profile`], interning strings, functions and locations as they're added.
+#[derive(Default)]
+struct ProfileBuilder {
+    profile: Profile,
+    strings: HashMap<String, i64>,
+    functions: HashMap<(String, String), u64>,
+    locations: HashMap<(u64, u32), u64>,
+}
+
+impl ProfileBuilder {
+    fn string(&mut self, value: &str) -> i64 {
+        if let Some(&index) = self.strings.get(value) {
+            return index;
+        }
+        let index = self.profile.string_table.len() as i64;
+        self.profile.string_table.push(value.to_owned());
+        self.strings.insert(value.to_owned(), index);
+        index
+    }
+
+    fn value_type(&mut self, kind: &str, unit: &str) -> ValueType {
+        ValueType {
+            r#type: self.string(kind),
+            unit: self.string(unit),
+        }
+    }
+
+    fn location(&mut self, symbol: &::pprof::Symbol) -> u64 {
+        let (name, filename) = (symbol.name(), symbol.filename().into_owned());
+        let function_id = match self.functions.get(&(name.clone(), filename.clone())) {
+            Some(&id) => id,
+            None => {
+                let id = self.profile.function.len() as u64 + 1;
+                let function = Function {
+                    id,
+                    name: self.string(&name),
+                    system_name: self.string(&symbol.sys_name()),
+                    filename: self.string(&filename),
+                };
+                self.profile.function.push(function);
+                self.functions.insert((name, filename), id);
+                id
+            }
+        };
+        let line = symbol.lineno();
+        *self
+            .locations
+            .entry((function_id, line))
+            .or_insert_with(|| {
+                let id = self.profile.location.len() as u64 + 1;
+                self.profile.location.push(Location {
+                    id,
+                    line: vec![Line {
+                        function_id,
+                        line: i64::from(line),
+                    }],
+                });
+                id
+            })
+    }
+}
+
+/// Convert `report` to a pprof profile, labelling each sample with the `trace_id` and
+/// `span_id` of the span running when it was taken, and its `thread`.
+pub(crate) fn encode(report: &Report, timeline: &SpanTimeline) -> Profile {
+    let mut builder = ProfileBuilder::default();
+    // The string table must start with the empty string
+    builder.string("");
+    let period = 1_000_000_000 / i64::from(report.timing.frequency.max(1));
+    builder.profile.sample_type = vec![
+        builder.value_type("samples", "count"),
+        builder.value_type("cpu", "nanoseconds"),
+    ];
+    builder.profile.period_type = Some(builder.value_type("cpu", "nanoseconds"));
+    builder.profile.period = period;
+    builder.profile.time_nanos = report
+        .timing
+        .start_time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as i64);
+    builder.profile.duration_nanos = report.timing.duration.as_nanos() as i64;
+
+    let (thread_key, trace_key, span_key) = (
+        builder.string("thread"),
+        builder.string("trace_id"),
+        builder.string("span_id"),
+    );
+    for (frames, &count) in &report.data {
+        // Leaf first, as pprof expects; each inlined symbol gets its own location
+        let location_id = frames
+            .frames
+            .iter()
+            .flatten()
+            .map(|symbol| builder.location(symbol))
+            .collect();
+        let mut label = vec![Label {
+            key: thread_key,
+            str: builder.string(&frames.thread_name_or_id()),
+        }];
+        if let Some((trace_id, span_id)) =
+            timeline.active(frames.thread_id, frames.sample_timestamp)
+        {
+            label.push(Label {
+                key: trace_key,
+                str: builder.string(&trace_id.to_string()),
+            });
+            label.push(Label {
+                key: span_key,
+                str: builder.string(&span_id.to_string()),
+            });
+        }
+        let count = count as i64;
+        builder.profile.sample.push(Sample {
+            location_id,
+            value: vec![count, count * period],
+            label,
+        });
+    }
+    builder.profile
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use ::pprof::{Frames, Symbol};
+    use opentelemetry::trace::{SpanId, TraceId};
+    use prost::Message;
+
+    use super::*;
+
+    fn symbol(name: &str, line: u32) -> Symbol {
+        Symbol {
+            name: Some(name.as_bytes().to_vec()),
+            addr: None,
+            lineno: Some(line),
+            filename: Some("src/orders.rs".into()),
+        }
+    }
+
+    #[test]
+    fn samples_are_labelled_with_the_running_span() {
+        let at = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+        let timeline = SpanTimeline::default();
+        let (trace_id, span_id) = (TraceId::from(42_u128), SpanId::from(7_u64));
+        timeline.enter(3, trace_id, span_id, at - Duration::from_millis(5));
+
+        let sample = |thread_id| Frames {
+            frames: vec![vec![symbol("price", 12)], vec![symbol("checkout", 40)]],
+            thread_name: "worker".to_owned(),
+            thread_id,
+            sample_timestamp: at,
+        };
+        let mut report = Report {
+            data: [(sample(3), 5), (sample(4), 2)].into_iter().collect(),
+            timing: Default::default(),
+        };
+        report.timing.frequency = 100;
+
+        let profile = Profile::decode(&*encode(&report, &timeline).encode_to_vec()).unwrap();
+        let string = |index: i64| profile.string_table[index as usize].as_str();
+        assert_eq!(string(0), "");
+        assert_eq!(profile.period, 10_000_000);
+        assert_eq!(profile.function.len(), 2);
+        assert_eq!(profile.location.len(), 2);
+
+        let labels = |thread_count: i64| {
+            let sample = profile
+                .sample
+                .iter()
+                .find(|sample| sample.value[0] == thread_count)
+                .unwrap();
+            assert_eq!(sample.value[1], thread_count * 10_000_000);
+            sample
+                .label
+                .iter()
+                .map(|label| (string(label.key), string(label.str)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            labels(5),
+            [
+                ("thread", "worker"),
+                ("trace_id", trace_id.to_string().as_str()),
+                ("span_id", span_id.to_string().as_str()),
+            ]
+        );
+        assert_eq!(labels(2), [("thread", "worker")]);
+    }
+}
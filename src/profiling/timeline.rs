@@ -0,0 +1,173 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use opentelemetry::trace::{SpanId, TraceId};
+use tracing::span;
+use tracing::Subscriber;
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Span entries kept per thread; older ones are dropped, and samples taken while they
+/// were entered go untagged.
+const MAX_ENTRIES_PER_THREAD: usize = 4096;
+
+/// When each span was entered and exited on each thread, so profile samples can be
+/// tagged with the span that was running.
+#[derive(Debug, Default)]
+pub(crate) struct SpanTimeline {
+    threads: Mutex<HashMap<u64, VecDeque<Entry>>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    trace_id: TraceId,
+    span_id: SpanId,
+    entered: SystemTime,
+    exited: Option<SystemTime>,
+}
+
+impl SpanTimeline {
+    pub(crate) fn enter(&self, thread: u64, trace_id: TraceId, span_id: SpanId, at: SystemTime) {
+        let mut threads = self.threads.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = threads.entry(thread).or_default();
+        if entries.len() == MAX_ENTRIES_PER_THREAD {
+            entries.pop_front();
+        }
+        entries.push_back(Entry {
+            trace_id,
+            span_id,
+            entered: at,
+            exited: None,
+        });
+    }
+
+    pub(crate) fn exit(&self, thread: u64, span_id: SpanId, at: SystemTime) {
+        let mut threads = self.threads.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = threads.get_mut(&thread).and_then(|entries| {
+            entries
+                .iter_mut()
+                .rev()
+                .find(|entry| entry.span_id == span_id && entry.exited.is_none())
+        });
+        if let Some(entry) = entry {
+            entry.exited = Some(at);
+        }
+    }
+
+    /// The innermost span running on `thread` at `at`.
+    pub(crate) fn active(&self, thread: u64, at: SystemTime) -> Option<(TraceId, SpanId)> {
+        let threads = self.threads.lock().unwrap_or_else(|e| e.into_inner());
+        threads
+            .get(&thread)?
+            .iter()
+            .rev()
+            .find(|entry| entry.entered <= at && entry.exited.is_none_or(|exited| at < exited))
+            .map(|entry| (entry.trace_id, entry.span_id))
+    }
+
+    /// Forget spans exited before `before`, and threads with nothing left.
+    pub(crate) fn prune(&self, before: SystemTime) {
+        let mut threads = self.threads.lock().unwrap_or_else(|e| e.into_inner());
+        threads.retain(|_, entries| {
+            entries.retain(|entry| entry.exited.is_none_or(|exited| exited >= before));
+            !entries.is_empty()
+        });
+    }
+}
+
+thread_local! {
+    /// The current thread's ID as the profiler records it: pprof keys each sample by
+    /// `pthread_self()` converted to a `u64`, so the timeline has to use the same value
+    /// to match samples to spans.
+    static THREAD: u64 = nix::sys::pthread::pthread_self() as u64;
+}
+
+fn current_thread() -> u64 {
+    THREAD.with(|thread| *thread)
+}
+
+/// Records span entries and exits into a [`SpanTimeline`]. Runs after the OTel layer,
+/// which assigns the IDs.
+pub(crate) struct ProfilingLayer {
+    timeline: Arc<SpanTimeline>,
+}
+
+impl ProfilingLayer {
+    pub(crate) fn new(timeline: Arc<SpanTimeline>) -> Self {
+        Self { timeline }
+    }
+}
+
+fn otel_ids<S>(id: &span::Id, ctx: &Context<'_, S>) -> Option<(TraceId, SpanId)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let span = ctx.span(id)?;
+    let extensions = span.extensions();
+    let otel = extensions.get::<OtelData>()?;
+    Some((otel.trace_id()?, otel.span_id()?))
+}
+
+impl<S> Layer<S> for ProfilingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some((trace_id, span_id)) = otel_ids(id, &ctx) {
+            let now = SystemTime::now();
+            self.timeline
+                .enter(current_thread(), trace_id, span_id, now);
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some((_, span_id)) = otel_ids(id, &ctx) {
+            let now = SystemTime::now();
+            self.timeline.exit(current_thread(), span_id, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn samples_are_matched_to_the_innermost_running_span() {
+        let timeline = SpanTimeline::default();
+        let t = |ms| SystemTime::UNIX_EPOCH + Duration::from_millis(ms);
+        let (trace, outer, inner) = (
+            TraceId::from(1_u128),
+            SpanId::from(1_u64),
+            SpanId::from(2_u64),
+        );
+
+        timeline.enter(7, trace, outer, t(10));
+        timeline.enter(7, trace, inner, t(20));
+        timeline.exit(7, inner, t(30));
+        timeline.exit(7, outer, t(40));
+
+        assert_eq!(timeline.active(7, t(5)), None);
+        assert_eq!(timeline.active(7, t(15)), Some((trace, outer)));
+        assert_eq!(timeline.active(7, t(25)), Some((trace, inner)));
+        assert_eq!(timeline.active(7, t(35)), Some((trace, outer)));
+        assert_eq!(timeline.active(8, t(25)), None);
+
+        timeline.prune(t(35));
+        assert_eq!(timeline.active(7, t(25)), Some((trace, outer)));
+        timeline.prune(t(45));
+        assert_eq!(timeline.active(7, t(35)), None);
+    }
+
+    #[test]
+    fn each_thread_records_under_its_own_id() {
+        let here = current_thread();
+        assert_eq!(current_thread(), here);
+        assert_ne!(std::thread::spawn(current_thread).join().unwrap(), here);
+    }
+}
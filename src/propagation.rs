@@ -0,0 +1,664 @@
+//! Trace context propagation through plain string maps and message attributes, for
+//! transports that aren't HTTP: queues, job metadata, custom RPC envelopes.
+//!
+//! Everything here uses the global propagator, which [`setup_otel`](crate::setup_otel) sets
+//! to W3C trace context (`traceparent` and `tracestate`). With
+//! [`build_pipeline`](crate::build_pipeline) set one yourself, or nothing is propagated.
+//!
+//! ```no_run
+//! use std::collections::HashMap;
+//!
+//! use raccoon_otel::propagation;
+//! use raccoon_otel::re_exports::tracing_opentelemetry::OpenTelemetrySpanExt;
+//!
+//! // Producer: inside the span the message belongs to
+//! let mut attributes = HashMap::new();
+//! propagation::inject_map(&mut attributes);
+//! // ... publish the message with `attributes` ...
+//!
+//! // Consumer: continue the producer's trace
+//! let span = tracing::info_span!("process_message");
+//! let _ = span.set_parent(propagation::extract_map(&attributes));
+//! ```
+//!
+//! For queues, [`producer_span`] and [`consumer_span`] create the `messaging.*` spans of
+//! [`spans`](crate::spans) and carry the context through any [`MessageAttributes`].
+//!
+//! For subprocesses, [`inject_command`] passes the context in environment variables, and
+//! the child's [`setup_otel`](crate::setup_otel) picks it up (see
+//! [`inherit_trace_context`](crate::OtelOptionsBuilder::inherit_trace_context)).
+
+use std::collections::HashMap;
+use std::process::Command;
+
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::{SpanContext, TraceContextExt, TraceState};
+use opentelemetry::Context;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// String attributes carried by a message, for propagating trace context through a
+/// queue or bus.
+///
+/// Implemented for `HashMap<String, String>`. For a client library's own attribute type
+/// (e.g. SQS or SNS `MessageAttributeValue` maps) implement it on a small wrapper:
+///
+/// ```ignore
+/// use aws_sdk_sqs::types::MessageAttributeValue;
+/// use raccoon_otel::propagation::MessageAttributes;
+///
+/// struct SqsAttributes<'a>(&'a mut HashMap<String, MessageAttributeValue>);
+///
+/// impl MessageAttributes for SqsAttributes<'_> {
+///     fn get_attribute(&self, key: &str) -> Option<&str> {
+///         self.0.get(key)?.string_value()
+///     }
+///
+///     fn set_attribute(&mut self, key: &str, value: String) {
+///         let value = MessageAttributeValue::builder()
+///             .data_type("String")
+///             .string_value(value)
+///             .build()
+///             .expect("data type is set");
+///         self.0.insert(key.to_owned(), value);
+///     }
+/// }
+/// ```
+///
+/// SQS allows 10 attributes per message; W3C trace context uses up to two.
+pub trait MessageAttributes {
+    /// The string value of attribute `key`, if present.
+    fn get_attribute(&self, key: &str) -> Option<&str>;
+
+    /// Set attribute `key` to a string value, replacing any existing one.
+    fn set_attribute(&mut self, key: &str, value: String);
+}
+
+impl MessageAttributes for HashMap<String, String> {
+    fn get_attribute(&self, key: &str) -> Option<&str> {
+        self.get(key).map(String::as_str)
+    }
+
+    fn set_attribute(&mut self, key: &str, value: String) {
+        self.insert(key.to_owned(), value);
+    }
+}
+
+/// Adapts [`MessageAttributes`] to the propagator's carrier traits.
+struct Carrier<'a, A: ?Sized>(&'a mut A);
+
+impl<A: MessageAttributes + ?Sized> Injector for Carrier<'_, A> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.set_attribute(key, value);
+    }
+}
+
+/// Read-only counterpart of [`Carrier`].
+struct ReadCarrier<'a, A: ?Sized>(&'a A);
+
+impl<A: MessageAttributes + ?Sized> Extractor for ReadCarrier<'_, A> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get_attribute(key)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        // Only consulted by propagators that scan for prefixed keys, not W3C trace context
+        Vec::new()
+    }
+}
+
+/// Write the current span's trace context into `map`, overwriting any existing entries
+/// for the propagator's keys.
+pub fn inject_map(map: &mut HashMap<String, String>) {
+    inject_attributes(map);
+}
+
+/// Read a trace context written by [`inject_map`] (or any W3C trace context producer).
+///
+/// Pass the result to `OpenTelemetrySpanExt::set_parent` on the span that handles the
+/// message. Keys are matched exactly, so a map with `Traceparent` isn't recognized.
+pub fn extract_map(map: &HashMap<String, String>) -> Context {
+    extract_attributes(map)
+}
+
+/// Write the current span's trace context into a message's attributes.
+pub fn inject_attributes<A: MessageAttributes + ?Sized>(attributes: &mut A) {
+    inject_span(&Span::current(), attributes);
+}
+
+/// Read the trace context from a message's attributes.
+pub fn extract_attributes<A: MessageAttributes + ?Sized>(attributes: &A) -> Context {
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&ReadCarrier(attributes))
+    })
+}
+
+/// A [`messaging_producer_span`](crate::spans::messaging_producer_span) whose context is
+/// written into `attributes`, so the consumer's span continues the trace from the send.
+///
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// let mut attributes = HashMap::new();
+/// let span = raccoon_otel::propagation::producer_span("aws_sqs", "orders", &mut attributes);
+/// let _entered = span.enter();
+/// // ... send the message with `attributes` ...
+/// ```
+pub fn producer_span<A: MessageAttributes + ?Sized>(
+    system: &str,
+    destination: &str,
+    attributes: &mut A,
+) -> Span {
+    let span = crate::spans::messaging_producer_span(system, destination);
+    inject_span(&span, attributes);
+    span
+}
+
+/// A [`messaging_consumer_span`](crate::spans::messaging_consumer_span) parented to the
+/// trace context in the received message's `attributes`.
+///
+/// Without a trace context in the attributes the span starts a new trace, or continues
+/// the current one when called inside a span.
+pub fn consumer_span<A: MessageAttributes + ?Sized>(
+    system: &str,
+    destination: &str,
+    attributes: &A,
+) -> Span {
+    let span = crate::spans::messaging_consumer_span(system, destination);
+    let cx = extract_attributes(attributes);
+    if cx.span().span_context().is_valid() {
+        let _ = span.set_parent(cx);
+    }
+    span
+}
+
+/// Pass the current span's trace context to a child process in environment variables,
+/// e.g. `TRACEPARENT` and `TRACESTATE`.
+///
+/// Keys are upper-cased, with characters other than letters, digits and `_` replaced by
+/// `_`. A child using raccoon-otel continues the trace from its root spans; other tools
+/// that follow the OTel environment carrier convention read the same variables.
+///
+/// ```no_run
+/// use std::process::Command;
+///
+/// let _span = tracing::info_span!("run_migrations").entered();
+/// let mut command = Command::new("migrate");
+/// raccoon_otel::propagation::inject_command(&mut command);
+/// let status = command.status()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn inject_command(command: &mut Command) {
+    let mut variables = HashMap::new();
+    inject_attributes(&mut variables);
+    for (key, value) in variables {
+        command.env(env_var_name(&key), value);
+    }
+}
+
+fn env_var_name(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The W3C trace context this process was started with, if its parent passed one in
+/// `TRACEPARENT` / `TRACESTATE`.
+pub(crate) fn process_parent() -> Option<Context> {
+    env_parent(|name| std::env::var(name).ok())
+}
+
+fn env_parent(var: impl Fn(&str) -> Option<String>) -> Option<Context> {
+    let variables: HashMap<String, String> = ["traceparent", "tracestate"]
+        .into_iter()
+        .filter_map(|key| Some((key.to_owned(), var(&env_var_name(key))?)))
+        .collect();
+    extract_w3c(&variables)
+}
+
+/// The current span's context as a W3C `traceparent` string
+/// (`00-<trace id>-<span id>-<flags>`), or `None` outside a recorded span.
+///
+/// For storing alongside work that's picked up later (a job record, a database row, a
+/// field of a custom protocol), to correlate it with the trace that created it. Always
+/// W3C, whatever the global propagator.
+///
+/// ```no_run
+/// let _span = tracing::info_span!("enqueue_report").entered();
+/// if let Some(traceparent) = raccoon_otel::current_traceparent() {
+///     // ... store `traceparent` with the job ...
+/// }
+/// ```
+pub fn current_traceparent() -> Option<String> {
+    traceparent(&context_of(&Span::current()))
+}
+
+/// `span`'s OTel context, or for a span skipped beneath an unsampled one (see
+/// [`skip_unsampled_spans`](crate::OtelOptionsBuilder::skip_unsampled_spans)) that of its
+/// nearest recorded ancestor.
+pub(crate) fn context_of(span: &Span) -> Context {
+    crate::layers::bridge::skipped_context(span).unwrap_or_else(|| span.context())
+}
+
+/// `cx`'s span as a W3C `traceparent` string, if it's valid.
+pub(crate) fn traceparent(cx: &Context) -> Option<String> {
+    if !cx.span().span_context().is_valid() {
+        return None;
+    }
+    let mut fields = HashMap::new();
+    TraceContextPropagator::new().inject_context(cx, &mut fields);
+    fields.remove("traceparent")
+}
+
+/// Parse a W3C `traceparent` string, e.g. one from [`current_traceparent`], into a
+/// context to pass to `OpenTelemetrySpanExt::set_parent` (or whose span context to pass to
+/// `add_link`).
+///
+/// Returns `None` if `traceparent` isn't valid.
+///
+/// ```no_run
+/// use raccoon_otel::re_exports::tracing_opentelemetry::OpenTelemetrySpanExt;
+///
+/// # let stored = String::new();
+/// let span = tracing::info_span!("run_report");
+/// if let Some(parent) = raccoon_otel::from_traceparent(&stored) {
+///     let _ = span.set_parent(parent);
+/// }
+/// ```
+pub fn from_traceparent(traceparent: &str) -> Option<Context> {
+    extract_w3c(&HashMap::from([(
+        "traceparent".to_owned(),
+        traceparent.trim().to_owned(),
+    )]))
+}
+
+/// Most entries a W3C `tracestate` may carry.
+const MAX_TRACESTATE_ENTRIES: usize = 32;
+/// Length of `tracestate` that every participant must propagate.
+const MAX_TRACESTATE_LEN: usize = 512;
+/// Entries longer than this are the first dropped when `tracestate` is too long.
+const LONG_TRACESTATE_ENTRY: usize = 128;
+
+/// The value of the vendor entry `key` in the current span's W3C `tracestate`, e.g. a
+/// routing hint set by an upstream service.
+///
+/// ```no_run
+/// let _span = tracing::info_span!("handle_request").entered();
+/// let region = raccoon_otel::propagation::tracestate_entry("acme");
+/// ```
+pub fn tracestate_entry(key: &str) -> Option<String> {
+    let cx = context_of(&Span::current());
+    let value = cx.span().span_context().trace_state().get(key)?.to_owned();
+    Some(value)
+}
+
+/// The current span's context with the vendor entry `key=value` at the front of its W3C
+/// `tracestate`, replacing any previous value for `key`.
+///
+/// A span's `tracestate` is fixed when it starts, so the entry applies to spans parented
+/// to the returned context, and to everything they propagate. Entries are dropped from
+/// the end to stay within 32 entries and 512 characters, long ones (over 128 characters)
+/// first, as the W3C spec asks.
+///
+/// ```no_run
+/// use std::collections::HashMap;
+///
+/// use raccoon_otel::propagation;
+/// use raccoon_otel::re_exports::tracing_opentelemetry::OpenTelemetrySpanExt;
+///
+/// # fn main() -> Result<(), propagation::TraceStateEntryError> {
+/// let span = tracing::info_span!("call_inventory");
+/// let _ = span.set_parent(propagation::with_tracestate_entry("acme", "region:eu")?);
+/// let _entered = span.enter();
+/// let mut headers = HashMap::new();
+/// propagation::inject_map(&mut headers); // tracestate: acme=region:eu,...
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if `key` or `value` isn't valid in a `tracestate` entry.
+pub fn with_tracestate_entry(key: &str, value: &str) -> Result<Context, TraceStateEntryError> {
+    let cx = context_of(&Span::current());
+    let trace_state = cx
+        .span()
+        .span_context()
+        .trace_state()
+        .insert(key, value)
+        .map_err(|_| TraceStateEntryError {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        })?;
+    Ok(with_trace_state(&cx, bounded(&trace_state)))
+}
+
+/// The current span's context without the vendor entry `key` in its W3C `tracestate`,
+/// e.g. to keep an internal hint from leaving the service. Like
+/// [`with_tracestate_entry`], it applies to spans parented to the returned context.
+pub fn without_tracestate_entry(key: &str) -> Context {
+    let cx = context_of(&Span::current());
+    match cx.span().span_context().trace_state().delete(key) {
+        Ok(trace_state) => with_trace_state(&cx, trace_state),
+        // An invalid key can't be in the tracestate
+        Err(_) => cx,
+    }
+}
+
+/// Error returned by [`with_tracestate_entry`] for an entry W3C `tracestate` doesn't allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStateEntryError {
+    key: String,
+    value: String,
+}
+
+impl std::fmt::Display for TraceStateEntryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid tracestate entry `{}={}`: keys are lowercase letters, digits, `_-*/` and \
+             one optional `@`, values are printable ASCII without `,` or `=`",
+            self.key, self.value
+        )
+    }
+}
+
+impl std::error::Error for TraceStateEntryError {}
+
+/// `cx` with its span context's `tracestate` replaced.
+fn with_trace_state(cx: &Context, trace_state: TraceState) -> Context {
+    let span_context = cx.span().span_context().clone();
+    cx.with_remote_span_context(SpanContext::new(
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags(),
+        span_context.is_remote(),
+        trace_state,
+    ))
+}
+
+/// `trace_state` cut down to the W3C limits, dropping entries from the end.
+fn bounded(trace_state: &TraceState) -> TraceState {
+    let header = trace_state.header();
+    let mut entries: Vec<&str> = header.split(',').filter(|e| !e.is_empty()).collect();
+    entries.truncate(MAX_TRACESTATE_ENTRIES);
+    let len = |entries: &[&str]| entries.iter().map(|e| e.len() + 1).sum::<usize>() - 1;
+    // The first entry is the one just set, so it's always kept
+    while entries.len() > 1 && len(&entries) > MAX_TRACESTATE_LEN {
+        let drop = entries
+            .iter()
+            .rposition(|e| e.len() > LONG_TRACESTATE_ENTRY)
+            .filter(|&i| i > 0)
+            .unwrap_or(entries.len() - 1);
+        entries.remove(drop);
+    }
+    let pairs = entries.iter().filter_map(|e| e.split_once('='));
+    TraceState::from_key_value(pairs).unwrap_or_else(|_| trace_state.clone())
+}
+
+/// A valid remote context from W3C `traceparent` / `tracestate` fields.
+fn extract_w3c(fields: &HashMap<String, String>) -> Option<Context> {
+    let cx = TraceContextPropagator::new().extract(&ReadCarrier(fields));
+    cx.span().span_context().is_valid().then_some(cx)
+}
+
+fn inject_span<A: MessageAttributes + ?Sized>(span: &Span, attributes: &mut A) {
+    let cx = context_of(span);
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&cx, &mut Carrier(attributes));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn spans_skipped_beneath_an_unsampled_span_propagate_its_context() {
+        use opentelemetry_sdk::trace::Sampler;
+
+        use crate::layers::bridge::OtelBridgeLayer;
+
+        let provider = SdkTracerProvider::builder()
+            .with_sampler(Sampler::AlwaysOff)
+            .build();
+        let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("test"));
+        let subscriber =
+            tracing_subscriber::registry().with(OtelBridgeLayer::new(layer, true, None, None));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _root = tracing::info_span!("root").entered();
+            let parent = current_traceparent().unwrap();
+            assert!(parent.ends_with("-00"), "{parent}");
+
+            let _child = tracing::info_span!("child").entered();
+            let _grandchild = tracing::info_span!("grandchild").entered();
+            assert_eq!(current_traceparent().as_ref(), Some(&parent));
+            let mut map = HashMap::new();
+            TraceContextPropagator::new().inject_context(&context_of(&Span::current()), &mut map);
+            assert_eq!(map.get("traceparent"), Some(&parent));
+        });
+    }
+
+    #[test]
+    fn context_round_trips_through_a_string_map() {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("producer");
+            let _entered = span.enter();
+            let mut map = HashMap::from([("message.kind".to_owned(), "order".to_owned())]);
+            inject_map(&mut map);
+            assert!(map.contains_key("traceparent"));
+            assert_eq!(map["message.kind"], "order");
+
+            let extracted = extract_map(&map);
+            let producer = span.context();
+            assert_eq!(
+                extracted.span().span_context().trace_id(),
+                producer.span().span_context().trace_id()
+            );
+            assert!(extracted.span().span_context().is_remote());
+        });
+
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        tracing::subscriber::with_default(subscriber, || {
+            let mut attributes = HashMap::new();
+            let producer = producer_span("aws_sqs", "orders", &mut attributes);
+            let consumer = consumer_span("aws_sqs", "orders", &attributes);
+            let (producer, consumer) = (producer.context(), consumer.context());
+            assert_eq!(
+                consumer.span().span_context().trace_id(),
+                producer.span().span_context().trace_id()
+            );
+        });
+
+        assert!(!extract_map(&HashMap::new())
+            .span()
+            .span_context()
+            .is_valid());
+    }
+
+    /// An SQS-style attribute map, whose values carry a data type.
+    #[derive(Default)]
+    struct SqsAttributes(HashMap<String, (&'static str, String)>);
+
+    impl MessageAttributes for SqsAttributes {
+        fn get_attribute(&self, key: &str) -> Option<&str> {
+            let (data_type, value) = self.0.get(key)?;
+            (*data_type == "String").then_some(value.as_str())
+        }
+
+        fn set_attribute(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), ("String", value));
+        }
+    }
+
+    #[test]
+    fn consumer_spans_continue_the_producer_through_message_attributes() {
+        use opentelemetry::trace::SpanKind;
+        use opentelemetry_sdk::trace::InMemorySpanExporter;
+
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let mut attributes = SqsAttributes::default();
+            attributes.set_attribute("tenant", "acme".to_owned());
+            drop(producer_span("aws_sqs", "orders", &mut attributes));
+            assert_eq!(attributes.0["traceparent"].0, "String");
+            assert!(attributes.0.contains_key("tenant"));
+
+            // A message that went through the queue
+            let received = SqsAttributes(attributes.0.clone());
+            drop(consumer_span("aws_sqs", "orders", &received));
+        });
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let [producer, consumer] = spans.as_slice() else {
+            panic!("expected two spans, got {spans:?}");
+        };
+        assert_eq!(producer.name, "send orders");
+        assert_eq!(producer.span_kind, SpanKind::Producer);
+        assert_eq!(consumer.name, "process orders");
+        assert_eq!(consumer.span_kind, SpanKind::Consumer);
+        assert_eq!(
+            consumer.span_context.trace_id(),
+            producer.span_context.trace_id()
+        );
+        assert_eq!(consumer.parent_span_id, producer.span_context.span_id());
+        assert!(consumer.parent_span_is_remote);
+        assert!(consumer
+            .attributes
+            .iter()
+            .any(|kv| kv.key.as_str() == "messaging.system" && kv.value.as_str() == "aws_sqs"));
+
+        // Attributes of another type aren't read as trace context
+        let mut untyped = SqsAttributes::default();
+        untyped.0.insert(
+            "traceparent".to_owned(),
+            (
+                "Binary",
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_owned(),
+            ),
+        );
+        assert!(!extract_attributes(&untyped)
+            .span()
+            .span_context()
+            .is_valid());
+    }
+
+    #[test]
+    fn subprocess_context_is_read_from_environment_variables() {
+        assert_eq!(env_var_name("traceparent"), "TRACEPARENT");
+        assert_eq!(env_var_name("uber-trace-id"), "UBER_TRACE_ID");
+
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let cx = env_parent(|name| (name == "TRACEPARENT").then(|| traceparent.to_owned()))
+            .expect("a valid traceparent");
+        assert_eq!(
+            cx.span().span_context().trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert!(env_parent(|_| None).is_none());
+        assert!(env_parent(|_| Some("garbage".to_owned())).is_none());
+    }
+
+    #[test]
+    fn traceparent_strings_round_trip() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            assert_eq!(current_traceparent(), None);
+
+            let span = tracing::info_span!("enqueue");
+            let _entered = span.enter();
+            let traceparent = current_traceparent().expect("inside a span");
+            let span_context = span.context().span().span_context().clone();
+            assert_eq!(
+                traceparent,
+                format!(
+                    "00-{}-{}-01",
+                    span_context.trace_id(),
+                    span_context.span_id()
+                )
+            );
+
+            let parsed = from_traceparent(&traceparent).expect("valid traceparent");
+            assert_eq!(
+                parsed.span().span_context().span_id(),
+                span_context.span_id()
+            );
+        });
+
+        assert!(from_traceparent("00-not-a-traceparent").is_none());
+    }
+
+    #[test]
+    fn tracestate_entries_reach_child_spans_within_limits() {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _parent = tracing::info_span!("handle_request").entered();
+            let cx = with_tracestate_entry("acme", "region:eu").unwrap();
+            let child = tracing::info_span!("call_inventory");
+            let _ = child.set_parent(cx);
+            let _entered = child.enter();
+            assert_eq!(tracestate_entry("acme").as_deref(), Some("region:eu"));
+            let mut headers = HashMap::new();
+            inject_map(&mut headers);
+            assert_eq!(headers["tracestate"], "acme=region:eu");
+
+            assert!(without_tracestate_entry("acme")
+                .span()
+                .span_context()
+                .trace_state()
+                .get("acme")
+                .is_none());
+            assert!(with_tracestate_entry("Acme", "x").is_err());
+        });
+
+        let full = TraceState::from_key_value((0..40).map(|i| (format!("k{i}"), "v"))).unwrap();
+        assert_eq!(bounded(&full).header().split(',').count(), 32);
+        let long = "x".repeat(200);
+        let oversized =
+            TraceState::from_key_value([("new", "1"), ("a", long.as_str()), ("b", long.as_str())])
+                .unwrap()
+                .insert("c", &long[..100])
+                .unwrap();
+        // Long entries go first, so the 100-character one survives
+        let kept: Vec<_> = bounded(&oversized)
+            .header()
+            .split(',')
+            .map(|e| e.split_once('=').unwrap().0.to_owned())
+            .collect();
+        assert_eq!(kept, ["c", "new", "a"]);
+    }
+}
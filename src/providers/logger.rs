@@ -1,42 +1,249 @@
+use std::sync::Arc;
+
 use anyhow::Context;
+use opentelemetry::logs::LoggerProvider as _;
 use opentelemetry_otlp::WithExportConfig;
-use opentelemetry_sdk::logs::SdkLoggerProvider;
+#[cfg(feature = "http")]
+use opentelemetry_otlp::WithHttpConfig;
+#[cfg(feature = "grpc")]
+use opentelemetry_otlp::WithTonicConfig;
+use opentelemetry_sdk::logs::{
+    BatchConfigBuilder, BatchLogProcessor, LogProcessor, SdkLoggerProvider,
+};
 use opentelemetry_sdk::Resource;
 
+#[cfg(feature = "azure-monitor")]
+use crate::azure_monitor::ConnectionString;
 use crate::env::ResolvedConfig;
+use crate::exporters::auth::AuthLogExporter;
+#[cfg(feature = "azure-monitor")]
+use crate::exporters::azure_monitor::AzureMonitorLogExporter;
+use crate::exporters::batch_size::{AdaptiveBatchLogExporter, MAX_BATCH_SIZE};
+use crate::exporters::budget::{BudgetLimiter, BudgetLogExporter};
+use crate::exporters::counting::CountingLogExporter;
+use crate::exporters::dead_letter::{DeadLetterLogExporter, DeadLetterWriter};
+use crate::exporters::fallback::{http_fallback_endpoint, FallbackLogExporter};
+#[cfg(feature = "file")]
+use crate::exporters::file::{FileLogExporter, OtlpFileWriter};
+use crate::exporters::health::HealthLogExporter;
+use crate::exporters::inspect::InspectLogExporter;
+use crate::exporters::queue::QueueDrainLogExporter;
+use crate::exporters::split::SplitLogExporter;
+use crate::exporters::tenant::TenantRoutingLogExporter;
+use crate::exporters::transport::TransportLogExporter;
+use crate::fork::{ForkLogProcessor, ForkPoint};
+use crate::health::ExportHealth;
 use crate::options::Protocol;
+use crate::processors::ambient::{ambient_attributes, AmbientLogTransform};
+use crate::processors::dedup_logs::DedupLogProcessor;
+use crate::processors::ingest::{IngestLogProcessor, IngestPoint};
+use crate::processors::log::{LogTransform, TransformingLogProcessor};
+use crate::processors::offload::{LogOffload, OffloadLogProcessor};
+use crate::processors::queue::{QueueMonitor, QueueMonitoringLogProcessor};
+use crate::processors::rename::RenameAttributesTransform;
+use crate::processors::shard::ShardedLogProcessor;
+use crate::processors::tenant::TenantLogTransform;
+use crate::processors::trace_buffer::{TraceBufferingLogProcessor, TraceLogBuffer};
+use crate::processors::truncate::TruncateTransform;
+use crate::stats::StatsCounters;
 
 /// Build a [`SdkLoggerProvider`] with an OTLP exporter.
 ///
+/// The provider is also registered for [`backfill`](crate::backfill) unless
+/// `config.set_global` is `false`.
+/// When `log_buffer` is given, low-severity records are held per trace until the
+/// tracer side decides whether the trace errored. Records handed to `ingest` join the
+/// processors like those of the log bridge. With `offload`, it starts emitting to the
+/// provider, and flushes wait for it. The processors are registered with `fork`.
+///
 /// # Errors
 ///
 /// Returns an error if the OTLP exporter or provider fails to initialize.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn build_logger_provider(
     resource: Resource,
     config: &ResolvedConfig,
+    log_buffer: Option<Arc<TraceLogBuffer>>,
+    budget: Option<Arc<BudgetLimiter>>,
+    health: Arc<ExportHealth>,
+    stats: Arc<StatsCounters>,
+    dead_letter: Option<Arc<DeadLetterWriter>>,
+    ingest: &IngestPoint,
+    offload: Option<Arc<LogOffload>>,
+    fork: &Arc<ForkPoint>,
+    #[cfg(feature = "record")] recorder: Option<Arc<crate::recording::Recorder>>,
 ) -> anyhow::Result<SdkLoggerProvider> {
-    let exporter = build_log_exporter(config).context("Failed to build OTLP log exporter")?;
+    // Benchmark mode keeps every processor but counts batches instead of exporting them
+    let exporter = if config.benchmark_mode {
+        TransportLogExporter::Counting(CountingLogExporter::new(Arc::clone(&stats)))
+    } else {
+        build_log_exporter(config, &config.logs_protocol, &config.logs_endpoint)
+            .context("Failed to build OTLP log exporter")?
+    };
+    let fallback = http_fallback_endpoint(
+        config.grpc_http_fallback && !config.benchmark_mode,
+        &config.logs_protocol,
+        &config.logs_endpoint,
+    )
+    .map(|endpoint| build_log_exporter(config, &Protocol::HttpProtobuf, &endpoint))
+    .transpose()
+    .context("Failed to build fallback HTTP log exporter")?;
+    let exporter = FallbackLogExporter::new(exporter, fallback);
+    let exporter = AuthLogExporter::new(
+        exporter,
+        super::auth_refresh(config, &config.logs_protocol, |config| {
+            build_log_exporter(config, &config.logs_protocol, &config.logs_endpoint)
+        }),
+    );
+    let exporter = TenantRoutingLogExporter::new(
+        exporter,
+        super::tenant_router(config, &config.logs_protocol, |config| {
+            build_log_exporter(config, &config.logs_protocol, &config.logs_endpoint)
+        }),
+    );
+    let exporter = SplitLogExporter::new(exporter, config.max_export_bytes);
+    let exporter = HealthLogExporter::new(exporter, Arc::clone(&health));
+    let exporter = DeadLetterLogExporter::new(exporter, health, dead_letter);
+    let exporter = InspectLogExporter::new(exporter, config.on_export.clone());
+    let exporter = BudgetLogExporter::new(exporter, budget);
+    #[cfg(feature = "record")]
+    let exporter = crate::exporters::record::RecordLogExporter::new(exporter, recorder);
+
+    let queue = Arc::new(QueueMonitor::logs(stats));
+    let exporter = QueueDrainLogExporter::new(exporter, Arc::clone(&queue));
+    let exporter = AdaptiveBatchLogExporter::new(
+        exporter,
+        config.adaptive_batch_size,
+        config.logs_export_timeout,
+    );
+
+    let mut batch = BatchLogProcessor::builder(exporter);
+    if config.adaptive_batch_size {
+        batch = batch.with_batch_config(
+            BatchConfigBuilder::default()
+                .with_max_export_batch_size(MAX_BATCH_SIZE)
+                .build(),
+        );
+    }
+    let mut batch = batch.build();
+    // Set up front: once shared with the trace buffer, the provider can't reach it
+    batch.set_resource(&resource);
+    let batch = QueueMonitoringLogProcessor::new(batch, queue);
+    let batch = ShardedLogProcessor::new(config.batch_queue_shards, batch);
+    let buffering = TraceBufferingLogProcessor::new(batch, log_buffer);
+    let dedup = DedupLogProcessor::new(buffering, config.dedup_logs_window);
+    let processor = TransformingLogProcessor::new(build_log_transforms(config)?, dedup);
+    let processor = IngestLogProcessor::new(processor, ingest);
+    let mut processor = OffloadLogProcessor::new(offload.clone(), processor);
+    processor.set_resource(&resource);
 
+    #[cfg_attr(not(feature = "file"), allow(unused_mut))]
+    let mut processors: Vec<Box<dyn LogProcessor>> = vec![Box::new(processor)];
+    // Runs after the transforms above, which rewrite records in place. A batch processor
+    // of its own, so the file doesn't wait on the collector.
+    #[cfg(feature = "file")]
+    if let Some(ref path) = config.log_file {
+        let writer = OtlpFileWriter::shared(path, config.file_rotation)?;
+        let mut file = BatchLogProcessor::builder(FileLogExporter::new(writer)).build();
+        file.set_resource(&resource);
+        processors.push(Box::new(file));
+    }
     let provider = SdkLoggerProvider::builder()
         .with_resource(resource)
-        .with_batch_exporter(exporter)
+        .with_log_processor(ForkLogProcessor::new(processors, Arc::clone(fork)))
         .build();
+    if let Some(offload) = offload {
+        offload.start(provider.logger(""));
+    }
+
+    // Register for `backfill::log`, as the tracer provider is registered globally
+    if config.set_global {
+        crate::backfill::set_logger_provider(&provider);
+    }
 
     Ok(provider)
 }
 
-fn build_log_exporter(config: &ResolvedConfig) -> anyhow::Result<opentelemetry_otlp::LogExporter> {
-    match config.protocol {
+/// Collect the log transforms enabled in `config`, in the order they are applied.
+///
+/// # Errors
+///
+/// Returns an error if an option requires a feature that is not enabled.
+fn build_log_transforms(config: &ResolvedConfig) -> anyhow::Result<Vec<Box<dyn LogTransform>>> {
+    let mut transforms: Vec<Box<dyn LogTransform>> = Vec::new();
+
+    if config.expand_json_fields {
+        #[cfg(feature = "json")]
+        transforms.push(Box::new(
+            crate::processors::json_fields::JsonFieldsTransform,
+        ));
+        #[cfg(not(feature = "json"))]
+        anyhow::bail!(
+            "JSON field expansion requested but the `json` feature is not enabled. \
+             Enable it in Cargo.toml: raccoon-otel = {{ features = [\"json\"] }}"
+        );
+    }
+
+    if !config.attribute_renames.is_empty() {
+        transforms.push(Box::new(RenameAttributesTransform::new(
+            &config.attribute_renames,
+        )));
+    }
+    transforms.extend(caller_log_transforms(config));
+    if let Some(max_len) = config.max_attribute_value_length {
+        transforms.push(Box::new(TruncateTransform::new(max_len)));
+    }
+    #[cfg(feature = "gcp")]
+    if config.gcp {
+        transforms.push(Box::new(
+            crate::processors::cloud_logging::CloudLoggingSeverityTransform,
+        ));
+    }
+
+    Ok(transforms)
+}
+
+/// The log transforms that read the emitting thread's context, which
+/// [`OffloadLogLayer`](crate::layers::offload::OffloadLogLayer) runs before queueing.
+pub(crate) fn caller_log_transforms(config: &ResolvedConfig) -> Vec<Box<dyn LogTransform>> {
+    let mut transforms: Vec<Box<dyn LogTransform>> = Vec::new();
+    if let Some(ref tenant) = config.tenant {
+        transforms.push(Box::new(TenantLogTransform::new(tenant.clone())));
+    }
+    if !config.ambient_attributes.is_empty() {
+        transforms.push(Box::new(AmbientLogTransform::new(ambient_attributes(
+            config,
+        ))));
+    }
+    transforms
+}
+
+fn build_log_exporter(
+    config: &ResolvedConfig,
+    protocol: &Protocol,
+    endpoint: &str,
+) -> anyhow::Result<TransportLogExporter> {
+    match protocol {
         Protocol::Grpc => {
             #[cfg(feature = "grpc")]
             {
-                let exporter = opentelemetry_otlp::LogExporter::builder()
+                let mut builder = opentelemetry_otlp::LogExporter::builder()
                     .with_tonic()
-                    .with_endpoint(&config.endpoint)
-                    .with_timeout(config.export_timeout)
+                    .with_metadata(super::grpc_metadata(config)?)
+                    .with_endpoint(endpoint)
+                    .with_timeout(config.logs_export_timeout);
+                if let Some(channel) =
+                    super::grpc_channel(config, endpoint, config.logs_export_timeout)?
+                {
+                    builder = builder.with_channel(channel);
+                }
+                if let Some(credentials) = super::call_credentials(config) {
+                    builder = builder.with_interceptor(credentials);
+                }
+                let exporter = builder
                     .build()
                     .context("Failed to build gRPC log exporter")?;
-                Ok(exporter)
+                Ok(TransportLogExporter::Otlp(exporter))
             }
             #[cfg(not(feature = "grpc"))]
             {
@@ -49,14 +256,15 @@ fn build_log_exporter(config: &ResolvedConfig) -> anyhow::Result<opentelemetry_o
         Protocol::HttpProtobuf | Protocol::HttpJson => {
             #[cfg(feature = "http")]
             {
-                let endpoint = format!("{}/v1/logs", config.endpoint.trim_end_matches('/'));
+                let endpoint = format!("{}/v1/logs", endpoint.trim_end_matches('/'));
                 let exporter = opentelemetry_otlp::LogExporter::builder()
                     .with_http()
+                    .with_headers(super::http_headers(config))
                     .with_endpoint(endpoint)
-                    .with_timeout(config.export_timeout)
+                    .with_timeout(config.logs_export_timeout)
                     .build()
                     .context("Failed to build HTTP log exporter")?;
-                Ok(exporter)
+                Ok(TransportLogExporter::Otlp(exporter))
             }
             #[cfg(not(feature = "http"))]
             {
@@ -66,5 +274,42 @@ fn build_log_exporter(config: &ResolvedConfig) -> anyhow::Result<opentelemetry_o
                 );
             }
         }
+        #[cfg(feature = "file")]
+        Protocol::File { path } => {
+            let writer = OtlpFileWriter::shared(path, config.file_rotation)?;
+            Ok(TransportLogExporter::File(FileLogExporter::new(writer)))
+        }
+        #[cfg(not(feature = "file"))]
+        Protocol::File { .. } => {
+            anyhow::bail!(
+                "File output requested but the `file` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"file\"] }}"
+            );
+        }
+        Protocol::Jaeger | Protocol::Zipkin => {
+            anyhow::bail!(
+                "Logs can't be sent to Jaeger or Zipkin. Set those per signal with \
+                 `traces_protocol()` and keep `protocol()` for logs"
+            );
+        }
+        #[cfg(feature = "azure-monitor")]
+        Protocol::AzureMonitor { connection_string } => {
+            let connection = ConnectionString::parse(connection_string)?;
+            Ok(TransportLogExporter::AzureMonitor(
+                AzureMonitorLogExporter::new(
+                    &connection,
+                    endpoint,
+                    super::http_headers(config),
+                    config.logs_export_timeout,
+                ),
+            ))
+        }
+        #[cfg(not(feature = "azure-monitor"))]
+        Protocol::AzureMonitor { .. } => {
+            anyhow::bail!(
+                "Azure Monitor export requested but the `azure-monitor` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"azure-monitor\"] }}"
+            );
+        }
     }
 }
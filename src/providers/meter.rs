@@ -0,0 +1,173 @@
+use anyhow::Context;
+use opentelemetry_otlp::WithExportConfig;
+#[cfg(feature = "http")]
+use opentelemetry_otlp::WithHttpConfig;
+#[cfg(feature = "grpc")]
+use opentelemetry_otlp::WithTonicConfig;
+use opentelemetry_sdk::metrics::{
+    Aggregation, Instrument, InstrumentKind, PeriodicReader, SdkMeterProvider, Stream, Temporality,
+};
+use opentelemetry_sdk::Resource;
+
+use crate::env::ResolvedConfig;
+use crate::exporters::auth::AuthMetricExporter;
+use crate::options::{HistogramAggregation, MetricsTemporality, Protocol};
+
+/// Bucket limit for exponential histograms (the SDK's and spec's default).
+const EXPONENTIAL_MAX_SIZE: u32 = 160;
+/// Highest resolution for exponential histograms (the spec's default).
+const EXPONENTIAL_MAX_SCALE: i8 = 20;
+
+/// Build a [`SdkMeterProvider`] with a periodic OTLP exporter, or a StatsD exporter if
+/// `config.statsd` is set, or one that discards everything in benchmark mode.
+///
+/// The provider is also registered globally unless `config.set_global` is `false`.
+///
+/// # Errors
+///
+/// Returns an error if the OTLP exporter fails to initialize.
+pub(crate) fn build_meter_provider(
+    resource: Resource,
+    config: &ResolvedConfig,
+) -> anyhow::Result<SdkMeterProvider> {
+    let mut builder = SdkMeterProvider::builder().with_resource(resource);
+
+    if config.benchmark_mode {
+        let exporter = crate::exporters::counting::DiscardMetricExporter::new(temporality(config));
+        builder = builder.with_reader(PeriodicReader::builder(exporter).build());
+    } else {
+        #[cfg(feature = "statsd")]
+        if let Some(ref statsd) = config.statsd {
+            let exporter = crate::sinks::statsd::StatsdExporter::new(statsd)
+                .context("Failed to build StatsD metric exporter")?;
+            builder = builder.with_reader(PeriodicReader::builder(exporter).build());
+        }
+        if config.statsd.is_none() {
+            let exporter =
+                build_metric_exporter(config).context("Failed to build OTLP metric exporter")?;
+            let exporter = AuthMetricExporter::new(
+                exporter,
+                super::auth_refresh(config, &config.protocol, build_metric_exporter),
+            );
+            builder = builder.with_reader(PeriodicReader::builder(exporter).build());
+        }
+    }
+    for view in &config.metric_views {
+        let view = view.clone();
+        builder = builder.with_view(move |instrument: &Instrument| (view.0)(instrument));
+    }
+    if config.histogram_aggregation == HistogramAggregation::Base2Exponential {
+        // Every matching view yields its own stream, so only cover histograms that no
+        // user view already handles
+        let user_views = config.metric_views.clone();
+        builder = builder.with_view(move |instrument: &Instrument| {
+            if user_views.iter().any(|view| (view.0)(instrument).is_some()) {
+                return None;
+            }
+            exponential_histograms(instrument)
+        });
+    }
+    let provider = builder.build();
+
+    // Register globally so `opentelemetry::global::meter()` users share the pipeline
+    if config.set_global {
+        opentelemetry::global::set_meter_provider(provider.clone());
+    }
+
+    Ok(provider)
+}
+
+/// View that switches every histogram instrument to base-2 exponential buckets.
+fn exponential_histograms(instrument: &Instrument) -> Option<Stream> {
+    if instrument.kind() != InstrumentKind::Histogram {
+        return None;
+    }
+    Stream::builder()
+        .with_aggregation(Aggregation::Base2ExponentialHistogram {
+            max_size: EXPONENTIAL_MAX_SIZE,
+            max_scale: EXPONENTIAL_MAX_SCALE,
+            record_min_max: true,
+        })
+        .build()
+        .ok()
+}
+
+fn temporality(config: &ResolvedConfig) -> Temporality {
+    match config.metrics_temporality {
+        MetricsTemporality::Cumulative => Temporality::Cumulative,
+        MetricsTemporality::Delta => Temporality::Delta,
+        MetricsTemporality::LowMemory => Temporality::LowMemory,
+    }
+}
+
+fn build_metric_exporter(
+    config: &ResolvedConfig,
+) -> anyhow::Result<opentelemetry_otlp::MetricExporter> {
+    match config.protocol {
+        Protocol::Grpc => {
+            #[cfg(feature = "grpc")]
+            {
+                let mut builder = opentelemetry_otlp::MetricExporter::builder()
+                    .with_tonic()
+                    .with_metadata(super::grpc_metadata(config)?)
+                    .with_temporality(temporality(config))
+                    .with_endpoint(&config.endpoint)
+                    .with_timeout(config.metrics_export_timeout);
+                if let Some(channel) =
+                    super::grpc_channel(config, &config.endpoint, config.metrics_export_timeout)?
+                {
+                    builder = builder.with_channel(channel);
+                }
+                if let Some(credentials) = super::call_credentials(config) {
+                    builder = builder.with_interceptor(credentials);
+                }
+                let exporter = builder
+                    .build()
+                    .context("Failed to build gRPC metric exporter")?;
+                Ok(exporter)
+            }
+            #[cfg(not(feature = "grpc"))]
+            {
+                anyhow::bail!(
+                    "gRPC transport requested but the `grpc` feature is not enabled. \
+                     Enable it in Cargo.toml: raccoon-otel = {{ features = [\"grpc\"] }}"
+                );
+            }
+        }
+        Protocol::HttpProtobuf | Protocol::HttpJson => {
+            #[cfg(feature = "http")]
+            {
+                let endpoint = format!("{}/v1/metrics", config.endpoint.trim_end_matches('/'));
+                let exporter = opentelemetry_otlp::MetricExporter::builder()
+                    .with_http()
+                    .with_headers(super::http_headers(config))
+                    .with_temporality(temporality(config))
+                    .with_endpoint(endpoint)
+                    .with_timeout(config.metrics_export_timeout)
+                    .build()
+                    .context("Failed to build HTTP metric exporter")?;
+                Ok(exporter)
+            }
+            #[cfg(not(feature = "http"))]
+            {
+                anyhow::bail!(
+                    "HTTP transport requested but the `http` feature is not enabled. \
+                     Enable it in Cargo.toml: raccoon-otel = {{ features = [\"http\"] }}"
+                );
+            }
+        }
+        Protocol::File { .. } => {
+            anyhow::bail!(
+                "Metrics can't be written to an OTLP file. Set the file protocol per signal \
+                 with `traces_protocol()` / `logs_protocol()` and keep `protocol()` for metrics"
+            );
+        }
+        Protocol::Jaeger | Protocol::Zipkin | Protocol::AzureMonitor { .. } => {
+            anyhow::bail!(
+                "Metrics can't be sent to {}. Set it per signal with `traces_protocol()` / \
+                 `logs_protocol()` and keep `protocol()` for metrics",
+                config.protocol
+            );
+        }
+    }
+}
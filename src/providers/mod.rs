@@ -1,2 +1,243 @@
 pub(crate) mod logger;
+#[cfg(feature = "metrics")]
+pub(crate) mod meter;
 pub(crate) mod tracer;
+
+#[cfg(feature = "http")]
+use std::collections::HashMap;
+
+use crate::env::ResolvedConfig;
+#[cfg(feature = "grpc")]
+use crate::exporters::auth::CallCredentials;
+use crate::exporters::auth::{AuthRefresh, AuthSource};
+#[cfg(feature = "grpc")]
+use crate::exporters::dns_balance::balanced_channel;
+use crate::exporters::tenant::TenantRouter;
+#[cfg(feature = "grpc")]
+use crate::exporters::vsock::VsockAddr;
+use crate::options::Protocol;
+
+/// Per-tenant exporters for [`TenantOptions::route_header`](crate::TenantOptions::route_header),
+/// or `None` when the signal isn't routed by tenant.
+///
+/// `build` creates a signal's exporter from a copy of `config` whose headers carry the
+/// tenant.
+fn tenant_router<X: 'static>(
+    config: &ResolvedConfig,
+    protocol: &Protocol,
+    build: fn(&ResolvedConfig) -> anyhow::Result<X>,
+) -> Option<TenantRouter<X>> {
+    let tenant = config.tenant.as_ref()?;
+    let header = tenant.route_header.clone()?;
+    if config.benchmark_mode || matches!(protocol, Protocol::File { .. }) {
+        return None;
+    }
+    // Tenant exporters are created on the batch processor's thread, but gRPC channels
+    // need the Tokio runtime that setup ran in
+    #[cfg(feature = "grpc")]
+    let runtime = tokio::runtime::Handle::try_current().ok();
+    let config = config.clone();
+    Some(TenantRouter::new(
+        &tenant.attribute,
+        Box::new(move |tenant| {
+            #[cfg(feature = "grpc")]
+            let _runtime = runtime.as_ref().map(tokio::runtime::Handle::enter);
+            let mut config = config.clone();
+            config.headers.insert(header.clone(), tenant.to_owned());
+            build(&config)
+        }),
+    ))
+}
+
+/// Where the `Authorization` value comes from: the
+/// [`OtelOptionsBuilder::authorization`](crate::OtelOptionsBuilder::authorization) callback
+/// or, with the [`gcp`](crate::OtelOptions::gcp) preset and no `Authorization` header
+/// configured, the Google Cloud service account. `None` when neither applies or
+/// [`benchmark_mode`](crate::OtelOptionsBuilder::benchmark_mode) is on.
+fn auth_source(config: &ResolvedConfig) -> Option<AuthSource> {
+    if config.benchmark_mode {
+        return None;
+    }
+    match config.authorization {
+        Some(ref provider) => Some(AuthSource::Callback(provider.clone())),
+        #[cfg(feature = "gcp")]
+        None if config.gcp
+            && !config
+                .headers
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case("authorization")) =>
+        {
+            Some(AuthSource::Gcp)
+        }
+        None => None,
+    }
+}
+
+/// An HTTP exporter whose `Authorization` header comes from [`auth_source`], rebuilt when
+/// the value changes. `None` without a source, and for files and gRPC, which gets
+/// [`call_credentials`] instead.
+///
+/// `build` creates a signal's exporter from a copy of `config` whose headers carry the
+/// value.
+fn auth_refresh<X: 'static>(
+    config: &ResolvedConfig,
+    protocol: &Protocol,
+    build: fn(&ResolvedConfig) -> anyhow::Result<X>,
+) -> Option<AuthRefresh<X>> {
+    if matches!(protocol, Protocol::File { .. } | Protocol::Grpc) {
+        return None;
+    }
+    let source = auth_source(config)?;
+    let config = config.clone();
+    Some(AuthRefresh::new(
+        source,
+        Box::new(move |authorization| {
+            let mut config = config.clone();
+            config
+                .headers
+                .retain(|name, _| !name.eq_ignore_ascii_case("authorization"));
+            config
+                .headers
+                .insert("Authorization".to_owned(), authorization.to_owned());
+            build(&config)
+        }),
+    ))
+}
+
+/// Call credentials setting the `authorization` metadata of each gRPC export call from
+/// [`auth_source`], so expiring tokens are replaced without reconnecting.
+#[cfg(feature = "grpc")]
+fn call_credentials(config: &ResolvedConfig) -> Option<CallCredentials> {
+    auth_source(config).map(CallCredentials::new)
+}
+
+/// Headers for HTTP exporters: the configured headers plus the user agent, if set.
+#[cfg(feature = "http")]
+fn http_headers(config: &ResolvedConfig) -> HashMap<String, String> {
+    let mut headers = config.headers.clone();
+    if let Some(ref user_agent) = config.user_agent {
+        headers.insert("User-Agent".to_owned(), user_agent.clone());
+    }
+    headers
+}
+
+/// Metadata for gRPC exporters: the configured headers, gRPC-only metadata and the
+/// user agent, if set.
+///
+/// # Errors
+///
+/// Returns an error if a key or value is not valid ASCII gRPC metadata.
+#[cfg(feature = "grpc")]
+fn grpc_metadata(
+    config: &ResolvedConfig,
+) -> anyhow::Result<opentelemetry_otlp::tonic_types::metadata::MetadataMap> {
+    use anyhow::Context;
+    use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+
+    let user_agent = config
+        .user_agent
+        .as_ref()
+        .map(|ua| ("user-agent".to_owned(), ua.clone()));
+    let mut metadata = MetadataMap::new();
+    for (key, value) in config
+        .headers
+        .iter()
+        .chain(&config.grpc_metadata)
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .chain(user_agent)
+    {
+        let name = MetadataKey::from_bytes(key.to_ascii_lowercase().as_bytes())
+            .with_context(|| format!("Invalid gRPC metadata key `{key}`"))?;
+        let value = MetadataValue::try_from(value.as_str())
+            .with_context(|| format!("Invalid gRPC metadata value for `{key}`"))?;
+        metadata.insert(name, value);
+    }
+    Ok(metadata)
+}
+
+/// A tonic channel with the configured connection tuning, or `None` to let the exporter
+/// build its default channel. `vsock://` endpoints always get a channel of their own, and
+/// [`grpc_dns_refresh`](crate::OtelOptionsBuilder::grpc_dns_refresh) a balanced one.
+///
+/// # Errors
+///
+/// Returns an error if `endpoint` is not a valid URI, or is a vsock endpoint without the
+/// `vsock` feature.
+#[cfg(feature = "grpc")]
+fn grpc_channel(
+    config: &ResolvedConfig,
+    endpoint: &str,
+    timeout: std::time::Duration,
+) -> anyhow::Result<Option<tonic::transport::Channel>> {
+    let vsock = VsockAddr::parse(endpoint)
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
+    if vsock.is_none()
+        && config.grpc_keepalive.is_none()
+        && config.grpc_connect_timeout.is_none()
+        && config.grpc_adaptive_window.is_none()
+        && config.grpc_dns_refresh.is_none()
+    {
+        return Ok(None);
+    }
+
+    // The vsock connector ignores the URI, but HTTP/2 still needs an authority
+    let uri = match vsock {
+        Some(addr) => format!("http://localhost:{}", addr.port),
+        None => endpoint.to_owned(),
+    };
+    let channel = grpc_endpoint(config, &uri, timeout)?;
+    match vsock {
+        #[cfg(all(feature = "vsock", target_os = "linux"))]
+        Some(addr) => Ok(Some(channel.connect_with_connector_lazy(
+            crate::exporters::vsock::VsockConnector::new(addr),
+        ))),
+        #[cfg(not(all(feature = "vsock", target_os = "linux")))]
+        Some(_) => anyhow::bail!(
+            "vsock endpoint `{endpoint}` requires the `vsock` feature, on Linux. Enable it in \
+             Cargo.toml: raccoon-otel = {{ features = [\"vsock\"] }}"
+        ),
+        None => match config.grpc_dns_refresh {
+            Some(interval) => {
+                let config = config.clone();
+                let channel = balanced_channel(endpoint, interval, move |uri| {
+                    grpc_endpoint(&config, uri, timeout)
+                })?;
+                Ok(Some(channel))
+            }
+            None => Ok(Some(channel.connect_lazy())),
+        },
+    }
+}
+
+/// A tonic endpoint for `uri` with the configured connection tuning.
+///
+/// # Errors
+///
+/// Returns an error if `uri` is not a valid URI.
+#[cfg(feature = "grpc")]
+fn grpc_endpoint(
+    config: &ResolvedConfig,
+    uri: &str,
+    timeout: std::time::Duration,
+) -> anyhow::Result<tonic::transport::Endpoint> {
+    use anyhow::Context;
+
+    // A custom channel replaces the exporter's own, so the timeout is applied here too
+    let mut channel = tonic::transport::Channel::from_shared(uri.to_owned())
+        .with_context(|| format!("Invalid gRPC endpoint `{uri}`"))?
+        .timeout(timeout);
+    if let Some((interval, keepalive_timeout)) = config.grpc_keepalive {
+        channel = channel
+            .http2_keep_alive_interval(interval)
+            .keep_alive_timeout(keepalive_timeout)
+            .keep_alive_while_idle(true);
+    }
+    if let Some(connect_timeout) = config.grpc_connect_timeout {
+        channel = channel.connect_timeout(connect_timeout);
+    }
+    if let Some(enabled) = config.grpc_adaptive_window {
+        channel = channel.http2_adaptive_window(enabled);
+    }
+    Ok(channel)
+}
@@ -1,47 +1,303 @@
+use std::sync::Arc;
+
 use anyhow::Context;
 use opentelemetry_otlp::WithExportConfig;
-use opentelemetry_sdk::trace::SdkTracerProvider;
+#[cfg(feature = "http")]
+use opentelemetry_otlp::WithHttpConfig;
+#[cfg(feature = "grpc")]
+use opentelemetry_otlp::WithTonicConfig;
+use opentelemetry_sdk::trace::{
+    BatchConfigBuilder, BatchSpanProcessor, Sampler, SdkTracerProvider, ShouldSample, SpanProcessor,
+};
 use opentelemetry_sdk::Resource;
 
+#[cfg(feature = "azure-monitor")]
+use crate::azure_monitor::ConnectionString;
+use crate::cardinality::CardinalityAnalyzer;
 use crate::env::ResolvedConfig;
+use crate::exporters::auth::AuthSpanExporter;
+#[cfg(feature = "azure-monitor")]
+use crate::exporters::azure_monitor::AzureMonitorSpanExporter;
+use crate::exporters::backpressure::BackpressureSpanExporter;
+use crate::exporters::batch_size::{span_batch_size, AdaptiveBatchSpanExporter};
+use crate::exporters::budget::{BudgetLimiter, BudgetSpanExporter};
+use crate::exporters::counting::CountingSpanExporter;
+use crate::exporters::dead_letter::{DeadLetterSpanExporter, DeadLetterWriter};
+use crate::exporters::factor::FactorSpanExporter;
+use crate::exporters::fallback::{http_fallback_endpoint, FallbackSpanExporter};
+#[cfg(feature = "file")]
+use crate::exporters::file::{FileSpanExporter, OtlpFileWriter};
+use crate::exporters::health::HealthSpanExporter;
+use crate::exporters::inspect::InspectSpanExporter;
+#[cfg(feature = "jaeger")]
+use crate::exporters::jaeger::JaegerSpanExporter;
+use crate::exporters::queue::QueueDrainSpanExporter;
+use crate::exporters::split::SplitSpanExporter;
+use crate::exporters::tenant::TenantRoutingSpanExporter;
+use crate::exporters::transport::TransportSpanExporter;
+#[cfg(feature = "zipkin")]
+use crate::exporters::zipkin::ZipkinSpanExporter;
+use crate::fork::{ForkPoint, ForkSpanProcessor};
+use crate::health::ExportHealth;
 use crate::options::Protocol;
+use crate::processors::ambient::{ambient_attributes, AmbientSpanProcessor};
+use crate::processors::cardinality::CardinalityTransform;
+use crate::processors::dedup_attributes::DedupAttributesTransform;
+use crate::processors::drop::DropSpansTransform;
+use crate::processors::event_summary::EventSummaryTransform;
+use crate::processors::ingest::{IngestPoint, IngestSpanProcessor};
+use crate::processors::offload::OffloadSpanProcessor;
+use crate::processors::queue::{QueueMonitor, QueueMonitoringSpanProcessor};
+use crate::processors::rename::RenameAttributesTransform;
+use crate::processors::shard::ShardedSpanProcessor;
+use crate::processors::span::{SpanTransform, TransformingSpanProcessor};
+use crate::processors::span_kind::SpanKindTransform;
+use crate::processors::span_name::SpanNameTransform;
+use crate::processors::tenant::TenantSpanProcessor;
+use crate::processors::thread_attributes::ThreadAttributesSpanProcessor;
+use crate::processors::trace_buffer::{TraceErrorTransform, TraceLogBuffer};
+use crate::processors::truncate::TruncateTransform;
+use crate::samplers::adaptive::AdaptiveSampler;
+use crate::samplers::consistent::env_sampler;
+use crate::samplers::dynamic::DynamicSampler;
+use crate::samplers::rules::RuleSampler;
+use crate::stats::StatsCounters;
 
-/// Build and globally register a [`SdkTracerProvider`] with an OTLP exporter.
+/// Build a [`SdkTracerProvider`] with an OTLP exporter.
+///
+/// The provider is also registered globally unless `config.set_global` is `false`.
+/// When `log_buffer` is given, finished spans report trace errors and completion to it.
+/// Spans handed to `ingest` join the processors below the sampler. The processors are
+/// registered with `fork`, whose sampler state the provider uses.
 ///
 /// # Errors
 ///
 /// Returns an error if the OTLP exporter or provider fails to initialize.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn build_tracer_provider(
     resource: Resource,
     config: &ResolvedConfig,
+    log_buffer: Option<Arc<TraceLogBuffer>>,
+    budget: Option<Arc<BudgetLimiter>>,
+    health: Arc<ExportHealth>,
+    stats: Arc<StatsCounters>,
+    dead_letter: Option<Arc<DeadLetterWriter>>,
+    cardinality: Option<Arc<CardinalityAnalyzer>>,
+    ingest: &IngestPoint,
+    fork: &Arc<ForkPoint>,
+    #[cfg(feature = "record")] recorder: Option<Arc<crate::recording::Recorder>>,
 ) -> anyhow::Result<SdkTracerProvider> {
-    let exporter = build_span_exporter(config).context("Failed to build OTLP span exporter")?;
+    // Benchmark mode keeps every processor but counts batches instead of exporting them
+    let exporter = if config.benchmark_mode {
+        TransportSpanExporter::Counting(CountingSpanExporter::new(Arc::clone(&stats)))
+    } else {
+        build_span_exporter(config, &config.traces_protocol, &config.traces_endpoint)
+            .context("Failed to build OTLP span exporter")?
+    };
+    let fallback = http_fallback_endpoint(
+        config.grpc_http_fallback && !config.benchmark_mode,
+        &config.traces_protocol,
+        &config.traces_endpoint,
+    )
+    .map(|endpoint| build_span_exporter(config, &Protocol::HttpProtobuf, &endpoint))
+    .transpose()
+    .context("Failed to build fallback HTTP span exporter")?;
+    let exporter = FallbackSpanExporter::new(exporter, fallback);
+    let exporter = AuthSpanExporter::new(
+        exporter,
+        super::auth_refresh(config, &config.traces_protocol, |config| {
+            build_span_exporter(config, &config.traces_protocol, &config.traces_endpoint)
+        }),
+    );
+    let exporter = TenantRoutingSpanExporter::new(
+        exporter,
+        super::tenant_router(config, &config.traces_protocol, |config| {
+            build_span_exporter(config, &config.traces_protocol, &config.traces_endpoint)
+        }),
+    );
+    let exporter = SplitSpanExporter::new(exporter, config.max_export_bytes);
+    let exporter = HealthSpanExporter::new(exporter, Arc::clone(&health));
+    let exporter = DeadLetterSpanExporter::new(exporter, health, dead_letter);
+    let exporter = InspectSpanExporter::new(exporter, config.on_export.clone());
+    let exporter = BudgetSpanExporter::new(exporter, budget);
+    let adaptive = fork.adaptive.clone();
+    #[cfg(feature = "record")]
+    let exporter = crate::exporters::record::RecordSpanExporter::new(exporter, recorder);
+    let queue = Arc::new(QueueMonitor::spans(stats));
+    let exporter = QueueDrainSpanExporter::new(exporter, Arc::clone(&queue));
+    let exporter = AdaptiveBatchSpanExporter::new(
+        exporter,
+        config.adaptive_batch_size,
+        config.traces_export_timeout,
+    );
+    // Outside the chunks, so a full batch is judged by the processor's own size
+    let batch_size = span_batch_size(config.adaptive_batch_size);
+    let exporter = BackpressureSpanExporter::new(exporter, adaptive.clone(), batch_size);
 
-    let provider = SdkTracerProvider::builder()
-        .with_resource(resource)
-        .with_batch_exporter(exporter)
+    let batch = BatchSpanProcessor::builder(exporter)
+        .with_batch_config(
+            BatchConfigBuilder::default()
+                .with_max_export_batch_size(batch_size)
+                .build(),
+        )
         .build();
+    let batch = QueueMonitoringSpanProcessor::new(batch, queue);
+    let batch = ShardedSpanProcessor::new(config.batch_queue_shards, batch);
+    #[cfg(feature = "test-util")]
+    let batch = crate::processors::span_capture::CaptureSpanProcessor::new(
+        batch,
+        config.span_capture.clone(),
+    );
+    let processor = TransformingSpanProcessor::new(
+        build_span_transforms(config, log_buffer, cardinality),
+        batch,
+    );
+    let processor = OffloadSpanProcessor::new(config.async_bridge, processor);
+    let processor = TenantSpanProcessor::new(config.tenant.clone(), processor);
+    let processor = AmbientSpanProcessor::new(ambient_attributes(config), processor);
+    let mut processor = ThreadAttributesSpanProcessor::new(processor);
+    // Set up front: once shared with the ingest point, the provider can't reach it
+    processor.set_resource(&resource);
+    let processor = IngestSpanProcessor::new(processor, ingest);
+
+    let mut builder = SdkTracerProvider::builder()
+        .with_resource(resource)
+        .with_span_processor(ForkSpanProcessor::new(
+            with_clock(config, processor),
+            Arc::clone(fork),
+        ));
+    #[cfg(feature = "test-util")]
+    if let Some(ref generator) = config.id_generator {
+        builder = builder.with_id_generator(generator.clone());
+    }
+    if config.span_event_summary.is_some() {
+        // Summarizing keeps the last events, which the SDK would otherwise drop
+        builder = builder.with_max_events_per_span(u32::MAX);
+    }
+    let consistent = config.consistent_sampling;
+    let mut sampler: Box<dyn ShouldSample> = match adaptive {
+        Some(ratio) => Box::new(Sampler::ParentBased(Box::new(AdaptiveSampler::new(
+            ratio, consistent,
+        )))),
+        // What the SDK would use by itself, read from `OTEL_TRACES_SAMPLER`
+        None => env_sampler(consistent),
+    };
+    if !config.sampling_rules.is_empty() {
+        sampler = Box::new(RuleSampler::new(
+            &config.sampling_rules,
+            sampler,
+            consistent,
+        ));
+    }
+    builder = builder.with_sampler(DynamicSampler::new(
+        Arc::clone(&fork.sampling_override),
+        sampler,
+        consistent,
+    ));
+    let provider = builder.build();
 
     // Register globally so auto-instrumentation and context propagation work
-    opentelemetry::global::set_tracer_provider(provider.clone());
+    if config.set_global {
+        opentelemetry::global::set_tracer_provider(provider.clone());
+    }
 
     Ok(provider)
 }
 
+/// `processor` behind the configured test clock or monotonic timestamps if either is set,
+/// so the transforms and exporter see the corrected timestamps.
+fn with_clock<P: SpanProcessor + 'static>(
+    config: &ResolvedConfig,
+    processor: P,
+) -> Box<dyn SpanProcessor> {
+    #[cfg(feature = "test-util")]
+    if let Some(ref clock) = config.test_clock {
+        return Box::new(crate::processors::test_clock::TestClockSpanProcessor::new(
+            clock.clone(),
+            processor,
+        ));
+    }
+    if config.monotonic_timestamps {
+        return Box::new(
+            crate::processors::monotonic_clock::MonotonicClockSpanProcessor::new(processor),
+        );
+    }
+    Box::new(processor)
+}
+
+/// Collect the span transforms enabled in `config`, in the order they are applied.
+fn build_span_transforms(
+    config: &ResolvedConfig,
+    log_buffer: Option<Arc<TraceLogBuffer>>,
+    cardinality: Option<Arc<CardinalityAnalyzer>>,
+) -> Vec<Box<dyn SpanTransform>> {
+    let mut transforms: Vec<Box<dyn SpanTransform>> = vec![Box::new(DedupAttributesTransform)];
+
+    // Before any drop rule, so dropped spans still report errors and trace completion
+    if let Some(buffer) = log_buffer {
+        transforms.push(Box::new(TraceErrorTransform::new(buffer)));
+    }
+    if !config.drop_span_filters.is_empty() {
+        transforms.push(Box::new(DropSpansTransform::new(
+            config.drop_span_filters.clone(),
+        )));
+    }
+    if !config.span_kind_rules.is_empty() {
+        transforms.push(Box::new(SpanKindTransform::new(
+            config.span_kind_rules.clone(),
+        )));
+    }
+    if !config.attribute_renames.is_empty() {
+        transforms.push(Box::new(RenameAttributesTransform::new(
+            &config.attribute_renames,
+        )));
+    }
+    if let Some(ref mapper) = config.span_name_mapper {
+        transforms.push(Box::new(SpanNameTransform::new(mapper.clone())));
+    }
+    if let Some((max_events, keep)) = config.span_event_summary {
+        transforms.push(Box::new(EventSummaryTransform::new(max_events, keep)));
+    }
+    if let Some(max_len) = config.max_attribute_value_length {
+        transforms.push(Box::new(TruncateTransform::new(max_len)));
+    }
+    // Last, so it sees spans as they are exported
+    if let Some(analyzer) = cardinality {
+        transforms.push(Box::new(CardinalityTransform::new(analyzer)));
+    }
+
+    transforms
+}
+
 fn build_span_exporter(
     config: &ResolvedConfig,
-) -> anyhow::Result<opentelemetry_otlp::SpanExporter> {
-    match config.protocol {
+    protocol: &Protocol,
+    endpoint: &str,
+) -> anyhow::Result<TransportSpanExporter> {
+    match protocol {
         Protocol::Grpc => {
             #[cfg(feature = "grpc")]
             {
-                let exporter = opentelemetry_otlp::SpanExporter::builder()
+                let mut builder = opentelemetry_otlp::SpanExporter::builder()
                     .with_tonic()
-                    .with_endpoint(&config.endpoint)
-                    .with_timeout(config.export_timeout)
+                    .with_metadata(super::grpc_metadata(config)?)
+                    .with_endpoint(endpoint)
+                    .with_timeout(config.traces_export_timeout);
+                if let Some(channel) =
+                    super::grpc_channel(config, endpoint, config.traces_export_timeout)?
+                {
+                    builder = builder.with_channel(channel);
+                }
+                if let Some(credentials) = super::call_credentials(config) {
+                    builder = builder.with_interceptor(credentials);
+                }
+                let exporter = builder
                     .build()
                     .context("Failed to build gRPC span exporter")?;
-                Ok(exporter)
+                Ok(TransportSpanExporter::Otlp(FactorSpanExporter::new(
+                    exporter,
+                    config.factor_common_attributes,
+                )))
             }
             #[cfg(not(feature = "grpc"))]
             {
@@ -54,14 +310,18 @@ fn build_span_exporter(
         Protocol::HttpProtobuf | Protocol::HttpJson => {
             #[cfg(feature = "http")]
             {
-                let endpoint = format!("{}/v1/traces", config.endpoint.trim_end_matches('/'));
+                let endpoint = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
                 let exporter = opentelemetry_otlp::SpanExporter::builder()
                     .with_http()
+                    .with_headers(super::http_headers(config))
                     .with_endpoint(endpoint)
-                    .with_timeout(config.export_timeout)
+                    .with_timeout(config.traces_export_timeout)
                     .build()
                     .context("Failed to build HTTP span exporter")?;
-                Ok(exporter)
+                Ok(TransportSpanExporter::Otlp(FactorSpanExporter::new(
+                    exporter,
+                    config.factor_common_attributes,
+                )))
             }
             #[cfg(not(feature = "http"))]
             {
@@ -71,5 +331,62 @@ fn build_span_exporter(
                 );
             }
         }
+        #[cfg(feature = "file")]
+        Protocol::File { path } => {
+            let writer = OtlpFileWriter::shared(path, config.file_rotation)?;
+            Ok(TransportSpanExporter::File(FileSpanExporter::new(writer)))
+        }
+        #[cfg(not(feature = "file"))]
+        Protocol::File { .. } => {
+            anyhow::bail!(
+                "File output requested but the `file` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"file\"] }}"
+            );
+        }
+        #[cfg(feature = "jaeger")]
+        Protocol::Jaeger => Ok(TransportSpanExporter::Jaeger(JaegerSpanExporter::new(
+            endpoint,
+            super::http_headers(config),
+            config.traces_export_timeout,
+        ))),
+        #[cfg(not(feature = "jaeger"))]
+        Protocol::Jaeger => {
+            anyhow::bail!(
+                "Jaeger export requested but the `jaeger` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"jaeger\"] }}"
+            );
+        }
+        #[cfg(feature = "zipkin")]
+        Protocol::Zipkin => Ok(TransportSpanExporter::Zipkin(ZipkinSpanExporter::new(
+            endpoint,
+            super::http_headers(config),
+            config.traces_export_timeout,
+        ))),
+        #[cfg(not(feature = "zipkin"))]
+        Protocol::Zipkin => {
+            anyhow::bail!(
+                "Zipkin export requested but the `zipkin` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"zipkin\"] }}"
+            );
+        }
+        #[cfg(feature = "azure-monitor")]
+        Protocol::AzureMonitor { connection_string } => {
+            let connection = ConnectionString::parse(connection_string)?;
+            Ok(TransportSpanExporter::AzureMonitor(
+                AzureMonitorSpanExporter::new(
+                    &connection,
+                    endpoint,
+                    super::http_headers(config),
+                    config.traces_export_timeout,
+                ),
+            ))
+        }
+        #[cfg(not(feature = "azure-monitor"))]
+        Protocol::AzureMonitor { .. } => {
+            anyhow::bail!(
+                "Azure Monitor export requested but the `azure-monitor` feature is not enabled. \
+                 Enable it in Cargo.toml: raccoon-otel = {{ features = [\"azure-monitor\"] }}"
+            );
+        }
     }
 }
@@ -1,8 +1,8 @@
 //! Curated re-exports of key OpenTelemetry and tracing types.
 //!
 //! These re-exports let users access commonly needed types without adding
-//! direct dependencies on `opentelemetry`, `opentelemetry_sdk`, or
-//! `tracing-opentelemetry` to their own `Cargo.toml`.
+//! direct dependencies on `opentelemetry`, `opentelemetry_sdk`,
+//! `tracing-opentelemetry`, or `tracing-subscriber` to their own `Cargo.toml`.
 
 /// Re-export of the `tracing` crate for convenient access.
 pub use tracing;
@@ -13,5 +13,13 @@ pub use opentelemetry;
 /// Re-export of the `opentelemetry_sdk` crate.
 pub use opentelemetry_sdk;
 
+/// Re-export of the `opentelemetry-semantic-conventions` attribute name constants.
+///
+/// See [`semconv`](crate::semconv) for typed versions of the most common ones.
+pub use opentelemetry_semantic_conventions;
+
 /// Re-export of `tracing_opentelemetry` for span context extensions.
 pub use tracing_opentelemetry;
+
+/// Re-export of `tracing_subscriber` for composing [`OtelPipeline`](crate::OtelPipeline) layers.
+pub use tracing_subscriber;
@@ -0,0 +1,329 @@
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use opentelemetry::logs::{AnyValue, LogRecord as _, Severity};
+use opentelemetry::trace::{
+    Event, Link, SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceId, TraceState,
+};
+use opentelemetry::{Array, InstrumentationScope, Key, KeyValue, StringValue, Value};
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{self as proto, any_value};
+use opentelemetry_proto::tonic::logs::v1::LogRecord;
+use opentelemetry_proto::tonic::resource::v1::Resource as ProtoResource;
+use opentelemetry_proto::tonic::trace::v1::{self as proto_trace, span, status};
+use opentelemetry_sdk::logs::SdkLogRecord;
+use opentelemetry_sdk::trace::{SpanData, SpanEvents, SpanLinks};
+use opentelemetry_sdk::Resource;
+
+/// Span flags bit saying whether the other `is_remote` bit is set.
+const HAS_IS_REMOTE: u32 = 0x100;
+/// Span flags bit set when the parent (or linked) span is remote.
+const IS_REMOTE: u32 = 0x200;
+
+/// OTLP severity numbers, from 1 (`TRACE`) to 24 (`FATAL4`).
+const SEVERITIES: [Severity; 24] = [
+    Severity::Trace,
+    Severity::Trace2,
+    Severity::Trace3,
+    Severity::Trace4,
+    Severity::Debug,
+    Severity::Debug2,
+    Severity::Debug3,
+    Severity::Debug4,
+    Severity::Info,
+    Severity::Info2,
+    Severity::Info3,
+    Severity::Info4,
+    Severity::Warn,
+    Severity::Warn2,
+    Severity::Warn3,
+    Severity::Warn4,
+    Severity::Error,
+    Severity::Error2,
+    Severity::Error3,
+    Severity::Error4,
+    Severity::Fatal,
+    Severity::Fatal2,
+    Severity::Fatal3,
+    Severity::Fatal4,
+];
+
+/// The spans of an OTLP trace export, each carrying the attributes of its sender's
+/// resource that `parent` doesn't define. Spans with invalid IDs are left out.
+pub(crate) fn spans(request: ExportTraceServiceRequest, parent: &Resource) -> Vec<SpanData> {
+    let mut spans = Vec::new();
+    for resource_spans in request.resource_spans {
+        let inherited = attributes(inherited_attributes(resource_spans.resource, parent));
+        for scope_spans in resource_spans.scope_spans {
+            let scope = scope(scope_spans.scope, scope_spans.schema_url);
+            spans.extend(
+                scope_spans
+                    .spans
+                    .into_iter()
+                    .filter_map(|span| span_data(span, &inherited, &scope)),
+            );
+        }
+    }
+    spans
+}
+
+/// The log records of an OTLP log export with their instrumentation scopes, each carrying
+/// the attributes of its sender's resource that `parent` doesn't define.
+pub(crate) fn logs(
+    request: ExportLogsServiceRequest,
+    parent: &Resource,
+    blank: impl Fn() -> SdkLogRecord,
+) -> Vec<(SdkLogRecord, InstrumentationScope)> {
+    let mut records = Vec::new();
+    for resource_logs in request.resource_logs {
+        let inherited: Vec<_> = inherited_attributes(resource_logs.resource, parent)
+            .into_iter()
+            .filter_map(log_attribute)
+            .collect();
+        for scope_logs in resource_logs.scope_logs {
+            let scope = scope(scope_logs.scope, scope_logs.schema_url);
+            for log in scope_logs.log_records {
+                let mut record = blank();
+                fill_record(&mut record, log, &inherited);
+                records.push((record, scope.clone()));
+            }
+        }
+    }
+    records
+}
+
+/// The attributes of the sender's `resource` whose keys `parent` doesn't have.
+fn inherited_attributes(
+    resource: Option<ProtoResource>,
+    parent: &Resource,
+) -> Vec<proto::KeyValue> {
+    resource
+        .map(|resource| resource.attributes)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|kv| parent.get(&Key::new(kv.key.clone())).is_none())
+        .collect()
+}
+
+fn span_data(
+    span: proto_trace::Span,
+    inherited: &[KeyValue],
+    scope: &InstrumentationScope,
+) -> Option<SpanData> {
+    let context = span_context(&span.trace_id, &span.span_id, &span.trace_state)?;
+    let mut attributes = inherited.to_vec();
+    // After the inherited ones, so the span's own win when duplicates are collapsed
+    attributes.extend(self::attributes(span.attributes));
+    // Both are non-exhaustive, so they're filled in rather than built
+    let mut events = SpanEvents::default();
+    events.events = span
+        .events
+        .into_iter()
+        .map(|event| {
+            Event::new(
+                event.name,
+                time(event.time_unix_nano),
+                self::attributes(event.attributes),
+                event.dropped_attributes_count,
+            )
+        })
+        .collect();
+    events.dropped_count = span.dropped_events_count;
+    let mut links = SpanLinks::default();
+    links.links = span
+        .links
+        .into_iter()
+        .filter_map(|link| {
+            let linked = span_context(&link.trace_id, &link.span_id, &link.trace_state)?;
+            Some(Link::new(
+                linked,
+                self::attributes(link.attributes),
+                link.dropped_attributes_count,
+            ))
+        })
+        .collect();
+    links.dropped_count = span.dropped_links_count;
+    let status = match span.status {
+        Some(status) if status.code == status::StatusCode::Error as i32 => {
+            Status::error(status.message)
+        }
+        Some(status) if status.code == status::StatusCode::Ok as i32 => Status::Ok,
+        _ => Status::Unset,
+    };
+    Some(SpanData {
+        span_context: context,
+        parent_span_id: span_id(&span.parent_span_id).unwrap_or(SpanId::INVALID),
+        parent_span_is_remote: span.flags & (HAS_IS_REMOTE | IS_REMOTE)
+            == HAS_IS_REMOTE | IS_REMOTE,
+        span_kind: span_kind(span.kind),
+        name: Cow::Owned(span.name),
+        start_time: time(span.start_time_unix_nano),
+        end_time: time(span.end_time_unix_nano),
+        attributes,
+        dropped_attributes_count: span.dropped_attributes_count,
+        events,
+        links,
+        status,
+        instrumentation_scope: scope.clone(),
+    })
+}
+
+fn fill_record(record: &mut SdkLogRecord, log: LogRecord, inherited: &[(Key, AnyValue)]) {
+    if log.time_unix_nano != 0 {
+        record.set_timestamp(time(log.time_unix_nano));
+    }
+    if log.observed_time_unix_nano != 0 {
+        record.set_observed_timestamp(time(log.observed_time_unix_nano));
+    }
+    let severity = usize::try_from(log.severity_number)
+        .ok()
+        .and_then(|number| SEVERITIES.get(number.checked_sub(1)?));
+    if let Some(&severity) = severity {
+        record.set_severity_number(severity);
+        // The SDK only takes static text, so the standard name stands in for the sender's
+        record.set_severity_text(severity.name());
+    }
+    if let Some(body) = log.body.and_then(any_value) {
+        record.set_body(body);
+    }
+    record.add_attributes(inherited.iter().cloned());
+    // The SDK only takes a static event name, so it's kept as an attribute instead
+    if !log.event_name.is_empty() {
+        record.add_attribute("event.name", log.event_name);
+    }
+    record.add_attributes(log.attributes.into_iter().filter_map(log_attribute));
+    if let (Some(trace_id), Some(span_id)) = (trace_id(&log.trace_id), span_id(&log.span_id)) {
+        let flags = TraceFlags::new((log.flags & 0xff) as u8);
+        record.set_trace_context(trace_id, span_id, Some(flags));
+    }
+}
+
+/// A sampled span context, since an exported span was sampled by its sender.
+fn span_context(trace_id: &[u8], span_id: &[u8], trace_state: &str) -> Option<SpanContext> {
+    Some(SpanContext::new(
+        self::trace_id(trace_id)?,
+        self::span_id(span_id)?,
+        TraceFlags::SAMPLED,
+        false,
+        TraceState::from_str(trace_state).unwrap_or_default(),
+    ))
+}
+
+fn trace_id(bytes: &[u8]) -> Option<TraceId> {
+    let id = TraceId::from_bytes(bytes.try_into().ok()?);
+    (id != TraceId::INVALID).then_some(id)
+}
+
+fn span_id(bytes: &[u8]) -> Option<SpanId> {
+    let id = SpanId::from_bytes(bytes.try_into().ok()?);
+    (id != SpanId::INVALID).then_some(id)
+}
+
+fn span_kind(kind: i32) -> SpanKind {
+    match span::SpanKind::try_from(kind) {
+        Ok(span::SpanKind::Server) => SpanKind::Server,
+        Ok(span::SpanKind::Client) => SpanKind::Client,
+        Ok(span::SpanKind::Producer) => SpanKind::Producer,
+        Ok(span::SpanKind::Consumer) => SpanKind::Consumer,
+        _ => SpanKind::Internal,
+    }
+}
+
+fn time(unix_nanos: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(unix_nanos)
+}
+
+fn scope(scope: Option<proto::InstrumentationScope>, schema_url: String) -> InstrumentationScope {
+    let scope = scope.unwrap_or_default();
+    let mut builder =
+        InstrumentationScope::builder(scope.name).with_attributes(attributes(scope.attributes));
+    if !scope.version.is_empty() {
+        builder = builder.with_version(scope.version);
+    }
+    if !schema_url.is_empty() {
+        builder = builder.with_schema_url(schema_url);
+    }
+    builder.build()
+}
+
+fn attributes(attributes: Vec<proto::KeyValue>) -> Vec<KeyValue> {
+    attributes
+        .into_iter()
+        .filter_map(|kv| Some(KeyValue::new(kv.key, attribute_value(kv.value?.value?))))
+        .collect()
+}
+
+/// A span attribute value. Maps and bytes, which span attributes can't hold, and mixed
+/// arrays become their debug text.
+fn attribute_value(value: any_value::Value) -> Value {
+    match value {
+        any_value::Value::StringValue(s) => Value::from(s),
+        any_value::Value::BoolValue(b) => Value::Bool(b),
+        any_value::Value::IntValue(i) => Value::I64(i),
+        any_value::Value::DoubleValue(d) => Value::F64(d),
+        any_value::Value::ArrayValue(array) => {
+            let values: Vec<_> = array
+                .values
+                .into_iter()
+                .filter_map(|value| value.value)
+                .collect();
+            match array_value(&values) {
+                Some(array) => Value::Array(array),
+                None => Value::from(format!("{values:?}")),
+            }
+        }
+        other => Value::from(format!("{other:?}")),
+    }
+}
+
+/// `values` as a homogeneous array, if they all have the same scalar type.
+fn array_value(values: &[any_value::Value]) -> Option<Array> {
+    use any_value::Value as V;
+
+    let strings = values.iter().map(|value| match value {
+        V::StringValue(s) => Some(StringValue::from(s.clone())),
+        _ => None,
+    });
+    let bools = values.iter().map(|value| match value {
+        V::BoolValue(b) => Some(*b),
+        _ => None,
+    });
+    let ints = values.iter().map(|value| match value {
+        V::IntValue(i) => Some(*i),
+        _ => None,
+    });
+    let doubles = values.iter().map(|value| match value {
+        V::DoubleValue(d) => Some(*d),
+        _ => None,
+    });
+    match values.first()? {
+        V::StringValue(_) => strings.collect::<Option<_>>().map(Array::String),
+        V::BoolValue(_) => bools.collect::<Option<_>>().map(Array::Bool),
+        V::IntValue(_) => ints.collect::<Option<_>>().map(Array::I64),
+        V::DoubleValue(_) => doubles.collect::<Option<_>>().map(Array::F64),
+        _ => None,
+    }
+}
+
+fn log_attribute(kv: proto::KeyValue) -> Option<(Key, AnyValue)> {
+    Some((Key::new(kv.key), any_value(kv.value?)?))
+}
+
+/// A log body or attribute value, which can hold any OTLP value.
+fn any_value(value: proto::AnyValue) -> Option<AnyValue> {
+    Some(match value.value? {
+        any_value::Value::StringValue(s) => AnyValue::from(s),
+        any_value::Value::BoolValue(b) => AnyValue::Boolean(b),
+        any_value::Value::IntValue(i) => AnyValue::Int(i),
+        any_value::Value::DoubleValue(d) => AnyValue::Double(d),
+        any_value::Value::BytesValue(bytes) => AnyValue::Bytes(Box::new(bytes)),
+        any_value::Value::ArrayValue(array) => AnyValue::ListAny(Box::new(
+            array.values.into_iter().filter_map(any_value).collect(),
+        )),
+        any_value::Value::KvlistValue(list) => AnyValue::Map(Box::new(
+            list.values.into_iter().filter_map(log_attribute).collect(),
+        )),
+    })
+}
@@ -0,0 +1,359 @@
+//! Embedded OTLP/HTTP receiver (`receiver` feature): accepts spans and logs from child
+//! processes and plugins, and forwards them through this process's pipeline.
+
+mod convert;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::Context;
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_sdk::Resource;
+use prost::Message;
+
+use crate::processors::ingest::IngestPoint;
+use crate::processors::log::blank_record;
+
+/// Largest request body accepted, well above a default OTLP batch.
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// How long an idle connection is kept open, so a stuck sender can't hold a thread forever.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A listener thread accepting OTLP/HTTP protobuf exports on `/v1/traces` and `/v1/logs`,
+/// with a thread per connection.
+#[derive(Debug)]
+pub(crate) struct Receiver {
+    addr: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Receiver {
+    /// Listen on `addr`, handing what's received to `ingest` with the attributes of the
+    /// sender's resource that `resource` doesn't define.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `addr` can't be bound.
+    pub(crate) fn start(
+        addr: &str,
+        ingest: Arc<IngestPoint>,
+        resource: Resource,
+    ) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .with_context(|| format!("Failed to bind the OTLP receiver to {addr}"))?;
+        let addr = listener.local_addr()?;
+        let stopping = Arc::new(AtomicBool::new(false));
+        let target = Arc::new(Target {
+            ingest,
+            resource,
+            stopping: Arc::clone(&stopping),
+        });
+        let thread = std::thread::Builder::new()
+            .name("raccoon-otel-receiver".into())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if target.stopping.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let target = Arc::clone(&target);
+                    let _ = std::thread::Builder::new()
+                        .name("raccoon-otel-receiver-conn".into())
+                        .spawn(move || target.serve(stream));
+                }
+            })
+            .context("Failed to start the OTLP receiver thread")?;
+        Ok(Self {
+            addr,
+            stopping,
+            thread: Some(thread),
+        })
+    }
+
+    /// The `http://host:port` endpoint senders should export to.
+    pub(crate) fn endpoint(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Stop accepting connections. Open connections drop what they receive from now on.
+    pub(crate) fn stop(&mut self) {
+        self.stopping.store(true, Ordering::Relaxed);
+        // Wake the listener, which is blocked in accept
+        let _ = TcpStream::connect_timeout(&self.addr, Duration::from_secs(1));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Where a receiver's connections forward what they receive.
+struct Target {
+    ingest: Arc<IngestPoint>,
+    resource: Resource,
+    stopping: Arc<AtomicBool>,
+}
+
+/// An HTTP response status with its reason phrase.
+type Status = (u16, &'static str);
+
+const OK: Status = (200, "OK");
+const BAD_REQUEST: Status = (400, "Bad Request");
+const NOT_FOUND: Status = (404, "Not Found");
+const METHOD_NOT_ALLOWED: Status = (405, "Method Not Allowed");
+const LENGTH_REQUIRED: Status = (411, "Length Required");
+const PAYLOAD_TOO_LARGE: Status = (413, "Payload Too Large");
+const UNSUPPORTED_MEDIA_TYPE: Status = (415, "Unsupported Media Type");
+
+/// The parts of an HTTP request the receiver looks at.
+struct Request {
+    method: String,
+    path: String,
+    content_length: Option<usize>,
+    protobuf: bool,
+    compressed: bool,
+    close: bool,
+}
+
+impl Target {
+    /// Serve requests on `stream` until the sender closes it or goes idle.
+    fn serve(&self, stream: TcpStream) {
+        let _ = stream.set_read_timeout(Some(IDLE_TIMEOUT));
+        let Ok(mut writer) = stream.try_clone() else {
+            return;
+        };
+        let mut reader = BufReader::new(stream);
+        while let Ok(Some(request)) = read_head(&mut reader) {
+            let status = match request.content_length {
+                None => LENGTH_REQUIRED,
+                Some(length) if length > MAX_BODY_BYTES => PAYLOAD_TOO_LARGE,
+                Some(length) => {
+                    let mut body = vec![0; length];
+                    if reader.read_exact(&mut body).is_err() {
+                        return;
+                    }
+                    self.handle(&request, &body)
+                }
+            };
+            // Anything but success leaves the body unread or the stream in doubt
+            let close = request.close || status != OK;
+            if respond(&mut writer, status, close).is_err() || close {
+                return;
+            }
+        }
+    }
+
+    fn handle(&self, request: &Request, body: &[u8]) -> Status {
+        if request.path != "/v1/traces" && request.path != "/v1/logs" {
+            return NOT_FOUND;
+        }
+        if request.method != "POST" {
+            return METHOD_NOT_ALLOWED;
+        }
+        if !request.protobuf || request.compressed {
+            return UNSUPPORTED_MEDIA_TYPE;
+        }
+        // Accepted but dropped, like any export racing shutdown
+        let forward = !self.stopping.load(Ordering::Relaxed);
+        if request.path == "/v1/traces" {
+            let Ok(export) = ExportTraceServiceRequest::decode(body) else {
+                return BAD_REQUEST;
+            };
+            if forward {
+                for span in convert::spans(export, &self.resource) {
+                    self.ingest.span(span);
+                }
+            }
+        } else {
+            let Ok(export) = ExportLogsServiceRequest::decode(body) else {
+                return BAD_REQUEST;
+            };
+            if forward {
+                for (mut record, scope) in convert::logs(export, &self.resource, blank_record) {
+                    self.ingest.log(&mut record, &scope);
+                }
+            }
+        }
+        OK
+    }
+}
+
+/// Read a request line and headers, or `None` once the sender closes the connection.
+fn read_head(reader: &mut impl BufRead) -> std::io::Result<Option<Request>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let target = parts.next().unwrap_or_default();
+    let mut request = Request {
+        method,
+        path: target.split('?').next().unwrap_or_default().to_owned(),
+        content_length: None,
+        protobuf: false,
+        compressed: false,
+        close: parts.next() == Some("HTTP/1.0"),
+    };
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            return Ok(Some(request));
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => request.content_length = value.parse().ok(),
+            "content-type" => request.protobuf = value.starts_with("application/x-protobuf"),
+            "content-encoding" => request.compressed = !value.eq_ignore_ascii_case("identity"),
+            // A chunked body has no usable length, so it's refused
+            "transfer-encoding" => request.content_length = None,
+            "connection" => request.close = value.eq_ignore_ascii_case("close"),
+            _ => {}
+        }
+    }
+}
+
+/// Write a response with an empty body: for a success, the empty protobuf export response.
+fn respond(writer: &mut impl Write, (code, reason): Status, close: bool) -> std::io::Result<()> {
+    let connection = if close { "close" } else { "keep-alive" };
+    write!(
+        writer,
+        "HTTP/1.1 {code} {reason}\r\n\
+         Content-Type: application/x-protobuf\r\n\
+         Content-Length: 0\r\n\
+         Connection: {connection}\r\n\r\n"
+    )?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::resource::v1::Resource as ProtoResource;
+    use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+
+    use super::*;
+
+    fn string(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_owned(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_owned())),
+            }),
+        }
+    }
+
+    #[test]
+    fn received_spans_keep_their_ids_and_the_sender_resource_fills_gaps() {
+        let export = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(ProtoResource {
+                    attributes: vec![string("service.name", "plugin"), string("plugin.id", "7")],
+                    ..Default::default()
+                }),
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![
+                        Span {
+                            trace_id: vec![1; 16],
+                            span_id: vec![2; 8],
+                            name: "plugin.run".to_owned(),
+                            kind: 2,
+                            flags: 0x301,
+                            start_time_unix_nano: 1_000,
+                            end_time_unix_nano: 2_000,
+                            attributes: vec![string("plugin.id", "8")],
+                            ..Default::default()
+                        },
+                        // Invalid IDs are left out
+                        Span::default(),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let parent = Resource::builder_empty().with_service_name("host").build();
+        let spans = convert::spans(export, &parent);
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(span.name, "plugin.run");
+        assert_eq!(span.span_context.span_id().to_bytes(), [2; 8]);
+        assert!(span.parent_span_is_remote);
+        assert_eq!(
+            span.start_time,
+            std::time::UNIX_EPOCH + Duration::from_micros(1)
+        );
+        let attributes: Vec<_> = span
+            .attributes
+            .iter()
+            .map(|kv| (kv.key.as_str(), kv.value.as_str().into_owned()))
+            .collect();
+        // `service.name` is the receiving process's; the span's own `plugin.id` comes last
+        assert_eq!(
+            attributes,
+            [("plugin.id", "7".to_owned()), ("plugin.id", "8".to_owned())]
+        );
+    }
+
+    #[test]
+    fn requests_are_routed_by_path_and_content_type() {
+        let receiver = Receiver::start(
+            "127.0.0.1:0",
+            Arc::default(),
+            Resource::builder_empty().build(),
+        )
+        .unwrap();
+        let post = |path: &str, content_type: &str, body: &[u8]| {
+            let mut stream = TcpStream::connect(receiver.addr).unwrap();
+            write!(
+                stream,
+                "POST {path} HTTP/1.1\r\nContent-Type: {content_type}\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(body).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response.lines().next().unwrap_or_default().to_owned()
+        };
+        let export = ExportTraceServiceRequest::default().encode_to_vec();
+        assert_eq!(
+            post("/v1/traces", "application/x-protobuf", &export),
+            "HTTP/1.1 200 OK"
+        );
+        assert_eq!(
+            post("/v1/traces", "application/json", b"{}"),
+            "HTTP/1.1 415 Unsupported Media Type"
+        );
+        assert_eq!(
+            post("/v1/logs", "application/x-protobuf", b"\xff"),
+            "HTTP/1.1 400 Bad Request"
+        );
+        assert_eq!(
+            post("/v1/metrics", "application/x-protobuf", b""),
+            "HTTP/1.1 404 Not Found"
+        );
+    }
+}
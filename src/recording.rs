@@ -0,0 +1,219 @@
+//! Recording of exported batches to a file, and replay into an OTLP endpoint.
+//!
+//! A recording is a sequence of records, one per exported batch: a signal tag byte
+//! (`1` for traces, `2` for logs) followed by the length-delimited protobuf encoding of the
+//! OTLP `ExportTraceServiceRequest` or `ExportLogsServiceRequest`. Records are appended as
+//! each batch is exported, so a recording survives a crash up to the last full batch.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use prost::Message;
+
+use crate::options::Protocol;
+
+/// Minimum time between two "recording failed" warnings on stderr.
+const WARN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Timeout for each replayed request.
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The signal a recorded batch belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Signal {
+    Traces,
+    Logs,
+}
+
+impl Signal {
+    fn tag(self) -> u8 {
+        match self {
+            Signal::Traces => 1,
+            Signal::Logs => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(Signal::Traces),
+            2 => Some(Signal::Logs),
+            _ => None,
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Signal::Traces => "/v1/traces",
+            Signal::Logs => "/v1/logs",
+        }
+    }
+}
+
+/// Appends encoded batches to the recording file, shared by the span and log exporters.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    path: PathBuf,
+    file: Mutex<File>,
+    warned: Mutex<Option<Instant>>,
+}
+
+impl Recorder {
+    /// Open `path` for appending, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be opened.
+    pub(crate) fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open recording file `{}`", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            file: Mutex::new(file),
+            warned: Mutex::new(None),
+        })
+    }
+
+    /// Append one batch. Failures are reported on stderr (rate-limited) and never affect
+    /// the export itself.
+    pub(crate) fn record(&self, signal: Signal, request: &impl Message) {
+        let record = encode_record(signal, request);
+        let result = self
+            .file
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .write_all(&record);
+        if let Err(err) = result {
+            let mut warned = self.warned.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            if warned.is_some_and(|at| now.duration_since(at) < WARN_INTERVAL) {
+                return;
+            }
+            *warned = Some(now);
+            eprintln!(
+                "raccoon-otel: failed to record batch to `{}`: {err}",
+                self.path.display()
+            );
+        }
+    }
+}
+
+fn encode_record(signal: Signal, request: &impl Message) -> Vec<u8> {
+    let mut record = Vec::with_capacity(1 + request.encoded_len() + 10);
+    record.push(signal.tag());
+    // Writing to a `Vec` can't run out of capacity
+    let _ = request.encode_length_delimited(&mut record);
+    record
+}
+
+/// Split a recording into its records, checking that every record is complete.
+///
+/// # Errors
+///
+/// Returns an error at the first unknown signal tag or truncated record.
+fn parse_records(mut data: &[u8]) -> anyhow::Result<Vec<(Signal, &[u8])>> {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while let Some((&tag, rest)) = data.split_first() {
+        let signal = Signal::from_tag(tag)
+            .with_context(|| format!("Unknown record type {tag} at byte {offset}"))?;
+        let mut cursor = rest;
+        let len = prost::decode_length_delimiter(&mut cursor)
+            .with_context(|| format!("Corrupt record length at byte {offset}"))?;
+        anyhow::ensure!(
+            cursor.len() >= len,
+            "Truncated record at byte {offset}: expected {len} bytes, found {}",
+            cursor.len()
+        );
+        let (body, rest) = cursor.split_at(len);
+        records.push((signal, body));
+        offset += data.len() - rest.len();
+        data = rest;
+    }
+    Ok(records)
+}
+
+/// Re-send every batch in the recording at `path` to an OTLP/HTTP endpoint, in order.
+///
+/// `endpoint` is the collector's base URL (e.g. `"http://collector:4318"`); batches are
+/// posted as protobuf to `/v1/traces` and `/v1/logs`. The whole file is validated before
+/// anything is sent. Returns the number of batches replayed.
+///
+/// Recordings are written by [`OtelOptionsBuilder::record_to`](crate::OtelOptionsBuilder::record_to).
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or is corrupt, if the endpoint is not a
+/// usable URL, or if the endpoint rejects a batch. Batches before the failing one have
+/// already been sent.
+pub fn replay(path: impl AsRef<Path>, endpoint: &str) -> anyhow::Result<usize> {
+    let path = path.as_ref();
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read recording `{}`", path.display()))?;
+    let records =
+        parse_records(&data).with_context(|| format!("Invalid recording `{}`", path.display()))?;
+    let endpoint = crate::env::normalize_endpoint(endpoint, &Protocol::HttpProtobuf)?;
+    let endpoint = endpoint.trim_end_matches('/');
+
+    // The blocking client panics inside an async runtime, so it gets its own thread
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| send_records(&records, endpoint))
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("Replay thread panicked")))
+    })
+}
+
+fn send_records(records: &[(Signal, &[u8])], endpoint: &str) -> anyhow::Result<usize> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(REPLAY_TIMEOUT)
+        .build()
+        .context("Failed to build HTTP client")?;
+    for (index, (signal, body)) in records.iter().enumerate() {
+        let url = format!("{endpoint}{}", signal.path());
+        client
+            .post(&url)
+            .header("Content-Type", "application/x-protobuf")
+            .body(body.to_vec())
+            .send()
+            .and_then(reqwest::blocking::Response::error_for_status)
+            .with_context(|| format!("Failed to replay batch {} to `{url}`", index + 1))?;
+    }
+    Ok(records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+    use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+
+    use super::*;
+
+    #[test]
+    fn records_round_trip_and_truncation_is_detected() {
+        let traces = ExportTraceServiceRequest::default();
+        let logs = ExportLogsServiceRequest {
+            resource_logs: vec![Default::default()],
+        };
+        let mut data = encode_record(Signal::Traces, &traces);
+        data.extend(encode_record(Signal::Logs, &logs));
+
+        let records = parse_records(&data).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], (Signal::Traces, &[][..]));
+        assert_eq!(records[1].0, Signal::Logs);
+        assert_eq!(
+            ExportLogsServiceRequest::decode(records[1].1).unwrap(),
+            logs
+        );
+
+        assert!(parse_records(&data[..data.len() - 1]).is_err());
+        assert!(parse_records(&[9, 0]).is_err());
+    }
+}
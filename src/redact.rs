@@ -0,0 +1,105 @@
+//! Masking of secrets when configuration is printed.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Key fragments that always mark a value as secret (matched case-insensitively).
+const SECRET_PATTERNS: &[&str] = &[
+    "authorization",
+    "token",
+    "secret",
+    "password",
+    "api-key",
+    "api_key",
+    "apikey",
+    "credential",
+    "cookie",
+];
+
+const REDACTED: &str = "<redacted>";
+
+/// Whether `key` contains one of the built-in secret patterns or one of `extra`
+/// (already lowercase, see `OtelOptionsBuilder::redact_keys`).
+pub(crate) fn is_secret_key(key: &str, extra: &[String]) -> bool {
+    let key = key.to_lowercase();
+    SECRET_PATTERNS.iter().any(|p| key.contains(p)) || extra.iter().any(|p| key.contains(p))
+}
+
+/// Debug view of a string map with all or only secret-looking values masked.
+pub(crate) struct RedactedMap<'a> {
+    map: &'a HashMap<String, String>,
+    extra: Option<&'a [String]>,
+}
+
+impl<'a> RedactedMap<'a> {
+    /// Mask every value (headers, gRPC metadata).
+    pub(crate) fn all(map: &'a HashMap<String, String>) -> Self {
+        Self { map, extra: None }
+    }
+
+    /// Mask values whose key matches a secret pattern.
+    pub(crate) fn matching(map: &'a HashMap<String, String>, extra: &'a [String]) -> Self {
+        Self {
+            map,
+            extra: Some(extra),
+        }
+    }
+
+    fn value(&self, key: &str, value: &'a str) -> &'a str {
+        match self.extra {
+            Some(extra) if !is_secret_key(key, extra) => value,
+            _ => REDACTED,
+        }
+    }
+
+    /// `key=value` pairs, sorted for stable output.
+    pub(crate) fn pairs(&self) -> Vec<String> {
+        let mut pairs: Vec<String> = self
+            .map
+            .iter()
+            .map(|(k, v)| format!("{k}={}", self.value(k, v)))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+}
+
+impl fmt::Debug for RedactedMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries: Vec<_> = self
+            .map
+            .iter()
+            .map(|(k, v)| (k, self.value(k, v)))
+            .collect();
+        entries.sort();
+        f.debug_map().entries(entries).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::OtelOptions;
+
+    #[test]
+    fn debug_output_masks_secrets() {
+        let opts = OtelOptions::builder()
+            .headers([("x-tenant", "acme-header")])
+            .grpc_metadata([("x-region", "eu-metadata")])
+            .resource_attributes([
+                ("deployment.environment", "production"),
+                ("db.password", "hunter2"),
+                ("vault.lease", "lease-123"),
+            ])
+            .redact_keys(["Lease"]);
+
+        let builder = format!("{opts:?}");
+        let built = format!("{:?}", opts.build());
+
+        for output in [builder, built] {
+            assert!(output.contains("production"));
+            for secret in ["acme-header", "eu-metadata", "hunter2", "lease-123"] {
+                assert!(!output.contains(secret), "{secret} leaked: {output}");
+            }
+        }
+    }
+}
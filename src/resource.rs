@@ -1,16 +1,117 @@
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 use opentelemetry::KeyValue;
 use opentelemetry_sdk::Resource;
 
+/// Resources built so far, most recent last. A handful covers the banner, the providers and
+/// a few side-by-side pipelines.
+static CACHE: Mutex<Vec<CachedResource>> = Mutex::new(Vec::new());
+const CACHE_CAPACITY: usize = 8;
+
+struct CachedResource {
+    service_name: String,
+    attributes: HashMap<String, String>,
+    key_values: Vec<KeyValue>,
+    resource: Resource,
+}
+
+impl CachedResource {
+    fn matches(
+        &self,
+        service_name: &str,
+        attributes: &HashMap<String, String>,
+        key_values: &[KeyValue],
+    ) -> bool {
+        self.service_name == service_name
+            && self.attributes == *attributes
+            && self.key_values == key_values
+    }
+}
+
 /// Build an OpenTelemetry [`Resource`] with the service name and optional attributes.
-pub(crate) fn build_resource(service_name: &str, attributes: &HashMap<String, String>) -> Resource {
-    let mut kvs: Vec<KeyValue> = Vec::with_capacity(attributes.len() + 1);
-    kvs.push(KeyValue::new("service.name", service_name.to_owned()));
+///
+/// `key_values` are applied last, so they win over `attributes` on a shared key. A resource
+/// built from the same inputs before is reused rather than rebuilt; `Resource` is reference
+/// counted, so every provider shares one copy.
+pub(crate) fn build_resource(
+    service_name: &str,
+    attributes: &HashMap<String, String>,
+    key_values: &[KeyValue],
+) -> Resource {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(cached) = cache
+        .iter()
+        .find(|cached| cached.matches(service_name, attributes, key_values))
+    {
+        return cached.resource.clone();
+    }
 
+    let mut kvs: Vec<KeyValue> = Vec::with_capacity(attributes.len() + key_values.len() + 1);
+    kvs.push(KeyValue::new("service.name", service_name.to_owned()));
     for (key, value) in attributes {
         kvs.push(KeyValue::new(key.clone(), value.clone()));
     }
+    // `&'static str` keys and values are cloned without copying the text
+    kvs.extend_from_slice(key_values);
+    let resource = Resource::builder().with_attributes(kvs).build();
 
-    Resource::builder().with_attributes(kvs).build()
+    if cache.len() == CACHE_CAPACITY {
+        cache.remove(0);
+    }
+    cache.push(CachedResource {
+        service_name: service_name.to_owned(),
+        attributes: attributes.clone(),
+        key_values: key_values.to_vec(),
+        resource: resource.clone(),
+    });
+    resource
+}
+
+/// `key_values` as strings, for redacted display.
+pub(crate) fn display_map(key_values: &[KeyValue]) -> HashMap<String, String> {
+    key_values
+        .iter()
+        .map(|kv| (kv.key.to_string(), kv.value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::{Key, Value};
+
+    use super::*;
+
+    #[test]
+    fn key_values_override_attributes_and_resources_are_reused() {
+        let attributes = HashMap::from([("region".to_owned(), "eu".to_owned())]);
+        let key_values = [
+            KeyValue::new("region", "us"),
+            KeyValue::new("service.instance.count", 3),
+        ];
+
+        let first = build_resource("resource-test", &attributes, &key_values);
+        assert_eq!(first.get(&Key::new("region")), Some(Value::from("us")));
+        assert_eq!(
+            first.get(&Key::new("service.instance.count")),
+            Some(Value::I64(3))
+        );
+
+        let second = build_resource("resource-test", &attributes, &key_values);
+        assert_eq!(first, second);
+        let cached = CACHE.lock().unwrap();
+        assert!(
+            cached
+                .iter()
+                .filter(|c| c.service_name == "resource-test")
+                .count()
+                <= 1
+        );
+        drop(cached);
+        let other = build_resource("resource-test-2", &attributes, &key_values);
+        assert_eq!(
+            other.get(&Key::new("service.name")),
+            Some(Value::from("resource-test-2"))
+        );
+    }
 }
@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceId};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::ShouldSample;
+
+use crate::samplers::consistent::RatioSampler;
+
+/// Consecutive pressured (or healthy) exports needed before the ratio changes.
+const STREAK: u32 = 3;
+/// Factor applied to the ratio when recovering from backpressure.
+const RECOVERY_FACTOR: f64 = 1.25;
+
+/// Sampling ratio shared between [`AdaptiveSampler`] and the exporter that observes
+/// backpressure.
+///
+/// After `STREAK` consecutive pressured exports the ratio is halved (down to `min`);
+/// after `STREAK` consecutive healthy exports it grows back towards 1.0.
+#[derive(Debug)]
+pub(crate) struct AdaptiveRatio {
+    /// Current ratio, stored as `f64` bits.
+    ratio: AtomicU64,
+    min: f64,
+    streak: Mutex<Streak>,
+}
+
+#[derive(Debug, Default)]
+struct Streak {
+    pressured: u32,
+    healthy: u32,
+}
+
+impl AdaptiveRatio {
+    pub(crate) fn new(min: f64) -> Self {
+        Self {
+            ratio: AtomicU64::new(1.0_f64.to_bits()),
+            min: min.clamp(0.0, 1.0),
+            streak: Mutex::new(Streak::default()),
+        }
+    }
+
+    pub(crate) fn ratio(&self) -> f64 {
+        f64::from_bits(self.ratio.load(Ordering::Relaxed))
+    }
+
+    /// Feed the outcome of one export: `pressured` if it failed or the queue was backed up.
+    pub(crate) fn observe(&self, pressured: bool) {
+        let mut streak = self.streak.lock().unwrap_or_else(|e| e.into_inner());
+        let current = self.ratio();
+
+        let next = if pressured {
+            streak.healthy = 0;
+            streak.pressured += 1;
+            (streak.pressured >= STREAK).then(|| (current / 2.0).max(self.min))
+        } else {
+            streak.pressured = 0;
+            streak.healthy += 1;
+            (streak.healthy >= STREAK).then(|| (current * RECOVERY_FACTOR).min(1.0))
+        };
+
+        let Some(next) = next else {
+            return;
+        };
+        *streak = Streak::default();
+        if next == current {
+            return;
+        }
+        self.ratio.store(next.to_bits(), Ordering::Relaxed);
+
+        if next < current {
+            eprintln!("raccoon-otel: export backpressure, lowering sampling ratio to {next:.3}");
+        } else if next == 1.0 {
+            eprintln!("raccoon-otel: exports healthy, sampling ratio restored to 1.0");
+        }
+    }
+}
+
+/// Root sampler that samples by trace ID using the current [`AdaptiveRatio`].
+///
+/// Meant to be wrapped in `Sampler::ParentBased` so traces are never cut midway.
+#[derive(Debug, Clone)]
+pub(crate) struct AdaptiveSampler {
+    ratio: Arc<AdaptiveRatio>,
+    consistent: bool,
+}
+
+impl AdaptiveSampler {
+    pub(crate) fn new(ratio: Arc<AdaptiveRatio>, consistent: bool) -> Self {
+        Self { ratio, consistent }
+    }
+}
+
+impl ShouldSample for AdaptiveSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        RatioSampler::new(self.ratio.ratio(), self.consistent).should_sample(
+            parent_context,
+            trace_id,
+            name,
+            span_kind,
+            attributes,
+            links,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sustained_pressure_lowers_ratio_down_to_floor() {
+        let ratio = AdaptiveRatio::new(0.2);
+        for _ in 0..STREAK {
+            ratio.observe(true);
+        }
+        assert_eq!(ratio.ratio(), 0.5);
+
+        for _ in 0..STREAK * 4 {
+            ratio.observe(true);
+        }
+        assert_eq!(ratio.ratio(), 0.2);
+    }
+
+    #[test]
+    fn isolated_failures_do_not_change_ratio_and_health_restores_it() {
+        let ratio = AdaptiveRatio::new(0.0);
+        for _ in 0..STREAK {
+            ratio.observe(true);
+        }
+        // A single healthy export breaks the pressure streak
+        ratio.observe(true);
+        ratio.observe(false);
+        ratio.observe(true);
+        assert_eq!(ratio.ratio(), 0.5);
+
+        for _ in 0..STREAK * 4 {
+            ratio.observe(false);
+        }
+        assert_eq!(ratio.ratio(), 1.0);
+    }
+}
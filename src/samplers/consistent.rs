@@ -0,0 +1,316 @@
+use opentelemetry::trace::{
+    Link, SamplingDecision, SamplingResult, SpanKind, TraceContextExt, TraceId, TraceState,
+};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::{Config, Sampler, ShouldSample};
+
+/// The tracestate key OpenTelemetry's sampling values live under.
+const OT_KEY: &str = "ot";
+/// Bits of randomness compared against the rejection threshold.
+const RANDOMNESS_BITS: u32 = 56;
+/// One past the largest randomness value; also the threshold that rejects everything.
+const MAX_THRESHOLD: u64 = 1 << RANDOMNESS_BITS;
+/// The W3C trace flag saying the trace ID's low 56 bits are random.
+const RANDOM_TRACE_ID: u8 = 0x02;
+
+/// Root sampler keeping `ratio` of traces by trace ID.
+///
+/// Without consistent sampling this is the SDK's `TraceIdRatioBased`. With it, the decision
+/// follows OpenTelemetry's consistent probability sampling: the trace's 56 bits of
+/// randomness (the tracestate `rv`, or the low bits of the trace ID) are compared against a
+/// rejection threshold, which sampled spans record as `th` in the `ot` tracestate entry.
+/// Any service sampling the same trace at the same or a higher ratio keeps it too, so
+/// independently sampled services don't leave partial traces.
+///
+/// The trace ID only counts as random if it was generated in this process or a remote
+/// parent set the W3C `random` flag. Otherwise there is no randomness to be consistent
+/// with, so the decision falls back to `TraceIdRatioBased` and no `th` is recorded.
+/// Randomness taken from the trace ID is written out as `rv`, since the SDK's traceparent
+/// never carries the `random` flag and downstream services couldn't trust the ID alone.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RatioSampler {
+    ratio: f64,
+    consistent: bool,
+}
+
+impl RatioSampler {
+    pub(crate) fn new(ratio: f64, consistent: bool) -> Self {
+        Self { ratio, consistent }
+    }
+}
+
+impl ShouldSample for RatioSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        if !self.consistent {
+            return Sampler::TraceIdRatioBased(self.ratio).should_sample(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            );
+        }
+        let parent = parent_context
+            .filter(|cx| cx.has_active_span())
+            .map(|cx| cx.span().span_context().clone());
+        let trace_state = parent
+            .as_ref()
+            .map(|parent| parent.trace_state().clone())
+            .unwrap_or_default();
+        let random_trace_id = parent.as_ref().is_none_or(|parent| {
+            !parent.is_remote() || parent.trace_flags().to_u8() & RANDOM_TRACE_ID != 0
+        });
+        let explicit = explicit_randomness(&trace_state);
+        let randomness =
+            explicit.or_else(|| random_trace_id.then(|| trace_id_randomness(trace_id)));
+        let Some(randomness) = randomness else {
+            let result = Sampler::TraceIdRatioBased(self.ratio).should_sample(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            );
+            return SamplingResult {
+                trace_state: with_threshold(&trace_state, None),
+                ..result
+            };
+        };
+        let trace_state = match explicit {
+            Some(_) => trace_state,
+            None => with_ot_field(&trace_state, "rv", Some(format!("{randomness:014x}"))),
+        };
+        let threshold = threshold(self.ratio).filter(|&threshold| randomness >= threshold);
+        SamplingResult {
+            decision: if threshold.is_some() {
+                SamplingDecision::RecordAndSample
+            } else {
+                SamplingDecision::Drop
+            },
+            attributes: Vec::new(),
+            trace_state: with_threshold(&trace_state, threshold),
+        }
+    }
+}
+
+/// The sampler `OTEL_TRACES_SAMPLER` selects, with its trace ID ratio made consistent when
+/// `consistent` is set.
+pub(crate) fn env_sampler(consistent: bool) -> Box<dyn ShouldSample> {
+    let ratio = || {
+        std::env::var("OTEL_TRACES_SAMPLER_ARG")
+            .ok()
+            .and_then(|arg| arg.parse().ok())
+            // The SDK's fallback for a missing or invalid argument
+            .unwrap_or(1.0)
+    };
+    match std::env::var("OTEL_TRACES_SAMPLER").as_deref() {
+        Ok("traceidratio") if consistent => Box::new(RatioSampler::new(ratio(), true)),
+        Ok("parentbased_traceidratio") if consistent => Box::new(Sampler::ParentBased(Box::new(
+            RatioSampler::new(ratio(), true),
+        ))),
+        _ => Config::default().sampler,
+    }
+}
+
+/// The rejection threshold that keeps `ratio` of traces, or `None` if none are kept.
+fn threshold(ratio: f64) -> Option<u64> {
+    if ratio >= 1.0 {
+        return Some(0);
+    }
+    if ratio.is_nan() || ratio <= 0.0 {
+        return None;
+    }
+    let threshold = ((1.0 - ratio) * MAX_THRESHOLD as f64).round() as u64;
+    (threshold < MAX_THRESHOLD).then_some(threshold)
+}
+
+/// The explicit `rv` randomness from the `ot` tracestate entry.
+fn explicit_randomness(trace_state: &TraceState) -> Option<u64> {
+    trace_state.get(OT_KEY).and_then(|ot| {
+        ot.split(';')
+            .find_map(|field| field.strip_prefix("rv:"))
+            .filter(|rv| rv.len() == 14)
+            .and_then(|rv| u64::from_str_radix(rv, 16).ok())
+    })
+}
+
+/// The trace ID's low 56 bits.
+fn trace_id_randomness(trace_id: TraceId) -> u64 {
+    (u128::from_be_bytes(trace_id.to_bytes()) & u128::from(MAX_THRESHOLD - 1)) as u64
+}
+
+/// `trace_state` with `th` in its `ot` entry set to `threshold`, or removed for `None`.
+/// Other `ot` fields are kept.
+fn with_threshold(trace_state: &TraceState, threshold: Option<u64>) -> TraceState {
+    let threshold = threshold.map(|threshold| {
+        // 14 hex digits with trailing zeros dropped, so 50% is `th:8`
+        let digits = format!("{threshold:014x}");
+        let digits = digits.trim_end_matches('0');
+        if digits.is_empty() { "0" } else { digits }.to_owned()
+    });
+    with_ot_field(trace_state, "th", threshold)
+}
+
+/// `trace_state` with the `key` field of its `ot` entry set to `value`, or removed for
+/// `None`. `th` goes first, as the spec writes it; other fields keep their order.
+fn with_ot_field(trace_state: &TraceState, key: &str, value: Option<String>) -> TraceState {
+    let prefix = format!("{key}:");
+    let mut fields: Vec<String> = trace_state
+        .get(OT_KEY)
+        .unwrap_or_default()
+        .split(';')
+        .filter(|field| !field.is_empty() && !field.starts_with(&prefix))
+        .map(str::to_owned)
+        .collect();
+    if let Some(value) = value {
+        let index = if key == "th" { 0 } else { fields.len() };
+        fields.insert(index, format!("{prefix}{value}"));
+    }
+    let updated = if fields.is_empty() {
+        trace_state.delete(OT_KEY)
+    } else {
+        trace_state.insert(OT_KEY, fields.join(";"))
+    };
+    updated.unwrap_or_else(|_| trace_state.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use opentelemetry::trace::{SpanContext, SpanId, TraceFlags};
+
+    use super::*;
+
+    fn decide(ratio: f64, trace_id: u128, trace_state: &str) -> SamplingResult {
+        decide_with_flags(
+            ratio,
+            trace_id,
+            trace_state,
+            TraceFlags::new(RANDOM_TRACE_ID),
+        )
+    }
+
+    fn decide_with_flags(
+        ratio: f64,
+        trace_id: u128,
+        trace_state: &str,
+        flags: TraceFlags,
+    ) -> SamplingResult {
+        let parent = Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from(trace_id),
+            SpanId::from(1_u64),
+            flags,
+            true,
+            TraceState::from_str(trace_state).unwrap(),
+        ));
+        let parent = (!trace_state.is_empty()).then_some(&parent);
+        RatioSampler::new(ratio, true).should_sample(
+            parent,
+            TraceId::from(trace_id),
+            "GET /orders",
+            &SpanKind::Server,
+            &[],
+            &[],
+        )
+    }
+
+    #[test]
+    fn decisions_compare_trace_randomness_with_the_threshold() {
+        // The low 56 bits are 0x90..: kept at 50% (threshold 0x80..), dropped at 25%
+        let trace_id = 0x0090_0000_0000_0000;
+        let kept = decide(0.5, trace_id, "");
+        assert_eq!(kept.decision, SamplingDecision::RecordAndSample);
+        assert_eq!(kept.trace_state.header(), "ot=th:8;rv:90000000000000");
+        let dropped = decide(0.25, trace_id, "");
+        assert_eq!(dropped.decision, SamplingDecision::Drop);
+        assert_eq!(dropped.trace_state.header(), "ot=rv:90000000000000");
+
+        // Explicit randomness wins over the trace ID, and other entries are kept
+        let kept = decide(0.25, trace_id, "ot=rv:f0000000000000;th:8,vendor=x");
+        assert_eq!(kept.decision, SamplingDecision::RecordAndSample);
+        assert_eq!(
+            kept.trace_state.header(),
+            "ot=th:c;rv:f0000000000000,vendor=x"
+        );
+
+        assert_eq!(
+            decide(1.0, 0, "").trace_state.header(),
+            "ot=th:0;rv:00000000000000"
+        );
+        assert_eq!(decide(0.0, u128::MAX, "").decision, SamplingDecision::Drop);
+    }
+
+    #[test]
+    fn remote_trace_ids_without_the_random_flag_fall_back_to_ratio_sampling() {
+        // Kept at 50% on its low 56 bits, but the remote parent doesn't say they're random:
+        // `TraceIdRatioBased` drops it on the top bit, and the stale `th` goes
+        let trace_id = 0x80f0_0000_0000_0000;
+        let fallback = decide_with_flags(0.5, trace_id, "ot=th:8,vendor=x", TraceFlags::SAMPLED);
+        assert_eq!(fallback.decision, SamplingDecision::Drop);
+        assert_eq!(fallback.trace_state.header(), "vendor=x");
+        let kept = decide_with_flags(0.5, 1, "vendor=x", TraceFlags::SAMPLED);
+        assert_eq!(kept.decision, SamplingDecision::RecordAndSample);
+        assert_eq!(kept.trace_state.header(), "vendor=x");
+
+        // With the flag, or with explicit randomness, the decision stays consistent
+        let random = TraceFlags::new(RANDOM_TRACE_ID | TraceFlags::SAMPLED.to_u8());
+        let consistent = decide_with_flags(0.5, trace_id, "vendor=x", random);
+        assert_eq!(consistent.decision, SamplingDecision::RecordAndSample);
+        assert_eq!(
+            consistent.trace_state.header(),
+            "ot=th:8;rv:f0000000000000,vendor=x"
+        );
+        let explicit =
+            decide_with_flags(0.5, trace_id, "ot=rv:f0000000000000", TraceFlags::SAMPLED);
+        assert_eq!(explicit.trace_state.header(), "ot=th:8;rv:f0000000000000");
+    }
+
+    #[test]
+    fn downstream_services_repeat_the_root_decision() {
+        use std::collections::HashMap;
+
+        use opentelemetry::propagation::TextMapPropagator;
+        use opentelemetry::trace::{Tracer, TracerProvider};
+        use opentelemetry_sdk::propagation::TraceContextPropagator;
+        use opentelemetry_sdk::trace::SdkTracerProvider;
+
+        let provider = SdkTracerProvider::builder()
+            .with_sampler(RatioSampler::new(0.5, true))
+            .build();
+        let tracer = provider.tracer("upstream");
+        let downstream = RatioSampler::new(0.5, true);
+        let mut kept = 0;
+        for _ in 0..64 {
+            let root = Context::new().with_span(tracer.start("GET /orders"));
+            let upstream = root.span().span_context().clone();
+            let mut headers = HashMap::new();
+            TraceContextPropagator::new().inject_context(&root, &mut headers);
+
+            let extracted = TraceContextPropagator::new().extract(&headers);
+            let result = downstream.should_sample(
+                Some(&extracted),
+                upstream.trace_id(),
+                "GET /stock",
+                &SpanKind::Server,
+                &[],
+                &[],
+            );
+            let sampled = result.decision == SamplingDecision::RecordAndSample;
+            assert_eq!(sampled, upstream.is_sampled(), "{headers:?}");
+            kept += usize::from(sampled);
+        }
+        // Both decisions were made, not a constant
+        assert!((1..64).contains(&kept), "{kept} of 64 kept");
+    }
+}
@@ -0,0 +1,161 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "remote-sampling")]
+use std::sync::RwLock;
+
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceContextExt, TraceId};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::{Sampler, ShouldSample};
+
+use crate::samplers::consistent::RatioSampler;
+#[cfg(feature = "remote-sampling")]
+use crate::samplers::remote::RemoteRates;
+
+/// Root sampling ratio set at runtime through
+/// [`OtelGuard::set_sampling_ratio`](crate::OtelGuard::set_sampling_ratio), and the rates
+/// fetched by [remote sampling](crate::OtelOptionsBuilder::remote_sampling).
+#[derive(Debug)]
+pub(crate) struct SamplingOverride {
+    /// The ratio as `f64` bits; NaN while unset.
+    ratio: AtomicU64,
+    #[cfg(feature = "remote-sampling")]
+    remote: RwLock<Option<RemoteRates>>,
+}
+
+impl Default for SamplingOverride {
+    fn default() -> Self {
+        Self {
+            ratio: AtomicU64::new(f64::NAN.to_bits()),
+            #[cfg(feature = "remote-sampling")]
+            remote: RwLock::default(),
+        }
+    }
+}
+
+impl SamplingOverride {
+    /// Set the ratio, clamped to 0.0..=1.0, or clear it with `None` (or NaN).
+    pub(crate) fn set(&self, ratio: Option<f64>) {
+        let ratio = ratio.map_or(f64::NAN, |ratio| ratio.clamp(0.0, 1.0));
+        self.ratio.store(ratio.to_bits(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self) -> Option<f64> {
+        let ratio = f64::from_bits(self.ratio.load(Ordering::Relaxed));
+        (!ratio.is_nan()).then_some(ratio)
+    }
+
+    /// Replace the remote rates, returning whether they changed.
+    #[cfg(feature = "remote-sampling")]
+    pub(crate) fn set_remote(&self, rates: RemoteRates) -> bool {
+        let mut remote = self.remote.write().unwrap_or_else(|e| e.into_inner());
+        let changed = remote.as_ref() != Some(&rates);
+        *remote = Some(rates);
+        changed
+    }
+
+    /// The ratio for a root span named `name`: the guard's if set, then the remote rates.
+    pub(crate) fn root_ratio(&self, name: &str) -> Option<f64> {
+        self.get().or_else(|| self.remote_ratio(name))
+    }
+
+    #[cfg(feature = "remote-sampling")]
+    fn remote_ratio(&self, name: &str) -> Option<f64> {
+        let remote = self.remote.read().unwrap_or_else(|e| e.into_inner());
+        remote.as_ref().map(|rates| rates.ratio(name))
+    }
+
+    #[cfg(not(feature = "remote-sampling"))]
+    fn remote_ratio(&self, _name: &str) -> Option<f64> {
+        None
+    }
+}
+
+/// Uses the [`SamplingOverride`] while it's set and the configured sampler otherwise.
+///
+/// With an override, root spans are sampled by trace ID at its ratio and child spans follow
+/// their parent, so switching mid-trace never cuts a trace in two.
+#[derive(Debug, Clone)]
+pub(crate) struct DynamicSampler {
+    ratio: Arc<SamplingOverride>,
+    configured: Box<dyn ShouldSample>,
+    consistent: bool,
+}
+
+impl DynamicSampler {
+    pub(crate) fn new(
+        ratio: Arc<SamplingOverride>,
+        configured: Box<dyn ShouldSample>,
+        consistent: bool,
+    ) -> Self {
+        Self {
+            ratio,
+            configured,
+            consistent,
+        }
+    }
+}
+
+impl ShouldSample for DynamicSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let Some(ratio) = self.ratio.root_ratio(name) else {
+            return self.configured.should_sample(
+                parent_context,
+                trace_id,
+                name,
+                span_kind,
+                attributes,
+                links,
+            );
+        };
+        let root;
+        let sampler: &dyn ShouldSample = match parent_context.filter(|cx| cx.has_active_span()) {
+            Some(cx) if cx.span().span_context().is_sampled() => &Sampler::AlwaysOn,
+            Some(_) => &Sampler::AlwaysOff,
+            None => {
+                root = RatioSampler::new(ratio, self.consistent);
+                &root
+            }
+        };
+        sampler.should_sample(parent_context, trace_id, name, span_kind, attributes, links)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::SamplingDecision;
+
+    use super::*;
+
+    #[test]
+    fn override_replaces_the_configured_sampler_until_cleared() {
+        let ratio = Arc::new(SamplingOverride::default());
+        let sampler = DynamicSampler::new(Arc::clone(&ratio), Box::new(Sampler::AlwaysOff), false);
+        let decide = || {
+            sampler
+                .should_sample(
+                    None,
+                    TraceId::from(7_u128),
+                    "GET /orders",
+                    &SpanKind::Server,
+                    &[],
+                    &[],
+                )
+                .decision
+        };
+
+        assert_eq!(decide(), SamplingDecision::Drop);
+        ratio.set(Some(2.0));
+        assert_eq!(ratio.get(), Some(1.0));
+        assert_eq!(decide(), SamplingDecision::RecordAndSample);
+        ratio.set(None);
+        assert_eq!(decide(), SamplingDecision::Drop);
+    }
+}
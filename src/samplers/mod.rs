@@ -0,0 +1,6 @@
+pub(crate) mod adaptive;
+pub(crate) mod consistent;
+pub(crate) mod dynamic;
+#[cfg(feature = "remote-sampling")]
+pub(crate) mod remote;
+pub(crate) mod rules;
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use serde_json::Value;
+
+use crate::samplers::dynamic::SamplingOverride;
+
+/// How long one strategy fetch may take, so a hung endpoint can't stall shutdown.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sampling rates from a remote strategy document: one per operation (span name), and a
+/// default for the rest.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RemoteRates {
+    default: f64,
+    operations: HashMap<String, f64>,
+}
+
+impl RemoteRates {
+    /// Parse a strategy in the Jaeger remote sampling format: `probabilisticSampling`,
+    /// optionally with per-operation rates in `operationSampling`.
+    pub(crate) fn parse(body: &str) -> anyhow::Result<Self> {
+        let strategy: Value = serde_json::from_str(body).context("the strategy isn't JSON")?;
+        let rate = |value: &Value| {
+            let rate = value.get("samplingRate")?.as_f64()?;
+            Some(rate.clamp(0.0, 1.0))
+        };
+
+        if let Some(operation_sampling) = strategy.get("operationSampling") {
+            let default = operation_sampling
+                .get("defaultSamplingProbability")
+                .and_then(Value::as_f64)
+                .context("`operationSampling` has no `defaultSamplingProbability`")?;
+            let operations = operation_sampling
+                .get("perOperationStrategies")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|operation| {
+                    let name = operation.get("operation")?.as_str()?;
+                    Some((
+                        name.to_owned(),
+                        rate(operation.get("probabilisticSampling")?)?,
+                    ))
+                })
+                .collect();
+            return Ok(Self {
+                default: default.clamp(0.0, 1.0),
+                operations,
+            });
+        }
+        if let Some(default) = strategy.get("probabilisticSampling").and_then(rate) {
+            return Ok(Self {
+                default,
+                operations: HashMap::new(),
+            });
+        }
+        if strategy.get("rateLimitingSampling").is_some() {
+            bail!("rate limiting strategies aren't supported, only probabilistic ones");
+        }
+        bail!("the strategy has no `probabilisticSampling` or `operationSampling`")
+    }
+
+    pub(crate) fn ratio(&self, operation: &str) -> f64 {
+        self.operations
+            .get(operation)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Background thread that fetches the sampling strategy every `interval` and applies it
+/// to the tracer provider's sampler. The last strategy fetched stays in effect while the
+/// endpoint is unreachable.
+#[derive(Debug)]
+pub(crate) struct RemoteSamplingPoller {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RemoteSamplingPoller {
+    pub(crate) fn start(
+        url: &str,
+        service_name: &str,
+        interval: Duration,
+        target: Arc<SamplingOverride>,
+    ) -> anyhow::Result<Self> {
+        let mut url = url::Url::parse(url).context("Invalid remote sampling URL")?;
+        url.query_pairs_mut().append_pair("service", service_name);
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name("raccoon-otel-sampling".into())
+            .spawn(move || {
+                // Built on this thread, as the blocking client can't be dropped inside an
+                // async runtime
+                let client = match reqwest::blocking::Client::builder()
+                    .timeout(FETCH_TIMEOUT)
+                    .build()
+                {
+                    Ok(client) => client,
+                    Err(e) => {
+                        eprintln!("raccoon-otel: remote sampling disabled: {e}");
+                        return;
+                    }
+                };
+                let mut failing = false;
+                loop {
+                    match fetch(&client, url.as_str()) {
+                        Ok(rates) => {
+                            if target.set_remote(rates) {
+                                eprintln!("raccoon-otel: applied the sampling strategy from {url}");
+                            }
+                            failing = false;
+                        }
+                        Err(e) => {
+                            // Once per outage rather than every interval
+                            if !failing {
+                                eprintln!(
+                                    "raccoon-otel: failed to fetch the sampling strategy from \
+                                     {url}, keeping the current one: {e:#}"
+                                );
+                            }
+                            failing = true;
+                        }
+                    }
+                    // Both a stop message and a dropped sender end the loop
+                    if !matches!(
+                        stopped.recv_timeout(interval),
+                        Err(RecvTimeoutError::Timeout)
+                    ) {
+                        break;
+                    }
+                }
+            })
+            .context("Failed to start the remote sampling thread")?;
+        Ok(Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Stop the thread, waiting for an in-progress fetch to finish.
+    pub(crate) fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for RemoteSamplingPoller {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn fetch(client: &reqwest::blocking::Client, url: &str) -> anyhow::Result<RemoteRates> {
+    let body = client
+        .get(url)
+        .send()
+        .and_then(reqwest::blocking::Response::error_for_status)
+        .and_then(reqwest::blocking::Response::text)?;
+    RemoteRates::parse(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jaeger_strategies_are_parsed() {
+        let rates = RemoteRates::parse(
+            r#"{
+                "strategyType": "PROBABILISTIC",
+                "probabilisticSampling": {"samplingRate": 0.5},
+                "operationSampling": {
+                    "defaultSamplingProbability": 0.1,
+                    "perOperationStrategies": [
+                        {"operation": "GET /healthz", "probabilisticSampling": {"samplingRate": 0}},
+                        {"operation": "POST /checkout", "probabilisticSampling": {"samplingRate": 1}}
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(rates.ratio("GET /healthz"), 0.0);
+        assert_eq!(rates.ratio("POST /checkout"), 1.0);
+        assert_eq!(rates.ratio("GET /orders"), 0.1);
+
+        let rates =
+            RemoteRates::parse(r#"{"probabilisticSampling": {"samplingRate": 0.25}}"#).unwrap();
+        assert_eq!(rates.ratio("anything"), 0.25);
+
+        assert!(
+            RemoteRates::parse(r#"{"rateLimitingSampling": {"maxTracesPerSecond": 5}}"#).is_err()
+        );
+        assert!(RemoteRates::parse("not json").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use opentelemetry::trace::{Link, SamplingResult, SpanKind, TraceContextExt, TraceId};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::ShouldSample;
+
+use crate::samplers::consistent::RatioSampler;
+use crate::span_filter::SpanFilter;
+
+/// Samples root spans at the ratio of the first rule whose filter matches them.
+///
+/// Spans with a parent, and root spans no rule matches, are left to `fallback`, so
+/// traces follow their root's decision when `fallback` is parent-based.
+#[derive(Debug, Clone)]
+pub(crate) struct RuleSampler {
+    rules: Vec<(SpanFilter, RatioSampler)>,
+    fallback: Box<dyn ShouldSample>,
+}
+
+impl RuleSampler {
+    pub(crate) fn new(
+        rules: &[(SpanFilter, f64)],
+        fallback: Box<dyn ShouldSample>,
+        consistent: bool,
+    ) -> Self {
+        let rules = rules
+            .iter()
+            .map(|(filter, ratio)| (filter.clone(), RatioSampler::new(*ratio, consistent)))
+            .collect();
+        Self { rules, fallback }
+    }
+}
+
+impl ShouldSample for RuleSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let is_root = !parent_context.is_some_and(|cx| cx.has_active_span());
+        let sampler = self
+            .rules
+            .iter()
+            .find(|(filter, _)| is_root && filter.matches_parts(name, attributes))
+            .map_or(&*self.fallback, |(_, sampler)| sampler as &dyn ShouldSample);
+        sampler.should_sample(parent_context, trace_id, name, span_kind, attributes, links)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::{SamplingDecision, SpanContext, SpanId, TraceFlags, TraceState};
+    use opentelemetry_sdk::trace::Sampler;
+
+    use super::*;
+
+    fn decide(sampler: &RuleSampler, parent: Option<&Context>, name: &str) -> SamplingDecision {
+        let attributes = [KeyValue::new("http.route", "/orders/{id}")];
+        sampler
+            .should_sample(
+                parent,
+                TraceId::from(42_u128),
+                name,
+                &SpanKind::Server,
+                &attributes,
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn first_matching_rule_decides_for_root_spans() {
+        let sampler = RuleSampler::new(
+            &[
+                (SpanFilter::name("GET /healthz"), 0.0),
+                (SpanFilter::attribute("http.route", "/orders/{id}"), 1.0),
+                (SpanFilter::any(), 0.0),
+            ],
+            Box::new(Sampler::AlwaysOn),
+            false,
+        );
+        assert_eq!(
+            decide(&sampler, None, "GET /healthz"),
+            SamplingDecision::Drop
+        );
+        assert_eq!(
+            decide(&sampler, None, "GET /orders/7"),
+            SamplingDecision::RecordAndSample
+        );
+
+        // Child spans go to the fallback, whatever their name
+        let parent = Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from(42_u128),
+            SpanId::from(1_u64),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        ));
+        assert_eq!(
+            decide(&sampler, Some(&parent), "GET /healthz"),
+            SamplingDecision::RecordAndSample
+        );
+
+        let sampler = RuleSampler::new(
+            &[(SpanFilter::name("POST /checkout"), 1.0)],
+            Box::new(Sampler::AlwaysOff),
+            false,
+        );
+        assert_eq!(
+            decide(&sampler, None, "GET /healthz"),
+            SamplingDecision::Drop
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Typed OpenTelemetry semantic convention attributes.
+//!
+//! Each constant pairs an attribute name from
+//! [`opentelemetry_semantic_conventions`]
+//! with the value type the spec requires, so instrumented code neither hardcodes
+//! attribute names nor drifts when the spec renames them.
+//!
+//! # Examples
+//!
+//! ```
+//! use raccoon_otel::semconv::{db, http};
+//!
+//! let attributes = [
+//!     http::REQUEST_METHOD.value("GET"),
+//!     http::RESPONSE_STATUS_CODE.value(200),
+//!     db::SYSTEM_NAME.value("postgresql"),
+//! ];
+//! assert_eq!(attributes[0].key.as_str(), "http.request.method");
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use opentelemetry::{Key, KeyValue, StringValue, Value};
+use opentelemetry_semantic_conventions::attribute as attr;
+
+/// An attribute name tied to the type of value the semantic conventions allow.
+pub struct Attribute<T> {
+    name: &'static str,
+    _value: PhantomData<fn(T)>,
+}
+
+impl<T> Attribute<T> {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _value: PhantomData,
+        }
+    }
+
+    /// The attribute name, e.g. `"http.request.method"`.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The attribute name as an OTel [`Key`].
+    pub const fn key(&self) -> Key {
+        Key::from_static_str(self.name)
+    }
+}
+
+impl<T: Into<Value>> Attribute<T> {
+    /// Build a [`KeyValue`] for this attribute.
+    pub fn value(&self, value: impl Into<T>) -> KeyValue {
+        KeyValue::new(self.key(), value.into())
+    }
+}
+
+impl<T> Clone for Attribute<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Attribute<T> {}
+
+impl<T> fmt::Debug for Attribute<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Attribute").field(&self.name).finish()
+    }
+}
+
+/// HTTP client and server attributes.
+pub mod http {
+    use super::*;
+
+    /// `http.request.method`, e.g. `"GET"`.
+    pub const REQUEST_METHOD: Attribute<StringValue> = Attribute::new(attr::HTTP_REQUEST_METHOD);
+    /// `http.response.status_code`, e.g. `200`.
+    pub const RESPONSE_STATUS_CODE: Attribute<i64> =
+        Attribute::new(attr::HTTP_RESPONSE_STATUS_CODE);
+    /// `http.route`, the matched route template, e.g. `"/users/{id}"`.
+    pub const ROUTE: Attribute<StringValue> = Attribute::new(attr::HTTP_ROUTE);
+}
+
+/// URL attributes.
+pub mod url {
+    use super::*;
+
+    /// `url.full`, the absolute request URL.
+    pub const FULL: Attribute<StringValue> = Attribute::new(attr::URL_FULL);
+    /// `url.path`, e.g. `"/search"`.
+    pub const PATH: Attribute<StringValue> = Attribute::new(attr::URL_PATH);
+    /// `url.scheme`, e.g. `"https"`.
+    pub const SCHEME: Attribute<StringValue> = Attribute::new(attr::URL_SCHEME);
+}
+
+/// Server (remote peer) attributes.
+pub mod server {
+    use super::*;
+
+    /// `server.address`, host name or IP of the server.
+    pub const ADDRESS: Attribute<StringValue> = Attribute::new(attr::SERVER_ADDRESS);
+    /// `server.port`, e.g. `443`.
+    pub const PORT: Attribute<i64> = Attribute::new(attr::SERVER_PORT);
+}
+
+/// Database client attributes.
+pub mod db {
+    use super::*;
+
+    /// `db.system.name`, e.g. `"postgresql"`.
+    pub const SYSTEM_NAME: Attribute<StringValue> = Attribute::new(attr::DB_SYSTEM_NAME);
+    /// `db.namespace`, the database or schema name.
+    pub const NAMESPACE: Attribute<StringValue> = Attribute::new(attr::DB_NAMESPACE);
+    /// `db.collection.name`, the table or collection.
+    pub const COLLECTION_NAME: Attribute<StringValue> = Attribute::new(attr::DB_COLLECTION_NAME);
+    /// `db.operation.name`, e.g. `"SELECT"`.
+    pub const OPERATION_NAME: Attribute<StringValue> = Attribute::new(attr::DB_OPERATION_NAME);
+    /// `db.query.text`, the (ideally parameterized) query.
+    pub const QUERY_TEXT: Attribute<StringValue> = Attribute::new(attr::DB_QUERY_TEXT);
+}
+
+/// Messaging attributes. These are still experimental in the specification.
+pub mod messaging {
+    use super::*;
+
+    /// `messaging.system`, e.g. `"kafka"`.
+    pub const SYSTEM: Attribute<StringValue> = Attribute::new(attr::MESSAGING_SYSTEM);
+    /// `messaging.operation.type`, e.g. `"send"` or `"process"`.
+    pub const OPERATION_TYPE: Attribute<StringValue> =
+        Attribute::new(attr::MESSAGING_OPERATION_TYPE);
+    /// `messaging.operation.name`, the system-specific operation name.
+    pub const OPERATION_NAME: Attribute<StringValue> =
+        Attribute::new(attr::MESSAGING_OPERATION_NAME);
+    /// `messaging.destination.name`, the queue or topic.
+    pub const DESTINATION_NAME: Attribute<StringValue> =
+        Attribute::new(attr::MESSAGING_DESTINATION_NAME);
+}
+
+/// Error attributes.
+pub mod error {
+    use super::*;
+
+    /// `error.type`, e.g. `"timeout"` or an exception type name.
+    pub const TYPE: Attribute<StringValue> = Attribute::new(attr::ERROR_TYPE);
+}
+
+/// Attribute names from semantic conventions before the stable HTTP, network, database and
+/// code conventions, with the names that replaced them, for
+/// [`OtelOptionsBuilder::rename_attributes`](crate::OtelOptionsBuilder::rename_attributes):
+///
+/// ```
+/// use raccoon_otel::{semconv, OtelOptions};
+///
+/// let options = OtelOptions::builder()
+///     .rename_attributes(semconv::LEGACY_RENAMES.iter().copied())
+///     .build();
+/// ```
+///
+/// Only one-to-one renames are listed. Attributes whose meaning was split or depends on the
+/// span kind, such as `http.target` or `net.peer.name`, aren't.
+pub const LEGACY_RENAMES: &[(&str, &str)] = &[
+    ("http.method", "http.request.method"),
+    ("http.status_code", "http.response.status_code"),
+    ("http.url", "url.full"),
+    ("http.scheme", "url.scheme"),
+    ("http.user_agent", "user_agent.original"),
+    ("http.request_content_length", "http.request.body.size"),
+    ("http.response_content_length", "http.response.body.size"),
+    ("net.protocol.name", "network.protocol.name"),
+    ("net.protocol.version", "network.protocol.version"),
+    ("net.sock.peer.addr", "network.peer.address"),
+    ("net.sock.peer.port", "network.peer.port"),
+    ("net.host.name", "server.address"),
+    ("net.host.port", "server.port"),
+    ("db.system", "db.system.name"),
+    ("db.name", "db.namespace"),
+    ("db.operation", "db.operation.name"),
+    ("db.statement", "db.query.text"),
+    ("code.filepath", "code.file.path"),
+    ("code.lineno", "code.line.number"),
+    ("code.function", "code.function.name"),
+];
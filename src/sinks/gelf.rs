@@ -0,0 +1,397 @@
+//! GELF (Graylog Extended Log Format) output for tracing events.
+
+/// Transport used to ship GELF messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GelfTransport {
+    /// GELF over UDP; messages larger than one datagram are chunked.
+    Udp,
+    /// GELF over TCP; messages are null-byte delimited.
+    Tcp,
+}
+
+/// Destination for the optional GELF sink. Requires the `gelf` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use raccoon_otel::{GelfOptions, OtelOptions};
+///
+/// let options = OtelOptions::builder()
+///     .gelf(GelfOptions::udp("graylog:12201").host("web-01"))
+///     .build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GelfOptions {
+    pub(crate) address: String,
+    pub(crate) transport: GelfTransport,
+    pub(crate) host: Option<String>,
+}
+
+impl GelfOptions {
+    /// Send GELF messages over UDP to `address` (e.g. `"graylog:12201"`).
+    pub fn udp(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            transport: GelfTransport::Udp,
+            host: None,
+        }
+    }
+
+    /// Send GELF messages over TCP to `address` (e.g. `"graylog:12201"`).
+    pub fn tcp(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            transport: GelfTransport::Tcp,
+            host: None,
+        }
+    }
+
+    /// Set the GELF `host` field. Defaults to `$HOSTNAME`, then the service name.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+}
+
+#[cfg(feature = "gelf")]
+pub(crate) use layer::GelfLayer;
+
+#[cfg(feature = "gelf")]
+mod layer {
+    use std::fmt;
+    use std::io::{self, Write};
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+    use std::sync::mpsc::{self, Receiver, SyncSender};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use serde_json::{Map, Value};
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Level, Subscriber};
+    use tracing_opentelemetry::OtelData;
+    use tracing_subscriber::layer::Context;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    use super::{GelfOptions, GelfTransport};
+
+    const QUEUE_CAPACITY: usize = 1024;
+    const CHUNK_SIZE: usize = 8192;
+    const CHUNK_HEADER_LEN: usize = 12;
+    const MAX_CHUNKS: usize = 128;
+
+    /// Tracing layer that formats events as GELF 1.1 and ships them from a background thread.
+    ///
+    /// Messages are dropped (never blocking the caller) when the queue is full.
+    pub(crate) struct GelfLayer {
+        host: String,
+        sender: SyncSender<Vec<u8>>,
+    }
+
+    impl GelfLayer {
+        pub(crate) fn new(options: &GelfOptions, service_name: &str) -> anyhow::Result<Self> {
+            let host = super::super::resolve_host(options.host.as_deref(), service_name);
+
+            let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+            let address = options.address.clone();
+            let transport = options.transport;
+            std::thread::Builder::new()
+                .name("raccoon-otel-gelf".into())
+                .spawn(move || run_sender(&address, transport, receiver))?;
+
+            Ok(Self { host, sender })
+        }
+    }
+
+    impl<S> Layer<S> for GelfLayer
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or_default();
+
+            let mut doc = Map::new();
+            doc.insert("version".into(), "1.1".into());
+            doc.insert("host".into(), self.host.clone().into());
+            doc.insert("timestamp".into(), timestamp.into());
+            doc.insert(
+                "level".into(),
+                syslog_level(event.metadata().level()).into(),
+            );
+            doc.insert("_target".into(), event.metadata().target().into());
+
+            if let Some(span) = ctx.event_span(event) {
+                if let Some(otel) = span.extensions().get::<OtelData>() {
+                    if let (Some(trace_id), Some(span_id)) = (otel.trace_id(), otel.span_id()) {
+                        doc.insert("_trace_id".into(), trace_id.to_string().into());
+                        doc.insert("_span_id".into(), span_id.to_string().into());
+                    }
+                }
+            }
+
+            event.record(&mut GelfVisitor(&mut doc));
+            doc.entry("short_message").or_insert_with(|| "".into());
+
+            if let Ok(payload) = serde_json::to_vec(&doc) {
+                let _ = self.sender.try_send(payload);
+            }
+        }
+    }
+
+    /// Map tracing levels to the syslog severities GELF uses.
+    fn syslog_level(level: &Level) -> u8 {
+        match *level {
+            Level::ERROR => 3,
+            Level::WARN => 4,
+            Level::INFO => 6,
+            Level::DEBUG | Level::TRACE => 7,
+        }
+    }
+
+    struct GelfVisitor<'a>(&'a mut Map<String, Value>);
+
+    impl GelfVisitor<'_> {
+        fn insert(&mut self, field: &Field, value: Value) {
+            let key = match field.name() {
+                "message" => "short_message".to_owned(),
+                // GELF reserves `_id`; additional fields must be prefixed with `_`
+                "id" => "_field_id".to_owned(),
+                name => format!("_{name}"),
+            };
+            self.0.insert(key, value);
+        }
+    }
+
+    impl Visit for GelfVisitor<'_> {
+        fn record_bool(&mut self, field: &Field, value: bool) {
+            self.insert(field, value.into());
+        }
+
+        fn record_i64(&mut self, field: &Field, value: i64) {
+            self.insert(field, value.into());
+        }
+
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            self.insert(field, value.into());
+        }
+
+        fn record_f64(&mut self, field: &Field, value: f64) {
+            self.insert(field, value.into());
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.insert(field, value.into());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.insert(field, format!("{value:?}").into());
+        }
+    }
+
+    fn run_sender(address: &str, transport: GelfTransport, receiver: Receiver<Vec<u8>>) {
+        match transport {
+            GelfTransport::Udp => {
+                let socket = match connect_udp(address) {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        eprintln!(
+                            "raccoon-otel: GELF sink could not reach {address} over UDP: {e}"
+                        );
+                        return;
+                    }
+                };
+                let mut message_id: u64 = rand_seed();
+                for payload in receiver {
+                    message_id = message_id.wrapping_add(1);
+                    send_udp(&socket, &payload, message_id);
+                }
+            }
+            GelfTransport::Tcp => {
+                let mut stream: Option<TcpStream> = None;
+                for mut payload in receiver {
+                    payload.push(0);
+                    if stream.is_none() {
+                        stream = TcpStream::connect(address).ok();
+                    }
+                    if let Some(ref mut s) = stream {
+                        if s.write_all(&payload).is_err() {
+                            // Reconnect on the next message
+                            stream = None;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// A UDP socket connected to `address`, so it's resolved once rather than per datagram.
+    fn connect_udp(address: &str) -> io::Result<UdpSocket> {
+        let target = address.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "address resolved to nothing")
+        })?;
+        let local: SocketAddr = if target.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(target)?;
+        Ok(socket)
+    }
+
+    fn send_udp(socket: &UdpSocket, payload: &[u8], message_id: u64) {
+        if payload.len() <= CHUNK_SIZE {
+            let _ = socket.send(payload);
+            return;
+        }
+        for datagram in chunked(payload, message_id).unwrap_or_default() {
+            let _ = socket.send(&datagram);
+        }
+    }
+
+    /// `payload` split into GELF chunks, or `None` if it needs more than `MAX_CHUNKS`.
+    fn chunked(payload: &[u8], message_id: u64) -> Option<Vec<Vec<u8>>> {
+        let chunks: Vec<&[u8]> = payload.chunks(CHUNK_SIZE - CHUNK_HEADER_LEN).collect();
+        if chunks.len() > MAX_CHUNKS {
+            return None;
+        }
+        let datagrams = chunks
+            .iter()
+            .enumerate()
+            .map(|(seq, chunk)| {
+                let mut datagram = Vec::with_capacity(CHUNK_HEADER_LEN + chunk.len());
+                datagram.extend_from_slice(&[0x1e, 0x0f]);
+                datagram.extend_from_slice(&message_id.to_be_bytes());
+                datagram.push(seq as u8);
+                datagram.push(chunks.len() as u8);
+                datagram.extend_from_slice(chunk);
+                datagram
+            })
+            .collect();
+        Some(datagrams)
+    }
+
+    fn rand_seed() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default()
+            ^ u64::from(std::process::id())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::io::Read;
+        use std::net::TcpListener;
+        use std::time::Duration;
+
+        use tracing_subscriber::layer::SubscriberExt;
+
+        use super::*;
+
+        #[test]
+        fn levels_map_to_syslog_severities() {
+            assert_eq!(syslog_level(&Level::ERROR), 3);
+            assert_eq!(syslog_level(&Level::WARN), 4);
+            assert_eq!(syslog_level(&Level::INFO), 6);
+            assert_eq!(syslog_level(&Level::DEBUG), 7);
+            assert_eq!(syslog_level(&Level::TRACE), 7);
+        }
+
+        #[test]
+        fn events_become_gelf_documents_with_prefixed_fields() {
+            let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+            let layer = GelfLayer {
+                host: "web-01".into(),
+                sender,
+            };
+            let subscriber = tracing_subscriber::registry().with(layer);
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::warn!(id = 7, order = "A-1", retry = true, "payment declined");
+            });
+
+            let doc: Map<String, Value> =
+                serde_json::from_slice(&receiver.recv().unwrap()).unwrap();
+            assert_eq!(doc["version"], "1.1");
+            assert_eq!(doc["host"], "web-01");
+            assert_eq!(doc["level"], 4);
+            assert_eq!(doc["short_message"], "payment declined");
+            assert_eq!(doc["_field_id"], 7);
+            assert_eq!(doc["_order"], "A-1");
+            assert_eq!(doc["_retry"], true);
+            assert!(doc.contains_key("_target"));
+            assert!(!doc.contains_key("message") && !doc.contains_key("id"));
+        }
+
+        #[test]
+        fn large_payloads_are_chunked_with_gelf_headers() {
+            let payload = vec![b'x'; 2 * CHUNK_SIZE];
+            let datagrams = chunked(&payload, 0x0102_0304_0506_0708).unwrap();
+            assert_eq!(datagrams.len(), 3);
+            for (seq, datagram) in datagrams.iter().enumerate() {
+                assert_eq!(datagram[..2], [0x1e, 0x0f]);
+                assert_eq!(datagram[2..10], [1, 2, 3, 4, 5, 6, 7, 8]);
+                assert_eq!(datagram[10], seq as u8);
+                assert_eq!(datagram[11], 3);
+                assert!(datagram.len() <= CHUNK_SIZE);
+            }
+            let body: Vec<u8> = datagrams
+                .iter()
+                .flat_map(|datagram| datagram[CHUNK_HEADER_LEN..].to_vec())
+                .collect();
+            assert_eq!(body, payload);
+        }
+
+        #[test]
+        fn payloads_needing_too_many_chunks_are_dropped() {
+            let per_chunk = CHUNK_SIZE - CHUNK_HEADER_LEN;
+            assert!(chunked(&vec![0; MAX_CHUNKS * per_chunk], 1).is_some());
+            assert!(chunked(&vec![0; MAX_CHUNKS * per_chunk + 1], 1).is_none());
+        }
+
+        #[test]
+        fn udp_sends_small_payloads_whole_and_large_ones_chunked() {
+            let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+            server
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let address = server.local_addr().unwrap().to_string();
+            let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+            sender.send(b"{}".to_vec()).unwrap();
+            sender.send(vec![b'x'; CHUNK_SIZE + 1]).unwrap();
+            drop(sender);
+            run_sender(&address, GelfTransport::Udp, receiver);
+
+            let mut buf = [0; CHUNK_SIZE];
+            let len = server.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], b"{}");
+            for seq in 0..2 {
+                let len = server.recv(&mut buf).unwrap();
+                assert_eq!(buf[..2], [0x1e, 0x0f]);
+                assert_eq!((buf[10], buf[11]), (seq, 2));
+                assert!(len > CHUNK_HEADER_LEN);
+            }
+        }
+
+        #[test]
+        fn tcp_messages_are_null_delimited() {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+            sender.send(br#"{"a":1}"#.to_vec()).unwrap();
+            sender.send(br#"{"b":2}"#.to_vec()).unwrap();
+            drop(sender);
+            let sending = std::thread::spawn(move || {
+                run_sender(&address, GelfTransport::Tcp, receiver);
+            });
+
+            let (mut stream, _) = listener.accept().unwrap();
+            sending.join().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            assert_eq!(received, b"{\"a\":1}\0{\"b\":2}\0");
+        }
+    }
+}
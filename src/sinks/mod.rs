@@ -0,0 +1,13 @@
+pub(crate) mod gelf;
+pub(crate) mod statsd;
+pub(crate) mod syslog;
+
+/// Resolve the host name reported by a sink: explicit value, then `$HOSTNAME`,
+/// then the service name.
+#[cfg_attr(not(any(feature = "gelf", feature = "syslog")), allow(dead_code))]
+pub(crate) fn resolve_host(explicit: Option<&str>, service_name: &str) -> String {
+    explicit
+        .map(str::to_owned)
+        .or_else(|| std::env::var("HOSTNAME").ok().filter(|h| !h.is_empty()))
+        .unwrap_or_else(|| service_name.to_owned())
+}
@@ -0,0 +1,345 @@
+//! StatsD / DogStatsD output for OTel metrics.
+
+use std::path::PathBuf;
+
+/// Transport used to reach the StatsD agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatsdTransport {
+    /// UDP datagrams to `host:port`.
+    Udp(String),
+    /// Datagrams on a Unix domain socket (Unix only).
+    Unix(PathBuf),
+}
+
+/// Destination for metrics sent to a StatsD or DogStatsD agent instead of OTLP.
+/// Requires the `statsd` feature.
+///
+/// Metrics are exported with delta temporality:
+/// - counters become `|c` counts
+/// - up/down counters become signed `|g` gauge deltas
+/// - gauges become `|g` gauges
+/// - histograms become `.count` and `.sum` counts plus `.min` / `.max` gauges
+///
+/// # Examples
+///
+/// ```no_run
+/// use raccoon_otel::{OtelOptions, StatsdOptions};
+///
+/// let options = OtelOptions::builder()
+///     .statsd(StatsdOptions::udp("127.0.0.1:8125").prefix("checkout"))
+///     .build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsdOptions {
+    pub(crate) transport: StatsdTransport,
+    pub(crate) prefix: Option<String>,
+    pub(crate) tags: bool,
+}
+
+impl StatsdOptions {
+    /// Send metrics over UDP to `address` (e.g. `"127.0.0.1:8125"`).
+    pub fn udp(address: impl Into<String>) -> Self {
+        Self {
+            transport: StatsdTransport::Udp(address.into()),
+            prefix: None,
+            tags: true,
+        }
+    }
+
+    /// Send metrics to a Unix domain datagram socket (e.g. `/var/run/datadog/dsd.socket`).
+    pub fn unix(path: impl Into<PathBuf>) -> Self {
+        Self {
+            transport: StatsdTransport::Unix(path.into()),
+            prefix: None,
+            tags: true,
+        }
+    }
+
+    /// Prepend `prefix.` to every metric name.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Omit DogStatsD `|#key:value` tags for agents that only speak plain StatsD.
+    /// Metric attributes are dropped in that case.
+    pub fn plain(mut self) -> Self {
+        self.tags = false;
+        self
+    }
+}
+
+#[cfg(feature = "statsd")]
+pub(crate) use exporter::StatsdExporter;
+
+#[cfg(feature = "statsd")]
+mod exporter {
+    use std::fmt::{Display, Write as _};
+    use std::net::UdpSocket;
+    #[cfg(unix)]
+    use std::os::unix::net::UnixDatagram;
+    use std::time::Duration;
+
+    use anyhow::Context as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData, ResourceMetrics};
+    use opentelemetry_sdk::metrics::exporter::PushMetricExporter;
+    use opentelemetry_sdk::metrics::Temporality;
+
+    use super::{StatsdOptions, StatsdTransport};
+
+    /// Largest UDP payload that avoids fragmentation on a typical 1500-byte MTU.
+    const UDP_PACKET_SIZE: usize = 1432;
+    /// DogStatsD's default buffer size for Unix domain sockets.
+    const UNIX_PACKET_SIZE: usize = 8192;
+
+    enum Socket {
+        Udp(UdpSocket),
+        #[cfg(unix)]
+        Unix(UnixDatagram),
+    }
+
+    /// Push exporter that writes metrics as StatsD lines, packed into datagrams.
+    pub(crate) struct StatsdExporter {
+        socket: Socket,
+        prefix: Option<String>,
+        tags: bool,
+    }
+
+    impl std::fmt::Debug for StatsdExporter {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("StatsdExporter").finish_non_exhaustive()
+        }
+    }
+
+    impl StatsdExporter {
+        pub(crate) fn new(options: &StatsdOptions) -> anyhow::Result<Self> {
+            let socket = match options.transport {
+                StatsdTransport::Udp(ref address) => {
+                    let socket =
+                        UdpSocket::bind("0.0.0.0:0").context("Failed to bind UDP socket")?;
+                    socket
+                        .connect(address)
+                        .with_context(|| format!("Failed to resolve StatsD address {address}"))?;
+                    Socket::Udp(socket)
+                }
+                #[cfg(unix)]
+                StatsdTransport::Unix(ref path) => {
+                    let socket = UnixDatagram::unbound().context("Failed to create Unix socket")?;
+                    socket.connect(path).with_context(|| {
+                        format!("Failed to connect to StatsD socket {}", path.display())
+                    })?;
+                    Socket::Unix(socket)
+                }
+                #[cfg(not(unix))]
+                StatsdTransport::Unix(_) => {
+                    anyhow::bail!("StatsD over Unix domain sockets is only supported on Unix")
+                }
+            };
+
+            Ok(Self {
+                socket,
+                prefix: options.prefix.clone(),
+                tags: options.tags,
+            })
+        }
+
+        fn packet_size(&self) -> usize {
+            match self.socket {
+                Socket::Udp(_) => UDP_PACKET_SIZE,
+                #[cfg(unix)]
+                Socket::Unix(_) => UNIX_PACKET_SIZE,
+            }
+        }
+
+        fn send(&self, packet: &str) -> std::io::Result<usize> {
+            match self.socket {
+                Socket::Udp(ref socket) => socket.send(packet.as_bytes()),
+                #[cfg(unix)]
+                Socket::Unix(ref socket) => socket.send(packet.as_bytes()),
+            }
+        }
+
+        /// Format every data point in `metrics` as one StatsD line.
+        fn lines(&self, metrics: &ResourceMetrics) -> Vec<String> {
+            let mut lines = Vec::new();
+            for scope in metrics.scope_metrics() {
+                for metric in scope.metrics() {
+                    let name = self.metric_name(metric.name());
+                    match metric.data() {
+                        AggregatedMetrics::F64(data) => self.push_data(&mut lines, &name, data),
+                        AggregatedMetrics::U64(data) => self.push_data(&mut lines, &name, data),
+                        AggregatedMetrics::I64(data) => self.push_data(&mut lines, &name, data),
+                    }
+                }
+            }
+            lines
+        }
+
+        fn push_data<T>(&self, lines: &mut Vec<String>, name: &str, data: &MetricData<T>)
+        where
+            T: Display + Copy,
+        {
+            match data {
+                MetricData::Gauge(gauge) => {
+                    for point in gauge.data_points() {
+                        let tags = self.tags(point.attributes());
+                        lines.push(line(name, point.value(), "g", &tags));
+                    }
+                }
+                MetricData::Sum(sum) => {
+                    for point in sum.data_points() {
+                        let tags = self.tags(point.attributes());
+                        if sum.is_monotonic() {
+                            lines.push(line(name, point.value(), "c", &tags));
+                        } else {
+                            // Signed gauge values are applied as deltas by StatsD
+                            lines.push(line(name, signed(point.value()), "g", &tags));
+                        }
+                    }
+                }
+                MetricData::Histogram(histogram) => {
+                    for point in histogram.data_points() {
+                        let tags = self.tags(point.attributes());
+                        push_summary(lines, name, &tags, point.count(), point.sum());
+                        push_min_max(lines, name, &tags, point.min(), point.max());
+                    }
+                }
+                MetricData::ExponentialHistogram(histogram) => {
+                    for point in histogram.data_points() {
+                        let tags = self.tags(point.attributes());
+                        push_summary(lines, name, &tags, point.count(), point.sum());
+                        push_min_max(lines, name, &tags, point.min(), point.max());
+                    }
+                }
+            }
+        }
+
+        /// Format attributes as a DogStatsD `|#key:value,...` suffix (empty if disabled).
+        fn tags<'a>(&self, attributes: impl Iterator<Item = &'a KeyValue>) -> String {
+            let mut tags = String::new();
+            if !self.tags {
+                return tags;
+            }
+            for kv in attributes {
+                tags.push_str(if tags.is_empty() { "|#" } else { "," });
+                let _ = write!(
+                    tags,
+                    "{}:{}",
+                    sanitize(kv.key.as_str()),
+                    sanitize(&kv.value.as_str())
+                );
+            }
+            tags
+        }
+
+        fn metric_name(&self, name: &str) -> String {
+            match self.prefix {
+                Some(ref prefix) => format!("{}.{}", sanitize(prefix), sanitize(name)),
+                None => sanitize(name),
+            }
+        }
+    }
+
+    impl PushMetricExporter for StatsdExporter {
+        async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+            for packet in pack(self.lines(metrics), self.packet_size()) {
+                // StatsD is fire-and-forget; an unreachable agent shouldn't fail the reader
+                let _ = self.send(&packet);
+            }
+            Ok(())
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _timeout: Duration) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn temporality(&self) -> Temporality {
+            Temporality::Delta
+        }
+    }
+
+    fn line(name: &str, value: impl Display, kind: &str, tags: &str) -> String {
+        format!("{name}:{value}|{kind}{tags}")
+    }
+
+    fn push_summary(
+        lines: &mut Vec<String>,
+        name: &str,
+        tags: &str,
+        count: impl Display,
+        sum: impl Display,
+    ) {
+        lines.push(line(&format!("{name}.count"), count, "c", tags));
+        lines.push(line(&format!("{name}.sum"), sum, "c", tags));
+    }
+
+    fn push_min_max<T: Display>(
+        lines: &mut Vec<String>,
+        name: &str,
+        tags: &str,
+        min: Option<T>,
+        max: Option<T>,
+    ) {
+        if let Some(min) = min {
+            lines.push(line(&format!("{name}.min"), min, "g", tags));
+        }
+        if let Some(max) = max {
+            lines.push(line(&format!("{name}.max"), max, "g", tags));
+        }
+    }
+
+    fn signed(value: impl Display) -> String {
+        let value = value.to_string();
+        if value.starts_with('-') {
+            value
+        } else {
+            format!("+{value}")
+        }
+    }
+
+    /// Replace characters that are part of the StatsD line syntax.
+    fn sanitize(s: &str) -> String {
+        s.replace([':', '|', '@', ',', '#', '\n'], "_")
+    }
+
+    /// Join `lines` with newlines into packets of at most `max` bytes.
+    fn pack(lines: Vec<String>, max: usize) -> Vec<String> {
+        let mut packets = Vec::new();
+        let mut packet = String::new();
+        for line in lines {
+            if !packet.is_empty() && packet.len() + 1 + line.len() > max {
+                packets.push(std::mem::take(&mut packet));
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            packets.push(packet);
+        }
+        packets
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn lines_are_packed_up_to_the_size_limit() {
+            let lines = vec!["a:1|c".to_owned(), "b:2|c".to_owned(), "c:3|c".to_owned()];
+            assert_eq!(pack(lines, 11), vec!["a:1|c\nb:2|c", "c:3|c"]);
+        }
+
+        #[test]
+        fn syntax_characters_are_replaced() {
+            assert_eq!(sanitize("http:route|x#y"), "http_route_x_y");
+        }
+    }
+}
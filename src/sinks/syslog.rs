@@ -0,0 +1,389 @@
+//! RFC 5424 syslog output for tracing events.
+
+use std::path::PathBuf;
+
+/// Transport used to ship syslog messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyslogTransport {
+    /// UDP datagrams to `host:port` (RFC 5426).
+    Udp(String),
+    /// TCP with octet-counting framing to `host:port` (RFC 6587).
+    Tcp(String),
+    /// Unix datagram socket, e.g. `/dev/log` (Unix only).
+    Unix(PathBuf),
+}
+
+/// Syslog facility stamped on every message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyslogFacility {
+    /// `user` (1) — default.
+    #[default]
+    User,
+    /// `daemon` (3).
+    Daemon,
+    /// `local0` (16) through `local7` (23).
+    Local(u8),
+}
+
+#[cfg(feature = "syslog")]
+impl SyslogFacility {
+    fn code(self) -> u8 {
+        match self {
+            SyslogFacility::User => 1,
+            SyslogFacility::Daemon => 3,
+            SyslogFacility::Local(n) => 16 + n.min(7),
+        }
+    }
+}
+
+/// Destination for the optional syslog sink. Requires the `syslog` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use raccoon_otel::{OtelOptions, SyslogFacility, SyslogOptions};
+///
+/// let options = OtelOptions::builder()
+///     .syslog(SyslogOptions::udp("syslog.local:514").facility(SyslogFacility::Local(0)))
+///     .build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyslogOptions {
+    pub(crate) transport: SyslogTransport,
+    pub(crate) facility: SyslogFacility,
+    pub(crate) host: Option<String>,
+}
+
+impl SyslogOptions {
+    /// Send messages over UDP to `address` (e.g. `"syslog.local:514"`).
+    pub fn udp(address: impl Into<String>) -> Self {
+        Self::new(SyslogTransport::Udp(address.into()))
+    }
+
+    /// Send messages over TCP to `address` (e.g. `"syslog.local:601"`).
+    pub fn tcp(address: impl Into<String>) -> Self {
+        Self::new(SyslogTransport::Tcp(address.into()))
+    }
+
+    /// Send messages to a Unix datagram socket (e.g. `"/dev/log"`).
+    pub fn unix(path: impl Into<PathBuf>) -> Self {
+        Self::new(SyslogTransport::Unix(path.into()))
+    }
+
+    fn new(transport: SyslogTransport) -> Self {
+        Self {
+            transport,
+            facility: SyslogFacility::default(),
+            host: None,
+        }
+    }
+
+    /// Set the syslog facility.
+    pub fn facility(mut self, facility: SyslogFacility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Set the HOSTNAME header field. Defaults to `$HOSTNAME`, then the service name.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+}
+
+#[cfg(feature = "syslog")]
+pub(crate) use layer::SyslogLayer;
+
+#[cfg(feature = "syslog")]
+mod layer {
+    use std::fmt::{self, Write as _};
+    use std::io::Write as _;
+    use std::net::{TcpStream, UdpSocket};
+    use std::sync::mpsc::{self, Receiver, SyncSender};
+
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Level, Subscriber};
+    use tracing_opentelemetry::OtelData;
+    use tracing_subscriber::fmt::format::Writer;
+    use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+    use tracing_subscriber::layer::Context;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    use super::{SyslogOptions, SyslogTransport};
+
+    const QUEUE_CAPACITY: usize = 1024;
+    // 32473 is the private enterprise number reserved for documentation (RFC 5612)
+    const SD_ID: &str = "fields@32473";
+
+    /// Tracing layer that formats events as RFC 5424 messages and ships them from a
+    /// background thread. Messages are dropped (never blocking the caller) when the
+    /// queue is full.
+    pub(crate) struct SyslogLayer {
+        header: Header,
+        sender: SyncSender<Vec<u8>>,
+    }
+
+    struct Header {
+        facility: u8,
+        host: String,
+        app_name: String,
+        proc_id: u32,
+    }
+
+    impl SyslogLayer {
+        pub(crate) fn new(options: &SyslogOptions, service_name: &str) -> anyhow::Result<Self> {
+            let header = Header {
+                facility: options.facility.code(),
+                host: header_value(&super::super::resolve_host(
+                    options.host.as_deref(),
+                    service_name,
+                )),
+                app_name: header_value(service_name),
+                proc_id: std::process::id(),
+            };
+
+            let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+            let transport = options.transport.clone();
+            std::thread::Builder::new()
+                .name("raccoon-otel-syslog".into())
+                .spawn(move || run_sender(transport, receiver))?;
+
+            Ok(Self { header, sender })
+        }
+    }
+
+    impl<S> Layer<S> for SyslogLayer
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let mut visitor = SyslogVisitor::default();
+            event.record(&mut visitor);
+
+            if let Some(span) = ctx.event_span(event) {
+                if let Some(otel) = span.extensions().get::<OtelData>() {
+                    if let (Some(trace_id), Some(span_id)) = (otel.trace_id(), otel.span_id()) {
+                        visitor
+                            .params
+                            .push(("trace_id".into(), trace_id.to_string()));
+                        visitor.params.push(("span_id".into(), span_id.to_string()));
+                    }
+                }
+            }
+
+            let mut timestamp = String::new();
+            if SystemTime
+                .format_time(&mut Writer::new(&mut timestamp))
+                .is_err()
+            {
+                timestamp = "-".into();
+            }
+
+            let line = format_message(
+                &self.header,
+                severity(event.metadata().level()),
+                &timestamp,
+                &visitor,
+            );
+            let _ = self.sender.try_send(line.into_bytes());
+        }
+    }
+
+    /// Map tracing levels to syslog severities.
+    fn severity(level: &Level) -> u8 {
+        match *level {
+            Level::ERROR => 3,
+            Level::WARN => 4,
+            Level::INFO => 6,
+            Level::DEBUG | Level::TRACE => 7,
+        }
+    }
+
+    fn format_message(
+        header: &Header,
+        severity: u8,
+        timestamp: &str,
+        visitor: &SyslogVisitor,
+    ) -> String {
+        let pri = u16::from(header.facility) * 8 + u16::from(severity);
+        let mut line = format!(
+            "<{pri}>1 {timestamp} {} {} {} - ",
+            header.host, header.app_name, header.proc_id
+        );
+
+        if visitor.params.is_empty() {
+            line.push('-');
+        } else {
+            let _ = write!(line, "[{SD_ID}");
+            for (name, value) in &visitor.params {
+                let _ = write!(line, " {}=\"{}\"", param_name(name), escape_param(value));
+            }
+            line.push(']');
+        }
+
+        if !visitor.message.is_empty() {
+            line.push(' ');
+            line.push_str(&visitor.message);
+        }
+        line
+    }
+
+    /// HOSTNAME / APP-NAME: printable ASCII without spaces, `-` when empty.
+    fn header_value(value: &str) -> String {
+        let sanitized: String = value
+            .chars()
+            .filter(|c| c.is_ascii_graphic())
+            .take(48)
+            .collect();
+        if sanitized.is_empty() {
+            "-".into()
+        } else {
+            sanitized
+        }
+    }
+
+    /// PARAM-NAME: up to 32 printable ASCII characters, excluding `=`, `]`, `"`.
+    fn param_name(name: &str) -> String {
+        name.chars()
+            .filter(|c| c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"'))
+            .take(32)
+            .collect()
+    }
+
+    /// PARAM-VALUE: `"`, `\`, and `]` must be escaped.
+    fn escape_param(value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if matches!(c, '"' | '\\' | ']') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    #[derive(Default)]
+    struct SyslogVisitor {
+        message: String,
+        params: Vec<(String, String)>,
+    }
+
+    impl Visit for SyslogVisitor {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.message = value.to_owned();
+            } else {
+                self.params
+                    .push((field.name().to_owned(), value.to_owned()));
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if field.name() == "message" {
+                self.message = format!("{value:?}");
+            } else {
+                self.params
+                    .push((field.name().to_owned(), format!("{value:?}")));
+            }
+        }
+    }
+
+    fn run_sender(transport: SyslogTransport, receiver: Receiver<Vec<u8>>) {
+        match transport {
+            SyslogTransport::Udp(address) => {
+                let socket = match UdpSocket::bind("0.0.0.0:0") {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        eprintln!("raccoon-otel: syslog sink could not bind a UDP socket: {e}");
+                        return;
+                    }
+                };
+                for payload in receiver {
+                    let _ = socket.send_to(&payload, &address);
+                }
+            }
+            SyslogTransport::Tcp(address) => {
+                let mut stream: Option<TcpStream> = None;
+                for payload in receiver {
+                    if stream.is_none() {
+                        stream = TcpStream::connect(&address).ok();
+                    }
+                    if let Some(ref mut s) = stream {
+                        let mut framed = format!("{} ", payload.len()).into_bytes();
+                        framed.extend_from_slice(&payload);
+                        if s.write_all(&framed).is_err() {
+                            // Reconnect on the next message
+                            stream = None;
+                        }
+                    }
+                }
+            }
+            SyslogTransport::Unix(path) => {
+                #[cfg(unix)]
+                {
+                    let socket = match std::os::unix::net::UnixDatagram::unbound() {
+                        Ok(socket) => socket,
+                        Err(e) => {
+                            eprintln!("raccoon-otel: syslog sink could not create a socket: {e}");
+                            return;
+                        }
+                    };
+                    for payload in receiver {
+                        let _ = socket.send_to(&payload, &path);
+                    }
+                }
+                #[cfg(not(unix))]
+                {
+                    eprintln!(
+                        "raccoon-otel: syslog Unix socket {} is not supported on this platform",
+                        path.display()
+                    );
+                    drop(receiver);
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn header() -> Header {
+            Header {
+                facility: 1,
+                host: "web-01".into(),
+                app_name: "my-service".into(),
+                proc_id: 42,
+            }
+        }
+
+        #[test]
+        fn formats_rfc5424_with_structured_data() {
+            let visitor = SyslogVisitor {
+                message: "Order failed".into(),
+                params: vec![("order_id".into(), "a\"b]".into())],
+            };
+
+            let line = format_message(&header(), 3, "2024-01-01T00:00:00Z", &visitor);
+
+            assert_eq!(
+                line,
+                "<11>1 2024-01-01T00:00:00Z web-01 my-service 42 - \
+                 [fields@32473 order_id=\"a\\\"b\\]\"] Order failed"
+            );
+        }
+
+        #[test]
+        fn uses_nil_structured_data_without_fields() {
+            let visitor = SyslogVisitor {
+                message: "hi".into(),
+                params: Vec::new(),
+            };
+
+            let line = format_message(&header(), 6, "-", &visitor);
+
+            assert_eq!(line, "<14>1 - web-01 my-service 42 - - hi");
+        }
+    }
+}
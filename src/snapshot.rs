@@ -0,0 +1,133 @@
+use std::fmt::Write as _;
+use std::sync::atomic::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::health::ExportHealth;
+use crate::stats::{ExportStats, StatsCounters};
+
+/// Point-in-time view of the export pipeline, for an application's admin or debug endpoint.
+///
+/// Obtained from [`OtelGuard::snapshot`](crate::OtelGuard::snapshot). Gives operators the
+/// state of the pipeline (what is waiting, what got through, what went wrong) without
+/// access to the telemetry backend. [`to_json`](Self::to_json) renders it for a
+/// `/debug/telemetry` style endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PipelineSnapshot {
+    /// Same as [`HealthStatus::healthy`](crate::HealthStatus::healthy).
+    pub healthy: bool,
+    /// Spans waiting in the batch processor's queue.
+    pub spans_queued: usize,
+    /// Log records waiting in the batch processor's queue.
+    pub logs_queued: usize,
+    /// Span batches exported successfully.
+    pub span_batches_exported: u64,
+    /// Log batches exported successfully.
+    pub log_batches_exported: u64,
+    /// When a span or log export last finished, successfully or not.
+    pub last_export: Option<SystemTime>,
+    /// The error from the most recent failed export.
+    pub last_error: Option<String>,
+    /// Telemetry dropped instead of exported.
+    pub dropped: ExportStats,
+}
+
+impl PipelineSnapshot {
+    pub(crate) fn new(stats: &StatsCounters, health: &ExportHealth) -> Self {
+        let status = health.snapshot();
+        Self {
+            healthy: status.healthy,
+            spans_queued: stats.spans_queued.load(Ordering::Relaxed),
+            logs_queued: stats.logs_queued.load(Ordering::Relaxed),
+            span_batches_exported: health.span_batches_exported(),
+            log_batches_exported: health.log_batches_exported(),
+            last_export: status.last_success.max(status.last_failure),
+            last_error: status.last_error,
+            dropped: stats.snapshot(),
+        }
+    }
+
+    /// The snapshot as a JSON object, with `last_export` in milliseconds since the Unix
+    /// epoch (or `null`).
+    ///
+    /// ```
+    /// let guard = raccoon_otel::OtelGuard::noop();
+    /// let json = guard.snapshot().to_json();
+    /// assert!(json.starts_with(r#"{"healthy":true,"spans_queued":0,"#));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let last_export = self
+            .last_export
+            .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+            .map_or_else(|| "null".to_owned(), |d| d.as_millis().to_string());
+        let last_error = self
+            .last_error
+            .as_deref()
+            .map_or_else(|| "null".to_owned(), json_string);
+        let dropped = &self.dropped;
+        format!(
+            "{{\"healthy\":{},\"spans_queued\":{},\"logs_queued\":{},\
+             \"span_batches_exported\":{},\"log_batches_exported\":{},\
+             \"last_export\":{last_export},\"last_error\":{last_error},\
+             \"dropped\":{{\"spans_over_budget\":{},\"logs_over_budget\":{},\
+             \"spans_queue_full\":{},\"logs_queue_full\":{}}}}}",
+            self.healthy,
+            self.spans_queued,
+            self.logs_queued,
+            self.span_batches_exported,
+            self.log_batches_exported,
+            dropped.spans_dropped_over_budget,
+            dropped.logs_dropped_over_budget,
+            dropped.spans_dropped_queue_full,
+            dropped.logs_dropped_queue_full,
+        )
+    }
+}
+
+/// Quote and escape `value` as a JSON string.
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_reports_queue_depth_batches_and_errors() {
+        let stats = StatsCounters::default();
+        let health = ExportHealth::default();
+        stats.spans_queued.store(12, Ordering::Relaxed);
+        health.record::<&str>("spans", Ok(()));
+        health.record::<&str>("logs", Ok(()));
+        health.record("logs", Err(&"status 503: \"unavailable\""));
+
+        let snapshot = PipelineSnapshot::new(&stats, &health);
+        assert_eq!(snapshot.spans_queued, 12);
+        assert_eq!(snapshot.span_batches_exported, 1);
+        assert_eq!(snapshot.log_batches_exported, 1);
+        assert!(snapshot.last_export.is_some());
+
+        let json = snapshot.to_json();
+        assert!(json.contains(r#""last_error":"logs export failed: status 503: \"unavailable\"""#));
+        assert!(json.ends_with(
+            r#""dropped":{"spans_over_budget":0,"logs_over_budget":0,"spans_queue_full":0,"logs_queue_full":0}}"#
+        ));
+    }
+}
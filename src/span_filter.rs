@@ -0,0 +1,67 @@
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_sdk::trace::SpanData;
+
+/// A rule matching spans by name or attribute.
+///
+/// Used with [`OtelOptionsBuilder::drop_spans_matching`](crate::OtelOptionsBuilder::drop_spans_matching)
+/// to keep infrastructure noise (health checks, readiness probes) out of the backend, and
+/// with [`span_kind`](crate::OtelOptionsBuilder::span_kind) and
+/// [`sampling_rule`](crate::OtelOptionsBuilder::sampling_rule).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanFilter {
+    rule: Rule,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Rule {
+    Any,
+    NamePrefix(String),
+    Name(String),
+    Attribute(String, String),
+}
+
+impl SpanFilter {
+    /// Match every span, e.g. as the last, catch-all sampling rule.
+    pub fn any() -> Self {
+        Self { rule: Rule::Any }
+    }
+
+    /// Match spans whose name starts with `prefix` (e.g. `"GET /healthz"`).
+    pub fn name_prefix(prefix: impl Into<String>) -> Self {
+        Self {
+            rule: Rule::NamePrefix(prefix.into()),
+        }
+    }
+
+    /// Match spans whose name is exactly `name`.
+    pub fn name(name: impl Into<String>) -> Self {
+        Self {
+            rule: Rule::Name(name.into()),
+        }
+    }
+
+    /// Match spans carrying a string attribute `key` equal to `value`
+    /// (e.g. `("url.path", "/readyz")`).
+    pub fn attribute(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            rule: Rule::Attribute(key.into(), value.into()),
+        }
+    }
+
+    pub(crate) fn matches(&self, span: &SpanData) -> bool {
+        self.matches_parts(&span.name, &span.attributes)
+    }
+
+    /// Match a span that hasn't started yet, by the name and attributes a sampler sees.
+    pub(crate) fn matches_parts(&self, name: &str, attributes: &[KeyValue]) -> bool {
+        match &self.rule {
+            Rule::Any => true,
+            Rule::NamePrefix(prefix) => name.starts_with(prefix.as_str()),
+            Rule::Name(expected) => name == expected.as_str(),
+            Rule::Attribute(key, value) => attributes.iter().any(|kv| {
+                kv.key.as_str() == key
+                    && matches!(&kv.value, Value::String(s) if s.as_str() == value)
+            }),
+        }
+    }
+}
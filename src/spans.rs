@@ -0,0 +1,131 @@
+//! Constructors for spans of common operations, pre-populated with semantic convention
+//! attributes and the matching span kind.
+//!
+//! Each helper returns an INFO-level [`tracing::Span`] whose OTel name follows the
+//! conventions for that operation (via the `otel.name` field). Fields that are only known
+//! later, such as the response status, are declared empty and can be filled in with
+//! [`Span::record`]:
+//!
+//! ```
+//! use raccoon_otel::spans;
+//!
+//! let span = spans::http_client_span("GET", "https://api.example.com/users");
+//! let _enter = span.enter();
+//! // ... send the request ...
+//! span.record("http.response.status_code", 200);
+//! ```
+//!
+//! Field names match the constants in [`semconv`](crate::semconv).
+
+use tracing::field::Empty;
+use tracing::Span;
+
+/// Span for an outgoing HTTP request (`client` kind), named after the method.
+///
+/// Recordable later: `http.response.status_code`, `error.type`, `otel.status_code`.
+pub fn http_client_span(method: &str, url: &str) -> Span {
+    tracing::info_span!(
+        "http.client",
+        otel.name = method,
+        otel.kind = "client",
+        http.request.method = method,
+        url.full = url,
+        http.response.status_code = Empty,
+        error.type = Empty,
+        otel.status_code = Empty,
+    )
+}
+
+/// Span for an incoming HTTP request (`server` kind), named `"{method} {route}"`.
+///
+/// `route` should be the low-cardinality route template (e.g. `/users/{id}`), not the
+/// raw path. Recordable later: `http.response.status_code`, `error.type`,
+/// `otel.status_code`.
+pub fn http_server_span(method: &str, route: &str) -> Span {
+    tracing::info_span!(
+        "http.server",
+        otel.name = format!("{method} {route}"),
+        otel.kind = "server",
+        http.request.method = method,
+        http.route = route,
+        http.response.status_code = Empty,
+        error.type = Empty,
+        otel.status_code = Empty,
+    )
+}
+
+/// Span for a database call (`client` kind).
+///
+/// `system` is the `db.system.name` value (e.g. `"postgresql"`). The operation name is
+/// taken from the statement's leading keyword (`SELECT`, `INSERT`, ...) and used as the
+/// span name, falling back to `system`. Pass parameterized statements only; the text is
+/// exported as-is. Recordable later: `db.namespace`, `db.collection.name`, `error.type`,
+/// `otel.status_code`.
+pub fn db_span(system: &str, statement: &str) -> Span {
+    let operation = db_operation(statement);
+    tracing::info_span!(
+        "db.query",
+        otel.name = operation.unwrap_or(system),
+        otel.kind = "client",
+        db.system.name = system,
+        db.query.text = statement,
+        db.operation.name = operation,
+        db.namespace = Empty,
+        db.collection.name = Empty,
+        error.type = Empty,
+        otel.status_code = Empty,
+    )
+}
+
+/// Span for publishing a message (`producer` kind), named `"send {destination}"`.
+pub fn messaging_producer_span(system: &str, destination: &str) -> Span {
+    tracing::info_span!(
+        "messaging.send",
+        otel.name = format!("send {destination}"),
+        otel.kind = "producer",
+        messaging.system = system,
+        messaging.operation.type = "send",
+        messaging.destination.name = destination,
+        error.type = Empty,
+        otel.status_code = Empty,
+    )
+}
+
+/// Span for handling a received message (`consumer` kind), named `"process {destination}"`.
+pub fn messaging_consumer_span(system: &str, destination: &str) -> Span {
+    tracing::info_span!(
+        "messaging.process",
+        otel.name = format!("process {destination}"),
+        otel.kind = "consumer",
+        messaging.system = system,
+        messaging.operation.type = "process",
+        messaging.destination.name = destination,
+        error.type = Empty,
+        otel.status_code = Empty,
+    )
+}
+
+/// The statement's leading SQL keyword, upper-cased, if it is a well-known operation.
+fn db_operation(statement: &str) -> Option<&'static str> {
+    const OPERATIONS: &[&str] = &[
+        "SELECT", "INSERT", "UPDATE", "DELETE", "UPSERT", "MERGE", "CREATE", "ALTER", "DROP",
+        "TRUNCATE", "CALL", "BEGIN", "COMMIT", "ROLLBACK",
+    ];
+    let keyword = statement.split_whitespace().next()?;
+    OPERATIONS
+        .iter()
+        .find(|op| op.eq_ignore_ascii_case(keyword))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn db_operation_is_the_leading_keyword() {
+        assert_eq!(db_operation("  select * from users"), Some("SELECT"));
+        assert_eq!(db_operation("WITH x AS (SELECT 1) SELECT * FROM x"), None);
+        assert_eq!(db_operation(""), None);
+    }
+}
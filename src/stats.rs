@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Counters describing telemetry the pipeline dropped instead of exporting.
+///
+/// Obtained from [`OtelGuard::stats`](crate::OtelGuard::stats). Counts are cumulative
+/// since the pipeline was built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExportStats {
+    /// Spans dropped because the export budget was exhausted.
+    pub spans_dropped_over_budget: u64,
+    /// Log records dropped because the export budget was exhausted.
+    pub logs_dropped_over_budget: u64,
+    /// Spans dropped because the batch processor's queue was full.
+    pub spans_dropped_queue_full: u64,
+    /// Log records dropped because the batch processor's queue was full.
+    pub logs_dropped_queue_full: u64,
+}
+
+/// Spans and log records handled by a pipeline in
+/// [`benchmark_mode`](crate::OtelOptionsBuilder::benchmark_mode).
+///
+/// Obtained from [`OtelGuard::throughput_report`](crate::OtelGuard::throughput_report).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ThroughputReport {
+    /// Spans that reached the counting sink.
+    pub spans: u64,
+    /// Log records that reached the counting sink.
+    pub logs: u64,
+    /// Spans and log records dropped because a batch processor's queue was full.
+    pub dropped: u64,
+    /// Time since the pipeline was built.
+    pub elapsed: Duration,
+}
+
+impl ThroughputReport {
+    /// Spans per second over [`elapsed`](Self::elapsed).
+    pub fn spans_per_second(&self) -> f64 {
+        per_second(self.spans, self.elapsed)
+    }
+
+    /// Log records per second over [`elapsed`](Self::elapsed).
+    pub fn logs_per_second(&self) -> f64 {
+        per_second(self.logs, self.elapsed)
+    }
+}
+
+fn per_second(count: u64, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+    count as f64 / elapsed.as_secs_f64()
+}
+
+/// The signal a per-signal counter belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Signal {
+    Spans,
+    Logs,
+}
+
+impl Signal {
+    /// The signal's name in messages, e.g. "dropped 3 spans".
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Signal::Spans => "spans",
+            Signal::Logs => "logs",
+        }
+    }
+}
+
+/// Live counters shared between the exporters and the guard.
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    pub(crate) spans_dropped_over_budget: AtomicU64,
+    pub(crate) logs_dropped_over_budget: AtomicU64,
+    pub(crate) spans_dropped_queue_full: AtomicU64,
+    pub(crate) logs_dropped_queue_full: AtomicU64,
+    // Current batch processor queue depths, maintained by the queue monitors
+    pub(crate) spans_queued: AtomicUsize,
+    pub(crate) logs_queued: AtomicUsize,
+    // Items that reached the counting sink in benchmark mode
+    pub(crate) spans_exported: AtomicU64,
+    pub(crate) logs_exported: AtomicU64,
+}
+
+impl StatsCounters {
+    /// `signal`'s current batch processor queue depth.
+    pub(crate) fn queued(&self, signal: Signal) -> &AtomicUsize {
+        match signal {
+            Signal::Spans => &self.spans_queued,
+            Signal::Logs => &self.logs_queued,
+        }
+    }
+
+    /// `signal`'s items dropped because the export budget ran out.
+    pub(crate) fn dropped_over_budget(&self, signal: Signal) -> &AtomicU64 {
+        match signal {
+            Signal::Spans => &self.spans_dropped_over_budget,
+            Signal::Logs => &self.logs_dropped_over_budget,
+        }
+    }
+
+    /// `signal`'s items dropped because its batch processor queue was full.
+    pub(crate) fn dropped_queue_full(&self, signal: Signal) -> &AtomicU64 {
+        match signal {
+            Signal::Spans => &self.spans_dropped_queue_full,
+            Signal::Logs => &self.logs_dropped_queue_full,
+        }
+    }
+
+    pub(crate) fn throughput(&self, elapsed: Duration) -> ThroughputReport {
+        ThroughputReport {
+            spans: self.spans_exported.load(Ordering::Relaxed),
+            logs: self.logs_exported.load(Ordering::Relaxed),
+            dropped: self.spans_dropped_queue_full.load(Ordering::Relaxed)
+                + self.logs_dropped_queue_full.load(Ordering::Relaxed),
+            elapsed,
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> ExportStats {
+        ExportStats {
+            spans_dropped_over_budget: self.spans_dropped_over_budget.load(Ordering::Relaxed),
+            logs_dropped_over_budget: self.logs_dropped_over_budget.load(Ordering::Relaxed),
+            spans_dropped_queue_full: self.spans_dropped_queue_full.load(Ordering::Relaxed),
+            logs_dropped_queue_full: self.logs_dropped_queue_full.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -1,43 +1,246 @@
-use opentelemetry_sdk::logs::SdkLoggerProvider;
-use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::sync::Arc;
+
+use tracing::Subscriber;
+use tracing_subscriber::layer::Identity;
 use tracing_subscriber::prelude::*;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::env::ResolvedConfig;
+use crate::guard::OtelGuard;
+use crate::layers::bridge::OtelBridgeLayer;
+use crate::layers::offload::OffloadLogLayer;
+use crate::layers::typed_fields::TypedFieldsLayer;
+use crate::options::ConsoleFormat;
 
 /// Compose and globally register a tracing subscriber with OTel layers.
 ///
 /// Layers added:
+/// - layers added with `with_extra_layer`, in order, innermost
 /// - [`EnvFilter`] — respects `RUST_LOG` / `OTEL_LOG_LEVEL` env vars (defaults to `info`)
-/// - `fmt` — formatted output to stdout
+/// - `fmt` — formatted output to stdout (text or ECS JSON, per [`ConsoleFormat`])
+/// - `GelfLayer` — ships events to Graylog (if configured)
+/// - `SyslogLayer` — ships events to a syslog receiver (if configured)
 /// - `OpenTelemetryLayer` — bridges tracing spans to OTel traces (if tracer provider given)
-/// - `OpenTelemetryTracingBridge` — bridges tracing events to OTel logs (if logger provider given)
+/// - `TypedFieldsLayer` — wraps the trace layer, keeping unsigned/128-bit integer span fields numeric
+/// - `ProfilingLayer` — records which span runs on each thread for profile samples (if profiling)
+/// - `OpenTelemetryTracingBridge` — bridges tracing events to OTel logs (if logger provider given),
+///   or `OffloadLogLayer`, which emits them from a background thread, with `async_bridge`
+/// - `MetricsLayer` — turns `monotonic_counter.*` / `counter.*` / `histogram.*` event fields
+///   into OTel metrics (if meter provider given)
+/// - `EventCountLayer` — increments a counter for events matching `count_events` rules
+///   (if meter provider given)
 ///
 /// # Errors
 ///
 /// Returns an error if the global subscriber has already been set.
-pub(crate) fn compose_subscriber(
-    tracer_provider: Option<&SdkTracerProvider>,
-    logger_provider: Option<&SdkLoggerProvider>,
-) -> anyhow::Result<()> {
-    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+pub(crate) fn compose_subscriber(guard: &OtelGuard, config: &ResolvedConfig) -> anyhow::Result<()> {
+    // Innermost, as extra layers are written against the bare `Registry`
+    let subscriber = tracing_subscriber::registry()
+        .with(config.extra_layers.take())
+        .with(env_filter())
+        .with(fmt_layer(config)?)
+        .with(gelf_layer(config)?)
+        .with(syslog_layer(config)?)
+        .with(otel_layer(guard, config));
 
-    let fmt_layer = tracing_subscriber::fmt::layer().with_target(true);
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| anyhow::anyhow!("Failed to set global subscriber: {e}"))?;
 
-    let otel_trace_layer = tracer_provider.map(|tp| {
-        use opentelemetry::trace::TracerProvider as _;
-        tracing_opentelemetry::layer().with_tracer(tp.tracer("raccoon-otel"))
-    });
+    Ok(())
+}
 
-    let otel_log_layer =
-        logger_provider.map(opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge::new);
+/// The [`EnvFilter`] from `RUST_LOG`, or `info` when it is unset or invalid.
+pub(crate) fn env_filter() -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))
+}
 
+/// Globally register a subscriber with only the `fmt` layer (and the [`EnvFilter`]).
+///
+/// # Errors
+///
+/// Returns an error if the global subscriber has already been set.
+pub(crate) fn compose_noop_subscriber() -> anyhow::Result<()> {
     let subscriber = tracing_subscriber::registry()
-        .with(env_filter)
-        .with(fmt_layer)
-        .with(otel_trace_layer)
-        .with(otel_log_layer);
+        .with(env_filter())
+        .with(tracing_subscriber::fmt::layer().with_target(true));
 
     tracing::subscriber::set_global_default(subscriber)
         .map_err(|e| anyhow::anyhow!("Failed to set global subscriber: {e}"))?;
 
     Ok(())
 }
+
+/// Build the console output layer for the configured [`ConsoleFormat`].
+///
+/// # Errors
+///
+/// Returns an error if the format requires a feature that is not enabled.
+fn fmt_layer<S>(config: &ResolvedConfig) -> anyhow::Result<Box<dyn Layer<S> + Send + Sync>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    match config.console_format {
+        ConsoleFormat::Text => Ok(tracing_subscriber::fmt::layer().with_target(true).boxed()),
+        ConsoleFormat::Ecs => {
+            #[cfg(feature = "json")]
+            {
+                let format = crate::layers::ecs::EcsFormat::new(config.service_name.clone());
+                Ok(tracing_subscriber::fmt::layer()
+                    .event_format(format)
+                    .boxed())
+            }
+            #[cfg(not(feature = "json"))]
+            {
+                anyhow::bail!(
+                    "ECS console format requested but the `json` feature is not enabled. \
+                     Enable it in Cargo.toml: raccoon-otel = {{ features = [\"json\"] }}"
+                );
+            }
+        }
+    }
+}
+
+/// Build the GELF sink layer, if configured.
+///
+/// # Errors
+///
+/// Returns an error if the `gelf` feature is not enabled or the sink fails to start.
+fn gelf_layer<S>(config: &ResolvedConfig) -> anyhow::Result<Option<Box<dyn Layer<S> + Send + Sync>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    let Some(ref gelf) = config.gelf else {
+        return Ok(None);
+    };
+
+    #[cfg(feature = "gelf")]
+    {
+        let layer = crate::sinks::gelf::GelfLayer::new(gelf, &config.service_name)?;
+        Ok(Some(layer.boxed()))
+    }
+    #[cfg(not(feature = "gelf"))]
+    {
+        let _ = gelf;
+        anyhow::bail!(
+            "GELF sink requested but the `gelf` feature is not enabled. \
+             Enable it in Cargo.toml: raccoon-otel = {{ features = [\"gelf\"] }}"
+        );
+    }
+}
+
+/// Build the syslog sink layer, if configured.
+///
+/// # Errors
+///
+/// Returns an error if the `syslog` feature is not enabled or the sink fails to start.
+fn syslog_layer<S>(
+    config: &ResolvedConfig,
+) -> anyhow::Result<Option<Box<dyn Layer<S> + Send + Sync>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    let Some(ref syslog) = config.syslog else {
+        return Ok(None);
+    };
+
+    #[cfg(feature = "syslog")]
+    {
+        let layer = crate::sinks::syslog::SyslogLayer::new(syslog, &config.service_name)?;
+        Ok(Some(layer.boxed()))
+    }
+    #[cfg(not(feature = "syslog"))]
+    {
+        let _ = syslog;
+        anyhow::bail!(
+            "syslog sink requested but the `syslog` feature is not enabled. \
+             Enable it in Cargo.toml: raccoon-otel = {{ features = [\"syslog\"] }}"
+        );
+    }
+}
+
+/// Build the OTel bridge layers (traces, logs, then metrics) for the guard's providers,
+/// followed by the event counters.
+///
+/// Each bridge is omitted when the guard has no provider for its signal.
+pub(crate) fn otel_layer<S>(
+    guard: &OtelGuard,
+    config: &ResolvedConfig,
+) -> impl Layer<S> + Send + Sync + 'static
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    let otel_trace_layer = guard.tracer_provider().map(|tp| {
+        use opentelemetry::trace::TracerProvider as _;
+        let layer = TypedFieldsLayer::new(
+            tracing_opentelemetry::layer()
+                .with_tracer(tp.tracer("raccoon-otel"))
+                // Set by `ThreadAttributesSpanProcessor` without allocating the name per span
+                .with_threads(false),
+            config.large_integer_mode,
+        );
+        let process_parent = config
+            .inherit_trace_context
+            .then(crate::propagation::process_parent)
+            .flatten();
+        let clock = config
+            .monotonic_timestamps
+            .then(crate::processors::monotonic_clock::MonotonicClock::start);
+        OtelBridgeLayer::new(layer, config.skip_unsampled_spans, process_parent, clock)
+    });
+    // Needs the span IDs from the trace layer
+    #[cfg(all(feature = "profiling", unix))]
+    let profiling_layer = guard
+        .profiler()
+        .filter(|_| guard.tracer_provider().is_some())
+        .map(|profiler| crate::profiling::timeline::ProfilingLayer::new(profiler.timeline()));
+    #[cfg(not(all(feature = "profiling", unix)))]
+    let profiling_layer: Option<Identity> = None;
+
+    let otel_log_layer = guard.logger_provider().map(|lp| match guard.log_offload() {
+        Some(offload) => {
+            use opentelemetry::logs::LoggerProvider as _;
+            OffloadLogLayer::new(
+                lp.logger(""),
+                crate::providers::logger::caller_log_transforms(config),
+                Arc::clone(offload),
+            )
+            .boxed()
+        }
+        None => opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge::new(lp).boxed(),
+    });
+
+    #[cfg(feature = "metrics")]
+    let otel_metrics_layer = guard
+        .meter_provider()
+        .map(|mp| tracing_opentelemetry::MetricsLayer::new(mp.clone()));
+    #[cfg(not(feature = "metrics"))]
+    let otel_metrics_layer: Option<Identity> = None;
+
+    #[cfg(feature = "metrics")]
+    let event_count_layer = guard
+        .meter_provider()
+        .filter(|_| !config.event_counters.is_empty())
+        .map(|mp| crate::layers::event_counts::EventCountLayer::new(mp, &config.event_counters));
+    #[cfg(not(feature = "metrics"))]
+    let event_count_layer: Option<Identity> = None;
+
+    or_identity(otel_trace_layer)
+        .and_then(or_identity(profiling_layer))
+        .and_then(or_identity(otel_log_layer))
+        .and_then(or_identity(otel_metrics_layer))
+        .and_then(or_identity(event_count_layer))
+}
+
+/// `layer`, or a layer that does nothing.
+///
+/// A `None` layer reports `OFF` as its max level, which wins over the bridges' lack of a
+/// hint and turns the whole stack off when no filter sets one (e.g. an
+/// [`OtelPipeline::layer`](crate::OtelPipeline::layer) on a bare registry).
+fn or_identity<S, L>(layer: Option<L>) -> Box<dyn Layer<S> + Send + Sync + 'static>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    L: Layer<S> + Send + Sync + 'static,
+{
+    layer.map_or_else(|| Identity::new().boxed(), Layer::boxed)
+}
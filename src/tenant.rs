@@ -0,0 +1,128 @@
+//! Per-tenant stamping and routing of telemetry for multi-tenant services.
+
+use std::sync::Arc;
+
+use opentelemetry::baggage::BaggageExt;
+use opentelemetry::Context;
+
+use crate::options::Callback;
+
+pub(crate) type TenantFn = dyn Fn() -> Option<String> + Send + Sync;
+
+const DEFAULT_TENANT_ATTRIBUTE: &str = "tenant.id";
+
+#[derive(Debug, Clone)]
+pub(crate) enum TenantSource {
+    Baggage(String),
+    Fn(Callback<TenantFn>),
+}
+
+/// Where to find the current tenant, used with
+/// [`OtelOptionsBuilder::tenant`](crate::OtelOptionsBuilder::tenant).
+///
+/// The tenant is stamped as an attribute (`tenant.id` by default) on every span when it
+/// starts and on every log record when it is emitted, so backends can filter or segregate
+/// telemetry per customer.
+///
+/// # Examples
+///
+/// ```no_run
+/// use raccoon_otel::{OtelOptions, TenantOptions};
+///
+/// tokio::task_local! {
+///     static TENANT: String;
+/// }
+///
+/// let options = OtelOptions::builder()
+///     .tenant(
+///         TenantOptions::from_fn(|| TENANT.try_with(Clone::clone).ok())
+///             .route_header("X-Scope-OrgID"),
+///     )
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TenantOptions {
+    pub(crate) source: TenantSource,
+    pub(crate) attribute: String,
+    pub(crate) route_header: Option<String>,
+}
+
+impl TenantOptions {
+    /// Read the tenant from the OpenTelemetry baggage entry `key` of the current context,
+    /// e.g. one propagated from an upstream service.
+    pub fn from_baggage(key: impl Into<String>) -> Self {
+        Self::new(TenantSource::Baggage(key.into()))
+    }
+
+    /// Read the tenant by calling `tenant`, e.g. to read a task-local or thread-local.
+    /// Return `None` when there is no current tenant.
+    pub fn from_fn<F>(tenant: F) -> Self
+    where
+        F: Fn() -> Option<String> + Send + Sync + 'static,
+    {
+        Self::new(TenantSource::Fn(Callback(Arc::new(tenant))))
+    }
+
+    fn new(source: TenantSource) -> Self {
+        Self {
+            source,
+            attribute: DEFAULT_TENANT_ATTRIBUTE.to_owned(),
+            route_header: None,
+        }
+    }
+
+    /// Set the attribute the tenant is stored in (default: `tenant.id`).
+    pub fn attribute(mut self, key: impl Into<String>) -> Self {
+        self.attribute = key.into();
+        self
+    }
+
+    /// Export each tenant's telemetry in separate requests carrying the tenant in header
+    /// `name` (e.g. `X-Scope-OrgID` for Grafana Tempo and Loki). Telemetry without a tenant
+    /// is exported as usual.
+    ///
+    /// Each tenant gets its own exporter, created on its first batch. Not used with
+    /// [`Protocol::File`](crate::Protocol::File).
+    pub fn route_header(mut self, name: impl Into<String>) -> Self {
+        self.route_header = Some(name.into());
+        self
+    }
+
+    /// The current tenant, looking at `cx` before the current context for baggage.
+    pub(crate) fn current(&self, cx: &Context) -> Option<String> {
+        match self.source {
+            TenantSource::Baggage(ref key) => cx
+                .baggage()
+                .get(key.as_str())
+                .map(|value| value.to_string())
+                .or_else(|| {
+                    Context::map_current(|current| {
+                        current.baggage().get(key.as_str()).map(|v| v.to_string())
+                    })
+                }),
+            TenantSource::Fn(ref tenant) => (tenant.0)(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::KeyValue;
+
+    use super::*;
+
+    #[test]
+    fn tenant_is_read_from_baggage_or_callback() {
+        let baggage = TenantOptions::from_baggage("tenant");
+        let cx = Context::current_with_baggage([KeyValue::new("tenant", "acme")]);
+        assert_eq!(baggage.current(&cx).as_deref(), Some("acme"));
+        assert_eq!(baggage.current(&Context::new()), None);
+
+        let _attached = cx.attach();
+        assert_eq!(baggage.current(&Context::new()).as_deref(), Some("acme"));
+
+        let callback = TenantOptions::from_fn(|| Some("globex".to_owned())).attribute("org.id");
+        assert_eq!(callback.current(&Context::new()).as_deref(), Some("globex"));
+        assert_eq!(callback.attribute, "org.id");
+    }
+}
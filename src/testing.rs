@@ -0,0 +1,373 @@
+//! Deterministic trace IDs and timestamps, and span assertions, for tests.
+//!
+//! Plug a [`SequentialIdGenerator`] and a [`TestClock`] into the tracer provider via
+//! [`OtelOptionsBuilder::id_generator`](crate::OtelOptionsBuilder::id_generator) and
+//! [`OtelOptionsBuilder::test_clock`](crate::OtelOptionsBuilder::test_clock), and every run
+//! exports the same OTLP payload, so it can be compared against a golden file.
+//!
+//! ```no_run
+//! use std::time::{Duration, SystemTime};
+//! use raccoon_otel::testing::{SequentialIdGenerator, TestClock};
+//! use raccoon_otel::OtelOptions;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let clock = TestClock::new(SystemTime::UNIX_EPOCH);
+//! let _guard = raccoon_otel::setup_otel("my-service", Some(
+//!     OtelOptions::builder()
+//!         .id_generator(SequentialIdGenerator::new())
+//!         .test_clock(clock.clone())
+//!         .build()
+//! ))?;
+//!
+//! let span = tracing::info_span!("work").entered();
+//! clock.advance(Duration::from_millis(5));
+//! drop(span);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To check instrumentation directly, capture finished spans with a [`SpanCapture`] and
+//! assert on them with [`assert_span_exists!`](crate::assert_span_exists) and
+//! [`assert_span_parent!`](crate::assert_span_parent):
+//!
+//! ```no_run
+//! use raccoon_otel::testing::SpanCapture;
+//! use raccoon_otel::{assert_span_exists, assert_span_parent, OtelOptions};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let spans = SpanCapture::new();
+//! let _guard = raccoon_otel::setup_otel("my-service", Some(
+//!     OtelOptions::builder().capture_spans(spans.clone()).build()
+//! ))?;
+//!
+//! {
+//!     let _order = tracing::info_span!("process_order", order_id = "order-123").entered();
+//!     tracing::info_span!("charge_card").in_scope(|| {});
+//! }
+//!
+//! assert_span_exists!(spans, name = "process_order", attr("order_id", "order-123"));
+//! assert_span_parent!(spans, child = "charge_card", parent = "process_order");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requires the `test-util` feature.
+
+use std::fmt;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use opentelemetry::trace::{SpanId, TraceId};
+use opentelemetry_sdk::trace::{IdGenerator, SpanData};
+
+/// A manually advanced clock for span timestamps.
+///
+/// Clones share the same time, so keep one in the test and hand a clone to the pipeline.
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    // Nanoseconds since the Unix epoch
+    nanos: Arc<AtomicU64>,
+}
+
+impl TestClock {
+    /// Create a clock frozen at `start`.
+    pub fn new(start: SystemTime) -> Self {
+        let nanos = start
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        Self {
+            nanos: Arc::new(AtomicU64::new(u64::try_from(nanos).unwrap_or(u64::MAX))),
+        }
+    }
+
+    /// Move the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        let by = u64::try_from(by.as_nanos()).unwrap_or(u64::MAX);
+        self.nanos.fetch_add(by, Ordering::Relaxed);
+    }
+
+    /// The current time of the clock.
+    pub fn now(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// Generates trace and span IDs counting up from 1, in the order spans are started.
+#[derive(Debug, Default)]
+pub struct SequentialIdGenerator {
+    next_trace_id: AtomicU64,
+    next_span_id: AtomicU64,
+}
+
+impl SequentialIdGenerator {
+    /// Create a generator whose first trace and span IDs are both 1.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn new_trace_id(&self) -> TraceId {
+        TraceId::from(u128::from(
+            self.next_trace_id.fetch_add(1, Ordering::Relaxed) + 1,
+        ))
+    }
+
+    fn new_span_id(&self) -> SpanId {
+        SpanId::from(self.next_span_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+}
+
+/// A user-supplied ID generator, shared between options, resolved config, and the
+/// tracer provider.
+#[derive(Clone)]
+pub(crate) struct SharedIdGenerator(pub(crate) Arc<dyn IdGenerator>);
+
+impl fmt::Debug for SharedIdGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl IdGenerator for SharedIdGenerator {
+    fn new_trace_id(&self) -> TraceId {
+        self.0.new_trace_id()
+    }
+
+    fn new_span_id(&self) -> SpanId {
+        self.0.new_span_id()
+    }
+}
+
+/// Finished spans kept in memory, for asserting on instrumentation in tests.
+///
+/// Hand a clone to [`OtelOptionsBuilder::capture_spans`](crate::OtelOptionsBuilder::capture_spans)
+/// and keep one in the test. Spans are captured when they end, after span transforms such
+/// as renaming and drop rules, so they look as they would when exported.
+#[derive(Debug, Clone, Default)]
+pub struct SpanCapture {
+    spans: Arc<Mutex<Vec<SpanData>>>,
+}
+
+impl SpanCapture {
+    /// Create an empty capture.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The spans captured so far, in the order they ended.
+    pub fn spans(&self) -> Vec<SpanData> {
+        self.lock().clone()
+    }
+
+    /// Forget the spans captured so far.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub(crate) fn push(&self, span: SpanData) {
+        self.lock().push(span);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<SpanData>> {
+        self.spans.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Implementation of [`assert_span_exists!`](crate::assert_span_exists).
+    #[doc(hidden)]
+    #[track_caller]
+    pub fn __assert_span_exists(&self, name: &str, attributes: &[(&str, String)]) -> SpanData {
+        let spans = self.lock();
+        let found = spans.iter().find(|span| {
+            span.name == name
+                && attributes
+                    .iter()
+                    .all(|(key, value)| attribute(span, key).as_deref() == Some(value.as_str()))
+        });
+        match found {
+            Some(span) => span.clone(),
+            None => {
+                let wanted: Vec<_> = attributes
+                    .iter()
+                    .map(|(key, value)| format!("{key} = {value:?}"))
+                    .collect();
+                panic!(
+                    "no span `{name}` with {{{}}} was captured\n{}",
+                    wanted.join(", "),
+                    describe(&spans)
+                )
+            }
+        }
+    }
+
+    /// Implementation of [`assert_span_parent!`](crate::assert_span_parent).
+    #[doc(hidden)]
+    #[track_caller]
+    pub fn __assert_span_parent(&self, child: &str, parent: &str) -> SpanData {
+        let spans = self.lock();
+        let children: Vec<_> = spans.iter().filter(|span| span.name == child).collect();
+        if children.is_empty() {
+            panic!("no span `{child}` was captured\n{}", describe(&spans));
+        }
+        let parent_of = |span: &SpanData| {
+            spans.iter().find(|candidate| {
+                candidate.span_context.span_id() == span.parent_span_id
+                    && candidate.span_context.trace_id() == span.span_context.trace_id()
+            })
+        };
+        if let Some(span) = children
+            .iter()
+            .find(|span| parent_of(span).is_some_and(|p| p.name == parent))
+        {
+            return (*span).clone();
+        }
+
+        let actual: Vec<_> = children
+            .iter()
+            .map(|span| match parent_of(span) {
+                Some(p) => format!("`{}`", p.name),
+                None if span.parent_span_id == SpanId::INVALID => "none (root span)".to_owned(),
+                None => format!("{} (not captured)", span.parent_span_id),
+            })
+            .collect();
+        panic!(
+            "span `{child}` is not a child of `{parent}`; its parent is {}\n{}",
+            actual.join(" / "),
+            describe(&spans)
+        )
+    }
+}
+
+/// A span attribute's value as a string, so expected values of any type compare by text.
+fn attribute(span: &SpanData, key: &str) -> Option<String> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| kv.value.as_str().into_owned())
+}
+
+/// List captured spans for a failed assertion.
+fn describe(spans: &[SpanData]) -> String {
+    if spans.is_empty() {
+        return "captured spans: none".to_owned();
+    }
+    let mut out = String::from("captured spans:");
+    for span in spans {
+        let attributes: Vec<_> = span
+            .attributes
+            .iter()
+            .map(|kv| format!("{} = {:?}", kv.key, kv.value.as_str()))
+            .collect();
+        let _ = write!(out, "\n  {} {{{}}}", span.name, attributes.join(", "));
+    }
+    out
+}
+
+/// Assert that a span with `name` and the given attributes was captured, and return it.
+///
+/// Attribute values are compared as text, so `attr("retries", 3)` matches an integer
+/// attribute of 3. On failure the message lists every captured span.
+///
+/// ```ignore
+/// let span = assert_span_exists!(spans, name = "process_order", attr("order_id", "order-123"));
+/// ```
+#[macro_export]
+macro_rules! assert_span_exists {
+    ($capture:expr, name = $name:expr $(, attr($key:expr, $value:expr))* $(,)?) => {
+        $crate::testing::SpanCapture::__assert_span_exists(
+            &$capture,
+            $name,
+            &[$(($key, ::std::string::ToString::to_string(&$value))),*],
+        )
+    };
+}
+
+/// Assert that a span named `child` was captured with a span named `parent` as its
+/// direct parent, and return the child.
+///
+/// ```ignore
+/// assert_span_parent!(spans, child = "charge_card", parent = "process_order");
+/// ```
+#[macro_export]
+macro_rules! assert_span_parent {
+    ($capture:expr, child = $child:expr, parent = $parent:expr $(,)?) => {
+        $crate::testing::SpanCapture::__assert_span_parent(&$capture, $child, $parent)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_and_time_are_deterministic() {
+        let ids = SequentialIdGenerator::new();
+        assert_eq!(ids.new_trace_id(), TraceId::from(1u128));
+        assert_eq!(ids.new_trace_id(), TraceId::from(2u128));
+        assert_eq!(ids.new_span_id(), SpanId::from(1u64));
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = TestClock::new(start);
+        let shared = clock.clone();
+        shared.advance(Duration::from_millis(250));
+        assert_eq!(clock.now(), start + Duration::from_millis(250));
+    }
+
+    #[derive(Debug)]
+    struct Discard;
+
+    impl opentelemetry_sdk::trace::SpanProcessor for Discard {
+        fn on_start(&self, _: &mut opentelemetry_sdk::trace::Span, _: &opentelemetry::Context) {}
+        fn on_end(&self, _: SpanData) {}
+        fn force_flush(&self) -> opentelemetry_sdk::error::OTelSdkResult {
+            Ok(())
+        }
+        fn shutdown_with_timeout(&self, _: Duration) -> opentelemetry_sdk::error::OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn span_assertions_match_names_attributes_and_parents() {
+        use opentelemetry::trace::{TraceContextExt, Tracer, TracerProvider as _};
+        use opentelemetry::KeyValue;
+
+        let capture = SpanCapture::new();
+        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+            .with_span_processor(crate::processors::span_capture::CaptureSpanProcessor::new(
+                Discard,
+                Some(capture.clone()),
+            ))
+            .build();
+        let tracer = provider.tracer("test");
+        tracer.in_span("process_order", |cx| {
+            cx.span().set_attributes([
+                KeyValue::new("order_id", "order-123"),
+                KeyValue::new("retries", 3),
+            ]);
+            tracer.in_span("charge_card", |_| {});
+        });
+
+        let span = assert_span_exists!(
+            capture,
+            name = "process_order",
+            attr("order_id", "order-123"),
+            attr("retries", 3),
+        );
+        assert_eq!(span.parent_span_id, SpanId::INVALID);
+        assert_span_parent!(capture, child = "charge_card", parent = "process_order");
+
+        let panic = std::panic::catch_unwind(|| {
+            assert_span_parent!(capture, child = "process_order", parent = "charge_card")
+        })
+        .unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.starts_with(
+            "span `process_order` is not a child of `charge_card`; its parent is none (root span)"
+        ));
+        assert!(message.contains("\n  process_order {order_id = \"order-123\", retries = \"3\"}"));
+    }
+}
@@ -0,0 +1,126 @@
+//! Carrying the current span onto other threads.
+//!
+//! A span entered on one thread isn't current on another, so spans created by work handed
+//! to a thread or a threadpool start new, disconnected traces.
+
+use std::thread::JoinHandle;
+
+use tracing::dispatcher::{self, Dispatch};
+use tracing::Span;
+
+/// Extension for closures run on another thread: [`with_current_context`] captures the
+/// caller's span and OTel context and re-enters them when the closure runs.
+///
+/// ```no_run
+/// use raccoon_otel::WithCurrentContext;
+///
+/// let _span = tracing::info_span!("resize_images").entered();
+/// // e.g. `rayon::spawn` or `rayon::join`, which take `FnOnce` closures as well
+/// std::thread::spawn(
+///     (|| {
+///         tracing::info!("resizing"); // recorded inside `resize_images`
+///     })
+///     .with_current_context(),
+/// );
+/// ```
+///
+/// For `par_iter().map(..)`-style closures, which run many times, use `instrumented()`
+/// from the `rayon` feature, or capture the span: `let span = Span::current();` then
+/// `.map(|x| span.in_scope(|| work(x)))`.
+///
+/// [`with_current_context`]: WithCurrentContext::with_current_context
+pub trait WithCurrentContext<T>: FnOnce() -> T + Send + Sized {
+    /// Wrap the closure so it runs inside the current span, OTel context and subscriber.
+    fn with_current_context(self) -> impl FnOnce() -> T + Send;
+}
+
+impl<F, T> WithCurrentContext<T> for F
+where
+    F: FnOnce() -> T + Send,
+{
+    fn with_current_context(self) -> impl FnOnce() -> T + Send {
+        let current = CurrentContext::capture();
+        move || current.in_scope(self)
+    }
+}
+
+/// The caller's subscriber, span and OTel context, to re-enter on another thread.
+#[derive(Debug)]
+pub(crate) struct CurrentContext {
+    dispatch: Dispatch,
+    span: Span,
+    cx: opentelemetry::Context,
+}
+
+impl CurrentContext {
+    pub(crate) fn capture() -> Self {
+        Self {
+            dispatch: dispatcher::get_default(Dispatch::clone),
+            span: Span::current(),
+            cx: opentelemetry::Context::current(),
+        }
+    }
+
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        // The subscriber too, so pipelines that aren't global keep working
+        dispatcher::with_default(&self.dispatch, || {
+            let _attached = self.cx.clone().attach();
+            self.span.in_scope(f)
+        })
+    }
+}
+
+/// [`std::thread::spawn`] with the current span, OTel context and subscriber carried onto
+/// the new thread, so its spans join the caller's trace.
+///
+/// ```no_run
+/// let _span = tracing::info_span!("checksum").entered();
+/// let handle = raccoon_otel::spawn_instrumented(|| {
+///     let _span = tracing::info_span!("hash_chunk").entered(); // child of `checksum`
+///     42
+/// });
+/// let sum = handle.join().expect("worker panicked");
+/// ```
+pub fn spawn_instrumented<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    std::thread::spawn(f.with_current_context())
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::{TraceContextExt, TraceId, TracerProvider as _};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    fn trace_id(span: &Span) -> TraceId {
+        span.context().span().span_context().trace_id()
+    }
+
+    #[test]
+    fn spawned_threads_continue_the_callers_trace() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let parent = tracing::info_span!("parent");
+            let _entered = parent.enter();
+
+            let child = spawn_instrumented(|| trace_id(&tracing::info_span!("child")))
+                .join()
+                .unwrap();
+            assert_eq!(child, trace_id(&parent));
+
+            let detached = std::thread::spawn(|| trace_id(&tracing::info_span!("detached")))
+                .join()
+                .unwrap();
+            assert_ne!(detached, trace_id(&parent));
+        });
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt;
+
+use crate::azure_monitor::ConnectionString;
+use crate::exporters::vsock::VsockAddr;
+use crate::layers::event_counts::EventCountRule;
+use crate::options::{OtelOptions, Protocol};
+
+/// A configuration mistake found by [`OtelOptionsBuilder::try_build`](crate::OtelOptionsBuilder::try_build).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// The endpoint is not an absolute `http://` or `https://` URL with a host.
+    InvalidEndpoint {
+        /// The endpoint as given.
+        endpoint: String,
+        /// What is wrong with it.
+        reason: String,
+    },
+    /// A header or gRPC metadata name contains characters not allowed in HTTP header names.
+    InvalidHeaderName(String),
+    /// A resource attribute key is empty or contains whitespace.
+    InvalidAttributeKey(String),
+    /// A value given as a string (protocol, timeout) or a number out of range.
+    InvalidValue(String),
+    /// Options that contradict each other or have no effect together.
+    Conflict(String),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEndpoint { endpoint, reason } => {
+                write!(f, "Invalid OTLP endpoint `{endpoint}`: {reason}")
+            }
+            Self::InvalidHeaderName(name) => write!(f, "Invalid header name `{name}`"),
+            Self::InvalidAttributeKey(key) => {
+                write!(f, "Invalid resource attribute key `{key}`")
+            }
+            Self::InvalidValue(message) | Self::Conflict(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Check options for mistakes that would otherwise surface as a confusing exporter error,
+/// or not at all. Environment variables are not consulted.
+pub(crate) fn validate(opts: &OtelOptions) -> Result<(), ValidationError> {
+    if let Some(ref invalid) = opts.invalid_protocol {
+        return Err(ValidationError::InvalidValue(invalid.to_string()));
+    }
+    if let Some(ref invalid) = opts.invalid_export_timeout {
+        return Err(ValidationError::InvalidValue(format!(
+            "Invalid export timeout `{invalid}`: expected e.g. 30s, 500ms or 1m30s"
+        )));
+    }
+    if let Some(min_ratio) = opts.adaptive_sampling_min_ratio {
+        if !(0.0..=1.0).contains(&min_ratio) {
+            return Err(ValidationError::InvalidValue(format!(
+                "Adaptive sampling minimum ratio {min_ratio} is outside 0.0..=1.0"
+            )));
+        }
+    }
+
+    if let Some((_, ratio)) = opts
+        .sampling_rules
+        .iter()
+        .find(|(_, ratio)| !(0.0..=1.0).contains(ratio))
+    {
+        return Err(ValidationError::InvalidValue(format!(
+            "Sampling rule ratio {ratio} is outside 0.0..=1.0"
+        )));
+    }
+
+    if let Some((url, interval)) = &opts.remote_sampling {
+        if let Err(e) = url::Url::parse(url) {
+            return Err(ValidationError::InvalidValue(format!(
+                "Remote sampling URL `{url}` is invalid: {e}"
+            )));
+        }
+        if interval.is_zero() {
+            return Err(ValidationError::InvalidValue(
+                "Remote sampling interval must be greater than zero".to_owned(),
+            ));
+        }
+    }
+
+    if let Some((endpoint, interval)) = &opts.opamp {
+        if let Err(e) = url::Url::parse(endpoint) {
+            return Err(ValidationError::InvalidValue(format!(
+                "OpAMP endpoint `{endpoint}` is invalid: {e}"
+            )));
+        }
+        if interval.is_zero() {
+            return Err(ValidationError::InvalidValue(
+                "OpAMP interval must be greater than zero".to_owned(),
+            ));
+        }
+        if opts.remote_sampling.is_some() {
+            return Err(ValidationError::Conflict(
+                "`remote_sampling` and `opamp` both set the sampling strategy; use one".to_owned(),
+            ));
+        }
+    }
+
+    if opts.discover_endpoint && opts.endpoint.is_some() {
+        return Err(ValidationError::Conflict(
+            "`discover_endpoint` and `endpoint` are both set; discovery only runs without an \
+             endpoint"
+                .to_owned(),
+        ));
+    }
+    if opts.discovery_file.is_some() && !opts.discover_endpoint {
+        return Err(ValidationError::Conflict(
+            "`discovery_file` is set but `discover_endpoint` is not enabled".to_owned(),
+        ));
+    }
+
+    if opts
+        .grpc_dns_refresh
+        .is_some_and(|interval| interval.is_zero())
+    {
+        return Err(ValidationError::InvalidValue(
+            "gRPC DNS refresh interval must be greater than zero".to_owned(),
+        ));
+    }
+
+    if let Some((_, period)) = &opts.profiling {
+        if period.is_zero() {
+            return Err(ValidationError::InvalidValue(
+                "Profiling period must be greater than zero".to_owned(),
+            ));
+        }
+    }
+
+    if opts
+        .cardinality_window
+        .is_some_and(|window| window.is_zero())
+    {
+        return Err(ValidationError::InvalidValue(
+            "Cardinality report window must be greater than zero".to_owned(),
+        ));
+    }
+
+    for (selector, metric) in &opts.event_counters {
+        EventCountRule::parse(selector, metric).map_err(ValidationError::InvalidValue)?;
+    }
+
+    if let Some((max_events, keep)) = opts.span_event_summary {
+        if 2 * keep >= max_events {
+            return Err(ValidationError::InvalidValue(format!(
+                "Span event summary keeps {keep} events at each end, which doesn't fit in \
+                 {max_events}; keep fewer than half"
+            )));
+        }
+    }
+    if opts.max_export_bytes == Some(0) {
+        return Err(ValidationError::InvalidValue(
+            "Maximum export size must be greater than zero".to_owned(),
+        ));
+    }
+
+    if let Some(ref endpoint) = opts.endpoint {
+        check_endpoint(endpoint)?;
+    }
+    let protocols = [&opts.protocol, &opts.traces_protocol, &opts.logs_protocol];
+    for protocol in protocols.into_iter().flatten() {
+        if let Protocol::AzureMonitor { connection_string } = protocol {
+            ConnectionString::parse(connection_string)
+                .map_err(|e| ValidationError::InvalidValue(e.to_string()))?;
+        }
+    }
+    let secret_headers = opts.header_sources.iter().map(|(name, _)| name);
+    let tenant_header = opts.tenant.as_ref().and_then(|t| t.route_header.as_ref());
+    for name in opts
+        .headers
+        .keys()
+        .chain(secret_headers)
+        .chain(tenant_header)
+        .chain(opts.grpc_metadata.keys())
+    {
+        if !is_header_name(name) {
+            return Err(ValidationError::InvalidHeaderName(name.clone()));
+        }
+    }
+    for key in opts
+        .resource_attributes
+        .keys()
+        .map(String::as_str)
+        .chain(opts.resource_key_values.iter().map(|kv| kv.key.as_str()))
+        .chain(opts.ambient_attributes.iter().map(|(key, _)| key.as_str()))
+    {
+        if key.is_empty() || key.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(ValidationError::InvalidAttributeKey(key.to_owned()));
+        }
+    }
+
+    check_conflicts(opts)
+}
+
+fn check_endpoint(endpoint: &str) -> Result<(), ValidationError> {
+    let invalid = |reason: String| ValidationError::InvalidEndpoint {
+        endpoint: endpoint.to_owned(),
+        reason,
+    };
+    if let Some(addr) = VsockAddr::parse(endpoint) {
+        return addr.map(drop).map_err(invalid);
+    }
+    let url = url::Url::parse(endpoint.trim()).map_err(|e| invalid(e.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid(format!(
+            "unsupported scheme `{}` (expected http or https)",
+            url.scheme()
+        )));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(invalid("missing host".to_owned()));
+    }
+    Ok(())
+}
+
+/// RFC 9110 `token`: the characters allowed in a header field name.
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+fn check_conflicts(opts: &OtelOptions) -> Result<(), ValidationError> {
+    // Only explicitly set protocols are known here; an unset one may still come from the
+    // environment at setup
+    let Some(ref protocol) = opts.protocol else {
+        return Ok(());
+    };
+    let signal_protocols = [
+        opts.traces_protocol.as_ref().unwrap_or(protocol),
+        opts.logs_protocol.as_ref().unwrap_or(protocol),
+        protocol,
+    ];
+
+    if !signal_protocols.contains(&&Protocol::Grpc) {
+        let grpc_options = [
+            ("grpc_http_fallback", opts.grpc_http_fallback == Some(true)),
+            ("grpc_keepalive", opts.grpc_keepalive.is_some()),
+            ("grpc_connect_timeout", opts.grpc_connect_timeout.is_some()),
+            ("grpc_adaptive_window", opts.grpc_adaptive_window.is_some()),
+            ("grpc_dns_refresh", opts.grpc_dns_refresh.is_some()),
+            ("grpc_metadata", !opts.grpc_metadata.is_empty()),
+            ("dedicated_export_thread", opts.dedicated_export_thread),
+        ];
+        if let Some((name, _)) = grpc_options.iter().find(|(_, set)| *set) {
+            return Err(ValidationError::Conflict(format!(
+                "`{name}` is set but no signal uses Protocol::Grpc"
+            )));
+        }
+    }
+
+    let file_paths: Vec<_> = signal_protocols
+        .iter()
+        .filter_map(|protocol| match protocol {
+            Protocol::File { path } => Some(path),
+            _ => None,
+        })
+        .collect();
+    if opts.file_rotation.is_some() && file_paths.is_empty() {
+        return Err(ValidationError::Conflict(
+            "`file_rotation` is set but no signal uses Protocol::File".to_owned(),
+        ));
+    }
+    if let Some(ref record_path) = opts.record_path {
+        if file_paths.contains(&record_path) {
+            return Err(ValidationError::Conflict(format!(
+                "`record_to` and Protocol::File both write to {}",
+                record_path.display()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn valid_options_pass() {
+        let opts = OtelOptions::builder()
+            .endpoint("https://otlp.example.com:4318/otel")
+            .protocol(Protocol::HttpProtobuf)
+            .headers([("Authorization", "Bearer token")])
+            .resource_attributes([("deployment.environment", "production")])
+            .try_build();
+
+        assert!(opts.is_ok());
+    }
+
+    #[test]
+    fn mistakes_are_reported() {
+        let err = |builder: crate::OtelOptionsBuilder| builder.try_build().unwrap_err();
+
+        assert!(matches!(
+            err(OtelOptions::builder().endpoint("collector:4318")),
+            ValidationError::InvalidEndpoint { .. }
+        ));
+        assert_eq!(
+            err(OtelOptions::builder().headers([("X Tenant", "acme")])),
+            ValidationError::InvalidHeaderName("X Tenant".to_owned())
+        );
+        assert_eq!(
+            err(OtelOptions::builder().resource_attributes([("", "x")])),
+            ValidationError::InvalidAttributeKey(String::new())
+        );
+        assert!(matches!(
+            err(OtelOptions::builder().protocol_str("http")),
+            ValidationError::InvalidValue(_)
+        ));
+        assert!(matches!(
+            err(OtelOptions::builder()
+                .protocol(Protocol::HttpProtobuf)
+                .grpc_keepalive(Duration::from_secs(30), Duration::from_secs(10))),
+            ValidationError::Conflict(_)
+        ));
+        assert!(matches!(
+            err(OtelOptions::builder()
+                .remote_sampling("http://jaeger:14268/api/sampling", Duration::from_secs(60))
+                .opamp("http://opamp:4320/v1/opamp", Duration::from_secs(30))),
+            ValidationError::Conflict(_)
+        ));
+        assert!(matches!(
+            err(OtelOptions::builder().count_events([("payment", "payment.events.total")])),
+            ValidationError::InvalidValue(_)
+        ));
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::logs::SdkLoggerProvider;
+#[cfg(feature = "metrics")]
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+
+use crate::health::ExportHealth;
+
+/// How long the SDK's batch processors wait for a flush before giving up. Not
+/// configurable, and the providers wrap the resulting timeout error in a generic one.
+const SDK_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Background thread that force-flushes the providers every `interval`, so a process
+/// that is killed or frozen without running `Drop` loses at most one interval of data.
+///
+/// A flush that times out means an export is stuck, which the exporters can't report
+/// themselves since they never return: it is counted as a failure in the guard's health
+/// and warned about on stderr once per stall.
+#[derive(Debug)]
+pub(crate) struct FlushWatchdog {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FlushWatchdog {
+    pub(crate) fn start(
+        interval: Duration,
+        tracer_provider: Option<SdkTracerProvider>,
+        logger_provider: Option<SdkLoggerProvider>,
+        #[cfg(feature = "metrics")] meter_provider: Option<SdkMeterProvider>,
+        health: Arc<ExportHealth>,
+    ) -> std::io::Result<Self> {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name("raccoon-otel-flush".into())
+            .spawn(move || {
+                let mut stalls = Stalls::new(health);
+                // Both a stop message and a dropped sender end the loop
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    if let Some(ref tp) = tracer_provider {
+                        stalls.check(0, "spans", || tp.force_flush());
+                    }
+                    if let Some(ref lp) = logger_provider {
+                        stalls.check(1, "logs", || lp.force_flush());
+                    }
+                    #[cfg(feature = "metrics")]
+                    if let Some(ref mp) = meter_provider {
+                        stalls.check(2, "metrics", || mp.force_flush());
+                    }
+                }
+            })?;
+        Ok(Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Stop the thread and wait for an in-progress flush to finish.
+    pub(crate) fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Which providers' last flush timed out, so each stall is warned about once.
+struct Stalls {
+    health: Arc<ExportHealth>,
+    stalled: [bool; 3],
+}
+
+impl Stalls {
+    fn new(health: Arc<ExportHealth>) -> Self {
+        Self {
+            health,
+            stalled: [false; 3],
+        }
+    }
+
+    fn check(&mut self, provider: usize, signal: &str, flush: impl FnOnce() -> OTelSdkResult) {
+        let started = Instant::now();
+        let result = flush();
+        // Quicker flush errors come from failed exports, which already show up in the
+        // guard's health
+        if result.is_ok() || started.elapsed() < SDK_FLUSH_TIMEOUT {
+            self.stalled[provider] = false;
+            return;
+        }
+        let error = format!("flush timed out after {SDK_FLUSH_TIMEOUT:?}, the exporter is stalled");
+        self.health.record(signal, Err(&error));
+        if !std::mem::replace(&mut self.stalled[provider], true) {
+            eprintln!("raccoon-otel: WARNING: {signal} {error}");
+        }
+    }
+}
+
+impl Drop for FlushWatchdog {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Instant;
+
+    use opentelemetry::trace::{Tracer as _, TracerProvider as _};
+    use opentelemetry_sdk::trace::{BatchSpanProcessor, SpanData, SpanExporter};
+
+    use super::*;
+
+    /// Exporter whose exports hang until released.
+    #[derive(Debug)]
+    struct Stalled(Arc<AtomicBool>);
+
+    impl SpanExporter for Stalled {
+        async fn export(&self, _: Vec<SpanData>) -> OTelSdkResult {
+            while !self.0.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn stalled_exporters_are_reported_in_the_health() {
+        let released = Arc::new(AtomicBool::new(false));
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(
+                BatchSpanProcessor::builder(Stalled(Arc::clone(&released))).build(),
+            )
+            .build();
+        provider.tracer("test").in_span("work", |_| {});
+        let health = Arc::new(ExportHealth::default());
+        let mut watchdog = FlushWatchdog::start(
+            Duration::from_millis(10),
+            Some(provider.clone()),
+            None,
+            #[cfg(feature = "metrics")]
+            None,
+            Arc::clone(&health),
+        )
+        .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while health.snapshot().last_error.is_none() {
+            assert!(Instant::now() < deadline, "the stall was never reported");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        released.store(true, Ordering::Relaxed);
+        watchdog.stop();
+
+        let status = health.snapshot();
+        assert!(status.consecutive_failures >= 1);
+        assert!(status.last_failure.is_some());
+        let error = status.last_error.unwrap();
+        assert!(
+            error.starts_with("spans export failed: flush timed out"),
+            "{error}"
+        );
+        provider.shutdown().unwrap();
+    }
+}